@@ -0,0 +1,710 @@
+// ============================================================================
+// AI 服务 HTTP 客户端与 API Key 审计
+// ============================================================================
+//
+// - 所有携带 API Key（Authorization / x-api-key / api-key 头）的请求都必须经过本模块发出：
+//   reqwest::Client 仅在本模块内部可见，调用方无法绕过审计直接发送带 Key 的请求
+// - 每次调用写入一条审计记录（发起功能、站点/Tab/项目、字节数、状态）到 api_audit.json
+// - 响应体字节数在读取过程中累计，响应对象释放时记入内存；落盘在后台线程进行，
+//   连续多次记录合并为一次写入
+// - 与用量记录共用保留策略（crate::HISTORY_MAX_RECORDS）
+// - 共享一个带连接池的客户端；代理取自 AppConfig.http_proxy，未配置时沿用 HTTPS_PROXY 等环境变量
+
+use once_cell::sync::Lazy;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 隐私站点在审计记录中的占位符（代替站点与 Tab id）
+const PRIVATE_PLACEHOLDER: &str = "private";
+
+/// 演示模式下审计记录的端点与 Key 标识（不发出任何网络请求）
+const DEMO_ENDPOINT: &str = "demo";
+
+/// 演示总结截取原文开头的字符数
+const DEMO_EXCERPT_CHARS: usize = 160;
+
+/// 建立连接的超时（请求总超时由调用方按功能指定）
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 空闲连接在池中保留的时长
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// 每个主机保留的空闲连接数上限
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// TCP keepalive 间隔
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// 共享客户端（仅本模块可见）；修改代理时整体替换，已发出的请求继续使用旧客户端
+static CLIENT: Lazy<Mutex<reqwest::Client>> = Lazy::new(|| {
+    Mutex::new(build_client(None).unwrap_or_else(|e| {
+        println!("[ai_client] 创建客户端失败，使用默认配置: {}", e);
+        reqwest::Client::new()
+    }))
+});
+
+static AUDIT_LOG: Lazy<Mutex<Vec<ApiAuditEntry>>> = Lazy::new(|| Mutex::new(load_entries()));
+
+/// 已安排后台落盘、尚未开始序列化
+static PERSIST_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// 串行化后台落盘，保证后开始的写入带着更新的快照
+static PERSIST_LOCK: Mutex<()> = Mutex::new(());
+
+/// 发起调用的功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ApiFeature {
+    /// 用户手动触发的总结（总结文本 / 总结当前 Tab）
+    ManualSummarize,
+    /// 后台任务（含自动化 API）触发的总结
+    SummarizeJob,
+    /// 总结预检（模型列表接口）
+    Preflight,
+    /// 设置界面的“测试连接”
+    ConnectionTest,
+    /// 设置界面的模型列表
+    ModelList,
+}
+
+impl ApiFeature {
+    fn as_str(self) -> &'static str {
+        match self {
+            ApiFeature::ManualSummarize => "manual_summarize",
+            ApiFeature::SummarizeJob => "summarize_job",
+            ApiFeature::Preflight => "preflight",
+            ApiFeature::ConnectionTest => "connection_test",
+            ApiFeature::ModelList => "model_list",
+        }
+    }
+}
+
+/// 一次调用的上下文（写入审计记录）
+#[derive(Debug, Clone)]
+pub(crate) struct ApiCallContext {
+    feature: ApiFeature,
+    site_id: Option<String>,
+    tab_id: Option<String>,
+    project_id: Option<String>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+impl ApiCallContext {
+    pub(crate) fn new(feature: ApiFeature) -> Self {
+        Self {
+            feature,
+            site_id: None,
+            tab_id: None,
+            project_id: None,
+        }
+    }
+
+    pub(crate) fn with_site(mut self, site_id: Option<String>) -> Self {
+        if let Some(site_id) = non_empty(site_id) {
+            self.site_id = Some(site_id);
+        }
+        self
+    }
+
+    pub(crate) fn with_tab(mut self, tab_id: Option<String>) -> Self {
+        if let Some(tab_id) = non_empty(tab_id) {
+            self.tab_id = Some(tab_id);
+        }
+        self
+    }
+
+    /// 隐私站点：站点与 Tab 均以占位符记录
+    pub(crate) fn private_site(mut self) -> Self {
+        self.site_id = Some(PRIVATE_PLACEHOLDER.to_string());
+        self.tab_id = Some(PRIVATE_PLACEHOLDER.to_string());
+        self
+    }
+
+    pub(crate) fn with_project(mut self, project_id: Option<String>) -> Self {
+        if let Some(project_id) = non_empty(project_id) {
+            self.project_id = Some(project_id);
+        }
+        self
+    }
+
+    /// 记录用的站点 id（隐私站点为占位符）
+    pub(crate) fn site_id(&self) -> Option<&str> {
+        self.site_id.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ApiAuditEntry {
+    pub ts: u64,
+    pub feature: ApiFeature,
+    pub method: String,
+    /// 主机 + 路径（不含 query）
+    pub endpoint: String,
+    /// 只保留 Key 的首尾少量字符，用于区分多个 Key
+    pub key_hint: String,
+    #[serde(default)]
+    pub site_id: Option<String>,
+    #[serde(default)]
+    pub tab_id: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    /// 演示模式生成的模拟响应（未发出网络请求）
+    #[serde(default)]
+    pub demo: bool,
+}
+
+/// 按天（UTC）汇总
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ApiAuditDaily {
+    pub date: String,
+    pub calls: u64,
+    pub failed_calls: u64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    pub by_feature: BTreeMap<String, u64>,
+}
+
+fn key_hint(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.trim().chars().collect();
+    if chars.len() <= 12 {
+        return "***".to_string();
+    }
+    let head: String = chars[..3].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+fn endpoint_of(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => format!("{}{}", parsed.host_str().unwrap_or_default(), parsed.path()),
+        Err(_) => url.split('?').next().unwrap_or_default().to_string(),
+    }
+}
+
+fn new_entry(ctx: &ApiCallContext, method: &str, url: &str, api_key: &str) -> ApiAuditEntry {
+    ApiAuditEntry {
+        ts: crate::now_ts(),
+        feature: ctx.feature,
+        method: method.to_string(),
+        endpoint: endpoint_of(url),
+        key_hint: key_hint(api_key),
+        site_id: ctx.site_id.clone(),
+        tab_id: ctx.tab_id.clone(),
+        project_id: ctx.project_id.clone(),
+        status: None,
+        error: None,
+        request_bytes: 0,
+        response_bytes: 0,
+        demo: false,
+    }
+}
+
+/// Anthropic Messages API 的版本头
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 请求携带 Key 的方式
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ApiAuth<'a> {
+    /// `Authorization: Bearer <key>`（OpenAI 兼容接口）
+    Bearer(&'a str),
+    /// `x-api-key: <key>` + `anthropic-version`
+    Anthropic(&'a str),
+    /// `api-key: <key>`（Azure OpenAI）
+    Azure(&'a str),
+    /// 不携带 Key（Ollama 等本地服务）
+    None,
+}
+
+impl<'a> ApiAuth<'a> {
+    fn key(self) -> &'a str {
+        match self {
+            ApiAuth::Bearer(key) | ApiAuth::Anthropic(key) | ApiAuth::Azure(key) => key,
+            ApiAuth::None => "",
+        }
+    }
+
+    fn apply(self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            ApiAuth::Bearer(key) => request.header(AUTHORIZATION, format!("Bearer {}", key)),
+            ApiAuth::Anthropic(key) => request
+                .header("x-api-key", key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
+            ApiAuth::Azure(key) => request.header("api-key", key),
+            ApiAuth::None => request,
+        }
+    }
+}
+
+/// 按代理设置创建客户端；`proxy` 为 None 时由 reqwest 读取 HTTPS_PROXY / HTTP_PROXY / NO_PROXY
+fn build_client(proxy: Option<&str>) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(TCP_KEEPALIVE);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(parse_proxy(proxy)?);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// 校验代理地址（仅支持 http/https 代理）
+fn parse_proxy(proxy: &str) -> Result<reqwest::Proxy, String> {
+    let url = url::Url::parse(proxy).map_err(|e| format!("代理地址无效: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "不支持的代理协议: {}（仅支持 http/https）",
+            url.scheme()
+        ));
+    }
+    if url.host_str().unwrap_or_default().is_empty() {
+        return Err("代理地址缺少主机名".to_string());
+    }
+    reqwest::Proxy::all(url.as_str()).map_err(|e| format!("代理地址无效: {}", e))
+}
+
+/// 按新的代理设置重建共享客户端；地址无效时返回错误且保留原客户端
+pub(crate) fn set_proxy(proxy: Option<&str>) -> Result<(), String> {
+    let client = build_client(proxy)?;
+    *CLIENT.lock().unwrap() = client;
+    Ok(())
+}
+
+fn client() -> reqwest::Client {
+    // 测试中任何发出网络请求的路径都直接失败（演示模式等不应联网的流程据此验证）
+    if cfg!(test) {
+        panic!("测试中不允许发出网络请求");
+    }
+    CLIENT.lock().unwrap().clone()
+}
+
+/// 发送 JSON POST 请求（带 Key）
+pub(crate) async fn post_json(
+    ctx: &ApiCallContext,
+    url: &str,
+    auth: ApiAuth<'_>,
+    body: &serde_json::Value,
+    timeout: Option<Duration>,
+) -> Result<AuditedResponse, reqwest::Error> {
+    let payload = serde_json::to_vec(body).unwrap_or_default();
+    let mut entry = new_entry(ctx, "POST", url, auth.key());
+    entry.request_bytes = payload.len() as u64;
+
+    let mut request = auth
+        .apply(client().post(url))
+        .header(CONTENT_TYPE, "application/json")
+        .body(payload);
+    if let Some(timeout) = timeout {
+        request = request.timeout(timeout);
+    }
+    send(request, entry).await
+}
+
+/// 发送 GET 请求（带 Key）
+pub(crate) async fn get(
+    ctx: &ApiCallContext,
+    url: &str,
+    auth: ApiAuth<'_>,
+    timeout: Option<Duration>,
+) -> Result<AuditedResponse, reqwest::Error> {
+    let entry = new_entry(ctx, "GET", url, auth.key());
+    let mut request = auth.apply(client().get(url));
+    if let Some(timeout) = timeout {
+        request = request.timeout(timeout);
+    }
+    send(request, entry).await
+}
+
+async fn send(
+    request: reqwest::RequestBuilder,
+    mut entry: ApiAuditEntry,
+) -> Result<AuditedResponse, reqwest::Error> {
+    match request.send().await {
+        Ok(resp) => {
+            entry.status = Some(resp.status().as_u16());
+            Ok(AuditedResponse {
+                inner: Some(resp),
+                entry,
+            })
+        }
+        Err(e) => {
+            entry.error = Some(e.to_string());
+            record(entry);
+            Err(e)
+        }
+    }
+}
+
+/// 带审计的响应：读取响应体时累计字节数，释放时写入审计记录
+pub(crate) struct AuditedResponse {
+    inner: Option<reqwest::Response>,
+    entry: ApiAuditEntry,
+}
+
+impl AuditedResponse {
+    pub(crate) fn status(&self) -> reqwest::StatusCode {
+        self.inner.as_ref().map(|r| r.status()).unwrap_or_default()
+    }
+
+    pub(crate) fn content_type(&self) -> Option<String> {
+        self.inner
+            .as_ref()?
+            .headers()
+            .get(CONTENT_TYPE)?
+            .to_str()
+            .ok()
+            .map(|v| v.to_string())
+    }
+
+    /// `Retry-After` 头（仅支持秒数形式）
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        let secs = self
+            .inner
+            .as_ref()?
+            .headers()
+            .get(RETRY_AFTER)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(Duration::from_secs(secs))
+    }
+
+    /// 读取下一段响应体（流式）
+    pub(crate) async fn chunk(&mut self) -> Result<Option<Vec<u8>>, reqwest::Error> {
+        let Some(resp) = self.inner.as_mut() else {
+            return Ok(None);
+        };
+        match resp.chunk().await {
+            Ok(Some(chunk)) => {
+                self.entry.response_bytes += chunk.len() as u64;
+                Ok(Some(chunk.to_vec()))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                self.entry.error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// 读取完整响应体
+    pub(crate) async fn bytes(mut self) -> Result<Vec<u8>, reqwest::Error> {
+        let Some(resp) = self.inner.take() else {
+            return Ok(Vec::new());
+        };
+        match resp.bytes().await {
+            Ok(bytes) => {
+                self.entry.response_bytes += bytes.len() as u64;
+                Ok(bytes.to_vec())
+            }
+            Err(e) => {
+                self.entry.error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) async fn text(self) -> Result<String, reqwest::Error> {
+        let bytes = self.bytes().await?;
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    pub(crate) async fn json<T: serde::de::DeserializeOwned>(self) -> Result<T, String> {
+        let bytes = self.bytes().await.map_err(|e| e.to_string())?;
+        serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+impl Drop for AuditedResponse {
+    fn drop(&mut self) {
+        record(self.entry.clone());
+    }
+}
+
+// ============================================================================
+// 演示模式
+// ============================================================================
+
+/// 演示模式的模拟总结：不发出网络请求，按原文开头生成固定格式、明确标注的假总结，
+/// 并写入一条 `demo` 审计记录
+pub(crate) fn demo_summary(ctx: &ApiCallContext, source: &str, language: &str) -> String {
+    let collapsed = source.split_whitespace().collect::<Vec<_>>().join(" ");
+    let total_chars = collapsed.chars().count();
+    let mut excerpt: String = collapsed.chars().take(DEMO_EXCERPT_CHARS).collect();
+    if total_chars > DEMO_EXCERPT_CHARS {
+        excerpt.push('…');
+    }
+    let paragraphs = source.lines().filter(|l| !l.trim().is_empty()).count();
+
+    let summary = if language.starts_with("zh") {
+        format!(
+            "【演示模式 · 非 AI 生成】\n\n## 摘要\n{}\n\n## 要点\n- 原文约 {} 个字符，{} 段\n- 以上内容直接截取自原文开头，仅用于演示总结流程\n\n配置 API Key 后即可获得真实总结。",
+            excerpt, total_chars, paragraphs
+        )
+    } else {
+        format!(
+            "[Demo mode · not AI-generated]\n\n## Summary\n{}\n\n## Key points\n- About {} characters in {} paragraphs\n- The text above is copied from the beginning of the source to demonstrate the flow\n\nConfigure an API key to get real summaries.",
+            excerpt, total_chars, paragraphs
+        )
+    };
+
+    let mut entry = new_entry(ctx, "POST", DEMO_ENDPOINT, "");
+    entry.key_hint = DEMO_ENDPOINT.to_string();
+    entry.request_bytes = source.len() as u64;
+    entry.response_bytes = summary.len() as u64;
+    entry.status = Some(200);
+    entry.demo = true;
+    record(entry);
+    summary
+}
+
+// ============================================================================
+// 审计记录存储
+// ============================================================================
+
+fn load_entries() -> Vec<ApiAuditEntry> {
+    let path = crate::get_api_audit_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<ApiAuditEntry>>(&content) {
+        Ok(mut entries) => {
+            crate::keep_newest_records(&mut entries);
+            entries
+        }
+        Err(e) => {
+            println!("[api_audit] 解析失败，重新开始记录: {}", e);
+            crate::quarantine_corrupt_file(&path);
+            Vec::new()
+        }
+    }
+}
+
+fn record(entry: ApiAuditEntry) {
+    println!(
+        "[api_audit] {} {} {} status={:?} req={}B resp={}B",
+        entry.feature.as_str(),
+        entry.method,
+        entry.endpoint,
+        entry.status,
+        entry.request_bytes,
+        entry.response_bytes
+    );
+    {
+        let mut log = AUDIT_LOG.lock().unwrap();
+        log.push(entry);
+        crate::keep_newest_records(&mut log);
+    }
+    schedule_persist();
+}
+
+/// 安排一次后台落盘：记录在 AuditedResponse 释放时（可能在 tokio 工作线程上）产生，
+/// 写文件与 fsync 交给阻塞线程池；已有待执行的落盘时直接复用
+fn schedule_persist() {
+    if PERSIST_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn_blocking(persist);
+}
+
+fn persist() {
+    let _guard = PERSIST_LOCK.lock().unwrap();
+    PERSIST_SCHEDULED.store(false, Ordering::SeqCst);
+    // 只读实例只保留内存中的记录
+    if crate::ensure_storage_writable().is_err() {
+        return;
+    }
+    let path = crate::get_api_audit_path();
+    // 只在序列化期间持有 AUDIT_LOG，写文件时不阻塞新的记录
+    let content = match serde_json::to_vec(&*AUDIT_LOG.lock().unwrap()) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("[api_audit] 序列化失败: {}", e);
+            return;
+        }
+    };
+    let result = crate::file_lock::acquire_file_lock(&path)
+        .and_then(|_lock| crate::write_file_atomic(&path, &content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("[api_audit] 写入失败: {}", e);
+    }
+}
+
+/// 站点被标记为隐私后，把已有记录中的站点与相关 Tab 替换为占位符
+pub(crate) fn pseudonymize_site(site_id: &str, tab_ids: &[String]) {
+    let mut log = AUDIT_LOG.lock().unwrap();
+    let mut changed = false;
+    for entry in log.iter_mut() {
+        let site_match = entry.site_id.as_deref() == Some(site_id);
+        let tab_match = entry
+            .tab_id
+            .as_ref()
+            .is_some_and(|tab| tab_ids.contains(tab));
+        if site_match || tab_match {
+            entry.site_id = Some(PRIVATE_PLACEHOLDER.to_string());
+            entry.tab_id = Some(PRIVATE_PLACEHOLDER.to_string());
+            changed = true;
+        }
+    }
+    drop(log);
+    if changed {
+        schedule_persist();
+    }
+}
+
+/// 返回 `since` 之后的记录（新的在前）
+pub(crate) fn entries_since(since: u64) -> Vec<ApiAuditEntry> {
+    let log = AUDIT_LOG.lock().unwrap();
+    log.iter()
+        .rev()
+        .filter(|e| e.ts >= since)
+        .cloned()
+        .collect()
+}
+
+/// 最近 `days` 天的每日汇总（新的在前）
+pub(crate) fn daily_rollup(days: u64) -> Vec<ApiAuditDaily> {
+    let since = crate::now_ts().saturating_sub(days * 86_400);
+    let mut by_day: BTreeMap<String, ApiAuditDaily> = BTreeMap::new();
+    for entry in entries_since(since) {
+        let date = crate::time::Timestamp::from_secs(entry.ts).utc_date();
+        let daily = by_day.entry(date.clone()).or_insert_with(|| ApiAuditDaily {
+            date,
+            calls: 0,
+            failed_calls: 0,
+            request_bytes: 0,
+            response_bytes: 0,
+            by_feature: BTreeMap::new(),
+        });
+        daily.calls += 1;
+        let failed = entry.error.is_some() || entry.status.is_none_or(|s| s >= 400);
+        if failed {
+            daily.failed_calls += 1;
+        }
+        daily.request_bytes += entry.request_bytes;
+        daily.response_bytes += entry.response_bytes;
+        *daily
+            .by_feature
+            .entry(entry.feature.as_str().to_string())
+            .or_default() += 1;
+    }
+    by_day.into_values().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demo_summary_is_labeled_deterministic_and_audited() {
+        crate::use_temp_config_dir();
+        let source = format!("第一段 {}\n\n第二段", "字".repeat(200));
+        let ctx =
+            ApiCallContext::new(ApiFeature::ManualSummarize).with_site(Some("demo_site".into()));
+
+        let zh = demo_summary(&ctx, &source, "zh-CN");
+        assert!(zh.starts_with("【演示模式 · 非 AI 生成】"));
+        assert_eq!(zh, demo_summary(&ctx, &source, "zh-CN"));
+        // 截取开头 DEMO_EXCERPT_CHARS 个字符（空白折叠）并标注省略
+        let excerpt: String = "第一段 字"
+            .chars()
+            .chain(std::iter::repeat_n('字', 155))
+            .collect();
+        assert!(zh.contains(&format!("{}…", excerpt)));
+        assert!(zh.contains("原文约 208 个字符，2 段"));
+
+        let en = demo_summary(&ctx, "short text", "en");
+        assert!(en.starts_with("[Demo mode · not AI-generated]"));
+        assert!(en.contains("## Summary\nshort text\n"));
+
+        let log = AUDIT_LOG.lock().unwrap();
+        let entry = log
+            .iter()
+            .rev()
+            .find(|e| e.site_id.as_deref() == Some("demo_site"))
+            .unwrap();
+        assert!(entry.demo);
+        assert_eq!(entry.endpoint, DEMO_ENDPOINT);
+        assert_eq!(entry.key_hint, DEMO_ENDPOINT);
+    }
+
+    #[test]
+    fn audit_records_are_kept_by_count_and_persisted_in_the_background() {
+        crate::use_temp_config_dir();
+        let ctx =
+            ApiCallContext::new(ApiFeature::ManualSummarize).with_site(Some("persist_site".into()));
+        let mut entry = new_entry(&ctx, "POST", "https://api.example.com/v1/chat", "");
+        // 与用量记录一致只按条数保留，很久以前的记录不会被按时间裁掉
+        entry.ts = 1;
+        record(entry);
+        assert!(AUDIT_LOG
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| e.site_id.as_deref() == Some("persist_site") && e.ts == 1));
+
+        persist();
+        let saved: Vec<ApiAuditEntry> =
+            serde_json::from_slice(&std::fs::read(crate::get_api_audit_path()).unwrap()).unwrap();
+        assert!(saved
+            .iter()
+            .any(|e| e.site_id.as_deref() == Some("persist_site")));
+    }
+
+    #[test]
+    #[should_panic(expected = "测试中不允许发出网络请求")]
+    fn network_is_unreachable_in_tests() {
+        client();
+    }
+
+    fn built_headers(auth: ApiAuth<'_>) -> reqwest::header::HeaderMap {
+        // 只构造请求、不发送，不经过 client()
+        auth.apply(reqwest::Client::new().get("http://localhost:11434/v1/models"))
+            .build()
+            .unwrap()
+            .headers()
+            .clone()
+    }
+
+    #[test]
+    fn keyless_auth_sends_no_credentials() {
+        let headers = built_headers(ApiAuth::None);
+        assert!(headers.get(AUTHORIZATION).is_none());
+        assert!(headers.get("api-key").is_none());
+        assert!(headers.get("x-api-key").is_none());
+        assert_eq!(ApiAuth::None.key(), "");
+
+        let headers = built_headers(ApiAuth::Bearer("sk-1"));
+        assert_eq!(headers[AUTHORIZATION], "Bearer sk-1");
+    }
+
+    #[test]
+    fn azure_auth_uses_api_key_header() {
+        let headers = built_headers(ApiAuth::Azure("azure-key"));
+        assert_eq!(headers["api-key"], "azure-key");
+        assert!(headers.get(AUTHORIZATION).is_none());
+
+        let headers = built_headers(ApiAuth::Anthropic("sk-ant"));
+        assert_eq!(headers["x-api-key"], "sk-ant");
+        assert_eq!(headers["anthropic-version"], ANTHROPIC_VERSION);
+    }
+}
@@ -0,0 +1,368 @@
+// ============================================================================
+// 本地自动化 API（默认关闭）
+// ============================================================================
+//
+// 供命令行脚本等外部工具驱动 AI Hub 的最小 JSON API：
+// - 仅绑定 127.0.0.1，端口可配置（`automation_api.port`）
+// - 每次启动生成随机 token，写入配置目录下的 `automation_token`（unix 下权限 0600），
+//   所有请求必须携带 `Authorization: Bearer <token>`
+// - 带 `Origin` 头的请求（浏览器发起）一律拒绝，防止网页通过 DNS rebinding/CSRF 调用
+//
+// 安全提示：开启后，本机上任何能读取 token 文件的进程都可以切换视图、创建 Tab、
+// 触发总结（会使用已配置的 API Key 调用 AI 服务）并读取项目总结。
+// 这会扩大攻击面，只在需要时开启。
+//
+// 路由（均返回 `{"ok": bool, "data"?: ..., "error"?: string}`）：
+// - GET  /v1/sites                      站点列表（展示顺序）
+// - POST /v1/view            {site_id}  切换到站点主 Tab
+// - POST /v1/tabs            {site_id}  创建 Tab，返回 tab_id
+// - POST /v1/tabs/{tab_id}/summarize    切换到 Tab 并启动总结任务，返回 job_id
+// - GET  /v1/jobs/{job_id}              查询任务状态
+// - GET  /v1/projects/{id}/summary      获取项目总结
+// - DELETE /v1/projects/{id}            删除项目
+// - POST /v1/sites/{id}/clear-cache     清除站点缓存与登录数据
+// - DELETE /v1/sites/{id}               删除自定义站点
+//
+// 破坏性操作（最后三个）首次调用返回 428 与确认 token，需在有效期内携带
+// `{"confirm_token": "..."}` 再次调用才会执行
+
+use std::convert::Infallible;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, ORIGIN};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// 请求体大小上限
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+struct RunningServer {
+    generation: u64,
+    port: u16,
+    cancel: CancellationToken,
+}
+
+static SERVER: Lazy<Mutex<Option<RunningServer>>> = Lazy::new(|| Mutex::new(None));
+static LAST_ERROR: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AutomationApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: Option<u16>,
+    pub token_path: String,
+    pub error: Option<String>,
+}
+
+pub(crate) fn status(enabled: bool) -> AutomationApiStatus {
+    let port = SERVER.lock().unwrap().as_ref().map(|s| s.port);
+    AutomationApiStatus {
+        enabled,
+        running: port.is_some(),
+        port,
+        token_path: crate::get_automation_token_path().display().to_string(),
+        error: LAST_ERROR.lock().unwrap().clone(),
+    }
+}
+
+/// 启动服务（已在运行时先停止）。绑定失败会同步返回错误。
+pub(crate) fn start(app: &tauri::AppHandle, port: u16) -> Result<(), String> {
+    stop();
+    let result = start_inner(app, port);
+    *LAST_ERROR.lock().unwrap() = result.as_ref().err().cloned();
+    result
+}
+
+fn start_inner(app: &tauri::AppHandle, port: u16) -> Result<(), String> {
+    if port == 0 {
+        return Err("端口无效".to_string());
+    }
+    let listener = std::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+        .map_err(|e| format!("绑定 127.0.0.1:{} 失败: {}", port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("设置非阻塞失败: {}", e))?;
+
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    write_token_file(&crate::get_automation_token_path(), &token)?;
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let cancel = CancellationToken::new();
+    *SERVER.lock().unwrap() = Some(RunningServer {
+        generation,
+        port,
+        cancel: cancel.clone(),
+    });
+    println!("[automation] 已启动: 127.0.0.1:{}", port);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(app, listener, Arc::new(token), generation, cancel).await {
+            println!("[automation] 服务异常退出: {}", e);
+            *LAST_ERROR.lock().unwrap() = Some(e);
+            let mut server = SERVER.lock().unwrap();
+            if server.as_ref().is_some_and(|s| s.generation == generation) {
+                *server = None;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// 停止服务并删除 token 文件
+pub(crate) fn stop() {
+    if let Some(server) = SERVER.lock().unwrap().take() {
+        server.cancel.cancel();
+        let _ = std::fs::remove_file(crate::get_automation_token_path());
+        println!("[automation] 已停止");
+    }
+}
+
+fn is_current(generation: u64) -> bool {
+    SERVER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|s| s.generation == generation)
+}
+
+fn write_token_file(path: &Path, token: &str) -> Result<(), String> {
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("写入 token 文件失败: {}", e))?;
+    file.write_all(token.as_bytes())
+        .map_err(|e| format!("写入 token 文件失败: {}", e))
+}
+
+async fn serve(
+    app: tauri::AppHandle,
+    listener: std::net::TcpListener,
+    token: Arc<String>,
+    generation: u64,
+    cancel: CancellationToken,
+) -> Result<(), String> {
+    let listener =
+        tokio::net::TcpListener::from_std(listener).map_err(|e| format!("监听失败: {}", e))?;
+    loop {
+        let Some(accepted) = cancel.run_until_cancelled(listener.accept()).await else {
+            return Ok(());
+        };
+        let (stream, _) = match accepted {
+            Ok(conn) => conn,
+            Err(e) => {
+                println!("[automation] accept 失败: {}", e);
+                continue;
+            }
+        };
+        let app = app.clone();
+        let token = token.clone();
+        tauri::async_runtime::spawn(async move {
+            let service = hyper::service::service_fn(move |req| {
+                let app = app.clone();
+                let token = token.clone();
+                async move { Ok::<_, Infallible>(handle(app, &token, generation, req).await) }
+            });
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                println!("[automation] 连接错误: {}", e);
+            }
+        });
+    }
+}
+
+type ApiResult = Result<serde_json::Value, (StatusCode, String)>;
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Full<Bytes>> {
+    let mut resp = Response::new(Full::new(Bytes::from(body.to_string())));
+    *resp.status_mut() = status;
+    resp.headers_mut().insert(
+        CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json; charset=utf-8"),
+    );
+    resp
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response<Full<Bytes>> {
+    json_response(status, serde_json::json!({ "ok": false, "error": error }))
+}
+
+/// 常量时间比较，避免通过响应时间猜测 token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn is_authorized(req: &Request<Incoming>, token: &str) -> bool {
+    req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|provided| constant_time_eq(provided.trim().as_bytes(), token.as_bytes()))
+}
+
+/// 内部错误映射为 HTTP 状态：锁定模式 → 403，需要确认 → 428，其余视为请求错误；
+/// 响应正文沿用带前缀的字符串格式
+fn command_error(e: impl Into<AppError>) -> (StatusCode, String) {
+    let e = e.into();
+    let status = match e {
+        AppError::Locked(_) => StatusCode::FORBIDDEN,
+        AppError::ConfirmationRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, e.to_string())
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &[u8]) -> Result<T, (StatusCode, String)> {
+    serde_json::from_slice(body).map_err(|e| (StatusCode::BAD_REQUEST, format!("请求体无效: {}", e)))
+}
+
+#[derive(Deserialize)]
+struct SiteRequest {
+    site_id: String,
+}
+
+#[derive(Deserialize, Default)]
+struct ConfirmRequest {
+    #[serde(default)]
+    confirm_token: Option<String>,
+}
+
+/// 破坏性操作的请求体可以为空（首次调用获取确认 token）
+fn parse_confirm_body(body: &[u8]) -> Result<ConfirmRequest, (StatusCode, String)> {
+    if body.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(ConfirmRequest::default());
+    }
+    parse_body(body)
+}
+
+fn authorize_destructive(command: &str, target: &str, body: &[u8]) -> Result<(), (StatusCode, String)> {
+    let req = parse_confirm_body(body)?;
+    crate::permissions::authorize(
+        crate::permissions::InvokeSurface::AutomationApi,
+        command,
+        target,
+        req.confirm_token.as_deref(),
+    )
+    .map_err(command_error)
+}
+
+async fn handle(
+    app: tauri::AppHandle,
+    token: &str,
+    generation: u64,
+    req: Request<Incoming>,
+) -> Response<Full<Bytes>> {
+    if !is_current(generation) {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "自动化 API 已停止");
+    }
+    if req.headers().contains_key(ORIGIN) {
+        return error_response(StatusCode::FORBIDDEN, "不接受浏览器发起的请求");
+    }
+    if !is_authorized(&req, token) {
+        return error_response(StatusCode::UNAUTHORIZED, "缺少或错误的 token");
+    }
+
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let body = match Limited::new(req.into_body(), MAX_BODY_BYTES).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(_) => return error_response(StatusCode::PAYLOAD_TOO_LARGE, "请求体过大"),
+    };
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match route(app, &method, &segments, &body).await {
+        Ok(data) => json_response(StatusCode::OK, serde_json::json!({ "ok": true, "data": data })),
+        Err((status, error)) => error_response(status, &error),
+    }
+}
+
+async fn route(app: tauri::AppHandle, method: &Method, segments: &[&str], body: &[u8]) -> ApiResult {
+    match (method, segments) {
+        (&Method::GET, ["v1", "sites"]) => {
+            let config = crate::APP_CONFIG.lock().unwrap().clone();
+            let sites: Vec<serde_json::Value> = crate::ordered_site_ids(&config)
+                .iter()
+                .filter_map(|id| config.sites.iter().find(|s| &s.id == id))
+                .map(|s| crate::localized_site(s, &config.language))
+                .map(|s| serde_json::json!({ "id": s.id, "name": s.name, "url": s.url }))
+                .collect();
+            Ok(serde_json::json!(sites))
+        }
+        (&Method::POST, ["v1", "view"]) => {
+            let req: SiteRequest = parse_body(body)?;
+            crate::switch_view_inner(app, req.site_id)
+                .await
+                .map_err(command_error)?;
+            Ok(serde_json::json!({}))
+        }
+        (&Method::POST, ["v1", "tabs"]) => {
+            let req: SiteRequest = parse_body(body)?;
+            let tab_id = crate::create_tab_inner(&req.site_id).map_err(command_error)?;
+            Ok(serde_json::json!({ "tab_id": tab_id }))
+        }
+        (&Method::POST, ["v1", "tabs", tab_id, "summarize"]) => {
+            crate::ensure_summarize_allowed().map_err(command_error)?;
+            crate::switch_tab_inner(app.clone(), tab_id.to_string())
+                .await
+                .map_err(command_error)?;
+            let job_id = crate::trigger_summarize_active_tab(&app, None);
+            Ok(serde_json::json!({ "job_id": job_id }))
+        }
+        (&Method::GET, ["v1", "jobs", job_id]) => crate::jobs::get_job(job_id)
+            .map(|job| serde_json::json!(job))
+            .ok_or_else(|| (StatusCode::NOT_FOUND, "任务不存在".to_string())),
+        (&Method::GET, ["v1", "projects", project_id, "summary"]) => crate::load_live_project(project_id)
+            .map(|p| {
+                serde_json::json!({
+                    "id": p.id,
+                    "title": p.title,
+                    "summary": p.summary,
+                    "updated_at": p.updated_at,
+                })
+            })
+            .ok_or_else(|| (StatusCode::NOT_FOUND, "项目不存在".to_string())),
+        (&Method::DELETE, ["v1", "projects", project_id]) => {
+            authorize_destructive("delete_project", project_id, body)?;
+            crate::delete_project_inner(project_id).map_err(command_error)?;
+            Ok(serde_json::json!({}))
+        }
+        (&Method::POST, ["v1", "sites", site_id, "clear-cache"]) => {
+            authorize_destructive("clear_view_cache", site_id, body)?;
+            crate::clear_view_cache_inner(&app, site_id, None)
+                .await
+                .map_err(command_error)?;
+            Ok(serde_json::json!({}))
+        }
+        (&Method::DELETE, ["v1", "sites", site_id]) => {
+            authorize_destructive("remove_site", site_id, body)?;
+            crate::remove_site_inner(&app, site_id.to_string(), false).map_err(command_error)?;
+            Ok(serde_json::json!({}))
+        }
+        _ => Err((StatusCode::NOT_FOUND, "未知接口".to_string())),
+    }
+}
@@ -0,0 +1,433 @@
+// ============================================================================
+// 浏览器书签导入（Netscape Bookmark File 格式）
+// ============================================================================
+//
+// Chrome / Edge / Firefox / Safari 导出的书签 HTML 都是这种格式：
+//   <DL><p>
+//     <DT><H3 ...>文件夹</H3>
+//     <DL><p>
+//       <DT><A HREF="https://..." ICON="data:image/png;base64,...">标题</A>
+//     </DL><p>
+//   </DL><p>
+// 浏览器输出的 HTML 并不规范（<DT>/<p> 不闭合、属性大小写混用、偶尔缺少 </A>），
+// 因此不按 DOM 解析，只顺序扫描标签：<H3> 给出文件夹名，紧随其后的 <DL> 即该文件夹的内容。
+// 只负责提取标题与地址；URL 是否可用、是否与已有站点重复由调用方判断。
+
+/// 书签图标（data URL）的大小上限，超出时使用默认图标
+const MAX_ICON_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Bookmark {
+    pub(crate) title: String,
+    pub(crate) url: String,
+    /// 书签自带的 `data:image/...` 图标
+    pub(crate) icon: Option<String>,
+}
+
+/// 提取书签；`folder` 不为空时只取该文件夹（含子文件夹）下的书签，文件夹名不区分大小写
+pub(crate) fn parse(html: &str, folder: Option<&str>) -> Result<Vec<Bookmark>, String> {
+    let folder = folder.map(str::trim).filter(|f| !f.is_empty());
+    let mut bookmarks = Vec::new();
+    // 每层 <DL> 对应的文件夹名（根层为 None）
+    let mut folder_stack: Vec<Option<String>> = Vec::new();
+    let mut pending_folder: Option<String> = None;
+    let mut heading: Option<String> = None;
+    let mut link: Option<OpenLink> = None;
+    let mut folder_found = folder.is_none();
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => {
+                if let Some(link) = link.as_mut() {
+                    link.title.push_str(text);
+                } else if let Some(heading) = heading.as_mut() {
+                    heading.push_str(text);
+                }
+            }
+            Token::Open { name, attrs } => {
+                // 缺少 </A> 时，下一个结构性标签即视为链接结束
+                if matches!(name.as_str(), "a" | "dt" | "dd" | "dl" | "h3") {
+                    if let Some(open) = link.take() {
+                        push_bookmark(&mut bookmarks, open, &folder_stack, folder);
+                    }
+                }
+                match name.as_str() {
+                    "h3" => heading = Some(String::new()),
+                    "dl" => folder_stack.push(pending_folder.take()),
+                    "a" => {
+                        link = attr(&attrs, "href").map(|href| OpenLink {
+                            url: decode_entities(href).trim().to_string(),
+                            icon: attr(&attrs, "icon").map(str::to_string),
+                            title: String::new(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            Token::Close(name) => match name.as_str() {
+                "a" => {
+                    if let Some(open) = link.take() {
+                        push_bookmark(&mut bookmarks, open, &folder_stack, folder);
+                    }
+                }
+                "h3" => {
+                    let name = heading
+                        .take()
+                        .map(|h| collapse_whitespace(&decode_entities(&h)));
+                    if let (Some(target), Some(name)) = (folder, name.as_deref()) {
+                        folder_found |= same_folder(name, target);
+                    }
+                    pending_folder = name;
+                }
+                "dl" => {
+                    folder_stack.pop();
+                }
+                _ => {}
+            },
+        }
+    }
+    if let Some(open) = link.take() {
+        push_bookmark(&mut bookmarks, open, &folder_stack, folder);
+    }
+
+    if !folder_found {
+        return Err(format!(
+            "书签中没有名为“{}”的文件夹",
+            folder.unwrap_or_default()
+        ));
+    }
+    Ok(bookmarks)
+}
+
+struct OpenLink {
+    url: String,
+    icon: Option<String>,
+    title: String,
+}
+
+fn push_bookmark(
+    bookmarks: &mut Vec<Bookmark>,
+    link: OpenLink,
+    folder_stack: &[Option<String>],
+    folder: Option<&str>,
+) {
+    if let Some(target) = folder {
+        let inside = folder_stack
+            .iter()
+            .flatten()
+            .any(|name| same_folder(name, target));
+        if !inside {
+            return;
+        }
+    }
+    let icon = link
+        .icon
+        .filter(|icon| icon.starts_with("data:image/") && icon.len() <= MAX_ICON_BYTES);
+    bookmarks.push(Bookmark {
+        title: collapse_whitespace(&decode_entities(&link.title)),
+        url: link.url,
+        icon,
+    });
+}
+
+fn same_folder(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+// ============================================================================
+// 宽松的标签扫描
+// ============================================================================
+
+enum Token<'a> {
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    Close(String),
+    Text(&'a str),
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map(|i| &after[i + 3..]).unwrap_or("");
+            continue;
+        }
+        let starts_tag = rest.starts_with('<')
+            && rest[1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        if !starts_tag {
+            // 不是标签的 `<`（如标题中的 “a < b”）按文本处理
+            let skip = if rest.starts_with('<') { 1 } else { 0 };
+            let end = rest[skip..]
+                .find('<')
+                .map(|i| i + skip)
+                .unwrap_or(rest.len());
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+        let Some(end) = tag_end(rest) else {
+            break;
+        };
+        if let Some(token) = parse_tag(&rest[1..end]) {
+            tokens.push(token);
+        }
+        rest = &rest[end + 1..];
+    }
+    tokens
+}
+
+/// 标签结束的 `>` 位置（跳过引号内的 `>`；引号不配对时退回第一个 `>`）
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote: Option<u8> = None;
+    for (i, b) in tag.bytes().enumerate().skip(1) {
+        match (quote, b) {
+            (Some(q), _) if b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(b),
+            (None, b'>') => return Some(i),
+            (None, _) => {}
+        }
+    }
+    tag.find('>')
+}
+
+fn parse_tag(inner: &str) -> Option<Token<'static>> {
+    if inner.starts_with('!') || inner.starts_with('?') {
+        return None;
+    }
+    if let Some(name) = inner.strip_prefix('/') {
+        return Some(Token::Close(tag_name(name.trim_start())));
+    }
+    let name = tag_name(inner);
+    let attrs = parse_attrs(&inner[name.len()..]);
+    Some(Token::Open { name, attrs })
+}
+
+fn tag_name(inner: &str) -> String {
+    inner
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// 属性名转小写；值支持双引号、单引号与无引号三种写法
+fn parse_attrs(mut rest: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            break;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        if name_end == 0 {
+            // 游离的 `=` 等字符
+            rest = &rest[1..];
+            continue;
+        }
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let Some(after_eq) = rest.strip_prefix('=') else {
+            attrs.push((name, String::new()));
+            continue;
+        };
+        let after_eq = after_eq.trim_start();
+        let (value, remaining) = match after_eq.chars().next() {
+            Some(q @ ('"' | '\'')) => {
+                let body = &after_eq[1..];
+                match body.find(q) {
+                    Some(end) => (&body[..end], &body[end + 1..]),
+                    None => (body, ""),
+                }
+            }
+            _ => {
+                let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                (&after_eq[..end], &after_eq[end..])
+            }
+        };
+        attrs.push((name, value.to_string()));
+        rest = remaining;
+    }
+    attrs
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// 解码常见实体与数字字符引用；无法识别的实体原样保留
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let ch = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    _ => entity.strip_prefix('#').and_then(|num| {
+                        let code = match num.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                            None => num.parse::<u32>().ok(),
+                        };
+                        code.and_then(char::from_u32)
+                    }),
+                };
+                ch.map(|ch| (ch, end + 2))
+            });
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME: &str = include_str!("../tests/fixtures/bookmarks_chrome.html");
+    const MESSY: &str = include_str!("../tests/fixtures/bookmarks_messy.html");
+
+    fn pairs(bookmarks: &[Bookmark]) -> Vec<(&str, &str)> {
+        bookmarks
+            .iter()
+            .map(|b| (b.title.as_str(), b.url.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_a_chrome_export() {
+        let bookmarks = parse(CHROME, None).unwrap();
+        assert_eq!(
+            pairs(&bookmarks),
+            vec![
+                ("News", "https://news.example.com/"),
+                ("DeepSeek", "https://chat.deepseek.com/"),
+                ("Kimi & 月之暗面", "https://kimi.moonshot.cn/"),
+                ("Corp LLM", "https://llm.corp.example.com/chat?team=a&b=1"),
+                ("Bookmarklet", "javascript:alert(1)"),
+            ]
+        );
+        assert_eq!(
+            bookmarks[1].icon.as_deref(),
+            Some("data:image/png;base64,iVBORw0KGgo=")
+        );
+        assert_eq!(bookmarks[0].icon, None);
+    }
+
+    #[test]
+    fn folder_filter_includes_subfolders_and_ignores_case() {
+        let bookmarks = parse(CHROME, Some("  ai 工具 ")).unwrap();
+        assert_eq!(
+            pairs(&bookmarks),
+            vec![
+                ("DeepSeek", "https://chat.deepseek.com/"),
+                ("Kimi & 月之暗面", "https://kimi.moonshot.cn/"),
+                ("Corp LLM", "https://llm.corp.example.com/chat?team=a&b=1"),
+            ]
+        );
+        let internal = parse(CHROME, Some("INTERNAL")).unwrap();
+        assert_eq!(internal.len(), 1);
+        // 空白的文件夹名等同于不筛选
+        assert_eq!(parse(CHROME, Some("  ")).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn missing_folder_is_an_error() {
+        let err = parse(CHROME, Some("Nope")).unwrap_err();
+        assert!(err.contains("Nope"), "{}", err);
+    }
+
+    #[test]
+    fn tolerates_messy_browser_html() {
+        let bookmarks = parse(MESSY, None).unwrap();
+        assert_eq!(
+            pairs(&bookmarks),
+            vec![
+                ("Claude", "https://claude.ai/new"),
+                // 缺少 </A>：下一个 <DT> 结束链接
+                ("Gemini", "https://gemini.google.com/app"),
+                ("通义 Qwen", "https://chat.qwen.ai/"),
+                // 去重由调用方负责
+                ("Claude duplicate", "https://claude.ai/new"),
+                ("FTP", "ftp://files.example.com/"),
+                ("", "https://no-title.example.com/"),
+                ("Outside &unknown; entity", "https://outside.example.com/"),
+            ]
+        );
+        // 只保留 data URL 图标
+        assert_eq!(bookmarks[2].icon, None);
+
+        let folder = parse(MESSY, Some("AI Tools")).unwrap();
+        assert_eq!(folder.len(), 6);
+        assert!(folder
+            .iter()
+            .all(|b| b.url != "https://outside.example.com/"));
+    }
+
+    #[test]
+    fn oversized_icons_are_dropped() {
+        let icon = format!("data:image/png;base64,{}", "A".repeat(MAX_ICON_BYTES));
+        let html = format!(
+            "<DL><DT><A HREF=\"https://a.example.com/\" ICON=\"{}\">A</A></DL>",
+            icon
+        );
+        let bookmarks = parse(&html, None).unwrap();
+        assert_eq!(bookmarks[0].icon, None);
+    }
+
+    #[test]
+    fn truncated_or_empty_input_does_not_panic() {
+        assert_eq!(parse("", None).unwrap(), Vec::new());
+        assert_eq!(parse("plain text, no tags", None).unwrap(), Vec::new());
+        let truncated = &CHROME[..CHROME.find("Kimi").unwrap()];
+        let bookmarks = parse(truncated, None).unwrap();
+        assert_eq!(bookmarks.last().unwrap().url, "https://kimi.moonshot.cn/");
+        assert_eq!(
+            parse("<DL><DT><A HREF=\"https://x.example.com/", None).unwrap(),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#x41;&#66; &nbsp;&amp"),
+            "a <b> AB  &amp"
+        );
+        assert_eq!(decode_entities("&#xFFFFFFFF;"), "&#xFFFFFFFF;");
+    }
+}
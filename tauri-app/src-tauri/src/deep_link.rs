@@ -0,0 +1,286 @@
+// ============================================================================
+// aihub:// 深度链接
+// ============================================================================
+//
+// - `aihub://open?site=<site_id>`：切换到该站点（与侧边栏相同的流程）
+// - `aihub://summarize`：总结当前 Tab（与自动化 API 相同，以后台任务运行）
+// - 多余的查询参数忽略；格式错误或站点不存在时由调用方发送 `deep-link-error` 事件
+// - Windows/Linux 通过命令行参数传入链接（已有实例时经单实例通道转交），macOS 通过 RunEvent::Opened
+// - 启动时为当前可执行文件注册协议：Windows 写 HKCU\Software\Classes\aihub，
+//   Linux 写 ~/.local/share/applications 下的 .desktop 并用 xdg-mime 设为默认处理程序，
+//   macOS 由 Info.plist 的 CFBundleURLTypes 声明
+
+/// 协议名
+pub(crate) const SCHEME: &str = "aihub";
+
+/// site id 的长度上限（内置与自定义站点 id 都远小于此值）
+const MAX_SITE_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DeepLink {
+    Open { site_id: String },
+    Summarize,
+}
+
+/// 解析深度链接；只校验格式，站点是否存在由调用方对照配置检查
+pub(crate) fn parse(raw: &str) -> Result<DeepLink, String> {
+    let url = url::Url::parse(raw.trim()).map_err(|e| format!("链接格式无效: {}", e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("不支持的链接协议: {}", url.scheme()));
+    }
+    // `aihub://open` 的动作在 host 中；`aihub:open` 则在 path 中
+    let action = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| url.path())
+        .trim_matches('/')
+        .to_ascii_lowercase();
+    match action.as_str() {
+        "open" => {
+            let site_id = url
+                .query_pairs()
+                .find(|(key, _)| key == "site")
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .ok_or("链接缺少 site 参数")?;
+            let valid = site_id.len() <= MAX_SITE_ID_LEN
+                && site_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(format!("站点 id 无效: {}", site_id));
+            }
+            Ok(DeepLink::Open { site_id })
+        }
+        "summarize" => Ok(DeepLink::Summarize),
+        "" => Err("链接缺少动作（open / summarize）".to_string()),
+        other => Err(format!("未知的链接动作: {}", other)),
+    }
+}
+
+/// 命令行参数中的深度链接
+pub(crate) fn find_in_args(args: &[String]) -> Option<&str> {
+    args.iter()
+        .map(String::as_str)
+        .find(|arg| arg.starts_with(&format!("{}:", SCHEME)))
+}
+
+/// 把协议关联到当前可执行文件（失败只记录日志）
+pub(crate) fn register_scheme() {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            println!("[deep-link] 获取可执行文件路径失败: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = platform::register(&exe) {
+        println!("[deep-link] 注册 {}:// 协议失败: {}", SCHEME, e);
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::SCHEME;
+
+    /// 不为 reg.exe 弹出控制台窗口
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn reg_add(key: &str, name: Option<&str>, value: &str) -> Result<(), String> {
+        let mut command = Command::new("reg");
+        command.creation_flags(CREATE_NO_WINDOW).args(["add", key]);
+        match name {
+            Some(name) => command.args(["/v", name]),
+            None => command.arg("/ve"),
+        };
+        let status = command
+            .args(["/d", value, "/f"])
+            .output()
+            .map_err(|e| e.to_string())?
+            .status;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("reg add {} 失败", key))
+        }
+    }
+
+    /// 读取键的默认值（键不存在时为 None）
+    fn reg_query_default(key: &str) -> Option<String> {
+        let output = Command::new("reg")
+            .creation_flags(CREATE_NO_WINDOW)
+            .args(["query", key, "/ve"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // 输出形如 `    (默认)    REG_SZ    "C:\...\app.exe" "%1"`
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.split_once("REG_SZ"))
+            .map(|(_, value)| value.trim().to_string())
+    }
+
+    pub(super) fn register(exe: &Path) -> Result<(), String> {
+        let root = format!(r"HKCU\Software\Classes\{}", SCHEME);
+        let command_key = format!(r"{}\shell\open\command", root);
+        let command = format!("\"{}\" \"%1\"", exe.display());
+        // 已指向当前可执行文件时不再改写注册表
+        if reg_query_default(&command_key).as_deref() == Some(command.as_str()) {
+            return Ok(());
+        }
+        reg_add(&root, None, "URL:AI Hub")?;
+        reg_add(&root, Some("URL Protocol"), "")?;
+        reg_add(&command_key, None, &command)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::SCHEME;
+
+    const DESKTOP_FILE: &str = "aihub-url-handler.desktop";
+
+    pub(super) fn register(exe: &Path) -> Result<(), String> {
+        let dirs = directories::BaseDirs::new().ok_or("无法获取用户目录")?;
+        let applications = dirs.data_dir().join("applications");
+        std::fs::create_dir_all(&applications).map_err(|e| e.to_string())?;
+        let content = format!(
+            "[Desktop Entry]\nType=Application\nName=AI Hub\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+            exe.display(),
+            SCHEME
+        );
+        let path = applications.join(DESKTOP_FILE);
+        if std::fs::read_to_string(&path).ok().as_deref() != Some(content.as_str()) {
+            std::fs::write(&path, content).map_err(|e| e.to_string())?;
+        }
+        let mime = format!("x-scheme-handler/{}", SCHEME);
+        // 已是默认处理程序时不再调用 xdg-mime 改写 mimeapps.list
+        let current = Command::new("xdg-mime")
+            .args(["query", "default", &mime])
+            .output()
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+        if current.as_deref() == Some(DESKTOP_FILE) {
+            return Ok(());
+        }
+        Command::new("xdg-mime")
+            .args(["default", DESKTOP_FILE, &mime])
+            .output()
+            .map_err(|e| format!("xdg-mime 执行失败: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::Path;
+
+    /// macOS 由 Info.plist 声明，打包后即生效
+    pub(super) fn register(_exe: &Path) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+mod platform {
+    use std::path::Path;
+
+    pub(super) fn register(_exe: &Path) -> Result<(), String> {
+        Err("当前平台不支持".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(site_id: &str) -> DeepLink {
+        DeepLink::Open {
+            site_id: site_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_open_and_summarize_links() {
+        assert_eq!(parse("aihub://open?site=deepseek"), Ok(open("deepseek")));
+        assert_eq!(parse("aihub:open?site=deepseek"), Ok(open("deepseek")));
+        assert_eq!(
+            parse("  AIHUB://OPEN/?site=my_site-2  "),
+            Ok(open("my_site-2"))
+        );
+        assert_eq!(parse("aihub://summarize"), Ok(DeepLink::Summarize));
+        assert_eq!(parse("aihub://summarize/"), Ok(DeepLink::Summarize));
+    }
+
+    #[test]
+    fn ignores_extra_query_params() {
+        assert_eq!(
+            parse("aihub://open?from=notes&site=kimi&site=other&x"),
+            Ok(open("kimi"))
+        );
+        assert_eq!(
+            parse("aihub://summarize?site=kimi"),
+            Ok(DeepLink::Summarize)
+        );
+    }
+
+    #[test]
+    fn decodes_percent_encoded_site_ids_before_validating() {
+        assert_eq!(parse("aihub://open?site=%64eepseek"), Ok(open("deepseek")));
+        assert_eq!(parse("aihub://open?site=%20kimi%20"), Ok(open("kimi")));
+        // 解码后含路径分隔符或空白的 id 不能通过校验
+        assert!(parse("aihub://open?site=..%2Fetc").is_err());
+        assert!(parse("aihub://open?site=a%20b").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_links() {
+        assert!(parse("").is_err());
+        assert!(parse("not a url").is_err());
+        assert!(parse("https://open?site=deepseek").is_err());
+        assert!(parse("aihub://").is_err());
+        assert!(parse("aihub://delete?site=deepseek").is_err());
+        assert!(parse(&format!(
+            "aihub://open?site={}",
+            "a".repeat(MAX_SITE_ID_LEN + 1)
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn open_requires_a_non_empty_site_param() {
+        assert!(parse("aihub://open").is_err());
+        assert!(parse("aihub://open?site=").is_err());
+        assert!(parse("aihub://open?site=%20").is_err());
+        assert!(parse("aihub://open?other=deepseek").is_err());
+    }
+
+    #[test]
+    fn finds_the_link_among_launch_args() {
+        let args = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let launch = args(&["/path/to/app", "--flag", "aihub://open?site=kimi"]);
+        assert_eq!(find_in_args(&launch), Some("aihub://open?site=kimi"));
+        assert_eq!(
+            find_in_args(&args(&["/path/to/app", "aihub:summarize"])),
+            Some("aihub:summarize")
+        );
+        assert_eq!(
+            find_in_args(&args(&["/path/to/app", "https://example.com"])),
+            None
+        );
+        assert_eq!(
+            find_in_args(&args(&["/path/to/app", "aihubx://open"])),
+            None
+        );
+        assert_eq!(find_in_args(&[]), None);
+    }
+}
@@ -0,0 +1,328 @@
+// ============================================================================
+// 子页面下载
+// ============================================================================
+//
+// - 站点页面发起的下载统一保存到下载目录（`AppConfig.download_dir`，默认系统下载文件夹）
+// - 文件名只取页面建议名的最后一段并去掉非法字符，防止路径穿越；重名时追加 " (1)"、" (2)"…
+// - Webview 只通知开始与结束，进度由定时检查目标文件大小得出（总大小未知）
+// - 最近的下载只保存在内存中，供下载栏展示与打开
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::Emitter;
+use uuid::Uuid;
+
+use crate::time::Timestamp;
+
+/// 保留的最近下载条数
+const MAX_RECENT_DOWNLOADS: usize = 50;
+
+/// 检查下载进度的间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 文件名的字符上限（不含重名序号）
+const MAX_FILE_NAME_CHARS: usize = 200;
+
+static RECENT: Lazy<Mutex<VecDeque<DownloadRecord>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DownloadState {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DownloadRecord {
+    pub(crate) id: String,
+    pub(crate) tab_id: String,
+    pub(crate) site_id: String,
+    pub(crate) url: String,
+    pub(crate) file_name: String,
+    pub(crate) path: String,
+    /// 已写入的字节数
+    pub(crate) bytes: u64,
+    pub(crate) state: DownloadState,
+    pub(crate) started_at: Timestamp,
+    pub(crate) finished_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgressPayload {
+    id: String,
+    tab_id: String,
+    file_name: String,
+    bytes: u64,
+}
+
+/// 页面发起下载：确定保存位置并开始跟踪进度，返回目标路径
+pub(crate) fn start(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    site_id: &str,
+    url: &tauri::Url,
+    suggested: &Path,
+    dir: &Path,
+) -> PathBuf {
+    let suggested_name = suggested
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_string))
+        })
+        .unwrap_or_default();
+    let file_name = sanitize_file_name(&suggested_name);
+
+    let record = {
+        let mut recent = RECENT.lock().unwrap();
+        // 在持锁时选定路径，避免同名下载同时开始时选到同一个文件
+        let path = unique_path(dir, &file_name, &recent);
+        let record = DownloadRecord {
+            id: Uuid::new_v4().to_string(),
+            tab_id: tab_id.to_string(),
+            site_id: site_id.to_string(),
+            url: url.to_string(),
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(file_name),
+            path: path.display().to_string(),
+            bytes: 0,
+            state: DownloadState::InProgress,
+            started_at: Timestamp::now(),
+            finished_at: None,
+        };
+        recent.push_front(record.clone());
+        recent.truncate(MAX_RECENT_DOWNLOADS);
+        record
+    };
+    println!("[download] 开始 tab_id={} path={}", tab_id, record.path);
+    let _ = app.emit("download-started", &record);
+    spawn_progress_watch(app.clone(), record.id.clone());
+    PathBuf::from(record.path)
+}
+
+/// 下载结束；`path` 在部分平台（macOS）上为空，此时使用开始时选定的路径
+pub(crate) fn finish(
+    app: &tauri::AppHandle,
+    url: &tauri::Url,
+    path: Option<PathBuf>,
+    success: bool,
+) {
+    let record = {
+        let mut recent = RECENT.lock().unwrap();
+        let url = url.to_string();
+        let Some(record) = recent
+            .iter_mut()
+            .find(|r| r.state == DownloadState::InProgress && r.url == url)
+        else {
+            return;
+        };
+        if let Some(path) = path.filter(|p| !p.as_os_str().is_empty()) {
+            record.path = path.display().to_string();
+        }
+        record.bytes = std::fs::metadata(&record.path)
+            .map(|m| m.len())
+            .unwrap_or(record.bytes);
+        record.state = if success {
+            DownloadState::Completed
+        } else {
+            DownloadState::Failed
+        };
+        record.finished_at = Some(Timestamp::now());
+        record.clone()
+    };
+    println!(
+        "[download] 结束 tab_id={} path={} success={}",
+        record.tab_id, record.path, success
+    );
+    let _ = app.emit("download-finished", &record);
+}
+
+/// 最近的下载（新的在前）
+pub(crate) fn recent() -> Vec<DownloadRecord> {
+    RECENT.lock().unwrap().iter().cloned().collect()
+}
+
+/// `path` 是否为已完成的下载（只允许打开由本应用下载的文件）
+pub(crate) fn is_completed_download(path: &Path) -> bool {
+    RECENT
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|r| r.state == DownloadState::Completed && Path::new(&r.path) == path)
+}
+
+fn spawn_progress_watch(app: tauri::AppHandle, id: String) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(PROGRESS_INTERVAL).await;
+            let payload = {
+                let mut recent = RECENT.lock().unwrap();
+                let Some(record) = recent
+                    .iter_mut()
+                    .find(|r| r.id == id && r.state == DownloadState::InProgress)
+                else {
+                    return;
+                };
+                let bytes = std::fs::metadata(&record.path)
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                if bytes == record.bytes {
+                    continue;
+                }
+                record.bytes = bytes;
+                DownloadProgressPayload {
+                    id: record.id.clone(),
+                    tab_id: record.tab_id.clone(),
+                    file_name: record.file_name.clone(),
+                    bytes,
+                }
+            };
+            let _ = app.emit("download-progress", payload);
+        }
+    });
+}
+
+/// 只保留文件名本身：去掉目录部分、路径分隔符与各平台不允许的字符，避开 Windows 保留名
+fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .filter(|c| !c.is_control() && !matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*'))
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    let mut name: String = cleaned.chars().take(MAX_FILE_NAME_CHARS).collect();
+    if name.is_empty() {
+        name = "download".to_string();
+    }
+    let stem = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.ends_with(|c: char| c.is_ascii_digit()));
+    if reserved {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// 目录中未被占用（含进行中的下载）的路径：`name.ext`、`name (1).ext`、`name (2).ext`…
+fn unique_path(dir: &Path, file_name: &str, recent: &VecDeque<DownloadRecord>) -> PathBuf {
+    let taken = |path: &Path| {
+        path.exists()
+            || recent
+                .iter()
+                .any(|r| r.state == DownloadState::InProgress && Path::new(&r.path) == path)
+    };
+    let candidate = dir.join(file_name);
+    if !taken(&candidate) {
+        return candidate;
+    }
+    let (stem, ext) = match file_name.rfind('.') {
+        Some(i) if i > 0 => (&file_name[..i], &file_name[i..]),
+        _ => (file_name, ""),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| !taken(path))
+        .unwrap_or(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_at(path: &Path, state: DownloadState) -> DownloadRecord {
+        DownloadRecord {
+            id: "d1".to_string(),
+            tab_id: "t1".to_string(),
+            site_id: "s1".to_string(),
+            url: "https://example.com/report.pdf".to_string(),
+            file_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            path: path.display().to_string(),
+            bytes: 0,
+            state,
+            started_at: Timestamp::from_secs(0),
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn sanitize_file_name_strips_directories_and_reserved_names() {
+        assert_eq!(sanitize_file_name("../../x"), "x");
+        assert_eq!(sanitize_file_name("..\\..\\x"), "x");
+        assert_eq!(sanitize_file_name("C:\\evil"), "evil");
+        assert_eq!(sanitize_file_name("C:evil"), "Cevil");
+        assert_eq!(sanitize_file_name(".."), "download");
+        assert_eq!(sanitize_file_name(""), "download");
+        assert_eq!(sanitize_file_name("  \t "), "download");
+        assert_eq!(sanitize_file_name("CON.txt"), "_CON.txt");
+        assert_eq!(sanitize_file_name("com1"), "_com1");
+        assert_eq!(sanitize_file_name("console.txt"), "console.txt");
+        assert_eq!(sanitize_file_name("a<b>|c?.pdf"), "abc.pdf");
+    }
+
+    #[test]
+    fn sanitize_file_name_caps_length_in_chars() {
+        assert_eq!(
+            sanitize_file_name(&"a".repeat(300)),
+            "a".repeat(MAX_FILE_NAME_CHARS)
+        );
+        let long = sanitize_file_name(&"字".repeat(300));
+        assert_eq!(long.chars().count(), MAX_FILE_NAME_CHARS);
+    }
+
+    #[test]
+    fn unique_path_skips_existing_files_and_in_progress_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recent = VecDeque::new();
+        assert_eq!(
+            unique_path(dir.path(), "report.pdf", &recent),
+            dir.path().join("report.pdf")
+        );
+
+        std::fs::write(dir.path().join("report.pdf"), b"old").unwrap();
+        let first = unique_path(dir.path(), "report.pdf", &recent);
+        assert_eq!(first, dir.path().join("report (1).pdf"));
+
+        // 进行中的下载尚未在磁盘上创建文件，也视为占用
+        recent.push_front(record_at(&first, DownloadState::InProgress));
+        assert_eq!(
+            unique_path(dir.path(), "report.pdf", &recent),
+            dir.path().join("report (2).pdf")
+        );
+
+        // 已结束且文件已被删除的记录不再占用
+        recent[0].state = DownloadState::Failed;
+        assert_eq!(unique_path(dir.path(), "report.pdf", &recent), first);
+    }
+
+    #[test]
+    fn unique_path_numbers_names_without_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("README"), b"").unwrap();
+        std::fs::write(dir.path().join(".env"), b"").unwrap();
+        let recent = VecDeque::new();
+        assert_eq!(
+            unique_path(dir.path(), "README", &recent),
+            dir.path().join("README (1)")
+        );
+        assert_eq!(
+            unique_path(dir.path(), ".env", &recent),
+            dir.path().join(".env (1)")
+        );
+    }
+}
@@ -0,0 +1,419 @@
+// ============================================================================
+// 命令错误
+// ============================================================================
+//
+// - 命令统一返回 `AppError`，序列化为 `{ code, message, details }` 传给前端：
+//   `code` 供程序判断，`message` 为直接展示给用户的本地化文本，`details` 为附加信息（可为 null）
+// - 内部函数大多仍返回 `String`，经 `?` 转换时一律归为 `other`；锁定/确认/未发送输入等
+//   需要程序判断的错误由辅助函数直接构造对应变体，途经的函数须返回 `AppError`
+// - 转为 `String`（自动化 API 响应、后台任务）时锁定/确认/未发送输入带前缀，便于外部调用方识别
+
+use std::fmt;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::permissions::CONFIRMATION_REQUIRED_PREFIX;
+use crate::{LOCKED_ERROR_PREFIX, UNSENT_INPUT_ERROR_PREFIX};
+
+#[derive(Debug, Clone)]
+pub(crate) enum AppError {
+    /// 调用方不是主窗口
+    NotAllowed,
+    /// 锁定模式下拒绝执行（说明文字）
+    Locked(String),
+    /// 破坏性操作需要确认（确认 token 与操作说明）
+    ConfirmationRequired(serde_json::Value),
+    /// Tab 输入框有未发送的内容（tab_id 与草稿长度）
+    UnsentInput(serde_json::Value),
+    /// 破坏性操作预览后状态已变化（旧预览与最新预览）
+    PreviewChanged {
+        previous: serde_json::Value,
+        current: serde_json::Value,
+    },
+    SiteNotFound(String),
+    /// 站点已停用
+    SiteDisabled(String),
+    TabNotFound(String),
+    WebviewNotFound(String),
+    /// 配置读写失败
+    ConfigIo(String),
+    ApiKeyMissing,
+    /// 服务拒绝了 API Key（401/403）
+    ApiAuth {
+        status: u16,
+        body: String,
+    },
+    /// 服务限流（429）
+    ApiRateLimited {
+        body: String,
+    },
+    /// 其他非 2xx 响应
+    ApiError {
+        status: u16,
+        body: String,
+    },
+    /// 连接失败、超时等网络错误
+    Network(String),
+    ExtractionTimeout {
+        secs: u64,
+    },
+    SummaryTimeout {
+        secs: u64,
+    },
+    Cancelled,
+    /// 当前平台不支持该功能（说明文字）
+    Unsupported(String),
+    /// 尚未细分的错误，原样展示
+    Other(String),
+}
+
+impl AppError {
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            AppError::NotAllowed => "not_allowed",
+            AppError::Locked(_) => "locked",
+            AppError::ConfirmationRequired(_) => "confirm_required",
+            AppError::UnsentInput(_) => "unsent_input",
+            AppError::PreviewChanged { .. } => "preview_changed",
+            AppError::SiteNotFound(_) => "site_not_found",
+            AppError::SiteDisabled(_) => "site_disabled",
+            AppError::TabNotFound(_) => "tab_not_found",
+            AppError::WebviewNotFound(_) => "webview_not_found",
+            AppError::ConfigIo(_) => "config_io",
+            AppError::ApiKeyMissing => "api_key_missing",
+            AppError::ApiAuth { .. } => "api_auth",
+            AppError::ApiRateLimited { .. } => "api_rate_limited",
+            AppError::ApiError { .. } => "api_error",
+            AppError::Network(_) => "network",
+            AppError::ExtractionTimeout { .. } => "extraction_timeout",
+            AppError::SummaryTimeout { .. } => "summary_timeout",
+            AppError::Cancelled => "cancelled",
+            AppError::Unsupported(_) => "unsupported",
+            AppError::Other(_) => "other",
+        }
+    }
+
+    /// 展示给用户的文本（不含前缀）
+    pub(crate) fn message(&self) -> String {
+        match self {
+            AppError::NotAllowed => "Not allowed".to_string(),
+            AppError::Locked(message) => message.clone(),
+            AppError::ConfirmationRequired(details) => details
+                .get("description")
+                .and_then(|d| d.as_str())
+                .map(|d| format!("需要确认：{}", d))
+                .unwrap_or_else(|| "需要确认后才能执行".to_string()),
+            AppError::UnsentInput(_) => "输入框中有未发送的内容".to_string(),
+            AppError::PreviewChanged { .. } => "预览后状态已变化，请确认新的影响后重试".to_string(),
+            AppError::SiteNotFound(site_id) => format!("站点不存在: {}", site_id),
+            AppError::SiteDisabled(site_id) => format!("站点已停用: {}", site_id),
+            AppError::TabNotFound(_) => "Tab 不存在".to_string(),
+            AppError::WebviewNotFound(_) => "Webview 不存在".to_string(),
+            AppError::ConfigIo(message) => message.clone(),
+            AppError::ApiKeyMissing => "未配置 API Key".to_string(),
+            AppError::ApiAuth { status, .. } => format!("服务拒绝了 API Key（{}）", status),
+            AppError::ApiRateLimited { .. } => "API 请求过于频繁（429），请稍后重试".to_string(),
+            AppError::ApiError { status, body } => format!("API 返回错误 {}: {}", status, body),
+            AppError::Network(message) => format!("请求失败: {}", message),
+            AppError::ExtractionTimeout { secs } => {
+                format!("提取超时（{}s）。页面较大时可在设置中调高“提取超时”", secs)
+            }
+            AppError::SummaryTimeout { secs } => {
+                format!(
+                    "总结超时（{}s）。模型响应较慢时可在设置中调高“总结超时”",
+                    secs
+                )
+            }
+            AppError::Cancelled => "总结已取消".to_string(),
+            AppError::Unsupported(message) => message.clone(),
+            AppError::Other(message) => message.clone(),
+        }
+    }
+
+    pub(crate) fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::ConfirmationRequired(details) | AppError::UnsentInput(details) => {
+                Some(details.clone())
+            }
+            AppError::PreviewChanged { previous, current } => {
+                Some(serde_json::json!({ "previous": previous, "current": current }))
+            }
+            AppError::SiteNotFound(site_id) | AppError::SiteDisabled(site_id) => {
+                Some(serde_json::json!({ "site_id": site_id }))
+            }
+            AppError::TabNotFound(tab_id) | AppError::WebviewNotFound(tab_id) => {
+                Some(serde_json::json!({ "tab_id": tab_id }))
+            }
+            AppError::ApiAuth { status, body } | AppError::ApiError { status, body } => {
+                Some(serde_json::json!({ "status": status, "body": body }))
+            }
+            AppError::ApiRateLimited { body } => {
+                Some(serde_json::json!({ "status": 429, "body": body }))
+            }
+            AppError::ExtractionTimeout { secs } | AppError::SummaryTimeout { secs } => {
+                Some(serde_json::json!({ "timeout_secs": secs }))
+            }
+            _ => None,
+        }
+    }
+
+    /// 按 HTTP 状态归类 AI 服务的错误响应
+    pub(crate) fn from_api_status(status: u16, body: String) -> Self {
+        match status {
+            401 | 403 => AppError::ApiAuth { status, body },
+            429 => AppError::ApiRateLimited { body },
+            _ => AppError::ApiError { status, body },
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.message())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+/// 旧的字符串格式：锁定/确认/未发送输入带前缀，其余为展示文本
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Locked(message) => write!(f, "{} {}", LOCKED_ERROR_PREFIX, message),
+            AppError::ConfirmationRequired(details) => {
+                write!(f, "{}{}", CONFIRMATION_REQUIRED_PREFIX, details)
+            }
+            AppError::UnsentInput(details) => write!(f, "{}{}", UNSENT_INPUT_ERROR_PREFIX, details),
+            _ => f.write_str(&self.message()),
+        }
+    }
+}
+
+impl From<String> for AppError {
+    fn from(e: String) -> Self {
+        AppError::Other(e)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(e: &str) -> Self {
+        AppError::from(e.to_string())
+    }
+}
+
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn serialized(e: &AppError) -> Value {
+        serde_json::to_value(e).unwrap()
+    }
+
+    #[test]
+    fn every_variant_serializes_to_code_message_details() {
+        let confirm = json!({ "confirm_token": "t1", "command": "remove_site", "description": "删除自定义站点" });
+        let unsent = json!({ "tab_id": "chatgpt", "draft_length": 12 });
+        let cases = [
+            (
+                AppError::NotAllowed,
+                "not_allowed",
+                "Not allowed",
+                Value::Null,
+            ),
+            (
+                AppError::Locked("当前为锁定模式".into()),
+                "locked",
+                "当前为锁定模式",
+                Value::Null,
+            ),
+            (
+                AppError::ConfirmationRequired(confirm.clone()),
+                "confirm_required",
+                "需要确认：删除自定义站点",
+                confirm,
+            ),
+            (
+                AppError::UnsentInput(unsent.clone()),
+                "unsent_input",
+                "输入框中有未发送的内容",
+                unsent,
+            ),
+            (
+                AppError::PreviewChanged {
+                    previous: json!({ "open_tabs": 1 }),
+                    current: json!({ "open_tabs": 2 }),
+                },
+                "preview_changed",
+                "预览后状态已变化，请确认新的影响后重试",
+                json!({ "previous": { "open_tabs": 1 }, "current": { "open_tabs": 2 } }),
+            ),
+            (
+                AppError::SiteNotFound("kimi".into()),
+                "site_not_found",
+                "站点不存在: kimi",
+                json!({ "site_id": "kimi" }),
+            ),
+            (
+                AppError::SiteDisabled("kimi".into()),
+                "site_disabled",
+                "站点已停用: kimi",
+                json!({ "site_id": "kimi" }),
+            ),
+            (
+                AppError::TabNotFound("tab_1".into()),
+                "tab_not_found",
+                "Tab 不存在",
+                json!({ "tab_id": "tab_1" }),
+            ),
+            (
+                AppError::WebviewNotFound("tab_1".into()),
+                "webview_not_found",
+                "Webview 不存在",
+                json!({ "tab_id": "tab_1" }),
+            ),
+            (
+                AppError::ConfigIo("写入配置失败".into()),
+                "config_io",
+                "写入配置失败",
+                Value::Null,
+            ),
+            (
+                AppError::ApiKeyMissing,
+                "api_key_missing",
+                "未配置 API Key",
+                Value::Null,
+            ),
+            (
+                AppError::ApiAuth {
+                    status: 401,
+                    body: "bad key".into(),
+                },
+                "api_auth",
+                "服务拒绝了 API Key（401）",
+                json!({ "status": 401, "body": "bad key" }),
+            ),
+            (
+                AppError::ApiRateLimited {
+                    body: "slow down".into(),
+                },
+                "api_rate_limited",
+                "API 请求过于频繁（429），请稍后重试",
+                json!({ "status": 429, "body": "slow down" }),
+            ),
+            (
+                AppError::ApiError {
+                    status: 500,
+                    body: "oops".into(),
+                },
+                "api_error",
+                "API 返回错误 500: oops",
+                json!({ "status": 500, "body": "oops" }),
+            ),
+            (
+                AppError::Network("connection refused".into()),
+                "network",
+                "请求失败: connection refused",
+                Value::Null,
+            ),
+            (
+                AppError::ExtractionTimeout { secs: 20 },
+                "extraction_timeout",
+                "提取超时（20s）。页面较大时可在设置中调高“提取超时”",
+                json!({ "timeout_secs": 20 }),
+            ),
+            (
+                AppError::SummaryTimeout { secs: 60 },
+                "summary_timeout",
+                "总结超时（60s）。模型响应较慢时可在设置中调高“总结超时”",
+                json!({ "timeout_secs": 60 }),
+            ),
+            (AppError::Cancelled, "cancelled", "总结已取消", Value::Null),
+            (
+                AppError::Unsupported("当前平台不支持".into()),
+                "unsupported",
+                "当前平台不支持",
+                Value::Null,
+            ),
+            (
+                AppError::Other("出错了".into()),
+                "other",
+                "出错了",
+                Value::Null,
+            ),
+        ];
+
+        for (error, code, message, details) in cases {
+            assert_eq!(
+                serialized(&error),
+                json!({ "code": code, "message": message, "details": details }),
+                "{:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn confirmation_without_description_has_generic_message() {
+        let error = AppError::ConfirmationRequired(json!({ "confirm_token": "t1" }));
+        assert_eq!(serialized(&error)["message"], "需要确认后才能执行");
+    }
+
+    #[test]
+    fn api_status_is_classified() {
+        assert_eq!(
+            AppError::from_api_status(401, String::new()).code(),
+            "api_auth"
+        );
+        assert_eq!(
+            AppError::from_api_status(403, String::new()).code(),
+            "api_auth"
+        );
+        assert_eq!(
+            AppError::from_api_status(429, String::new()).code(),
+            "api_rate_limited"
+        );
+        assert_eq!(
+            AppError::from_api_status(500, String::new()).code(),
+            "api_error"
+        );
+    }
+
+    #[test]
+    fn strings_convert_to_other_without_parsing_prefixes() {
+        for raw in [
+            format!("{} 当前为锁定模式", LOCKED_ERROR_PREFIX),
+            format!(
+                "{}{{\"confirm_token\":\"t1\"}}",
+                CONFIRMATION_REQUIRED_PREFIX
+            ),
+            format!("{}{{\"tab_id\":\"a\"}}", UNSENT_INPUT_ERROR_PREFIX),
+        ] {
+            let error = AppError::from(raw.clone());
+            assert_eq!(error.code(), "other");
+            assert_eq!(error.message(), raw);
+        }
+    }
+
+    #[test]
+    fn display_keeps_prefixes_for_string_consumers() {
+        assert_eq!(
+            AppError::Locked("只读".into()).to_string(),
+            format!("{} 只读", LOCKED_ERROR_PREFIX)
+        );
+        assert_eq!(
+            String::from(AppError::UnsentInput(json!({ "tab_id": "a" }))),
+            format!("{}{{\"tab_id\":\"a\"}}", UNSENT_INPUT_ERROR_PREFIX)
+        );
+        assert_eq!(AppError::Cancelled.to_string(), "总结已取消");
+    }
+}
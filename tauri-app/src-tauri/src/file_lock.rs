@@ -0,0 +1,230 @@
+// ============================================================================
+// 跨进程文件锁
+// ============================================================================
+//
+// - 写 config.json 与项目文件（contexts/*.json）时，对同目录下的 `<file>.lock` sidecar 文件加
+//   操作系统的排他咨询锁（flock / LockFileEx），等待时间有上限；sidecar 文件本身保留不删除
+// - 启动时对 `aihub.lock` 加锁并在进程存活期间一直持有：加锁失败说明已有存活进程在使用配置目录，
+//   进入只读模式，避免两个实例互相覆盖配置
+// - 锁随文件句柄关闭而释放，进程崩溃时由操作系统回收，不存在残留锁
+
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use fs2::FileExt;
+
+/// 写文件时等待 sidecar 锁的最长时间
+const FILE_LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 轮询间隔
+const FILE_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// 持有 sidecar 锁期间的守卫，Drop 时关闭句柄即释放锁
+#[derive(Debug)]
+pub(crate) struct FileLockGuard {
+    _file: File,
+}
+
+fn sidecar_lock_path(path: &Path) -> PathBuf {
+    let mut raw = path.as_os_str().to_owned();
+    raw.push(".lock");
+    PathBuf::from(raw)
+}
+
+fn open_lock_file(lock_path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path)
+}
+
+fn is_contended(e: &std::io::Error) -> bool {
+    e.kind() == ErrorKind::WouldBlock
+        || e.raw_os_error() == fs2::lock_contended_error().raw_os_error()
+}
+
+/// 获取 `path` 对应的 sidecar 锁（默认等待上限）
+pub(crate) fn acquire_file_lock(path: &Path) -> Result<FileLockGuard, String> {
+    acquire_file_lock_with_timeout(path, FILE_LOCK_WAIT_TIMEOUT)
+}
+
+/// 获取 `path` 对应的 sidecar 锁，超过 `timeout` 仍被占用则返回错误
+pub(crate) fn acquire_file_lock_with_timeout(
+    path: &Path,
+    timeout: Duration,
+) -> Result<FileLockGuard, String> {
+    let lock_path = sidecar_lock_path(path);
+    let file = open_lock_file(&lock_path).map_err(|e| format!("创建文件锁失败: {}", e))?;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => return Ok(FileLockGuard { _file: file }),
+            Err(e) if is_contended(&e) => {
+                if Instant::now() >= deadline {
+                    return Err(format!("等待文件锁超时: {}", lock_path.display()));
+                }
+                std::thread::sleep(FILE_LOCK_POLL_INTERVAL);
+            }
+            Err(e) => return Err(format!("获取文件锁失败: {}", e)),
+        }
+    }
+}
+
+// ============================================================================
+// 实例锁（启动检测）
+// ============================================================================
+
+/// 当前进程持有的实例锁句柄（进程存活期间一直持有）
+static INSTANCE_LOCK: Mutex<Option<File>> = Mutex::new(None);
+
+/// 启动时实例锁的获取结果
+#[derive(Debug, Clone)]
+pub(crate) enum InstanceLockStatus {
+    /// 当前进程持有实例锁，可正常写入
+    Acquired,
+    /// 另一个存活进程持有实例锁，当前进程应只读（PID 读取失败时为 None，如 Windows 上文件被锁定）
+    HeldByOther { pid: Option<u32> },
+}
+
+/// 尝试获取实例锁：加锁成功后写入当前 PID 供其他实例提示；已被其他进程锁定时返回 `HeldByOther`
+pub(crate) fn acquire_instance_lock(lock_path: &Path) -> InstanceLockStatus {
+    let mut held = INSTANCE_LOCK.lock().unwrap();
+    if held.is_some() {
+        return InstanceLockStatus::Acquired;
+    }
+    let mut file = match open_lock_file(lock_path) {
+        Ok(file) => file,
+        Err(e) => {
+            // 无法创建锁文件（如目录只读）时不阻止启动，后续写入会自行报错
+            println!("[file_lock] 创建实例锁失败: {}", e);
+            return InstanceLockStatus::Acquired;
+        }
+    };
+    match file.try_lock_exclusive() {
+        Ok(()) => {
+            let _ = file.set_len(0);
+            let _ = file.rewind();
+            let _ = write!(file, "{}", std::process::id());
+            let _ = file.flush();
+            *held = Some(file);
+            InstanceLockStatus::Acquired
+        }
+        Err(e) if is_contended(&e) => {
+            let mut content = String::new();
+            let pid = file
+                .read_to_string(&mut content)
+                .ok()
+                .and_then(|_| content.trim().parse::<u32>().ok());
+            InstanceLockStatus::HeldByOther { pid }
+        }
+        Err(e) => {
+            println!("[file_lock] 获取实例锁失败: {}", e);
+            InstanceLockStatus::Acquired
+        }
+    }
+}
+
+/// 释放实例锁（仅当锁由当前进程持有时）
+pub(crate) fn release_instance_lock() {
+    INSTANCE_LOCK.lock().unwrap().take();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn second_holder_times_out_while_lock_is_held() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        let guard = acquire_file_lock(&path).unwrap();
+
+        let contender = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                acquire_file_lock_with_timeout(&path, Duration::from_millis(100)).map(|_| ())
+            })
+        };
+        let err = contender.join().unwrap().unwrap_err();
+        assert!(err.contains("等待文件锁超时"), "{}", err);
+
+        drop(guard);
+        assert!(acquire_file_lock_with_timeout(&path, Duration::from_millis(100)).is_ok());
+        // sidecar 文件保留，下次加锁复用
+        assert!(sidecar_lock_path(&path).exists());
+    }
+
+    #[test]
+    fn waiting_holder_gets_lock_after_release() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        let guard = acquire_file_lock(&path).unwrap();
+
+        let contender = {
+            let path = path.clone();
+            std::thread::spawn(move || acquire_file_lock(&path).map(|_| ()))
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        drop(guard);
+        assert!(contender.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn holders_never_overlap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = Arc::new(tmp.path().join("contexts.json"));
+        let inside = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                let inside = inside.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        let _guard = acquire_file_lock(&path).unwrap();
+                        assert_eq!(inside.fetch_add(1, Ordering::SeqCst), 0);
+                        std::thread::sleep(Duration::from_millis(1));
+                        inside.fetch_sub(1, Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn instance_lock_held_by_other_handle_reports_pid() {
+        let tmp = tempfile::tempdir().unwrap();
+        let lock_path = tmp.path().join("aihub.lock");
+        // 模拟另一个进程：独立句柄持有锁并写入 PID
+        let mut other = open_lock_file(&lock_path).unwrap();
+        other.try_lock_exclusive().unwrap();
+        write!(other, "4242").unwrap();
+
+        let status = acquire_instance_lock(&lock_path);
+        #[cfg(unix)]
+        assert!(matches!(
+            status,
+            InstanceLockStatus::HeldByOther { pid: Some(4242) }
+        ));
+        #[cfg(not(unix))]
+        assert!(matches!(status, InstanceLockStatus::HeldByOther { .. }));
+
+        // 持有者退出（句柄关闭）后锁自动释放，无需清理残留
+        drop(other);
+        assert!(matches!(
+            acquire_instance_lock(&lock_path),
+            InstanceLockStatus::Acquired
+        ));
+        release_instance_lock();
+    }
+}
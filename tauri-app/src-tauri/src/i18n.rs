@@ -0,0 +1,60 @@
+// ============================================================================
+// 文案目录（按 config.language 解析）
+// ============================================================================
+//
+// - 内置站点名称、自动创建的项目标题等用户可见文案通过稳定的 key 查找
+// - 中文（zh / zh-CN）使用原有文案；其余语言暂时统一回退到英文
+
+/// 内置站点名称 key
+pub(crate) const SITE_DEEPSEEK: &str = "site.deepseek";
+pub(crate) const SITE_DOUBAO: &str = "site.doubao";
+pub(crate) const SITE_OPENAI: &str = "site.openai";
+pub(crate) const SITE_QIANWEN: &str = "site.qianwen";
+
+/// 自动创建项目的默认标题 key
+pub(crate) const PROJECT_DEFAULT_TITLE: &str = "project.default_title";
+
+fn is_chinese(language: &str) -> bool {
+    language == "zh" || language.starts_with("zh-")
+}
+
+fn zh(key: &str) -> Option<&'static str> {
+    Some(match key {
+        SITE_DEEPSEEK => "DeepSeek",
+        SITE_DOUBAO => "豆包",
+        SITE_OPENAI => "ChatGPT",
+        SITE_QIANWEN => "通义千问",
+        PROJECT_DEFAULT_TITLE => "默认项目",
+        _ => return None,
+    })
+}
+
+fn en(key: &str) -> Option<&'static str> {
+    Some(match key {
+        SITE_DEEPSEEK => "DeepSeek",
+        SITE_DOUBAO => "Doubao",
+        SITE_OPENAI => "ChatGPT",
+        SITE_QIANWEN => "Qwen",
+        PROJECT_DEFAULT_TITLE => "Default Project",
+        _ => return None,
+    })
+}
+
+/// 查找文案，未知 key 返回 `None`
+pub(crate) fn lookup(language: &str, key: &str) -> Option<&'static str> {
+    if is_chinese(language) {
+        zh(key)
+    } else {
+        en(key).or_else(|| zh(key))
+    }
+}
+
+/// 查找文案，未知 key 原样返回
+pub(crate) fn text(language: &str, key: &str) -> String {
+    lookup(language, key).unwrap_or(key).to_string()
+}
+
+/// 某个 key 在任一语言下的文案是否等于 `value`（用于识别“未被用户改名”的内置名称）
+pub(crate) fn matches_any_language(key: &str, value: &str) -> bool {
+    zh(key) == Some(value) || en(key) == Some(value)
+}
@@ -0,0 +1,227 @@
+// ============================================================================
+// 后台任务（Job）框架
+// ============================================================================
+//
+// - `start_job` 立即返回 job id，任务在 async runtime 中执行
+// - 进度/结果统一通过 `job-updated` 事件推送，也可用 `get_job`/`list_jobs` 查询
+// - `cancel_job` 触发协作式取消：任务 future 会在下一个 await 点被丢弃
+// - 结束的任务记录保留 JOB_RETENTION_SECS 后过期清理
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::now_ts;
+
+/// 已结束任务的保留时长（秒）
+const JOB_RETENTION_SECS: u64 = 10 * 60;
+
+/// 最多保留的已结束任务数量（超出时从最早结束的开始清理）
+const MAX_FINISHED_JOBS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobPhase {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobPhase {
+    fn is_finished(self) -> bool {
+        !matches!(self, JobPhase::Running)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct JobRecord {
+    pub id: String,
+    pub kind: String,
+    pub phase: JobPhase,
+    /// 0.0 ~ 1.0
+    pub progress: f64,
+    pub message: String,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+struct JobEntry {
+    record: JobRecord,
+    cancel: CancellationToken,
+}
+
+static JOBS: Lazy<Mutex<HashMap<String, JobEntry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 传给任务体的句柄，用于上报进度
+#[derive(Clone)]
+pub(crate) struct JobHandle {
+    app: tauri::AppHandle,
+    id: String,
+}
+
+impl JobHandle {
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// 上报进度（0.0 ~ 1.0）与阶段描述
+    pub(crate) fn progress(&self, progress: f64, message: &str) {
+        let record = {
+            let mut jobs = JOBS.lock().unwrap();
+            let Some(entry) = jobs.get_mut(&self.id) else {
+                return;
+            };
+            if entry.record.phase.is_finished() {
+                return;
+            }
+            entry.record.progress = progress.clamp(0.0, 1.0);
+            entry.record.message = message.to_string();
+            entry.record.updated_at = now_ts();
+            entry.record.clone()
+        };
+        let _ = self.app.emit("job-updated", record);
+    }
+}
+
+/// 启动一个后台任务，返回 job id
+pub(crate) fn start_job<F, Fut, T, E>(app: &tauri::AppHandle, kind: &str, body: F) -> String
+where
+    F: FnOnce(JobHandle) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Serialize,
+    E: Into<String>,
+{
+    prune_expired_jobs();
+
+    let id = format!("job_{}", Uuid::new_v4().simple());
+    let ts = now_ts();
+    let cancel = CancellationToken::new();
+    let record = JobRecord {
+        id: id.clone(),
+        kind: kind.to_string(),
+        phase: JobPhase::Running,
+        progress: 0.0,
+        message: String::new(),
+        result: None,
+        error: None,
+        created_at: ts,
+        updated_at: ts,
+        finished_at: None,
+    };
+    JOBS.lock().unwrap().insert(
+        id.clone(),
+        JobEntry {
+            record: record.clone(),
+            cancel: cancel.clone(),
+        },
+    );
+    let _ = app.emit("job-updated", record);
+
+    let handle = JobHandle {
+        app: app.clone(),
+        id: id.clone(),
+    };
+    let fut = body(handle);
+    let app = app.clone();
+    let job_id = id.clone();
+    tauri::async_runtime::spawn(async move {
+        let (phase, result, error) = match cancel.run_until_cancelled(fut).await {
+            None => (JobPhase::Cancelled, None, Some("任务已取消".to_string())),
+            Some(Ok(value)) => match serde_json::to_value(value) {
+                Ok(v) => (JobPhase::Completed, Some(v), None),
+                Err(e) => (JobPhase::Failed, None, Some(format!("序列化任务结果失败: {}", e))),
+            },
+            Some(Err(e)) => (JobPhase::Failed, None, Some(e.into())),
+        };
+        finish_job(&app, &job_id, phase, result, error);
+    });
+
+    id
+}
+
+fn finish_job(
+    app: &tauri::AppHandle,
+    id: &str,
+    phase: JobPhase,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+) {
+    let record = {
+        let mut jobs = JOBS.lock().unwrap();
+        let Some(entry) = jobs.get_mut(id) else {
+            return;
+        };
+        let ts = now_ts();
+        entry.record.phase = phase;
+        if phase == JobPhase::Completed {
+            entry.record.progress = 1.0;
+        }
+        entry.record.result = result;
+        entry.record.error = error;
+        entry.record.updated_at = ts;
+        entry.record.finished_at = Some(ts);
+        entry.record.clone()
+    };
+    let _ = app.emit("job-updated", record);
+}
+
+pub(crate) fn get_job(id: &str) -> Option<JobRecord> {
+    prune_expired_jobs();
+    JOBS.lock().unwrap().get(id).map(|e| e.record.clone())
+}
+
+/// 列出所有未过期任务（最新的在前）
+pub(crate) fn list_jobs() -> Vec<JobRecord> {
+    prune_expired_jobs();
+    let mut records: Vec<JobRecord> = JOBS
+        .lock()
+        .unwrap()
+        .values()
+        .map(|e| e.record.clone())
+        .collect();
+    records.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+    records
+}
+
+/// 请求取消任务（协作式）。已结束的任务返回错误。
+pub(crate) fn cancel_job(id: &str) -> Result<(), String> {
+    let jobs = JOBS.lock().unwrap();
+    let entry = jobs.get(id).ok_or_else(|| "任务不存在".to_string())?;
+    if entry.record.phase.is_finished() {
+        return Err("任务已结束".to_string());
+    }
+    entry.cancel.cancel();
+    Ok(())
+}
+
+/// 清理过期的已结束任务
+pub(crate) fn prune_expired_jobs() {
+    let now = now_ts();
+    let mut jobs = JOBS.lock().unwrap();
+    jobs.retain(|_, e| match e.record.finished_at {
+        Some(finished) => now.saturating_sub(finished) <= JOB_RETENTION_SECS,
+        None => true,
+    });
+
+    let mut finished: Vec<(u64, String)> = jobs
+        .values()
+        .filter_map(|e| e.record.finished_at.map(|ts| (ts, e.record.id.clone())))
+        .collect();
+    if finished.len() > MAX_FINISHED_JOBS {
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED_JOBS;
+        for (_, id) in finished.into_iter().take(excess) {
+            jobs.remove(&id);
+        }
+    }
+}
@@ -1,10 +1,10 @@
-use std::collections::{HashMap, HashSet};
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
 use std::io::Write;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::path::PathBuf;
 use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
+use std::time::Duration;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -12,8 +12,32 @@ use tauri::{
     webview::{PageLoadEvent, WebviewBuilder},
     Manager, Emitter, WebviewUrl, LogicalPosition, LogicalSize,
 };
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use tauri_plugin_opener::OpenerExt;
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+mod ai_client;
+mod automation_api;
+mod bookmarks;
+mod deep_link;
+mod downloads;
+mod error;
+mod file_lock;
+mod i18n;
+mod jobs;
+mod notify;
+mod permissions;
+mod project_store;
+mod screenshot;
+mod secret_store;
+mod shortcuts;
+mod single_instance;
+mod site_stats;
+mod site_url;
+mod time;
+mod usage;
+
+use error::AppError;
 
 // ============================================================================
 // 常量配置
@@ -22,13 +46,22 @@ use tokio::sync::oneshot;
 /// Chrome User Agent - 与真实浏览器一致
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
 
-/// 反检测脚本 - 在页面加载前注入，消除 WebView 特征
-const ANTI_DETECTION_SCRIPT: &str = r#"
+/// 反检测脚本（webdriver 部分）- 在页面加载前注入，消除自动化特征
+const ANTI_DETECTION_WEBDRIVER_SCRIPT: &str = r#"
 // 消除 webdriver 检测
 Object.defineProperty(navigator, 'webdriver', {
     get: () => undefined
 });
 
+// 消除 automation 检测
+delete window.cdc_adoQpoasnfa76pfcZLmcfl_Array;
+delete window.cdc_adoQpoasnfa76pfcZLmcfl_Promise;
+delete window.cdc_adoQpoasnfa76pfcZLmcfl_Symbol;
+"#;
+
+/// 反检测脚本（浏览器特征伪造部分）：plugins / languages / platform / vendor 各自可关闭，
+/// chrome 对象在 full 模式下总是伪造
+const ANTI_DETECTION_PLUGINS_SCRIPT: &str = r#"
 // 伪造 plugins（真实浏览器有 PDF 插件等）
 Object.defineProperty(navigator, 'plugins', {
     get: () => {
@@ -43,27 +76,31 @@ Object.defineProperty(navigator, 'plugins', {
         return plugins;
     }
 });
+"#;
 
+/// `{languages}` 替换为 JSON 数组
+const ANTI_DETECTION_LANGUAGES_SCRIPT: &str = r#"
 // 伪造 languages
 Object.defineProperty(navigator, 'languages', {
-    get: () => ['zh-CN', 'zh', 'en-US', 'en']
+    get: () => {languages}
 });
+"#;
 
+const ANTI_DETECTION_PLATFORM_SCRIPT: &str = r#"
 // 伪造 platform
 Object.defineProperty(navigator, 'platform', {
     get: () => 'Win32'
 });
+"#;
 
+const ANTI_DETECTION_VENDOR_SCRIPT: &str = r#"
 // 伪造 vendor
 Object.defineProperty(navigator, 'vendor', {
     get: () => 'Google Inc.'
 });
+"#;
 
-// 消除 automation 检测
-delete window.cdc_adoQpoasnfa76pfcZLmcfl_Array;
-delete window.cdc_adoQpoasnfa76pfcZLmcfl_Promise;
-delete window.cdc_adoQpoasnfa76pfcZLmcfl_Symbol;
-
+const ANTI_DETECTION_CHROME_SCRIPT: &str = r#"
 // 伪造 chrome 对象
 window.chrome = {
     runtime: {},
@@ -73,9 +110,318 @@ window.chrome = {
 };
 "#;
 
-/// Webview 空闲超时时长（秒）
+/// `{timezone}` 替换为 IANA 时区名（JSON 字符串）。
+/// 修改 Intl.DateTimeFormat 的默认时区、Date 的本地化输出与 getTimezoneOffset；时区无效时不做任何修改
+const ANTI_DETECTION_TIMEZONE_SCRIPT: &str = r#"
+// 伪造时区
+(function () {
+    const tz = {timezone};
+    const OrigDateTimeFormat = Intl.DateTimeFormat;
+    try {
+        new OrigDateTimeFormat('en-US', { timeZone: tz });
+    } catch (e) {
+        return;
+    }
+    const withTimeZone = (options) => {
+        const merged = Object.assign({}, options);
+        if (merged.timeZone === undefined) merged.timeZone = tz;
+        return merged;
+    };
+    const PatchedDateTimeFormat = function (locales, options) {
+        return new OrigDateTimeFormat(locales, withTimeZone(options));
+    };
+    PatchedDateTimeFormat.prototype = OrigDateTimeFormat.prototype;
+    PatchedDateTimeFormat.supportedLocalesOf = OrigDateTimeFormat.supportedLocalesOf;
+    Intl.DateTimeFormat = PatchedDateTimeFormat;
+
+    for (const name of ['toLocaleString', 'toLocaleDateString', 'toLocaleTimeString']) {
+        const orig = Date.prototype[name];
+        Date.prototype[name] = function (locales, options) {
+            return orig.call(this, locales, withTimeZone(options));
+        };
+    }
+
+    const partsFormatter = new OrigDateTimeFormat('en-US', {
+        timeZone: tz, hourCycle: 'h23',
+        year: 'numeric', month: 'numeric', day: 'numeric',
+        hour: 'numeric', minute: 'numeric', second: 'numeric'
+    });
+    const origGetTimezoneOffset = Date.prototype.getTimezoneOffset;
+    Date.prototype.getTimezoneOffset = function () {
+        const time = this.getTime();
+        if (isNaN(time)) return origGetTimezoneOffset.call(this);
+        const parts = {};
+        for (const part of partsFormatter.formatToParts(this)) parts[part.type] = part.value;
+        const wallClock = Date.UTC(+parts.year, +parts.month - 1, +parts.day, +parts.hour, +parts.minute, +parts.second);
+        return Math.round((Math.floor(time / 1000) * 1000 - wallClock) / 60000);
+    };
+})();
+"#;
+
+/// `{vendor}` / `{renderer}` 替换为 JSON 字符串。
+/// WEBGL_debug_renderer_info 的 UNMASKED_VENDOR_WEBGL = 0x9245，UNMASKED_RENDERER_WEBGL = 0x9246
+const ANTI_DETECTION_WEBGL_SCRIPT: &str = r#"
+// 伪造 WebGL 显卡信息
+(function () {
+    const vendor = {vendor};
+    const renderer = {renderer};
+    const patch = (proto) => {
+        if (!proto) return;
+        const orig = proto.getParameter;
+        proto.getParameter = function (param) {
+            if (param === 0x9245) return vendor;
+            if (param === 0x9246) return renderer;
+            return orig.call(this, param);
+        };
+    };
+    patch(window.WebGLRenderingContext && WebGLRenderingContext.prototype);
+    patch(window.WebGL2RenderingContext && WebGL2RenderingContext.prototype);
+})();
+"#;
+
+/// 站点反检测模式
+/// - full: webdriver 消除 + 浏览器特征伪造（默认）
+/// - webdriver_only: 仅消除 webdriver/automation 特征
+/// - off: 不注入
+const ANTI_DETECTION_MODES: &[&str] = &["full", "webdriver_only", "off"];
+
+/// 站点弹窗策略（与打开页面同源的弹窗总是作为真实弹窗打开，以免 OAuth 登录失效）
+/// - system: 在系统默认浏览器中打开（默认）
+/// - new_tab: 在该站点的新 Tab 中打开
+/// - block: 拦截并通知前端
+const POPUP_POLICIES: &[&str] = &["system", "new_tab", "block"];
+
+/// 按站点模式与全局反检测设置拼出注入脚本；`language` 用于推断未配置的伪造时区
+fn build_anti_detection_script(
+    mode: &str,
+    settings: &AntiDetectionConfig,
+    language: &str,
+) -> String {
+    if !settings.enabled {
+        return String::new();
+    }
+    match mode {
+        "off" => String::new(),
+        "webdriver_only" => ANTI_DETECTION_WEBDRIVER_SCRIPT.to_string(),
+        _ => {
+            let mut script = ANTI_DETECTION_WEBDRIVER_SCRIPT.to_string();
+            if settings.spoof_plugins {
+                script.push_str(ANTI_DETECTION_PLUGINS_SCRIPT);
+            }
+            if !settings.spoof_languages.is_empty() {
+                let languages = serde_json::to_string(&settings.spoof_languages)
+                    .unwrap_or_else(|_| "[]".to_string());
+                script
+                    .push_str(&ANTI_DETECTION_LANGUAGES_SCRIPT.replace("{languages}", &languages));
+            }
+            if settings.spoof_platform {
+                script.push_str(ANTI_DETECTION_PLATFORM_SCRIPT);
+            }
+            if settings.spoof_vendor {
+                script.push_str(ANTI_DETECTION_VENDOR_SCRIPT);
+            }
+            if settings.spoof_timezone {
+                let timezone = if settings.timezone.is_empty() {
+                    default_timezone_for_language(language)
+                } else {
+                    settings.timezone.as_str()
+                };
+                let timezone = serde_json::to_string(timezone).unwrap_or_default();
+                script.push_str(&ANTI_DETECTION_TIMEZONE_SCRIPT.replace("{timezone}", &timezone));
+            }
+            if settings.spoof_webgl {
+                // 单遍替换：厂商字符串中的 `{renderer}` 不会被再次展开
+                script.push_str(&render_prompt_template(
+                    ANTI_DETECTION_WEBGL_SCRIPT,
+                    |name| {
+                        let value = match name {
+                            "vendor" => &settings.webgl_vendor,
+                            "renderer" => &settings.webgl_renderer,
+                            _ => return None,
+                        };
+                        Some(serde_json::to_string(value).unwrap_or_default())
+                    },
+                ));
+            }
+            script.push_str(ANTI_DETECTION_CHROME_SCRIPT);
+            script
+        }
+    }
+}
+
+/// 站点实际生效的反检测模式（`disable_anti_detection` 时为 off）
+fn effective_anti_detection_mode(site: &AiSite) -> &str {
+    if site.disable_anti_detection {
+        "off"
+    } else {
+        &site.anti_detection_mode
+    }
+}
+
+/// 站点的反检测注入脚本
+fn site_anti_detection_script(
+    site: &AiSite,
+    settings: &AntiDetectionConfig,
+    language: &str,
+) -> String {
+    build_anti_detection_script(effective_anti_detection_mode(site), settings, language)
+}
+
+/// 站点自定义初始化脚本的长度上限（字符）
+const MAX_SITE_INIT_SCRIPT_CHARS: usize = 16 * 1024;
+
+fn validate_site_init_script(script: &str) -> Result<(), String> {
+    if script.chars().count() > MAX_SITE_INIT_SCRIPT_CHARS {
+        return Err(format!(
+            "初始化脚本过长（上限 {} 个字符）",
+            MAX_SITE_INIT_SCRIPT_CHARS
+        ));
+    }
+    Ok(())
+}
+
+/// 包装站点自定义脚本：放进独立作用域并捕获异常，脚本出错不影响页面与其他注入脚本
+fn build_site_init_script(script: &str) -> String {
+    if script.trim().is_empty() {
+        return String::new();
+    }
+    format!(
+        "(function () {{\n  try {{\n{}\n  }} catch (e) {{\n    console.warn('[aihub] 站点初始化脚本出错', e);\n  }}\n}})();",
+        script
+    )
+}
+
+/// 页面加载耗时上报脚本：load 后稍等（等待 FCP 记录），通过受限命令回报给后端
+const PAGE_TIMING_SCRIPT: &str = r#"
+(function () {
+  if (window.top !== window) return;
+  const report = () => {
+    try {
+      const invoke = window.__TAURI__ && window.__TAURI__.core && window.__TAURI__.core.invoke;
+      if (!invoke) return;
+      const nav = performance.getEntriesByType('navigation')[0];
+      const legacy = performance.timing;
+      const fromLegacy = (end) => (legacy && legacy[end] && legacy.navigationStart)
+        ? legacy[end] - legacy.navigationStart
+        : null;
+      const paint = performance.getEntriesByName('first-contentful-paint')[0];
+      invoke('aihub_report_page_timing', {
+        url: location.href,
+        domContentLoadedMs: nav ? nav.domContentLoadedEventEnd : fromLegacy('domContentLoadedEventEnd'),
+        loadMs: nav ? nav.loadEventEnd : fromLegacy('loadEventEnd'),
+        firstContentfulPaintMs: paint ? paint.startTime : null
+      }).catch(() => {});
+    } catch (_) {}
+  };
+  const schedule = () => setTimeout(report, 1500);
+  if (document.readyState === 'complete') {
+    schedule();
+  } else {
+    window.addEventListener('load', schedule, { once: true });
+  }
+})();
+"#;
+
+/// 桥接探测脚本：页面上下文中 `__TAURI__.core.invoke` 可用时回报后端
+const BRIDGE_PROBE_SCRIPT: &str = r#"
+(function () {
+  if (window.top !== window) return;
+  const probe = () => {
+    try {
+      const invoke = window.__TAURI__ && window.__TAURI__.core && window.__TAURI__.core.invoke;
+      if (!invoke) return;
+      invoke('aihub_report_bridge', { url: location.href }).catch(() => {});
+    } catch (_) {}
+  };
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', probe, { once: true });
+  } else {
+    probe();
+  }
+})();
+"#;
+
+/// 标题上报脚本：标题或地址变化（含单页应用内跳转）时回报后端
+const PAGE_TITLE_SCRIPT: &str = r#"
+(function () {
+  if (window.top !== window) return;
+  let last = '';
+  const report = () => {
+    try {
+      const invoke = window.__TAURI__ && window.__TAURI__.core && window.__TAURI__.core.invoke;
+      if (!invoke) return;
+      const title = document.title || '';
+      const key = title + '\n' + location.href;
+      if (key === last) return;
+      last = key;
+      invoke('aihub_report_title', { title, url: location.href }).catch(() => {});
+    } catch (_) {}
+  };
+  const start = () => {
+    report();
+    const root = document.head || document.documentElement;
+    if (root) {
+      new MutationObserver(report).observe(root, { subtree: true, childList: true, characterData: true });
+    }
+    // pushState 跳转不一定改标题，定时兜底
+    setInterval(report, 2000);
+  };
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', start, { once: true });
+  } else {
+    start();
+  }
+})();
+"#;
+
+/// Ctrl/⌘ + 滚轮、Ctrl/⌘ + =/-/0 调整页面缩放（经 `aihub_zoom_step` 由后端统一处理并保存）
+const ZOOM_KEYS_SCRIPT: &str = r#"
+(function () {
+  if (window.top !== window) return;
+  const step = (direction) => {
+    try {
+      const invoke = window.__TAURI__ && window.__TAURI__.core && window.__TAURI__.core.invoke;
+      if (invoke) invoke('aihub_zoom_step', { direction }).catch(() => {});
+    } catch (_) {}
+  };
+  let lastWheel = 0;
+  window.addEventListener('wheel', (e) => {
+    if (!(e.ctrlKey || e.metaKey) || e.deltaY === 0) return;
+    e.preventDefault();
+    // 触控板会连续触发，限制频率
+    const now = Date.now();
+    if (now - lastWheel < 120) return;
+    lastWheel = now;
+    step(e.deltaY < 0 ? 1 : -1);
+  }, { passive: false, capture: true });
+  window.addEventListener('keydown', (e) => {
+    if (!(e.ctrlKey || e.metaKey) || e.altKey) return;
+    const direction = { '=': 1, '+': 1, '-': -1, '_': -1, '0': 0 }[e.key];
+    if (direction === undefined) return;
+    e.preventDefault();
+    step(direction);
+  }, true);
+})();
+"#;
+
+/// 页面加载完成后等待桥接回报的时长，超时视为站点阻止了脚本通信
+const BRIDGE_REPORT_GRACE: Duration = Duration::from_secs(3);
+
+/// 页面开始加载后等待完成的时长，超时视为加载失败（离线、DNS 无响应等）
+const PAGE_LOAD_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Webview 存活检测间隔（仅检测桥接可用且未在加载中的页面）
+const WEBVIEW_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 存活检测等待回应的时长，超时视为渲染进程崩溃或页面卡死
+const WEBVIEW_PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webview 空闲超时时长（秒）：未配置 `tab_hibernate_minutes` 时的兜底回收
 const WEBVIEW_IDLE_TTL_SECS: u64 = 3 * 24 * 60 * 60;
 
+/// 空闲 Webview 的检查间隔
+const HIBERNATE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 // ============================================================================
 // 数据结构
 // ============================================================================
@@ -91,6 +437,148 @@ pub struct AiSite {
     pub builtin: bool,
     #[serde(default)]
     pub summary_prompt_override: String,
+    /// 反检测模式（full / webdriver_only / off）
+    #[serde(default = "default_anti_detection_mode")]
+    pub anti_detection_mode: String,
+    /// 不为该站点注入反检测脚本（优先于 `anti_detection_mode`，用于伪造特征干扰站点自身检测的情况）
+    #[serde(default)]
+    pub disable_anti_detection: bool,
+    /// 聊天输入框的 CSS 选择器（为空时使用内置站点默认值或通用选择器）
+    #[serde(default)]
+    pub input_selector: String,
+    /// 隐私站点：不进入“最近使用”、不记录加载耗时统计，审计记录中以占位符代替
+    #[serde(default)]
+    pub private: bool,
+    /// 总结时提取对话内容的 CSS 选择器（为空时使用内置站点默认值；匹配不到时回退到整页文本）
+    #[serde(default)]
+    pub extract_selector: String,
+    /// 内置站点名称的文案 key；用户改名后清空，以 `name` 为准
+    #[serde(default)]
+    pub name_key: String,
+    /// 引用的命名提示词模板（优先于 `summary_prompt_override`；模板被删除时忽略）
+    #[serde(default)]
+    pub prompt_template_id: String,
+    /// 发送按钮的 CSS 选择器（为空时使用内置站点默认值，找不到时在输入框上模拟回车）
+    #[serde(default)]
+    pub submit_selector: String,
+    /// 站点自定义的初始化脚本（如自动关闭 Cookie 提示），在反检测脚本之后注入
+    #[serde(default)]
+    pub init_script: String,
+    /// 停用的站点不在侧边栏显示、不能切换，但保留配置与在 `site_order` 中的位置
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 页面打开新窗口（window.open / target=_blank）时的处理方式（system / new_tab / block）
+    #[serde(default = "default_popup_policy")]
+    pub popup_policy: String,
+    /// 页面缩放比例（新建 Webview 时应用；在 Tab 中调整缩放时更新）
+    #[serde(default = "default_zoom")]
+    pub zoom: f64,
+}
+
+/// 命名的总结提示词模板（如“会议纪要”“代码评审”）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+}
+
+fn default_anti_detection_mode() -> String {
+    "full".to_string()
+}
+
+fn default_popup_policy() -> String {
+    "system".to_string()
+}
+
+fn default_zoom() -> f64 {
+    1.0
+}
+
+impl Default for AiSite {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            url: String::new(),
+            icon: String::new(),
+            builtin: false,
+            summary_prompt_override: String::new(),
+            anti_detection_mode: default_anti_detection_mode(),
+            disable_anti_detection: false,
+            input_selector: String::new(),
+            private: false,
+            extract_selector: String::new(),
+            name_key: String::new(),
+            prompt_template_id: String::new(),
+            submit_selector: String::new(),
+            init_script: String::new(),
+            enabled: true,
+            popup_policy: default_popup_policy(),
+            zoom: default_zoom(),
+        }
+    }
+}
+
+/// AI 服务的接口协议
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiProvider {
+    /// OpenAI 及兼容接口（`/chat/completions`，Bearer Key）
+    #[default]
+    OpenAi,
+    /// Anthropic Messages API（`/messages`，`x-api-key`）
+    Anthropic,
+    /// Azure OpenAI（按部署名寻址，`api-key` 头，响应格式同 OpenAI）
+    Azure,
+}
+
+/// OpenAI 兼容接口的鉴权方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiAuthMode {
+    /// `Authorization: Bearer <key>`
+    #[default]
+    Bearer,
+    /// 不需要 Key（Ollama 等本地服务的 OpenAI 兼容接口）
+    None,
+}
+
+impl AiProvider {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            AiProvider::OpenAi => "https://api.openai.com/v1",
+            AiProvider::Anthropic => "https://api.anthropic.com/v1",
+            // Azure 的资源地址因人而异，没有默认值
+            AiProvider::Azure => "",
+        }
+    }
+}
+
+/// AI 服务配置档案（如工作用 DeepSeek、个人用 OpenAI）
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AiApiProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub provider: AiProvider,
+    #[serde(default)]
+    pub auth: AiAuthMode,
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub model: String,
+    /// Azure 部署名称（为空时使用 `model`）
+    #[serde(default)]
+    pub deployment: String,
+    /// Azure `api-version`（为空时使用 AZURE_DEFAULT_API_VERSION）
+    #[serde(default)]
+    pub api_version: String,
+    #[serde(default)]
+    pub key: String,
+    /// Key 已保存在系统密钥环中（此时 `key` 为空）
+    #[serde(default)]
+    pub key_in_keyring: bool,
 }
 
 /// 应用配置
@@ -110,18 +598,386 @@ pub struct AppConfig {
     pub language: String,
     #[serde(default = "default_summary_prompt_template")]
     pub summary_prompt_template: String,
+    /// 命名提示词模板库
+    #[serde(default)]
+    pub prompt_templates: Vec<PromptTemplate>,
+    /// 总结请求的 temperature（0.0–2.0；Anthropic 上限为 1.0）
+    #[serde(default = "default_ai_api_temperature")]
+    pub ai_api_temperature: f64,
+    /// 输出 token 上限；None 时使用服务端默认值（Anthropic 使用 ANTHROPIC_MAX_TOKENS）
+    #[serde(default)]
+    pub ai_api_max_tokens: Option<u32>,
+    /// 系统提示词；为空时使用 DEFAULT_AI_SYSTEM_PROMPT
+    #[serde(default)]
+    pub ai_system_prompt: String,
+    #[serde(default)]
+    pub ai_api_provider: AiProvider,
+    #[serde(default)]
+    pub ai_api_auth: AiAuthMode,
     #[serde(default)]
     pub ai_api_base_url: String,
     #[serde(default)]
     pub ai_api_model: String,
+    /// Azure 部署名称（为空时使用 `ai_api_model`）
+    #[serde(default)]
+    pub ai_api_deployment: String,
+    /// Azure `api-version`（为空时使用 AZURE_DEFAULT_API_VERSION）
+    #[serde(default)]
+    pub ai_api_version: String,
     #[serde(default)]
     pub ai_api_key: String,
+    /// API Key 已保存在系统密钥环中（此时 `ai_api_key` 为空）
+    #[serde(default)]
+    pub ai_api_key_in_keyring: bool,
+    /// 命名的 AI 服务配置档案；未选中档案时使用上面的 `ai_api_*` 字段
+    #[serde(default)]
+    pub ai_api_profiles: Vec<AiApiProfile>,
+    #[serde(default)]
+    pub active_ai_profile_id: String,
     #[serde(default)]
     pub active_project_id: String,
     #[serde(default)]
     pub last_active_tab_id: String,
     #[serde(default)]
     pub last_active_site_id: String,
+    /// 锁定（只读查看）模式：只能通过编辑 config.json 或 `--locked` 启动参数开启
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub lock_exemptions: LockExemptions,
+    /// 本地自动化 API（默认关闭，开启会扩大攻击面）
+    #[serde(default)]
+    pub automation_api: AutomationApiConfig,
+    /// 全局反检测设置（站点可通过 anti_detection_mode 单独关闭或只保留 webdriver 部分）
+    #[serde(default)]
+    pub anti_detection: AntiDetectionConfig,
+    /// 演示模式：总结使用本地模拟响应，不需要 API Key，也不发出任何网络请求；
+    /// 通过 set_ai_api_settings 配置 Key 后自动退出
+    #[serde(default)]
+    pub demo_mode: bool,
+    /// 隐藏的 Tab 空闲多少分钟后休眠（关闭 Webview、保留 Tab，切回时重新加载）；0 = 不休眠
+    #[serde(default)]
+    pub tab_hibernate_minutes: u32,
+    /// 每个项目保留的总结记录条数
+    #[serde(default = "default_project_entry_limit")]
+    pub project_entry_limit: u32,
+    /// 总结当前 Tab 时提取文本的字符上限；超过时不发送请求。None = 不限制
+    #[serde(default)]
+    pub summary_max_chars: Option<u32>,
+    /// 提取页面文本的超时（秒）；None 时使用 SUMMARY_EXTRACT_TIMEOUT
+    #[serde(default)]
+    pub summary_extract_timeout_secs: Option<u32>,
+    /// 总结的总耗时上限（秒）；None 时使用 SUMMARY_TIMEOUT（本地模型 LOCAL_MODEL_SUMMARY_TIMEOUT）
+    #[serde(default)]
+    pub summary_total_timeout_secs: Option<u32>,
+    /// 总结当前 Tab 时把提取的原文追加到项目笔记末尾；关闭时不改动笔记
+    #[serde(default)]
+    pub summary_append_notes: bool,
+    /// AI 服务请求使用的 HTTP 代理（http/https）；None 时沿用 HTTPS_PROXY 等环境变量
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+    /// 站点页面下载文件的保存目录；为空时使用系统下载文件夹
+    #[serde(default)]
+    pub download_dir: String,
+    /// 总结当前 Tab 完成后发送桌面通知（主窗口在前台时不发送）
+    #[serde(default = "default_true")]
+    pub notify_on_summary: bool,
+    /// 总结失败时也发送通知（附错误代码）
+    #[serde(default)]
+    pub notify_on_summary_failure: bool,
+    /// 已应用的内置站点目录版本（见 BUILTIN_CATALOG_VERSION）
+    #[serde(default)]
+    pub builtin_catalog_version: u32,
+    /// 全局快捷键：动作 → 快捷键（见 shortcuts 模块）
+    #[serde(default = "default_shortcuts")]
+    pub shortcuts: BTreeMap<String, String>,
+    /// 上次退出时的 Tab 与布局（启动时恢复）
+    #[serde(default)]
+    pub session: SessionState,
+}
+
+/// 持久化的会话：已打开的额外 Tab、活跃 Tab 与分屏布局
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    #[serde(default)]
+    pub tabs: Vec<SessionTab>,
+    #[serde(default)]
+    pub active_tab_id: String,
+    /// single / split
+    #[serde(default = "default_session_layout_mode")]
+    pub layout_mode: String,
+    #[serde(default = "default_session_ratio")]
+    pub ratio: f64,
+    #[serde(default)]
+    pub left_tab_id: Option<String>,
+    #[serde(default)]
+    pub right_tab_id: Option<String>,
+    /// 分屏中从左到右的 Tab（为空时回退到 left/right，兼容旧配置）
+    #[serde(default)]
+    pub pane_tab_ids: Vec<String>,
+    #[serde(default)]
+    pub pane_ratios: Vec<f64>,
+    /// horizontal / vertical
+    #[serde(default = "default_session_orientation")]
+    pub orientation: String,
+    /// 最近一次退出分屏前的布局（“恢复分屏”时使用）
+    #[serde(default)]
+    pub last_split: Option<LastSplit>,
+    /// 最近关闭的 Tab（新的在前）
+    #[serde(default)]
+    pub closed_tabs: Vec<ClosedTab>,
+}
+
+/// 最近关闭的 Tab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClosedTab {
+    pub tab_id: String,
+    pub site_id: String,
+    /// 关闭时页面所在地址（未知时为空，重新打开后停留在站点首页）
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub closed_at: u64,
+}
+
+/// 退出分屏时暂存的分屏布局
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastSplit {
+    #[serde(default)]
+    pub left_tab_id: Option<String>,
+    #[serde(default)]
+    pub right_tab_id: Option<String>,
+    #[serde(default = "default_session_ratio")]
+    pub ratio: f64,
+    #[serde(default)]
+    pub pane_tab_ids: Vec<String>,
+    #[serde(default)]
+    pub pane_ratios: Vec<f64>,
+    #[serde(default = "default_session_orientation")]
+    pub orientation: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub tab_id: String,
+    pub site_id: String,
+}
+
+fn default_session_layout_mode() -> String {
+    "single".to_string()
+}
+
+fn default_session_ratio() -> f64 {
+    0.5
+}
+
+fn default_session_orientation() -> String {
+    "horizontal".to_string()
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        Self {
+            tabs: Vec::new(),
+            active_tab_id: String::new(),
+            layout_mode: default_session_layout_mode(),
+            ratio: default_session_ratio(),
+            left_tab_id: None,
+            right_tab_id: None,
+            pane_tab_ids: Vec::new(),
+            pane_ratios: Vec::new(),
+            orientation: default_session_orientation(),
+            last_split: None,
+            closed_tabs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_automation_api_port")]
+    pub port: u16,
+}
+
+impl Default for AutomationApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_automation_api_port(),
+        }
+    }
+}
+
+fn default_automation_api_port() -> u16 {
+    17321
+}
+
+/// 反检测脚本的全局设置；默认值与引入设置前的行为一致
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AntiDetectionConfig {
+    /// 关闭后所有站点都不注入反检测脚本
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub spoof_plugins: bool,
+    /// 伪造的 navigator.languages；为空时不伪造
+    #[serde(default = "default_spoof_languages")]
+    pub spoof_languages: Vec<String>,
+    #[serde(default = "default_true")]
+    pub spoof_platform: bool,
+    #[serde(default = "default_true")]
+    pub spoof_vendor: bool,
+    /// 伪造时区（默认关闭）
+    #[serde(default)]
+    pub spoof_timezone: bool,
+    /// IANA 时区名（如 "Asia/Shanghai"）；为空时按界面语言推断
+    #[serde(default)]
+    pub timezone: String,
+    /// 伪造 WebGL 的 UNMASKED_VENDOR/RENDERER（默认关闭）
+    #[serde(default)]
+    pub spoof_webgl: bool,
+    #[serde(default = "default_webgl_vendor")]
+    pub webgl_vendor: String,
+    #[serde(default = "default_webgl_renderer")]
+    pub webgl_renderer: String,
+}
+
+impl Default for AntiDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            spoof_plugins: true,
+            spoof_languages: default_spoof_languages(),
+            spoof_platform: true,
+            spoof_vendor: true,
+            spoof_timezone: false,
+            timezone: String::new(),
+            spoof_webgl: false,
+            webgl_vendor: default_webgl_vendor(),
+            webgl_renderer: default_webgl_renderer(),
+        }
+    }
+}
+
+fn default_webgl_vendor() -> String {
+    "Google Inc. (NVIDIA)".to_string()
+}
+
+fn default_webgl_renderer() -> String {
+    "ANGLE (NVIDIA, NVIDIA GeForce GTX 1650 Direct3D11 vs_5_0 ps_5_0, D3D11)".to_string()
+}
+
+/// 未配置伪造时区时，按界面语言取常见时区
+fn default_timezone_for_language(language: &str) -> &'static str {
+    match language {
+        "zh-TW" => "Asia/Taipei",
+        "zh-HK" => "Asia/Hong_Kong",
+        l if l == "zh" || l.starts_with("zh-") => "Asia/Shanghai",
+        "en-GB" => "Europe/London",
+        l if l == "en" || l.starts_with("en-") => "America/New_York",
+        l if l == "ja" || l.starts_with("ja-") => "Asia/Tokyo",
+        l if l == "ko" || l.starts_with("ko-") => "Asia/Seoul",
+        "es-AR" => "America/Argentina/Buenos_Aires",
+        l if l == "es" || l.starts_with("es-") => "Europe/Madrid",
+        l if l == "fr" || l.starts_with("fr-") => "Europe/Paris",
+        _ => "UTC",
+    }
+}
+
+/// WebGL 伪造字符串的长度上限
+const MAX_WEBGL_STRING_CHARS: usize = 256;
+
+/// 校验并规范化反检测设置（语言标签、时区名、WebGL 字符串）
+fn normalize_anti_detection_config(
+    settings: AntiDetectionConfig,
+) -> Result<AntiDetectionConfig, String> {
+    let timezone = settings.timezone.trim().to_string();
+    // 只校验格式；时区是否存在由页面中的 Intl 判断，无效时脚本不生效
+    let valid_timezone = timezone.len() <= 64
+        && timezone
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '+'));
+    if !valid_timezone {
+        return Err(format!("时区名无效: {}", timezone));
+    }
+    let webgl_string = |value: String, default: fn() -> String| -> Result<String, String> {
+        let value = value.trim().to_string();
+        if value.chars().count() > MAX_WEBGL_STRING_CHARS {
+            return Err(format!(
+                "WebGL 字符串过长（上限 {} 个字符）",
+                MAX_WEBGL_STRING_CHARS
+            ));
+        }
+        Ok(if value.is_empty() { default() } else { value })
+    };
+    Ok(AntiDetectionConfig {
+        spoof_languages: normalize_spoof_languages(settings.spoof_languages)?,
+        timezone,
+        webgl_vendor: webgl_string(settings.webgl_vendor, default_webgl_vendor)?,
+        webgl_renderer: webgl_string(settings.webgl_renderer, default_webgl_renderer)?,
+        ..settings
+    })
+}
+
+fn default_spoof_languages() -> Vec<String> {
+    ["zh-CN", "zh", "en-US", "en"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// 伪造语言列表的条数上限
+const MAX_SPOOF_LANGUAGES: usize = 16;
+
+/// 校验并规范化语言列表：去空白、去重，只允许 BCP 47 形式的标签（字母、数字与 `-`）
+fn normalize_spoof_languages(languages: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for language in languages {
+        let language = language.trim().to_string();
+        if language.is_empty() {
+            continue;
+        }
+        let valid = language.len() <= 35
+            && language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !valid {
+            return Err(format!("语言标签无效: {}", language));
+        }
+        if !normalized.iter().any(|l| l.eq_ignore_ascii_case(&language)) {
+            normalized.push(language);
+        }
+    }
+    if normalized.len() > MAX_SPOOF_LANGUAGES {
+        return Err(format!("语言最多 {} 个", MAX_SPOOF_LANGUAGES));
+    }
+    Ok(normalized)
+}
+
+/// 锁定模式下的豁免项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockExemptions {
+    /// 是否允许总结（会调用 API 并写入项目）
+    #[serde(default = "default_true")]
+    pub allow_summarize: bool,
+    /// 允许执行的修改类命令（如 "create_project"）
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+impl Default for LockExemptions {
+    fn default() -> Self {
+        Self {
+            allow_summarize: true,
+            commands: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
 fn default_sidebar_expanded_width() -> f64 {
@@ -132,10 +988,29 @@ fn default_language() -> String {
     "zh-CN".to_string()
 }
 
+/// 未配置系统提示词时使用的默认值
+const DEFAULT_AI_SYSTEM_PROMPT: &str = "你是一个擅长提炼上下文与约束的助手。";
+
+fn default_project_entry_limit() -> u32 {
+    50
+}
+
+fn default_shortcuts() -> BTreeMap<String, String> {
+    BTreeMap::from([(
+        shortcuts::TOGGLE_WINDOW_ACTION.to_string(),
+        "Ctrl+Shift+Space".to_string(),
+    )])
+}
+
+fn default_ai_api_temperature() -> f64 {
+    0.2
+}
+
 fn default_summary_prompt_template() -> String {
-    // Variables:
+    // Variables (see PROMPT_VARIABLES):
     // - {language}: desired output language label
     // - {text}: extracted page text
+    // - {site_name} / {site_url} / {date} / {time} / {project_title}
     "请把以下内容总结成可迁移的上下文（输出语言：{language}）：\n\n要求：\n1) 1段简短摘要（<=120字）\n2) 5-10条要点列表\n3) 关键约束/偏好（如有）\n\n输出为纯文本，结构：\n摘要: ...\n要点: - ...\n约束: - ...\n\n内容：\n{text}".to_string()
 }
 
@@ -153,16 +1028,52 @@ impl Default for AppConfig {
             sidebar_expanded_width: default_sidebar_expanded_width(),
             language: default_language(),
             summary_prompt_template: default_summary_prompt_template(),
-            ai_api_base_url: "https://api.openai.com/v1".to_string(),
+            prompt_templates: Vec::new(),
+            ai_api_temperature: default_ai_api_temperature(),
+            ai_api_max_tokens: None,
+            ai_system_prompt: String::new(),
+            ai_api_provider: AiProvider::OpenAi,
+            ai_api_auth: AiAuthMode::Bearer,
+            ai_api_base_url: AiProvider::OpenAi.default_base_url().to_string(),
             ai_api_model: "".to_string(),
+            ai_api_deployment: String::new(),
+            ai_api_version: String::new(),
             ai_api_key: "".to_string(),
+            ai_api_key_in_keyring: false,
+            ai_api_profiles: Vec::new(),
+            active_ai_profile_id: String::new(),
             active_project_id: "".to_string(),
             last_active_tab_id: String::new(),
             last_active_site_id: String::new(),
+            locked: false,
+            lock_exemptions: LockExemptions::default(),
+            automation_api: AutomationApiConfig::default(),
+            anti_detection: AntiDetectionConfig::default(),
+            demo_mode: false,
+            tab_hibernate_minutes: 0,
+            project_entry_limit: default_project_entry_limit(),
+            summary_max_chars: None,
+            summary_extract_timeout_secs: None,
+            summary_total_timeout_secs: None,
+            summary_append_notes: false,
+            http_proxy: None,
+            download_dir: String::new(),
+            notify_on_summary: true,
+            notify_on_summary_failure: false,
+            builtin_catalog_version: BUILTIN_CATALOG_VERSION,
+            shortcuts: default_shortcuts(),
+            session: SessionState::default(),
         }
     }
 }
 
+/// 内置站点目录版本：内置站点的默认地址变更时递增，并把旧默认地址登记到
+/// BUILTIN_SITE_URL_HISTORY；升级时仍为旧默认地址（用户未改过）的站点改为新地址
+const BUILTIN_CATALOG_VERSION: u32 = 1;
+
+/// 内置站点曾经使用过的默认地址：(site_id, 旧地址)
+const BUILTIN_SITE_URL_HISTORY: &[(&str, &str)] = &[];
+
 /// 获取内置站点列表
 fn get_builtin_sites() -> Vec<AiSite> {
     vec![
@@ -172,7 +1083,8 @@ fn get_builtin_sites() -> Vec<AiSite> {
             url: "https://chat.deepseek.com".to_string(),
             icon: "deepseek".to_string(),
             builtin: true,
-            summary_prompt_override: String::new(),
+            name_key: i18n::SITE_DEEPSEEK.to_string(),
+            ..Default::default()
         },
         AiSite {
             id: "doubao".to_string(),
@@ -180,7 +1092,8 @@ fn get_builtin_sites() -> Vec<AiSite> {
             url: "https://www.doubao.com/chat/".to_string(),
             icon: "doubao".to_string(),
             builtin: true,
-            summary_prompt_override: String::new(),
+            name_key: i18n::SITE_DOUBAO.to_string(),
+            ..Default::default()
         },
         AiSite {
             id: "openai".to_string(),
@@ -188,7 +1101,8 @@ fn get_builtin_sites() -> Vec<AiSite> {
             url: "https://chatgpt.com".to_string(),
             icon: "openai".to_string(),
             builtin: true,
-            summary_prompt_override: String::new(),
+            name_key: i18n::SITE_OPENAI.to_string(),
+            ..Default::default()
         },
         AiSite {
             id: "qianwen".to_string(),
@@ -196,17 +1110,56 @@ fn get_builtin_sites() -> Vec<AiSite> {
             url: "https://tongyi.aliyun.com/qianwen/".to_string(),
             icon: "qianwen".to_string(),
             builtin: true,
-            summary_prompt_override: String::new(),
+            name_key: i18n::SITE_QIANWEN.to_string(),
+            ..Default::default()
         },
     ]
 }
 
-// ============================================================================
-// 配置文件管理
-// ============================================================================
+/// 两个站点地址规范化后是否相同（任一无法解析时按原文比较）
+fn urls_equivalent(a: &str, b: &str) -> bool {
+    match (site_url::parse_site_url(a), site_url::parse_site_url(b)) {
+        (Ok(a), Ok(b)) => site_url::normalized_eq(&a, &b),
+        _ => a.trim() == b.trim(),
+    }
+}
 
-/// 获取配置文件路径
-fn get_config_path() -> PathBuf {
+/// 按当前语言解析站点显示名称（用户改过名的站点保持原样）
+fn localized_site(site: &AiSite, language: &str) -> AiSite {
+    let mut site = site.clone();
+    if !site.name_key.is_empty() {
+        if let Some(name) = i18n::lookup(language, &site.name_key) {
+            site.name = name.to_string();
+        }
+    }
+    site
+}
+
+/// 自动创建项目时使用的默认标题
+fn default_project_title(language: &str) -> String {
+    i18n::text(language, i18n::PROJECT_DEFAULT_TITLE)
+}
+
+// ============================================================================
+// 配置文件管理
+// ============================================================================
+
+/// 测试进程改用临时配置目录，避免读写真实的用户配置
+/// （Linux 上 directories 读取 XDG_CONFIG_HOME，macOS 上读取 HOME）
+#[cfg(test)]
+pub(crate) fn use_temp_config_dir() {
+    static DIR: Lazy<tempfile::TempDir> = Lazy::new(|| {
+        let dir = tempfile::tempdir().expect("创建临时配置目录失败");
+        std::env::set_var("XDG_CONFIG_HOME", dir.path().join("config"));
+        std::env::set_var("XDG_DATA_HOME", dir.path().join("data"));
+        std::env::set_var("HOME", dir.path());
+        dir
+    });
+    Lazy::force(&DIR);
+}
+
+/// 获取配置文件路径
+fn get_config_path() -> PathBuf {
     let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
         .expect("Could not get project directories");
     let config_dir = proj_dirs.config_dir();
@@ -214,6 +1167,42 @@ fn get_config_path() -> PathBuf {
     config_dir.join("config.json")
 }
 
+/// 实例锁路径（用于检测是否有其他进程正在使用配置目录）
+fn get_instance_lock_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("aihub.lock")
+}
+
+/// 主实例的单实例监听端口文件路径
+fn get_instance_ipc_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("instance_ipc")
+}
+
+/// 自动化 API token 文件路径
+fn get_automation_token_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("automation_token")
+}
+
+fn get_api_audit_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("api_audit.json")
+}
+
+/// 旧版本的单文件项目存储（启动时迁移到 `contexts/` 目录）
 fn get_contexts_path() -> PathBuf {
     let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
         .expect("Could not get project directories");
@@ -222,6 +1211,13 @@ fn get_contexts_path() -> PathBuf {
     config_dir.join("contexts.json")
 }
 
+/// 项目目录：每个项目一个文件（见 project_store）
+fn get_contexts_dir() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    proj_dirs.config_dir().join("contexts")
+}
+
 fn get_webview_error_log_path() -> PathBuf {
     let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
         .expect("Could not get project directories");
@@ -230,6 +1226,33 @@ fn get_webview_error_log_path() -> PathBuf {
     config_dir.join("webview-errors.log")
 }
 
+/// 总结 token 用量记录
+fn get_usage_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("usage.json")
+}
+
+/// 站点使用统计
+fn get_site_stats_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("stats.json")
+}
+
+/// 经确认执行的破坏性操作日志
+fn get_destructive_audit_log_path() -> PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let config_dir = proj_dirs.config_dir();
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join("destructive-actions.log")
+}
+
 fn append_webview_error_log(tab_id: &str, site_id: &str, url: &str, message: &str) {
     let path = get_webview_error_log_path();
     if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(path) {
@@ -244,23 +1267,63 @@ fn append_webview_error_log(tab_id: &str, site_id: &str, url: &str, message: &st
 
 /// 加载配置
 fn load_config() -> AppConfig {
-    let config_path = get_config_path();
+    load_config_from(&get_config_path())
+}
 
+/// 从指定路径加载配置；文件损坏时移走备份并写入默认配置
+fn load_config_from(config_path: &std::path::Path) -> AppConfig {
     if config_path.exists() {
-        match fs::read_to_string(&config_path) {
+        match fs::read_to_string(config_path) {
             Ok(content) => {
                 match serde_json::from_str::<AppConfig>(&content) {
                     Ok(mut config) => {
+                        // 各清理步骤实际改动了配置时才写回，避免每次启动都改写 config.json
+                        let mut changed = false;
+
                         // 去重 sites（避免历史 bug 导致重复站点）
                         let mut seen_sites: HashSet<String> = HashSet::new();
+                        let site_count = config.sites.len();
                         config.sites.retain(|s| seen_sites.insert(s.id.clone()));
+                        changed |= config.sites.len() != site_count;
 
-                        // 确保内置站点存在
+                        // 确保内置站点存在（已有的条目原样保留，包括停用状态）
                         let builtin_sites = get_builtin_sites();
                         for builtin in &builtin_sites {
                             if !config.sites.iter().any(|s| s.id == builtin.id) {
                                 config.sites.push(builtin.clone());
                                 config.site_order.push(builtin.id.clone());
+                                changed = true;
+                            }
+                        }
+
+                        // 内置站点目录升级：只更新仍为旧默认地址的站点，不覆盖用户修改
+                        if config.builtin_catalog_version < BUILTIN_CATALOG_VERSION {
+                            for site in config.sites.iter_mut().filter(|s| s.builtin) {
+                                let Some(builtin) = builtin_sites.iter().find(|b| b.id == site.id) else {
+                                    continue;
+                                };
+                                let is_old_default = BUILTIN_SITE_URL_HISTORY
+                                    .iter()
+                                    .any(|(id, old_url)| *id == site.id && urls_equivalent(old_url, &site.url));
+                                if is_old_default && site.url != builtin.url {
+                                    println!("[catalog] 内置站点 {} 地址更新为 {}", site.id, builtin.url);
+                                    site.url = builtin.url.clone();
+                                }
+                            }
+                            config.builtin_catalog_version = BUILTIN_CATALOG_VERSION;
+                            changed = true;
+                        }
+
+                        // 迁移：未被改名的内置站点补上名称 key，以便按语言显示
+                        for site in config.sites.iter_mut() {
+                            if !site.builtin || !site.name_key.is_empty() {
+                                continue;
+                            }
+                            if let Some(builtin) = builtin_sites.iter().find(|b| b.id == site.id) {
+                                if i18n::matches_any_language(&builtin.name_key, &site.name) {
+                                    site.name_key = builtin.name_key.clone();
+                                    changed = true;
+                                }
                             }
                         }
 
@@ -285,6 +1348,7 @@ fn load_config() -> AppConfig {
                                 next_order.push(site.id.clone());
                             }
                         }
+                        changed |= next_order != config.site_order;
                         config.site_order = next_order;
 
                         // 侧边栏展开宽度：迁移旧配置
@@ -293,13 +1357,17 @@ fn load_config() -> AppConfig {
                             && config.sidebar_expanded_width <= MIN_SIDEBAR_WIDTH
                         {
                             config.sidebar_expanded_width = config.sidebar_width;
+                            changed = true;
                         }
 
                         // 迁移 AI API base_url：若为空则使用默认
                         if config.ai_api_base_url.trim().is_empty() {
                             config.ai_api_base_url = "https://api.openai.com/v1".to_string();
+                            changed = true;
                         }
 
+                        let pinned_count = config.pinned_site_ids.len();
+                        let recent_count = config.recent_site_ids.len();
                         let mut seen = std::collections::HashSet::<String>::new();
                         config.pinned_site_ids.retain(|id| {
                             if !existing_ids.contains(id) {
@@ -323,30 +1391,67 @@ fn load_config() -> AppConfig {
                             seen_recent.insert(id.clone());
                             true
                         });
+                        changed |= config.pinned_site_ids.len() != pinned_count
+                            || config.recent_site_ids.len() != recent_count;
 
                         // 启动恢复：仅允许恢复存在的站点（Tab 仅限主 Tab）
+                        let last_active_before = (
+                            config.last_active_site_id.clone(),
+                            config.last_active_tab_id.clone(),
+                        );
                         let last_site = config.last_active_site_id.trim().to_string();
                         if last_site.is_empty() || !existing_ids.contains(&last_site) {
                             config.last_active_site_id.clear();
                             config.last_active_tab_id.clear();
                         } else {
                             config.last_active_site_id = last_site.clone();
-                            if config.last_active_tab_id.trim().is_empty()
-                                || !existing_ids.contains(&config.last_active_tab_id)
-                            {
-                                config.last_active_tab_id = last_site;
-                            } else {
-                                // 额外 Tab 不做持久化恢复，统一回落到主 Tab
-                                config.last_active_tab_id = last_site;
+                            // 额外 Tab 由 session 恢复，这里统一回落到主 Tab
+                            config.last_active_tab_id = last_site;
+                        }
+                        changed |= last_active_before
+                            != (
+                                config.last_active_site_id.clone(),
+                                config.last_active_tab_id.clone(),
+                            );
+
+                        // 迁移明文 API Key 到系统密钥环（只读模式下不迁移，避免与主实例冲突）；
+                        // 密钥环不可用或写入失败时 Key 留在配置文件中，不算作变化，以免每次启动都重写
+                        if !STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+                            if !config.ai_api_key.trim().is_empty() {
+                                let key = std::mem::take(&mut config.ai_api_key);
+                                store_ai_api_key(&mut config, key.trim());
+                                changed |= config.ai_api_key_in_keyring;
+                            }
+                            for profile in config.ai_api_profiles.iter_mut() {
+                                if !profile.key.trim().is_empty() {
+                                    let key = std::mem::take(&mut profile.key);
+                                    store_ai_profile_key(profile, key.trim());
+                                    changed |= profile.key_in_keyring;
+                                }
                             }
                         }
+                        if !config
+                            .ai_api_profiles
+                            .iter()
+                            .any(|p| p.id == config.active_ai_profile_id)
+                        {
+                            changed |= !config.active_ai_profile_id.is_empty();
+                            config.active_ai_profile_id.clear();
+                        }
 
-                        // 将清理/补齐后的配置写回，避免重复脏数据导致 UI 重复
-                        let _ = save_config(&config);
+                        // 将清理/补齐后的配置写回，避免重复脏数据导致 UI 重复；
+                        // 写入失败（如目录只读）时继续使用内存中清理后的配置
+                        if changed {
+                            if let Err(e) = save_config_to(config_path, &config) {
+                                println!("[config] 清理后的配置未能写回，本次使用内存中的配置: {}", e);
+                            }
+                        }
                         return config;
                     }
                     Err(e) => {
                         println!("配置解析失败: {}, 使用默认配置", e);
+                        // 保留损坏文件，避免用默认配置覆盖后无法找回
+                        quarantine_corrupt_file(config_path);
                     }
                 }
             }
@@ -358,1569 +1463,9664 @@ fn load_config() -> AppConfig {
 
     // 返回默认配置并保存
     let config = AppConfig::default();
-    let _ = save_config(&config);
+    let _ = save_config_to(config_path, &config);
     config
 }
 
 /// 保存配置
-fn save_config(config: &AppConfig) -> Result<(), String> {
-    let config_path = get_config_path();
+fn save_config(config: &AppConfig) -> Result<(), AppError> {
+    save_config_to(&get_config_path(), config)
+}
+
+fn save_config_to(config_path: &std::path::Path, config: &AppConfig) -> Result<(), AppError> {
+    ensure_storage_writable().map_err(AppError::ConfigIo)?;
     let content = serde_json::to_string_pretty(config)
-        .map_err(|e| format!("序列化配置失败: {}", e))?;
-    fs::write(&config_path, content)
-        .map_err(|e| format!("写入配置失败: {}", e))?;
+        .map_err(|e| AppError::ConfigIo(format!("序列化配置失败: {}", e)))?;
+    let _lock = file_lock::acquire_file_lock(config_path).map_err(AppError::ConfigIo)?;
+    write_file_atomic(config_path, content.as_bytes())
+        .map_err(|e| AppError::ConfigIo(format!("写入配置失败: {}", e)))?;
     Ok(())
 }
 
-// ============================================================================
-// 全局状态（使用 Mutex 保证线程安全）
-// ============================================================================
-
-/// 应用配置
-static APP_CONFIG: Lazy<Mutex<AppConfig>> = Lazy::new(|| Mutex::new(load_config()));
-
-/// 当前显示的视图 ID
-static CURRENT_VIEW: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+/// 原子写入：先写同目录临时文件并 fsync，再 rename 覆盖目标文件，
+/// 进程在写入中途被杀时目标文件保持旧内容而不会被截断
+fn write_file_atomic(path: &std::path::Path, content: &[u8]) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp_path = path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
 
-/// 已创建的 Webview 记录
-static CREATED_VIEWS: Lazy<Mutex<HashMap<String, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// 启动时发现并移走的损坏文件（前端就绪后通过 `get_startup_warnings` 获取）
+static CORRUPT_FILE_BACKUPS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
 
-/// 额外 Tab → 站点映射（主 Tab 使用 `tab_id == site_id`，不存这里）
-static TAB_SITE_MAP: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+#[derive(Debug, Clone, Serialize)]
+struct CorruptFilePayload {
+    original: String,
+    backup: String,
+}
 
-/// 当前活跃 Tab（用于单视图模式）
-static ACTIVE_TAB_ID: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+/// API 审计记录与 token 用量记录共用的保留条数
+const HISTORY_MAX_RECORDS: usize = 10_000;
 
-#[derive(Debug, Clone)]
-struct RetryState {
-    url: String,
-    attempts: u8,
+/// 按统一的保留策略裁剪按时间追加的记录：只保留最新的 HISTORY_MAX_RECORDS 条
+fn keep_newest_records<T>(records: &mut Vec<T>) {
+    if records.len() > HISTORY_MAX_RECORDS {
+        let excess = records.len() - HISTORY_MAX_RECORDS;
+        records.drain(..excess);
+    }
 }
 
-/// Tab 最后使用时间（Unix 秒）
-static LAST_USED_AT: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// 将解析失败的文件改名为 `<name>.corrupt-<timestamp>` 保留下来
+fn quarantine_corrupt_file(path: &std::path::Path) {
+    // 只读实例不改动文件，交由持有实例锁的进程处理
+    if ensure_storage_writable().is_err() {
+        return;
+    }
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup = path.with_file_name(format!("{}.corrupt-{}", file_name, now_ts()));
+    match fs::rename(path, &backup) {
+        Ok(()) => {
+            println!("[storage] 已将损坏文件移至 {}", backup.display());
+            CORRUPT_FILE_BACKUPS
+                .lock()
+                .unwrap()
+                .push(backup.to_string_lossy().to_string());
+        }
+        Err(e) => println!("[storage] 移动损坏文件失败: {}", e),
+    }
+}
 
-/// Tab 最近请求的 URL（用于失败重试）
-static LAST_REQUESTED_URL: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// 已移走的损坏文件（原路径与备份路径）
+fn corrupt_file_warnings() -> Vec<CorruptFilePayload> {
+    CORRUPT_FILE_BACKUPS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|backup| CorruptFilePayload {
+            original: backup
+                .rsplit_once(".corrupt-")
+                .map(|(orig, _)| orig.to_string())
+                .unwrap_or_else(|| backup.clone()),
+            backup: backup.clone(),
+        })
+        .collect()
+}
 
-/// Tab 重试状态（每个 URL 只重试一次）
-static RETRY_STATE: Lazy<Mutex<HashMap<String, RetryState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+// ============================================================================
+// 只读模式（另一个存活进程持有实例锁时启用）
+// ============================================================================
 
-#[derive(Debug, Clone)]
-enum LayoutMode {
-    Single,
-    Split,
-}
+static STORAGE_READ_ONLY: AtomicBool = AtomicBool::new(false);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ProjectContext {
-    id: String,
-    title: String,
-    notes: String,
-    summary: String,
-    created_at: u64,
-    updated_at: u64,
-}
+/// 持有实例锁的其他进程 PID（0 表示无）
+static STORAGE_LOCK_HOLDER_PID: AtomicU32 = AtomicU32::new(0);
 
-#[derive(Debug, Clone, Serialize)]
-struct ProjectSummary {
-    id: String,
-    title: String,
-    updated_at: u64,
+fn ensure_storage_writable() -> Result<(), String> {
+    if STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+        return Err("另一个 AI Hub 进程正在使用配置目录，当前为只读模式".to_string());
+    }
+    Ok(())
 }
 
-fn now_ts() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs()
+/// 启动时检测实例锁，被其他存活进程持有时进入只读模式
+fn init_storage_lock() {
+    match file_lock::acquire_instance_lock(&get_instance_lock_path()) {
+        file_lock::InstanceLockStatus::Acquired => {
+            STORAGE_READ_ONLY.store(false, Ordering::SeqCst);
+            STORAGE_LOCK_HOLDER_PID.store(0, Ordering::SeqCst);
+        }
+        file_lock::InstanceLockStatus::HeldByOther { pid } => {
+            println!("[storage] 配置目录被进程 {:?} 占用，进入只读模式", pid);
+            STORAGE_READ_ONLY.store(true, Ordering::SeqCst);
+            STORAGE_LOCK_HOLDER_PID.store(pid.unwrap_or(0), Ordering::SeqCst);
+        }
+    }
 }
 
-fn load_contexts() -> Vec<ProjectContext> {
-    let path = get_contexts_path();
-    if !path.exists() {
-        return Vec::new();
-    }
-    match fs::read_to_string(&path) {
-        Ok(content) => serde_json::from_str::<Vec<ProjectContext>>(&content).unwrap_or_default(),
-        Err(_) => Vec::new(),
+fn release_storage_lock() {
+    if !STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+        file_lock::release_instance_lock();
     }
 }
 
-fn save_contexts(contexts: &[ProjectContext]) -> Result<(), String> {
-    let path = get_contexts_path();
-    let content = serde_json::to_string_pretty(contexts)
-        .map_err(|e| format!("序列化 contexts 失败: {}", e))?;
-    fs::write(&path, content).map_err(|e| format!("写入 contexts 失败: {}", e))?;
-    Ok(())
+#[derive(Debug, Clone, Serialize)]
+struct StorageStatus {
+    read_only: bool,
+    holder_pid: Option<u32>,
+    /// 本次启动因解析失败而被移走的文件备份路径
+    corrupt_backups: Vec<String>,
+    /// 已运行的实例没有响应唤起请求（本进程因此以只读模式启动）
+    existing_instance_unresponsive: bool,
 }
 
-#[derive(Debug, Clone)]
-struct LayoutState {
-    mode: LayoutMode,
-    ratio: f64,
-    left_tab_id: Option<String>,
-    right_tab_id: Option<String>,
+fn storage_status() -> StorageStatus {
+    let pid = STORAGE_LOCK_HOLDER_PID.load(Ordering::SeqCst);
+    StorageStatus {
+        read_only: STORAGE_READ_ONLY.load(Ordering::SeqCst),
+        holder_pid: if pid == 0 { None } else { Some(pid) },
+        corrupt_backups: CORRUPT_FILE_BACKUPS.lock().unwrap().clone(),
+        existing_instance_unresponsive: single_instance::existing_unresponsive(),
+    }
 }
 
-impl Default for LayoutState {
-    fn default() -> Self {
-        Self {
-            mode: LayoutMode::Single,
-            ratio: 0.5,
-            left_tab_id: None,
-            right_tab_id: None,
+/// 另一个进程启动时唤起本实例：在主线程上前置主窗口（主线程卡住时返回 false），
+/// 参数中带 `--site <id>` 时按侧边栏的流程切换到该站点
+fn activate_from_second_instance(app: &tauri::AppHandle, args: Vec<String>) -> bool {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = app.clone();
+    let scheduled = app.run_on_main_thread(move || {
+        if let Ok(window) = get_main_window(&handle) {
+            let _ = window.show();
+            let _ = window.unminimize();
+            let _ = window.set_focus();
         }
+        let _ = tx.send(());
+    });
+    if scheduled.is_err() || rx.recv_timeout(single_instance::MAIN_THREAD_TIMEOUT).is_err() {
+        return false;
+    }
+    if let Some(link) = deep_link::find_in_args(&args) {
+        handle_deep_link(app, link);
+    } else if let Some(site_id) = single_instance::site_arg(&args) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            match switch_view_inner(app.clone(), site_id.clone()).await {
+                Ok(()) => {
+                    let _ = app.emit("view-switched", site_id);
+                }
+                Err(e) => println!("[single-instance] 打开站点失败: {}", e),
+            }
+        });
     }
+    true
 }
 
-static LAYOUT_STATE: Lazy<Mutex<LayoutState>> = Lazy::new(|| Mutex::new(LayoutState::default()));
-
-const TOP_BAR_HEIGHT: f64 = 48.0;
-
-/// 避免在创建 Webview 时处理 Resized 事件导致的潜在死锁
-static WEBVIEW_CREATE_IN_PROGRESS: AtomicUsize = AtomicUsize::new(0);
-static SUMMARY_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+// ============================================================================
+// API Key 存储（优先系统密钥环，不可用时回退到 config.json）
+// ============================================================================
 
-#[derive(Debug)]
-struct PendingExtract {
-    token: String,
-    tx: oneshot::Sender<String>,
-}
+/// 密钥环中的账户名
+const AI_API_KEY_ACCOUNT: &str = "ai_api_key";
 
-static PENDING_EXTRACTS: Lazy<Mutex<HashMap<String, PendingExtract>>> =
+/// 从密钥环读取的 API Key 缓存（account → key，避免每次总结都访问密钥环）
+static AI_API_KEY_CACHE: Lazy<Mutex<HashMap<String, String>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-fn is_main_invoker_webview(webview: &tauri::Webview) -> bool {
-    webview.label() == "main"
+fn ai_profile_key_account(profile_id: &str) -> String {
+    format!("ai_profile:{}", profile_id)
 }
 
-fn language_label(code: &str) -> &'static str {
-    match code {
-        "zh-CN" | "zh" => "中文",
-        "en" | "en-US" | "en-GB" => "English",
-        "ja" | "ja-JP" => "日本語",
-        "ko" | "ko-KR" => "한국어",
-        "es" | "es-ES" | "es-AR" => "Español",
-        "fr" | "fr-FR" => "Français",
-        _ => "English",
+/// 保存一个 Key：空字符串表示清除。只修改 `plain`/`in_keyring`，由调用方负责 `save_config`
+fn store_api_key_secret(account: &str, key: &str, plain: &mut String, in_keyring: &mut bool) {
+    AI_API_KEY_CACHE.lock().unwrap().remove(account);
+    if key.is_empty() {
+        if *in_keyring {
+            if let Err(e) = secret_store::delete_secret(account) {
+                println!("[secret] 删除密钥环中的 API Key 失败: {}", e);
+            }
+        }
+        plain.clear();
+        *in_keyring = false;
+        return;
     }
+    if secret_store::backend().is_available() {
+        match secret_store::set_secret(account, key) {
+            Ok(()) => {
+                plain.clear();
+                *in_keyring = true;
+                AI_API_KEY_CACHE
+                    .lock()
+                    .unwrap()
+                    .insert(account.to_string(), key.to_string());
+                return;
+            }
+            Err(e) => println!("[secret] 写入密钥环失败，回退到配置文件: {}", e),
+        }
+    }
+    *plain = key.to_string();
+    *in_keyring = false;
 }
 
-fn build_summary_prompt(template: &str, language: &str, text: &str) -> String {
-    let mut rendered = template
-        .replace("{language}", language)
-        .replace("{text}", text);
-    if !template.contains("{language}") {
-        rendered.push_str("\n\nLanguage: ");
-        rendered.push_str(language);
+/// 读取实际生效的 Key（密钥环或配置文件）
+fn resolve_api_key_secret(account: &str, plain: &str, in_keyring: bool) -> Result<String, String> {
+    if !in_keyring {
+        return Ok(plain.to_string());
     }
-    if !template.contains("{text}") {
-        rendered.push_str("\n\n");
-        rendered.push_str(text);
+    if let Some(key) = AI_API_KEY_CACHE.lock().unwrap().get(account).cloned() {
+        return Ok(key);
     }
-    rendered
+    let key = secret_store::get_secret(account)
+        .map_err(|e| format!("读取系统密钥环失败: {}", e))?
+        .unwrap_or_default();
+    AI_API_KEY_CACHE
+        .lock()
+        .unwrap()
+        .insert(account.to_string(), key.clone());
+    Ok(key)
 }
 
-// ============================================================================
-// 工具函数
-// ============================================================================
+fn store_ai_api_key(config: &mut AppConfig, key: &str) {
+    store_api_key_secret(
+        AI_API_KEY_ACCOUNT,
+        key,
+        &mut config.ai_api_key,
+        &mut config.ai_api_key_in_keyring,
+    );
+}
 
-/// 获取站点数据目录（用于 Cookie 持久化）
-fn get_data_dir(site_id: &str) -> std::path::PathBuf {
-    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
-        .expect("Could not get project directories");
-    proj_dirs.data_dir().join("webviews").join(site_id)
+fn resolve_ai_api_key(config: &AppConfig) -> Result<String, String> {
+    resolve_api_key_secret(
+        AI_API_KEY_ACCOUNT,
+        &config.ai_api_key,
+        config.ai_api_key_in_keyring,
+    )
 }
 
-/// 获取 Tab 对应的数据目录
-/// - 主 Tab（tab_id == site_id）使用站点目录（兼容已有数据）
-/// - 额外 Tab 使用独立目录，避免多个 WebView2 实例同时占用同一 profile 目录导致卡死
-fn get_tab_data_dir(site_id: &str, tab_id: &str) -> std::path::PathBuf {
-    if tab_id == site_id {
-        return get_data_dir(site_id);
+fn store_ai_profile_key(profile: &mut AiApiProfile, key: &str) {
+    let account = ai_profile_key_account(&profile.id);
+    store_api_key_secret(&account, key, &mut profile.key, &mut profile.key_in_keyring);
+}
+
+fn resolve_ai_profile_key(profile: &AiApiProfile) -> Result<String, String> {
+    resolve_api_key_secret(
+        &ai_profile_key_account(&profile.id),
+        &profile.key,
+        profile.key_in_keyring,
+    )
+}
+
+/// 去掉首尾空白与末尾的 `/`，为空时使用该协议的默认地址
+fn normalize_ai_base_url(raw: &str, provider: AiProvider) -> String {
+    let trimmed = raw.trim().trim_end_matches('/');
+    if trimmed.is_empty() {
+        provider.default_base_url().to_string()
+    } else {
+        trimmed.to_string()
     }
-    // 注意：避免把一个 profile 目录嵌套在另一个 profile 目录内（Windows WebView2 可能会卡住）
-    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
-        .expect("Could not get project directories");
-    proj_dirs
-        .data_dir()
-        .join("webviews_tabs")
-        .join(site_id)
-        .join(tab_id)
 }
 
-/// 计算 Webview 的位置和尺寸
-fn calculate_webview_bounds(window: &tauri::Window) -> (LogicalPosition<f64>, LogicalSize<f64>) {
-    let size = window.inner_size().unwrap_or_default();
-    let scale = window.scale_factor().unwrap_or(1.0);
+/// 总结请求实际使用的 AI 设置
+#[derive(Debug, Clone)]
+struct ResolvedAiSettings {
+    provider: AiProvider,
+    auth_mode: AiAuthMode,
+    base_url: String,
+    model: String,
+    api_key: String,
+    deployment: String,
+    api_version: String,
+}
 
-    // 获取动态侧边栏宽度
-    let sidebar_width = APP_CONFIG.lock().unwrap().sidebar_width;
+/// Anthropic 要求显式指定输出上限
+const ANTHROPIC_MAX_TOKENS: u32 = 4096;
 
-    // 转换为逻辑像素
-    let window_width = size.width as f64 / scale;
-    let window_height = size.height as f64 / scale;
+/// Azure 未指定 `api-version` 时使用的版本
+const AZURE_DEFAULT_API_VERSION: &str = "2024-06-01";
 
-    // Webview 位置：从侧边栏右侧开始，并避开顶部栏（chrome）
-    let position = LogicalPosition::new(sidebar_width, TOP_BAR_HEIGHT);
+/// 总结的总耗时上限；本地模型首次加载与首 token 较慢，单独放宽
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(60);
+const LOCAL_MODEL_SUMMARY_TIMEOUT: Duration = Duration::from_secs(180);
 
-    // Webview 尺寸：窗口宽度减去侧边栏，高度减去顶部栏
-    let webview_width = (window_width - sidebar_width).max(100.0);
-    let webview_height = (window_height - TOP_BAR_HEIGHT).max(100.0);
-    let size = LogicalSize::new(webview_width, webview_height);
+/// 提取页面文本的默认超时
+const SUMMARY_EXTRACT_TIMEOUT: Duration = Duration::from_secs(20);
 
-    (position, size)
-}
+/// 可配置的提取/总结超时范围（秒）
+const SUMMARY_TIMEOUT_RANGE_SECS: std::ops::RangeInclusive<u32> = 5..=600;
 
-fn get_main_window(app: &tauri::AppHandle) -> Result<tauri::Window, String> {
-    if let Some(main_window) = app.get_webview_window("main") {
-        Ok(main_window.as_ref().window().clone())
-    } else if let Some(win) = app.get_window("main") {
-        Ok(win)
-    } else {
-        Err("主窗口不存在".to_string())
-    }
+/// 配置的超时（超出范围的旧值按范围截断）
+fn configured_timeout(secs: Option<u32>) -> Option<Duration> {
+    secs.map(|s| {
+        Duration::from_secs(
+            s.clamp(*SUMMARY_TIMEOUT_RANGE_SECS.start(), *SUMMARY_TIMEOUT_RANGE_SECS.end()) as u64,
+        )
+    })
 }
 
-fn get_site_by_id(site_id: &str) -> Result<AiSite, String> {
-    let config = APP_CONFIG.lock().unwrap();
-    config
-        .sites
-        .iter()
-        .find(|s| s.id == site_id)
-        .cloned()
-        .ok_or_else(|| format!("站点不存在: {}", site_id))
+fn summary_extract_timeout(config: &AppConfig) -> Duration {
+    configured_timeout(config.summary_extract_timeout_secs).unwrap_or(SUMMARY_EXTRACT_TIMEOUT)
 }
 
-fn get_tab_site_id(tab_id: &str) -> Result<String, String> {
-    // 主 Tab：tab_id == site_id
-    if APP_CONFIG.lock().unwrap().sites.iter().any(|s| s.id == tab_id) {
-        return Ok(tab_id.to_string());
+/// 总结的总耗时上限：用户配置优先，否则按服务是否为本地模型取默认值
+fn summary_total_timeout(config: &AppConfig) -> Duration {
+    configured_timeout(config.summary_total_timeout_secs).unwrap_or_else(|| {
+        resolve_ai_settings(config)
+            .map(|settings| settings.summary_timeout())
+            .unwrap_or(SUMMARY_TIMEOUT)
+    })
+}
+
+impl ResolvedAiSettings {
+    fn auth(&self) -> ai_client::ApiAuth<'_> {
+        match (self.provider, self.auth_mode) {
+            (AiProvider::Anthropic, _) => ai_client::ApiAuth::Anthropic(&self.api_key),
+            (AiProvider::Azure, _) => ai_client::ApiAuth::Azure(&self.api_key),
+            (AiProvider::OpenAi, AiAuthMode::Bearer) => ai_client::ApiAuth::Bearer(&self.api_key),
+            (AiProvider::OpenAi, AiAuthMode::None) => ai_client::ApiAuth::None,
+        }
     }
 
-    TAB_SITE_MAP
-        .lock()
-        .unwrap()
-        .get(tab_id)
-        .cloned()
-        .ok_or_else(|| "Tab 不存在".to_string())
-}
+    /// 需要 Key 却未配置（只有 OpenAI 兼容接口可以不带 Key）
+    fn missing_key(&self) -> bool {
+        let required = self.provider != AiProvider::OpenAi || self.auth_mode == AiAuthMode::Bearer;
+        required && self.api_key.is_empty()
+    }
 
-fn upsert_recent_site(site_id: &str) {
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.recent_site_ids.retain(|id| id != site_id);
-    config.recent_site_ids.insert(0, site_id.to_string());
-    config.recent_site_ids.truncate(10);
-    let _ = save_config(&config);
-}
+    /// 未配置模型（Azure 为部署名称）
+    fn missing_model(&self) -> bool {
+        match self.provider {
+            AiProvider::Azure => self.azure_deployment().is_empty(),
+            _ => self.model.is_empty(),
+        }
+    }
 
-fn update_last_active(tab_id: &str, site_id: &str) {
-    let tab_id = tab_id.trim();
-    let site_id = site_id.trim();
-    if tab_id.is_empty() || site_id.is_empty() {
-        return;
+    fn azure_deployment(&self) -> &str {
+        if self.deployment.is_empty() {
+            &self.model
+        } else {
+            &self.deployment
+        }
     }
-    let mut config = APP_CONFIG.lock().unwrap();
-    if config.last_active_tab_id == tab_id && config.last_active_site_id == site_id {
-        return;
+
+    fn azure_api_version(&self) -> &str {
+        if self.api_version.is_empty() {
+            AZURE_DEFAULT_API_VERSION
+        } else {
+            &self.api_version
+        }
     }
-    config.last_active_tab_id = tab_id.to_string();
-    config.last_active_site_id = site_id.to_string();
-    let _ = save_config(&config);
-}
 
-fn clear_last_active() {
-    let mut config = APP_CONFIG.lock().unwrap();
-    if config.last_active_tab_id.is_empty() && config.last_active_site_id.is_empty() {
-        return;
+    /// Base URL 指向本机（localhost / 回环地址）
+    fn is_local(&self) -> bool {
+        let Ok(url) = url::Url::parse(&self.base_url) else {
+            return false;
+        };
+        match url.host() {
+            Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        }
     }
-    config.last_active_tab_id.clear();
-    config.last_active_site_id.clear();
-    let _ = save_config(&config);
-}
 
-fn touch_tab(tab_id: &str) {
-    if tab_id.trim().is_empty() {
-        return;
+    fn summary_timeout(&self) -> Duration {
+        if self.is_local() {
+            LOCAL_MODEL_SUMMARY_TIMEOUT
+        } else {
+            SUMMARY_TIMEOUT
+        }
     }
-    LAST_USED_AT
-        .lock()
-        .unwrap()
-        .insert(tab_id.to_string(), now_ts());
-}
 
-fn mark_last_requested_url(tab_id: &str, url: &tauri::Url) {
-    if is_error_url(url) {
-        return;
+    fn models_url(&self) -> String {
+        match self.provider {
+            AiProvider::Azure => format!(
+                "{}/openai/models?api-version={}",
+                self.base_url,
+                self.azure_api_version()
+            ),
+            _ => format!("{}/models", self.base_url),
+        }
+    }
+
+    /// 对话请求的 URL 与请求体；`max_tokens` 为空时 OpenAI 使用服务端默认值
+    fn chat_request(
+        &self,
+        system: Option<&str>,
+        user: &str,
+        max_tokens: Option<u32>,
+        stream: bool,
+    ) -> (String, serde_json::Value) {
+        match self.provider {
+            AiProvider::OpenAi | AiProvider::Azure => {
+                let mut messages = Vec::new();
+                if let Some(system) = system {
+                    messages.push(serde_json::json!({ "role": "system", "content": system }));
+                }
+                messages.push(serde_json::json!({ "role": "user", "content": user }));
+                let mut body = serde_json::json!({
+                    "model": self.model,
+                    "messages": messages,
+                    "stream": stream
+                });
+                if let Some(max_tokens) = max_tokens {
+                    body["max_tokens"] = max_tokens.into();
+                }
+                // 流式响应默认不带 usage；Azure 旧版 api-version 不识别该参数，不发送
+                if stream && self.provider == AiProvider::OpenAi {
+                    body["stream_options"] = serde_json::json!({ "include_usage": true });
+                }
+                let url = if self.provider == AiProvider::Azure {
+                    format!(
+                        "{}/openai/deployments/{}/chat/completions?api-version={}",
+                        self.base_url,
+                        self.azure_deployment(),
+                        self.azure_api_version()
+                    )
+                } else {
+                    format!("{}/chat/completions", self.base_url)
+                };
+                (url, body)
+            }
+            // 暂不支持流式：一次性返回后整体推送
+            AiProvider::Anthropic => {
+                let mut body = serde_json::json!({
+                    "model": self.model,
+                    "messages": [{ "role": "user", "content": user }],
+                    "max_tokens": max_tokens.unwrap_or(ANTHROPIC_MAX_TOKENS)
+                });
+                if let Some(system) = system {
+                    body["system"] = system.into();
+                }
+                (format!("{}/messages", self.base_url), body)
+            }
+        }
     }
-    LAST_REQUESTED_URL
-        .lock()
-        .unwrap()
-        .insert(tab_id.to_string(), url.as_str().to_string());
 }
 
-fn reset_retry_state(tab_id: &str) {
-    RETRY_STATE.lock().unwrap().remove(tab_id);
+/// 解析生效的 AI 设置：优先使用选中的档案，否则回退到旧的平铺字段
+fn resolve_ai_settings(config: &AppConfig) -> Result<ResolvedAiSettings, String> {
+    let (provider, auth_mode, base_url, model, api_key, deployment, api_version) = match config
+        .ai_api_profiles
+        .iter()
+        .find(|p| !config.active_ai_profile_id.is_empty() && p.id == config.active_ai_profile_id)
+    {
+        Some(profile) => (
+            profile.provider,
+            profile.auth,
+            profile.base_url.clone(),
+            profile.model.clone(),
+            resolve_ai_profile_key(profile)?,
+            profile.deployment.clone(),
+            profile.api_version.clone(),
+        ),
+        None => (
+            config.ai_api_provider,
+            config.ai_api_auth,
+            config.ai_api_base_url.clone(),
+            config.ai_api_model.clone(),
+            resolve_ai_api_key(config)?,
+            config.ai_api_deployment.clone(),
+            config.ai_api_version.clone(),
+        ),
+    };
+    Ok(ResolvedAiSettings {
+        provider,
+        auth_mode,
+        base_url: normalize_ai_base_url(&base_url, provider),
+        model: model.trim().to_string(),
+        api_key: api_key.trim().to_string(),
+        deployment: deployment.trim().to_string(),
+        api_version: api_version.trim().to_string(),
+    })
 }
 
-fn register_retry_attempt(tab_id: &str, url: &str) -> bool {
-    let mut state = RETRY_STATE.lock().unwrap();
-    let entry = state.entry(tab_id.to_string()).or_insert(RetryState {
-        url: url.to_string(),
-        attempts: 0,
-    });
-    if entry.url != url {
-        entry.url = url.to_string();
-        entry.attempts = 0;
-    }
-    if entry.attempts == 0 {
-        entry.attempts = 1;
-        return true;
+/// 返回给前端的档案信息（不含 Key）
+#[derive(Debug, Clone, Serialize)]
+struct AiApiProfileInfo {
+    id: String,
+    name: String,
+    provider: AiProvider,
+    auth: AiAuthMode,
+    base_url: String,
+    model: String,
+    deployment: String,
+    api_version: String,
+    has_key: bool,
+    key_in_keyring: bool,
+    active: bool,
+}
+
+fn ai_profile_info(profile: &AiApiProfile, active_id: &str) -> AiApiProfileInfo {
+    AiApiProfileInfo {
+        id: profile.id.clone(),
+        name: profile.name.clone(),
+        provider: profile.provider,
+        auth: profile.auth,
+        base_url: profile.base_url.clone(),
+        model: profile.model.clone(),
+        deployment: profile.deployment.clone(),
+        api_version: profile.api_version.clone(),
+        has_key: profile.key_in_keyring || !profile.key.trim().is_empty(),
+        key_in_keyring: profile.key_in_keyring,
+        active: profile.id == active_id,
     }
-    state.remove(tab_id);
-    false
 }
 
-fn gc_idle_webviews(app: &tauri::AppHandle) {
-    let now = now_ts();
-    let layout = LAYOUT_STATE.lock().unwrap().clone();
-    let active = ACTIVE_TAB_ID.lock().unwrap().clone();
-    let current = CURRENT_VIEW.lock().unwrap().clone();
+#[derive(Debug, Clone, Serialize)]
+struct AiApiKeyStatus {
+    /// 当前平台的密钥环后端（`unavailable` 表示回退到 config.json）
+    backend: String,
+    in_keyring: bool,
+    has_key: bool,
+    /// 演示模式（前端据此显示提示横幅）
+    demo_mode: bool,
+}
 
-    let mut protected: HashSet<String> = HashSet::new();
-    if !active.is_empty() {
-        protected.insert(active);
-    }
-    if !current.is_empty() {
-        protected.insert(current);
+// ============================================================================
+// 全局状态（使用 Mutex 保证线程安全）
+// ============================================================================
+
+/// 应用配置
+static APP_CONFIG: Lazy<Mutex<AppConfig>> = Lazy::new(|| Mutex::new(load_config()));
+
+/// 视图、Tab 与布局状态（见 `AppState`）
+static APP_STATE: Lazy<Mutex<AppState>> = Lazy::new(|| Mutex::new(AppState::default()));
+
+#[derive(Debug, Clone)]
+struct RetryState {
+    url: String,
+    attempts: u8,
+}
+
+
+#[derive(Debug, Clone, Default)]
+struct BridgeState {
+    /// 页面加载序号（每次开始加载递增，用于丢弃过期的超时判定）
+    load_seq: u64,
+    /// None = 尚未确定；Some(false) = 页面无法调用后端命令
+    ok: Option<bool>,
+    /// 最近一次存活检测的序号
+    ping_seq: u64,
+    /// 页面回应过的最大检测序号
+    pong_seq: u64,
+    /// 已判定为无响应并通知前端（收到回应或重新加载前不再重复通知）
+    unresponsive: bool,
+}
+
+/// 单个 Tab 的运行时状态。
+///
+/// 关闭 Webview（休眠、重启、加载失败）时由 `destroy_tab_webview` 整体移除，只把缩放与静音放回；
+/// 关闭 Tab 时由 `close_tab_webview` 整体移除。新增的按 Tab 状态应放在这里，而不是另开一个全局表
+#[derive(Debug, Clone, Default)]
+struct TabRuntime {
+    /// 最后使用时间（Unix 秒）
+    last_used_at: Option<u64>,
+    /// 已休眠（Webview 已关闭，切回时由 ensure_tab_webview 重建）
+    hibernated: bool,
+    /// 最近请求的 URL（用于失败重试）
+    last_requested_url: Option<String>,
+    /// 重试状态（每个 URL 只重试一次）
+    retry: Option<RetryState>,
+    /// 页面与后端的脚本桥接状态（含存活检测）
+    bridge: BridgeState,
+    /// 当前页面的标题、地址与加载状态
+    page: TabPageState,
+    /// 最近一次提取的页面文本
+    extraction: Option<CachedExtraction>,
+    /// 所在独立窗口的 label
+    detached_window: Option<String>,
+    /// 缩放比例（休眠后保留）；没有记录时使用站点的 `zoom`
+    zoom: Option<f64>,
+    /// 已静音（休眠后保留）
+    muted: bool,
+}
+
+impl TabRuntime {
+    /// 关闭 Webview 后仍需保留的部分；没有需要保留的内容时为 None
+    fn kept_after_webview_closed(&self) -> Option<TabRuntime> {
+        (self.zoom.is_some() || self.muted).then(|| TabRuntime {
+            zoom: self.zoom,
+            muted: self.muted,
+            ..TabRuntime::default()
+        })
     }
-    if let LayoutMode::Split = layout.mode {
-        if let Some(left) = layout.left_tab_id {
-            protected.insert(left);
-        }
-        if let Some(right) = layout.right_tab_id {
-            protected.insert(right);
-        }
+}
+
+/// tab_id -> 运行时状态
+static TAB_RUNTIME: Lazy<Mutex<HashMap<String, TabRuntime>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 读取 Tab 的运行时状态（没有记录时返回 None，不创建）
+fn read_tab_runtime<R>(tab_id: &str, f: impl FnOnce(&TabRuntime) -> R) -> Option<R> {
+    TAB_RUNTIME.lock().unwrap().get(tab_id).map(f)
+}
+
+/// 修改 Tab 的运行时状态（没有记录时先创建）
+fn update_tab_runtime<R>(tab_id: &str, f: impl FnOnce(&mut TabRuntime) -> R) -> R {
+    f(TAB_RUNTIME
+        .lock()
+        .unwrap()
+        .entry(tab_id.to_string())
+        .or_default())
+}
+
+/// Webview 已关闭：移除 Tab 的运行时状态并放回需要保留的部分，返回移除前的状态
+fn reset_tab_runtime(tab_id: &str) -> Option<TabRuntime> {
+    let mut runtimes = TAB_RUNTIME.lock().unwrap();
+    let removed = runtimes.remove(tab_id)?;
+    if let Some(kept) = removed.kept_after_webview_closed() {
+        runtimes.insert(tab_id.to_string(), kept);
     }
+    Some(removed)
+}
 
-    let stale_tabs: Vec<String> = LAST_USED_AT
+/// 满足条件的 Tab
+fn tab_runtime_ids(filter: impl Fn(&TabRuntime) -> bool) -> Vec<String> {
+    TAB_RUNTIME
         .lock()
         .unwrap()
         .iter()
-        .filter(|(tab_id, ts)| {
-            now.saturating_sub(**ts) > WEBVIEW_IDLE_TTL_SECS && !protected.contains(tab_id.as_str())
-        })
+        .filter(|(_, runtime)| filter(runtime))
         .map(|(tab_id, _)| tab_id.clone())
-        .collect();
+        .collect()
+}
 
-    for tab_id in stale_tabs {
-        destroy_tab_webview(app, &tab_id);
+#[derive(Debug, Clone)]
+enum LayoutMode {
+    Single,
+    Split,
+}
+
+/// 分屏方向：horizontal = 左右排列，vertical = 上下排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum SplitOrientation {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+impl SplitOrientation {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "horizontal" => Ok(SplitOrientation::Horizontal),
+            "vertical" => Ok(SplitOrientation::Vertical),
+            other => Err(format!("orientation 仅支持 horizontal|vertical: {}", other)),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SplitOrientation::Horizontal => "horizontal",
+            SplitOrientation::Vertical => "vertical",
+        }
     }
 }
 
-fn is_error_url(url: &tauri::Url) -> bool {
-    let raw = url.as_str();
-    raw.starts_with("edge-error://")
-        || raw.starts_with("chrome-error://")
-        || raw.starts_with("about:neterror")
-        || raw.starts_with("about:blank")
-        || raw.starts_with("about:error")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProjectContext {
+    id: String,
+    title: String,
+    notes: String,
+    summary: String,
+    /// notes 的来源页面（多 Tab 合并总结时记录每个来源）
+    #[serde(default)]
+    sources: Vec<ProjectSource>,
+    /// 历次总结（按时间先后）；`summary` 始终为最近一次，兼容旧版本
+    #[serde(default)]
+    entries: Vec<ContextEntry>,
+    /// 标签（已规范化，见 `normalize_project_tags`）
+    #[serde(default)]
+    tags: Vec<String>,
+    created_at: time::Timestamp,
+    updated_at: time::Timestamp,
+    /// 移到回收站的时间；回收站中的项目不出现在项目列表中，超过 TRASH_RETENTION_SECS 后清除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<time::Timestamp>,
 }
 
-fn restore_last_active_state(app: &tauri::AppHandle) {
-    let site_id = {
-        let config = APP_CONFIG.lock().unwrap();
-        config.last_active_site_id.clone()
-    };
+/// 回收站保留时长（30 天）
+const TRASH_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
 
-    if site_id.trim().is_empty() {
-        return;
+/// 项目中的一次总结记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ContextEntry {
+    id: String,
+    /// 来源站点；多 Tab 合并总结时为空（来源见项目的 `sources`）
+    #[serde(default)]
+    site_id: String,
+    /// 分屏对比总结的两个来源站点（其他记录为空）
+    #[serde(default)]
+    site_ids: Vec<String>,
+    created_at: time::Timestamp,
+    /// 原文开头（至多 CONTEXT_ENTRY_EXCERPT_CHARS 个字符）
+    #[serde(default)]
+    source_excerpt: String,
+    summary: String,
+    /// 原文的规模估算（旧记录没有）
+    #[serde(default)]
+    estimate: Option<SummaryEstimate>,
+}
+
+/// 总结记录中保留的原文字符数
+const CONTEXT_ENTRY_EXCERPT_CHARS: usize = 500;
+
+/// 文本规模估算：字符数与大致 token 数
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct SummaryEstimate {
+    chars: usize,
+    approx_tokens: usize,
+}
+
+impl SummaryEstimate {
+    fn of(text: &str) -> Self {
+        Self {
+            chars: text.chars().count(),
+            approx_tokens: estimate_tokens(text),
+        }
     }
+}
 
-    if get_site_by_id(&site_id).is_err() {
-        clear_last_active();
-        return;
+/// 粗略估算 token 数：CJK 等宽字符约 1 字 1 token，其余按约 4 字符 1 token。
+/// 只用于提示请求规模，不追求与具体模型的分词一致
+fn estimate_tokens(text: &str) -> usize {
+    let (wide, other) = text.chars().fold((0usize, 0usize), |(wide, other), c| {
+        if (c as u32) >= 0x2E80 && !c.is_whitespace() {
+            (wide + 1, other)
+        } else {
+            (wide, other + 1)
+        }
+    });
+    wide + other.div_ceil(4)
+}
+
+/// 超过配置的字数上限时拒绝总结（`max_chars` 为 None 表示不限制）
+fn check_summary_size(estimate: SummaryEstimate, max_chars: Option<u32>) -> Result<(), String> {
+    match max_chars {
+        Some(max_chars) if estimate.chars > max_chars as usize => Err(format!(
+            "页面文本约 {} 字（约 {} tokens），超过上限 {} 字。请配置对话内容选择器缩小提取范围，或在设置中调高上限",
+            estimate.chars, estimate.approx_tokens, max_chars
+        )),
+        _ => Ok(()),
     }
+}
 
-    {
-        let mut layout = LAYOUT_STATE.lock().unwrap();
-        layout.mode = LayoutMode::Single;
-        layout.left_tab_id = None;
-        layout.right_tab_id = None;
+impl ProjectContext {
+    fn new(id: String, title: String) -> Self {
+        let ts = time::Timestamp::now();
+        Self {
+            id,
+            title,
+            notes: String::new(),
+            summary: String::new(),
+            sources: Vec::new(),
+            entries: Vec::new(),
+            tags: Vec::new(),
+            created_at: ts,
+            updated_at: ts,
+            deleted_at: None,
+        }
     }
 
-    *ACTIVE_TAB_ID.lock().unwrap() = site_id.clone();
-    *CURRENT_VIEW.lock().unwrap() = site_id.clone();
+    fn is_trashed(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// 追加一次总结并更新 `summary`；超过 `limit` 条时丢弃最早的记录
+    fn append_entry(&mut self, site_id: &str, source: &str, summary: &str, limit: usize) {
+        let now = time::Timestamp::now();
+        self.entries.push(ContextEntry {
+            id: format!("entry_{}", Uuid::new_v4().simple()),
+            site_id: site_id.to_string(),
+            site_ids: Vec::new(),
+            created_at: now,
+            source_excerpt: source
+                .trim()
+                .chars()
+                .take(CONTEXT_ENTRY_EXCERPT_CHARS)
+                .collect(),
+            summary: summary.to_string(),
+            estimate: Some(SummaryEstimate::of(source)),
+        });
+        if self.entries.len() > limit.max(1) {
+            let excess = self.entries.len() - limit.max(1);
+            self.entries.drain(..excess);
+        }
+        self.summary = summary.to_string();
+        self.updated_at = now;
+    }
 
-    if ensure_tab_webview(app, &site_id, &site_id).is_err() {
-        *ACTIVE_TAB_ID.lock().unwrap() = String::new();
-        *CURRENT_VIEW.lock().unwrap() = String::new();
-        clear_last_active();
-        return;
+    /// 旧版本只有 `summary`：转换为一条记录（id 固定，重复加载结果一致）
+    fn migrate_legacy_summary(&mut self) {
+        if !self.entries.is_empty() || self.summary.trim().is_empty() {
+            return;
+        }
+        self.entries.push(ContextEntry {
+            id: format!("entry_legacy_{}", self.id),
+            site_id: self
+                .sources
+                .first()
+                .map(|s| s.site_id.clone())
+                .unwrap_or_default(),
+            site_ids: Vec::new(),
+            created_at: self.updated_at,
+            source_excerpt: self
+                .notes
+                .trim()
+                .chars()
+                .take(CONTEXT_ENTRY_EXCERPT_CHARS)
+                .collect(),
+            summary: self.summary.clone(),
+            estimate: None,
+        });
     }
+}
 
-    let _ = resize_webviews_inner(app, true);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProjectSource {
+    tab_id: String,
+    site_id: String,
+    site_name: String,
+    title: String,
+    url: String,
 }
 
-fn ensure_tab_webview(app: &tauri::AppHandle, tab_id: &str, site_id: &str) -> Result<(), String> {
-    let window = get_main_window(app)?;
-    let (position, size) = calculate_webview_bounds(&window);
-    let webview_label = format!("ai_{}", tab_id);
+#[derive(Debug, Clone, Serialize)]
+struct ProjectSummary {
+    id: String,
+    title: String,
+    created_at: time::Timestamp,
+    updated_at: time::Timestamp,
+    /// 相对更新时间（如“3 天前”），按界面语言生成
+    updated_label: String,
+    tags: Vec<String>,
+    /// 笔记与总结的字符数（列表中展示规模，无需拉取完整项目）
+    notes_chars: usize,
+    summary_chars: usize,
+}
 
-    println!(
-        "[ensure_tab_webview] tab_id={} site_id={} label={}",
-        tab_id, site_id, webview_label
-    );
+/// 项目列表的排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ProjectSortBy {
+    #[default]
+    Updated,
+    Created,
+    Title,
+}
 
-    let view_exists = CREATED_VIEWS.lock().unwrap().contains_key(tab_id);
-    if view_exists {
-        if let Some(webview) = app.get_webview(&webview_label) {
-            let _ = webview.set_position(position);
-            let _ = webview.set_size(size);
-            touch_tab(tab_id);
-            return Ok(());
+impl ProjectSortBy {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "updated" => Ok(ProjectSortBy::Updated),
+            "created" => Ok(ProjectSortBy::Created),
+            "title" => Ok(ProjectSortBy::Title),
+            other => Err(format!("sort_by 仅支持 updated|created|title: {}", other)),
         }
-
-        CREATED_VIEWS.lock().unwrap().remove(tab_id);
     }
+}
 
-    let site = get_site_by_id(site_id)?;
-    let _ = app.emit("webview-loading", site_id);
+/// 每个项目的标签数上限
+const MAX_PROJECT_TAGS: usize = 20;
 
-    let data_dir = get_tab_data_dir(site_id, tab_id);
-    println!("[ensure_tab_webview] data_dir={}", data_dir.display());
-    let _ = std::fs::create_dir_all(&data_dir);
+/// 单个标签的字符数上限
+const MAX_PROJECT_TAG_CHARS: usize = 32;
 
-    let url: tauri::Url = site
-        .url
-        .parse()
-        .map_err(|e| format!("URL 解析失败: {}", e))?;
+/// 去除首尾空白、丢弃空标签，并按不区分大小写去重（保留首次出现的写法）
+fn normalize_project_tags(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut seen = HashSet::new();
+    let mut normalized = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_PROJECT_TAG_CHARS {
+            return Err(format!(
+                "标签过长（至多 {} 个字符）: {}",
+                MAX_PROJECT_TAG_CHARS, tag
+            ));
+        }
+        if seen.insert(tag.to_lowercase()) {
+            normalized.push(tag.to_string());
+        }
+    }
+    if normalized.len() > MAX_PROJECT_TAGS {
+        return Err(format!("每个项目至多 {} 个标签", MAX_PROJECT_TAGS));
+    }
+    Ok(normalized)
+}
 
-    mark_last_requested_url(tab_id, &url);
+fn now_ts() -> u64 {
+    time::Timestamp::now().as_secs()
+}
 
-    let app_handle = app.clone();
-    let site_id_clone = site_id.to_string();
-    let tab_id_clone = tab_id.to_string();
+fn new_project_id() -> String {
+    format!("proj_{}", Uuid::new_v4().to_string().split('-').next().unwrap())
+}
 
-    let webview_builder = WebviewBuilder::new(&webview_label, WebviewUrl::External(url))
-        .user_agent(USER_AGENT)
-        .initialization_script(ANTI_DETECTION_SCRIPT)
-        .data_directory(data_dir)
-        .on_page_load(move |webview, payload| match payload.event() {
-            PageLoadEvent::Started => {
-                println!("[{}] 页面开始加载", webview.label());
-                mark_last_requested_url(&tab_id_clone, payload.url());
-            }
-            PageLoadEvent::Finished => {
-                if is_error_url(payload.url()) {
-                    let error_url = payload.url().as_str().to_string();
-                    let retry_url = LAST_REQUESTED_URL
-                        .lock()
-                        .unwrap()
-                        .get(&tab_id_clone)
-                        .cloned()
-                        .unwrap_or_else(|| error_url.clone());
-                    let should_retry = register_retry_attempt(&tab_id_clone, &retry_url);
-                    let message = if should_retry {
-                        format!("页面加载失败，准备重试，error_url={}", error_url)
-                    } else {
-                        format!("页面加载失败，重试后仍失败，error_url={}", error_url)
-                    };
-                    append_webview_error_log(&tab_id_clone, &site_id_clone, &retry_url, &message);
+/// 项目标题（项目不存在时为空）
+fn project_title(project_id: &str) -> String {
+    project_store::list_index()
+        .into_iter()
+        .find(|e| e.id == project_id)
+        .map(|e| e.title)
+        .unwrap_or_default()
+}
 
-                    if should_retry {
-                        let app_handle = app_handle.clone();
-                        let tab_id = tab_id_clone.clone();
-                        let retry_url_clone = retry_url.clone();
-                        let site_id_for_spawn = site_id_clone.clone();
-                        tauri::async_runtime::spawn(async move {
-                            tokio::time::sleep(Duration::from_secs(2)).await;
-                            let webview_label = format!("ai_{}", tab_id);
-                            if let Some(child) = app_handle.get_webview(&webview_label) {
-                                if let Ok(url) = retry_url_clone.parse::<tauri::Url>() {
-                                    let _ = child.navigate(url);
-                                } else {
-                                    let message = "重试 URL 解析失败".to_string();
-                                    append_webview_error_log(
-                                        &tab_id,
-                                        &site_id_for_spawn,
-                                        &retry_url_clone,
-                                        &message,
-                                    );
-                                    let payload = WebviewLoadFailedPayload {
-                                        tab_id: tab_id.clone(),
-                                        site_id: site_id_for_spawn.clone(),
-                                        url: retry_url_clone.clone(),
-                                        message,
-                                    };
-                                    let _ = app_handle.emit("webview-load-failed", payload);
-                                    handle_webview_load_failure(&app_handle, &tab_id, &site_id_for_spawn);
-                                }
-                            }
-                        });
+/// 未在回收站中的项目
+fn load_live_project(project_id: &str) -> Option<ProjectContext> {
+    project_store::load_project(project_id).filter(|p| !p.is_trashed())
+}
+
+/// 启动时修正项目中为 0、误存为毫秒或明显错误的时间戳；
+/// 无法修正的用项目文件的修改时间回填，updated_at 不早于 created_at
+fn migrate_project_timestamps() {
+    for entry in project_store::list_index() {
+        if entry.created_at.normalized() == Some(entry.created_at)
+            && entry.updated_at.normalized() == Some(entry.updated_at)
+            && entry.updated_at >= entry.created_at
+        {
+            continue;
+        }
+        let fallback = project_store::project_modified_at(&entry.id)
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|d| time::Timestamp::from_secs(d.as_secs()).normalized())
+            .unwrap_or_else(time::Timestamp::now);
+        let result = project_store::update_project(&entry.id, |p| {
+            p.created_at = p.created_at.normalized().unwrap_or(fallback);
+            p.updated_at = p.updated_at.normalized().unwrap_or(fallback).max(p.created_at);
+            Ok(())
+        });
+        if let Err(e) = result {
+            println!("[time] 修正项目时间戳失败: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LayoutState {
+    mode: LayoutMode,
+    /// 分屏中从左到右的 Tab（仅 Split 模式有效）
+    pane_tab_ids: Vec<String>,
+    /// 各分屏的宽度（上下排列时为高度）占比（与 pane_tab_ids 等长，和为 1）
+    pane_ratios: Vec<f64>,
+    /// 分屏方向（切回单视图后保留，下次分屏沿用）
+    orientation: SplitOrientation,
+    /// 最近一次两分屏的第一个分屏占比（切回单视图后保留，下次分屏沿用）
+    split_ratio: f64,
+    /// 最近一次退出分屏前的布局（切回单视图时暂存）
+    last_split: Option<LastSplit>,
+}
+
+impl Default for LayoutState {
+    fn default() -> Self {
+        Self {
+            mode: LayoutMode::Single,
+            pane_tab_ids: Vec::new(),
+            pane_ratios: Vec::new(),
+            orientation: SplitOrientation::Horizontal,
+            split_ratio: 0.5,
+            last_split: None,
+        }
+    }
+}
+
+/// 分屏数量上限
+const MAX_SPLIT_PANES: usize = 4;
+
+/// 单个分屏的最小宽度（逻辑像素）
+const MIN_PANE_WIDTH: f64 = 240.0;
+
+/// 上下分屏时单个分屏的最小高度（逻辑像素）
+const MIN_PANE_HEIGHT: f64 = 160.0;
+
+/// 规范化分屏占比：长度与分屏数一致，非法值回退为均分，结果和为 1
+fn normalize_pane_ratios(ratios: &[f64], count: usize) -> Vec<f64> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let valid = ratios.len() == count && ratios.iter().all(|r| r.is_finite() && *r > 0.0);
+    if !valid {
+        return vec![1.0 / count as f64; count];
+    }
+    let total: f64 = ratios.iter().sum();
+    ratios.iter().map(|r| r / total).collect()
+}
+
+/// 按占比计算各分屏尺寸：不足最小尺寸的分屏固定为最小值，其余按占比分摊剩余空间
+fn pane_sizes(total: f64, ratios: &[f64], min_size: f64) -> Vec<f64> {
+    let count = ratios.len();
+    if count == 0 {
+        return Vec::new();
+    }
+    if total <= min_size * count as f64 {
+        return vec![total / count as f64; count];
+    }
+    let mut pinned = vec![false; count];
+    loop {
+        let pinned_count = pinned.iter().filter(|p| **p).count();
+        let free_width = total - min_size * pinned_count as f64;
+        let free_ratio: f64 = ratios
+            .iter()
+            .zip(&pinned)
+            .filter(|(_, p)| !**p)
+            .map(|(r, _)| *r)
+            .sum();
+        let mut changed = false;
+        for i in 0..count {
+            if !pinned[i] && free_width * ratios[i] / free_ratio < min_size {
+                pinned[i] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            return (0..count)
+                .map(|i| {
+                    if pinned[i] {
+                        min_size
                     } else {
-                        let payload = WebviewLoadFailedPayload {
-                            tab_id: tab_id_clone.clone(),
-                            site_id: site_id_clone.clone(),
-                            url: retry_url.clone(),
-                            message,
-                        };
-                        let _ = app_handle.emit("webview-load-failed", payload);
-                        handle_webview_load_failure(&app_handle, &tab_id_clone, &site_id_clone);
+                        free_width * ratios[i] / free_ratio
                     }
-                    return;
-                }
+                })
+                .collect();
+        }
+    }
+}
 
-                println!("[{}] 页面加载完成", webview.label());
-                touch_tab(&tab_id_clone);
-                reset_retry_state(&tab_id_clone);
-                let _ = app_handle.emit("webview-loaded", &site_id_clone);
+impl LayoutState {
+    fn set_single(&mut self) {
+        if self.is_split() && self.pane_tab_ids.len() >= 2 {
+            self.last_split = Some(LastSplit {
+                left_tab_id: self.left_tab_id(),
+                right_tab_id: self.right_tab_id(),
+                ratio: self.ratio(),
+                pane_tab_ids: self.pane_tab_ids.clone(),
+                pane_ratios: self.pane_ratios.clone(),
+                orientation: self.orientation.as_str().to_string(),
+            });
+        }
+        self.mode = LayoutMode::Single;
+        self.pane_tab_ids.clear();
+        self.pane_ratios.clear();
+    }
+
+    fn set_split(&mut self, panes: Vec<String>, ratios: &[f64]) {
+        self.mode = LayoutMode::Split;
+        self.pane_ratios = normalize_pane_ratios(ratios, panes.len());
+        self.pane_tab_ids = panes;
+        if self.pane_ratios.len() == 2 {
+            self.split_ratio = self.pane_ratios[0];
+        }
+    }
+
+    /// 拖动第 `divider` 条分隔条（位于第 divider 与 divider+1 个分屏之间）到 `position`
+    /// （占整个内容区的比例）；相邻两个分屏各自至少保留两者合计的 20%
+    fn move_divider(&mut self, divider: usize, position: f64) -> Result<(), String> {
+        if !self.is_split() || self.pane_tab_ids.len() < 2 {
+            return Err("当前不是分屏布局".to_string());
+        }
+        if divider + 1 >= self.pane_ratios.len() {
+            return Err("分隔条不存在".to_string());
+        }
+        if !position.is_finite() {
+            return Err("ratio 无效".to_string());
+        }
+        let start: f64 = self.pane_ratios[..divider].iter().sum();
+        let pair = self.pane_ratios[divider] + self.pane_ratios[divider + 1];
+        let first = (position - start).clamp(pair * 0.2, pair * 0.8);
+        self.pane_ratios[divider] = first;
+        self.pane_ratios[divider + 1] = pair - first;
+        if self.pane_ratios.len() == 2 {
+            self.split_ratio = first;
+        }
+        Ok(())
+    }
+
+    /// 移除某个分屏，其占比按比例分给剩余分屏；返回是否移除
+    fn remove_pane(&mut self, tab_id: &str) -> bool {
+        let Some(index) = self.pane_tab_ids.iter().position(|id| id == tab_id) else {
+            return false;
+        };
+        self.pane_tab_ids.remove(index);
+        if index < self.pane_ratios.len() {
+            self.pane_ratios.remove(index);
+        }
+        self.pane_ratios = normalize_pane_ratios(&self.pane_ratios, self.pane_tab_ids.len());
+        true
+    }
+
+    fn is_split(&self) -> bool {
+        matches!(self.mode, LayoutMode::Split)
+    }
+
+    /// 兼容两分屏的字段：最左侧分屏
+    fn left_tab_id(&self) -> Option<String> {
+        self.pane_tab_ids.first().cloned()
+    }
+
+    /// 兼容两分屏的字段：第二个分屏
+    fn right_tab_id(&self) -> Option<String> {
+        self.pane_tab_ids.get(1).cloned()
+    }
+
+    /// 兼容两分屏的字段：第一个分屏的占比（单视图时返回最近一次两分屏的占比）
+    fn ratio(&self) -> f64 {
+        if self.is_split() {
+            if let Some(ratio) = self.pane_ratios.first() {
+                return *ratio;
             }
+        }
+        self.split_ratio
+    }
+
+    /// 暂存的分屏布局（其中的 Tab 须全部仍然存在，否则视为无可恢复）；
+    /// 会查询 Tab 映射，须在布局快照上调用
+    fn restorable_last_split(&self) -> Option<LastSplit> {
+        let last = self.last_split.as_ref()?;
+        let panes_exist = !last.pane_tab_ids.is_empty()
+            && last.pane_tab_ids.iter().all(|id| get_tab_site_id(id).is_ok());
+        panes_exist.then(|| last.clone())
+    }
+}
+
+/// 已创建的 Webview、Tab 映射、活跃 Tab、当前视图与分屏布局，统一由 `APP_STATE` 一把锁保护：
+/// - 只在短临界区内读写；调用 Webview API 或其他会加锁的函数（如 `get_tab_site_id`）前先克隆快照并释放锁
+/// - 需要同时持有配置锁时，先锁 APP_CONFIG 再锁 APP_STATE
+#[derive(Debug, Default)]
+struct AppState {
+    /// 已创建 Webview 的 Tab
+    views: HashSet<String>,
+    /// 正在创建 Webview 的 Tab（自检期间跳过本轮）
+    creating_views: HashSet<String>,
+    /// 额外 Tab → 站点映射（主 Tab 使用 `tab_id == site_id`，不存这里）
+    tabs: HashMap<String, String>,
+    /// 当前活跃 Tab（用于单视图模式）
+    active_tab: String,
+    /// 当前显示的视图 ID（活跃 Tab 所属站点）
+    current_view: String,
+    layout: LayoutState,
+}
+
+impl AppState {
+    fn set_active(&mut self, tab_id: &str, site_id: &str) {
+        self.active_tab = tab_id.to_string();
+        self.current_view = site_id.to_string();
+    }
+
+    fn clear_active(&mut self) {
+        self.active_tab.clear();
+        self.current_view.clear();
+    }
+
+    /// 单视图下显示的 Tab：优先活跃 Tab，其次当前视图
+    fn focused_tab_id(&self) -> String {
+        if !self.active_tab.is_empty() {
+            self.active_tab.clone()
+        } else {
+            self.current_view.clone()
+        }
+    }
+
+    /// 已创建 Webview 或已登记映射的 Tab
+    fn known_tab_ids(&self) -> HashSet<String> {
+        self.views.iter().chain(self.tabs.keys()).cloned().collect()
+    }
+}
+
+const TOP_BAR_HEIGHT: f64 = 48.0;
+
+/// 正在总结的 Tab（同一 Tab 同时只允许一个总结，不同 Tab 可并行）
+static SUMMARIES_IN_PROGRESS: Lazy<Mutex<HashSet<String>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 主 UI 弹窗期间活跃 Webview 被 `set_active_view_visible(false)` 隐藏，
+/// 对账/自检重新布局时不应把它们重新显示出来
+static ACTIVE_VIEW_SUPPRESSED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug)]
+struct PendingExtract {
+    token: String,
+    /// 页面回传的文本；超出大小限制时为错误
+    tx: oneshot::Sender<Result<String, String>>,
+    created_at: std::time::Instant,
+    /// 回传内容的大小上限（字节）
+    max_bytes: usize,
+}
+
+/// 提取请求的最长保留时间（超过后视为泄漏，插入新请求时清理）
+const PENDING_EXTRACT_MAX_AGE: Duration = Duration::from_secs(120);
+
+/// 页面回传文本的大小上限（字节）
+const MAX_SUBMITTED_PAGE_TEXT_BYTES: usize = 2 * 1024 * 1024;
+
+/// 清理等待方已放弃或超过 PENDING_EXTRACT_MAX_AGE 的提取请求，返回清理数量
+fn sweep_pending_extracts(pending: &mut HashMap<String, PendingExtract>) -> usize {
+    let before = pending.len();
+    pending.retain(|_, p| !p.tx.is_closed() && p.created_at.elapsed() < PENDING_EXTRACT_MAX_AGE);
+    before - pending.len()
+}
+
+/// 在 `eval_page_text` 返回（含超时、出错与 future 被取消）时移除自己的提取请求
+struct PendingExtractGuard(String);
+
+impl Drop for PendingExtractGuard {
+    fn drop(&mut self) {
+        PENDING_EXTRACTS.lock().unwrap().remove(&self.0);
+    }
+}
+
+static PENDING_EXTRACTS: Lazy<Mutex<HashMap<String, PendingExtract>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 已登记、等待页面经 `aihub_submit_page_text` 回传的提取请求（每次调用独立的 id 与 token）
+struct PendingExtractRequest {
+    request_id: String,
+    token: String,
+    rx: oneshot::Receiver<Result<String, String>>,
+    _guard: PendingExtractGuard,
+}
+
+impl PendingExtractRequest {
+    /// 登记前顺带清理已泄漏的请求
+    fn register(max_bytes: usize) -> Self {
+        let request_id = Uuid::new_v4().to_string();
+        let token = Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel::<Result<String, String>>();
+        {
+            let mut pending = PENDING_EXTRACTS.lock().unwrap();
+            sweep_pending_extracts(&mut pending);
+            pending.insert(
+                request_id.clone(),
+                PendingExtract {
+                    token: token.clone(),
+                    tx,
+                    created_at: std::time::Instant::now(),
+                    max_bytes,
+                },
+            );
+        }
+        Self {
+            _guard: PendingExtractGuard(request_id.clone()),
+            request_id,
+            token,
+            rx,
+        }
+    }
+
+    /// 等待页面回传；超时返回 `Ok(None)`，返回时（含被取消）移除登记
+    async fn wait(self, timeout: Duration) -> Result<Option<String>, String> {
+        match tokio::time::timeout(timeout, self.rx).await {
+            Ok(Ok(res)) => res.map(Some),
+            Ok(Err(_)) => Err("页面脚本未返回结果".to_string()),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+fn is_main_invoker_webview(webview: &tauri::Webview) -> bool {
+    webview.label() == "main"
+}
+
+/// 通过 `--locked` 启动参数开启的锁定模式
+static LOCKED_BY_CLI: Lazy<bool> = Lazy::new(|| std::env::args().any(|arg| arg == "--locked"));
+
+/// 锁定模式错误（`AppError::Locked`）转为字符串时的前缀（自动化 API 响应、后台任务错误）
+const LOCKED_ERROR_PREFIX: &str = "[locked]";
+
+fn is_locked(config: &AppConfig) -> bool {
+    config.locked || *LOCKED_BY_CLI
+}
+
+/// 修改类命令入口校验：锁定模式下除豁免列表外一律拒绝
+fn ensure_unlocked(command: &str) -> Result<(), AppError> {
+    let config = APP_CONFIG.lock().unwrap();
+    if !is_locked(&config) || config.lock_exemptions.commands.iter().any(|c| c == command) {
+        return Ok(());
+    }
+    Err(AppError::Locked(format!("当前为锁定模式，不允许执行: {}", command)))
+}
+
+fn ensure_summarize_allowed() -> Result<(), AppError> {
+    let config = APP_CONFIG.lock().unwrap();
+    if !is_locked(&config) || config.lock_exemptions.allow_summarize {
+        return Ok(());
+    }
+    Err(AppError::Locked("当前为锁定模式，不允许总结".to_string()))
+}
+
+fn language_label(code: &str) -> &'static str {
+    match code {
+        "zh-CN" | "zh" => "中文",
+        "en" | "en-US" | "en-GB" => "English",
+        "ja" | "ja-JP" => "日本語",
+        "ko" | "ko-KR" => "한국어",
+        "es" | "es-ES" | "es-AR" => "Español",
+        "fr" | "fr-FR" => "Français",
+        _ => "English",
+    }
+}
+
+/// 选择总结提示词模板，优先级：
+/// 指定的命名模板 > 站点引用的命名模板 > 站点自定义模板 > 全局模板 > 内置默认模板；
+/// 引用的模板不存在（已被删除）或内容为空时跳过该级
+fn resolve_summary_template(
+    config: &AppConfig,
+    site_id: Option<&str>,
+    template_id: Option<&str>,
+) -> String {
+    let named = |id: &str| {
+        config
+            .prompt_templates
+            .iter()
+            .find(|t| !id.is_empty() && t.id == id && !t.body.trim().is_empty())
+            .map(|t| t.body.clone())
+    };
+    let site = site_id.and_then(|id| config.sites.iter().find(|s| s.id == id));
+    template_id
+        .and_then(named)
+        .or_else(|| site.and_then(|s| named(&s.prompt_template_id)))
+        .or_else(|| {
+            site.map(|s| s.summary_prompt_override.clone())
+                .filter(|t| !t.trim().is_empty())
+        })
+        .or_else(|| {
+            Some(config.summary_prompt_template.clone()).filter(|t| !t.trim().is_empty())
+        })
+        .unwrap_or_else(default_summary_prompt_template)
+}
+
+/// 提示词模板支持的变量（名称, 说明）
+const PROMPT_VARIABLES: &[(&str, &str)] = &[
+    ("language", "输出语言"),
+    ("text", "提取的页面文本"),
+    ("site_name", "来源站点名称"),
+    ("site_url", "来源站点地址"),
+    ("date", "当前本地日期（YYYY-MM-DD）"),
+    ("time", "当前本地时间（HH:MM）"),
+    ("project_title", "当前项目标题"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+struct PromptVariable {
+    name: &'static str,
+    description: &'static str,
+}
+
+/// 渲染提示词时的上下文（站点、项目）；缺失的值替换为空字符串
+#[derive(Debug, Clone, Default)]
+struct PromptContext {
+    site_name: String,
+    site_url: String,
+    project_title: String,
+}
+
+impl PromptContext {
+    /// 按站点 id 与项目 id 查找名称、地址与标题，找不到时留空
+    fn resolve(config: &AppConfig, site_id: Option<&str>, project_id: &str) -> Self {
+        let site = site_id.and_then(|id| config.sites.iter().find(|s| s.id == id));
+        let project_title = if project_id.trim().is_empty() {
+            String::new()
+        } else {
+            project_title(project_id)
+        };
+        Self {
+            site_name: site.map(|s| s.name.clone()).unwrap_or_default(),
+            site_url: site.map(|s| s.url.clone()).unwrap_or_default(),
+            project_title,
+        }
+    }
+}
+
+/// 单遍替换 `{name}` 占位符：未知变量与不成对的花括号原样保留，
+/// 替换进来的值（如页面文本中的 `{date}`）不会被再次展开
+fn render_prompt_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            let valid = !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            valid.then(|| lookup(name).map(|v| (v, end))).flatten()
         });
+        match value {
+            Some((value, end)) => {
+                rendered.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn build_summary_prompt(
+    template: &str,
+    language: &str,
+    text: &str,
+    context: &PromptContext,
+) -> String {
+    let now = time::Timestamp::now();
+    let mut rendered = render_prompt_template(template, |name| {
+        let value = match name {
+            "language" => language.to_string(),
+            "text" => text.to_string(),
+            "site_name" => context.site_name.clone(),
+            "site_url" => context.site_url.clone(),
+            "date" => now.local_date(),
+            "time" => now.local_time(),
+            "project_title" => context.project_title.clone(),
+            _ => return None,
+        };
+        Some(value)
+    });
+    if !template.contains("{language}") {
+        rendered.push_str("\n\nLanguage: ");
+        rendered.push_str(language);
+    }
+    if !template.contains("{text}") {
+        rendered.push_str("\n\n");
+        rendered.push_str(text);
+    }
+    rendered
+}
+
+// ============================================================================
+// 工具函数
+// ============================================================================
+
+/// 站点主 Tab 数据目录的根目录（每个站点一个子目录）
+fn get_data_root() -> std::path::PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    proj_dirs.data_dir().join("webviews")
+}
+
+/// 额外 Tab 数据目录的根目录（`<site_id>/<tab_id>`）
+fn get_tab_data_root() -> std::path::PathBuf {
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    proj_dirs.data_dir().join("webviews_tabs")
+}
+
+/// 获取站点数据目录（用于 Cookie 持久化）
+fn get_data_dir(site_id: &str) -> std::path::PathBuf {
+    get_data_root().join(site_id)
+}
+
+/// 获取 Tab 对应的数据目录
+/// - 主 Tab（tab_id == site_id）使用站点目录（兼容已有数据）
+/// - 额外 Tab 使用独立目录，避免多个 WebView2 实例同时占用同一 profile 目录导致卡死
+fn get_tab_data_dir(site_id: &str, tab_id: &str) -> std::path::PathBuf {
+    if tab_id == site_id {
+        return get_data_dir(site_id);
+    }
+    // 注意：避免把一个 profile 目录嵌套在另一个 profile 目录内（Windows WebView2 可能会卡住）
+    get_tab_data_root().join(site_id).join(tab_id)
+}
+
+/// 计算 Webview 的位置和尺寸
+fn calculate_webview_bounds(window: &tauri::Window) -> (LogicalPosition<f64>, LogicalSize<f64>) {
+    let size = window.inner_size().unwrap_or_default();
+    let scale = window.scale_factor().unwrap_or(1.0);
+
+    // 获取动态侧边栏宽度
+    let sidebar_width = APP_CONFIG.lock().unwrap().sidebar_width;
+
+    // 转换为逻辑像素
+    let window_width = size.width as f64 / scale;
+    let window_height = size.height as f64 / scale;
+
+    // Webview 位置：从侧边栏右侧开始，并避开顶部栏（chrome）
+    let position = LogicalPosition::new(sidebar_width, TOP_BAR_HEIGHT);
+
+    // Webview 尺寸：窗口宽度减去侧边栏，高度减去顶部栏
+    let webview_width = (window_width - sidebar_width).max(100.0);
+    let webview_height = (window_height - TOP_BAR_HEIGHT).max(100.0);
+    let size = LogicalSize::new(webview_width, webview_height);
+
+    (position, size)
+}
+
+fn get_main_window(app: &tauri::AppHandle) -> Result<tauri::Window, String> {
+    if let Some(main_window) = app.get_webview_window("main") {
+        Ok(main_window.as_ref().window().clone())
+    } else if let Some(win) = app.get_window("main") {
+        Ok(win)
+    } else {
+        Err("主窗口不存在".to_string())
+    }
+}
+
+fn get_site_by_id(site_id: &str) -> Result<AiSite, AppError> {
+    let config = APP_CONFIG.lock().unwrap();
+    config
+        .sites
+        .iter()
+        .find(|s| s.id == site_id)
+        .cloned()
+        .ok_or_else(|| AppError::SiteNotFound(site_id.to_string()))
+}
+
+/// 同 get_site_by_id，但拒绝已停用的站点
+fn get_enabled_site_by_id(site_id: &str) -> Result<AiSite, AppError> {
+    let site = get_site_by_id(site_id)?;
+    if !site.enabled {
+        return Err(AppError::SiteDisabled(site_id.to_string()));
+    }
+    Ok(site)
+}
+
+fn get_tab_site_id(tab_id: &str) -> Result<String, AppError> {
+    // 主 Tab：tab_id == site_id
+    if APP_CONFIG.lock().unwrap().sites.iter().any(|s| s.id == tab_id) {
+        return Ok(tab_id.to_string());
+    }
+
+    APP_STATE
+        .lock()
+        .unwrap()
+        .tabs
+        .get(tab_id)
+        .cloned()
+        .ok_or_else(|| AppError::TabNotFound(tab_id.to_string()))
+}
+
+fn is_private_site(site_id: &str) -> bool {
+    APP_CONFIG
+        .lock()
+        .unwrap()
+        .sites
+        .iter()
+        .any(|s| s.id == site_id && s.private)
+}
+
+fn upsert_recent_site(site_id: &str) {
+    let mut config = APP_CONFIG.lock().unwrap();
+    if config.sites.iter().any(|s| s.id == site_id && s.private) {
+        return;
+    }
+    config.recent_site_ids.retain(|id| id != site_id);
+    config.recent_site_ids.insert(0, site_id.to_string());
+    config.recent_site_ids.truncate(10);
+    let _ = save_config(&config);
+}
+
+/// 记录站点激活（隐私站点不统计）
+fn record_site_activation(site_id: &str) {
+    let private = APP_CONFIG
+        .lock()
+        .unwrap()
+        .sites
+        .iter()
+        .any(|s| s.id == site_id && s.private);
+    if private {
+        site_stats::set_foreground(None);
+    } else {
+        site_stats::record_activation(site_id);
+    }
+}
+
+/// 当前显示的站点（隐私站点视为无）
+fn foreground_stats_site_id() -> Option<String> {
+    let active_tab = APP_STATE.lock().unwrap().active_tab.clone();
+    if active_tab.is_empty() {
+        return None;
+    }
+    let site_id = get_tab_site_id(&active_tab).ok()?;
+    let config = APP_CONFIG.lock().unwrap();
+    let site = config.sites.iter().find(|s| s.id == site_id)?;
+    (!site.private).then_some(site_id)
+}
+
+/// 定时把站点使用统计落盘；顺带按当前 Tab 校正前台站点（关闭/休眠 Tab 等不经切换的变化）
+fn start_site_stats_timer() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(site_stats::FLUSH_INTERVAL).await;
+            site_stats::set_foreground(foreground_stats_site_id().as_deref());
+            site_stats::flush();
+        }
+    });
+}
+
+fn update_last_active(tab_id: &str, site_id: &str) {
+    let tab_id = tab_id.trim();
+    let site_id = site_id.trim();
+    if tab_id.is_empty() || site_id.is_empty() {
+        return;
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    if config.last_active_tab_id == tab_id && config.last_active_site_id == site_id {
+        return;
+    }
+    config.last_active_tab_id = tab_id.to_string();
+    config.last_active_site_id = site_id.to_string();
+    let _ = save_config(&config);
+}
+
+fn clear_last_active() {
+    let mut config = APP_CONFIG.lock().unwrap();
+    if config.last_active_tab_id.is_empty() && config.last_active_site_id.is_empty() {
+        return;
+    }
+    config.last_active_tab_id.clear();
+    config.last_active_site_id.clear();
+    let _ = save_config(&config);
+}
+
+fn touch_tab(tab_id: &str) {
+    if tab_id.trim().is_empty() {
+        return;
+    }
+    update_tab_runtime(tab_id, |runtime| runtime.last_used_at = Some(now_ts()));
+}
+
+fn tab_bridge_ok(tab_id: &str) -> Option<bool> {
+    read_tab_runtime(tab_id, |runtime| runtime.bridge.ok).flatten()
+}
+
+/// 页面开始加载：重置桥接状态，返回新的加载序号
+fn reset_bridge_state(tab_id: &str) -> u64 {
+    update_tab_runtime(tab_id, |runtime| {
+        let state = &mut runtime.bridge;
+        state.load_seq += 1;
+        state.ok = None;
+        state.unresponsive = false;
+        state.load_seq
+    })
+}
+
+/// 页面加载完成后若仍未收到桥接回报，标记为不可用
+fn schedule_bridge_timeout(tab_id: String) {
+    let load_seq = read_tab_runtime(&tab_id, |runtime| runtime.bridge.load_seq).unwrap_or_default();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(BRIDGE_REPORT_GRACE).await;
+        let mut runtimes = TAB_RUNTIME.lock().unwrap();
+        if let Some(state) = runtimes.get_mut(&tab_id).map(|runtime| &mut runtime.bridge) {
+            if state.load_seq == load_seq && state.ok.is_none() {
+                println!("[bridge] tab_id={} 未收到桥接回报，视为被阻止", tab_id);
+                state.ok = Some(false);
+            }
+        }
+    });
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct WebviewCrashedPayload {
+    tab_id: String,
+    site_id: String,
+}
+
+/// 定时检测 Webview 是否仍有响应：注入回报脚本，超时未回应时发送 `webview-crashed`
+///
+/// 渲染进程崩溃后 Webview 对象仍然存在（白屏），无法从 `views` 中发现，只能靠回应判断。
+fn start_webview_watchdog(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(WEBVIEW_PING_INTERVAL).await;
+            let views = APP_STATE.lock().unwrap().views.clone();
+            for tab_id in views {
+                if is_tab_detached(&tab_id) {
+                    continue;
+                }
+                let loading = read_tab_runtime(&tab_id, |runtime| runtime.page.loading).unwrap_or(false);
+                if !loading {
+                    ping_tab_webview(&app, &tab_id);
+                }
+            }
+        }
+    });
+}
+
+fn ping_tab_webview(app: &tauri::AppHandle, tab_id: &str) {
+    let Some(webview) = app.get_webview(&format!("ai_{}", tab_id)) else {
+        return;
+    };
+    let (load_seq, seq) = {
+        let mut runtimes = TAB_RUNTIME.lock().unwrap();
+        let Some(state) = runtimes.get_mut(tab_id).map(|runtime| &mut runtime.bridge) else {
+            return;
+        };
+        if state.ok != Some(true) || state.unresponsive {
+            return;
+        }
+        state.ping_seq += 1;
+        (state.load_seq, state.ping_seq)
+    };
+    let script = format!(
+        "try {{ window.__TAURI__.core.invoke('aihub_report_alive', {{ seq: {} }}).catch(() => {{}}); }} catch (_) {{}}",
+        seq
+    );
+    let _ = webview.eval(&script);
+
+    let app = app.clone();
+    let tab_id = tab_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(WEBVIEW_PING_TIMEOUT).await;
+        let timed_out = {
+            let mut runtimes = TAB_RUNTIME.lock().unwrap();
+            match runtimes.get_mut(&tab_id).map(|runtime| &mut runtime.bridge) {
+                Some(state)
+                    if state.load_seq == load_seq && state.pong_seq < seq && !state.unresponsive =>
+                {
+                    state.unresponsive = true;
+                    true
+                }
+                _ => false,
+            }
+        };
+        if !timed_out {
+            return;
+        }
+        let Ok(site_id) = get_tab_site_id(&tab_id) else {
+            return;
+        };
+        println!("[watchdog] tab_id={} 存活检测超时，视为崩溃", tab_id);
+        append_webview_error_log(&tab_id, &site_id, "", "Webview 无响应（存活检测超时）");
+        let _ = app.emit("webview-crashed", WebviewCrashedPayload { tab_id, site_id });
+    });
+}
+
+fn mark_last_requested_url(tab_id: &str, url: &tauri::Url) {
+    if is_browser_error_url(url) || is_blank_url(url.as_str()) {
+        return;
+    }
+    update_tab_runtime(tab_id, |runtime| {
+        runtime.last_requested_url = Some(url.as_str().to_string())
+    });
+}
+
+fn reset_retry_state(tab_id: &str) {
+    if let Some(runtime) = TAB_RUNTIME.lock().unwrap().get_mut(tab_id) {
+        runtime.retry = None;
+    }
+}
+
+fn register_retry_attempt(tab_id: &str, url: &str) -> bool {
+    let mut runtimes = TAB_RUNTIME.lock().unwrap();
+    let runtime = runtimes.entry(tab_id.to_string()).or_default();
+    let entry = runtime.retry.get_or_insert_with(|| RetryState {
+        url: url.to_string(),
+        attempts: 0,
+    });
+    if entry.url != url {
+        entry.url = url.to_string();
+        entry.attempts = 0;
+    }
+    if entry.attempts == 0 {
+        entry.attempts = 1;
+        return true;
+    }
+    runtime.retry = None;
+    false
+}
+
+fn gc_idle_webviews(app: &tauri::AppHandle) {
+    let now = now_ts();
+    let mut protected: HashSet<String> = HashSet::new();
+    {
+        let state = APP_STATE.lock().unwrap();
+        if !state.active_tab.is_empty() {
+            protected.insert(state.active_tab.clone());
+        }
+        if !state.current_view.is_empty() {
+            protected.insert(state.current_view.clone());
+        }
+        if state.layout.is_split() {
+            protected.extend(state.layout.pane_tab_ids.iter().cloned());
+        }
+    }
+    protected.extend(detached_tab_ids());
+
+    let hibernate_minutes = APP_CONFIG.lock().unwrap().tab_hibernate_minutes;
+    let idle_ttl = if hibernate_minutes > 0 {
+        u64::from(hibernate_minutes) * 60
+    } else {
+        WEBVIEW_IDLE_TTL_SECS
+    };
+
+    let stale_tabs: Vec<String> = tab_runtime_ids(|runtime| {
+        runtime
+            .last_used_at
+            .is_some_and(|ts| now.saturating_sub(ts) > idle_ttl)
+    })
+    .into_iter()
+    .filter(|tab_id| !protected.contains(tab_id))
+    .collect();
+
+    // 输入框有未发送内容的 Tab 不休眠（下次检查时再判断）；探测期间 Tab 可能被切换到前台
+    for tab_id in stale_tabs {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let probe = probe_unsent_input(&app, &tab_id).await;
+            if probe.has_unsent {
+                println!(
+                    "[hibernate] tab_id={} 有未发送的内容（{} 字），暂不休眠",
+                    tab_id, probe.draft_length
+                );
+                return;
+            }
+            if !is_tab_visible(&tab_id) {
+                hibernate_tab_webview(&app, &tab_id);
+            }
+        });
+    }
+}
+
+/// Tab 是否正在显示（当前 Tab、分屏中的 Tab 或独立窗口中的 Tab）
+fn is_tab_visible(tab_id: &str) -> bool {
+    let visible = {
+        let state = APP_STATE.lock().unwrap();
+        state.active_tab == tab_id
+            || state.current_view == tab_id
+            || state.layout.pane_tab_ids.iter().any(|id| id == tab_id)
+    };
+    visible || is_tab_detached(tab_id)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TabHibernatedPayload {
+    tab_id: String,
+    site_id: String,
+}
+
+/// 休眠 Tab：关闭 Webview 但保留 Tab（Tab 映射不变），通知前端置灰
+fn hibernate_tab_webview(app: &tauri::AppHandle, tab_id: &str) {
+    let Ok(site_id) = get_tab_site_id(tab_id) else {
+        destroy_tab_webview(app, tab_id);
+        return;
+    };
+    destroy_tab_webview(app, tab_id);
+    update_tab_runtime(tab_id, |runtime| runtime.hibernated = true);
+    println!("[hibernate] tab_id={} site_id={}", tab_id, site_id);
+    let _ = app.emit(
+        "tab-hibernated",
+        TabHibernatedPayload {
+            tab_id: tab_id.to_string(),
+            site_id,
+        },
+    );
+}
+
+/// 定时休眠空闲的隐藏 Tab（切换 Tab 时也会检查一次）
+fn start_hibernate_timer(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(HIBERNATE_CHECK_INTERVAL).await;
+            gc_idle_webviews(&app);
+        }
+    });
+}
+
+/// 浏览器内置的错误页
+fn is_browser_error_url(url: &tauri::Url) -> bool {
+    let raw = url.as_str();
+    raw.starts_with("edge-error://")
+        || raw.starts_with("chrome-error://")
+        || raw.starts_with("about:neterror")
+        || raw.starts_with("about:error")
+}
+
+fn is_blank_url(url: &str) -> bool {
+    url.starts_with("about:blank")
+}
+
+/// 页面加载结束于错误页。`about:blank` 只有在本次加载请求的是其他地址时才算失败：
+/// 初次创建（尚未开始加载）与弹窗的中间页都会经过 about:blank
+fn is_failed_load(finished: &tauri::Url, started_url: &str) -> bool {
+    is_browser_error_url(finished)
+        || (is_blank_url(finished.as_str()) && !started_url.is_empty() && !is_blank_url(started_url))
+}
+
+/// 把内存中的 Tab 与布局写入配置（无变化时不落盘）
+fn persist_session() {
+    let (mut tabs, active_tab_id, layout) = {
+        let state = APP_STATE.lock().unwrap();
+        let tabs: Vec<SessionTab> = state
+            .tabs
+            .iter()
+            .map(|(tab_id, site_id)| SessionTab {
+                tab_id: tab_id.clone(),
+                site_id: site_id.clone(),
+            })
+            .collect();
+        (tabs, state.active_tab.clone(), state.layout.clone())
+    };
+    tabs.sort_by(|a, b| a.tab_id.cmp(&b.tab_id));
+    let session = SessionState {
+        tabs,
+        active_tab_id,
+        layout_mode: match layout.mode {
+            LayoutMode::Single => "single".to_string(),
+            LayoutMode::Split => "split".to_string(),
+        },
+        ratio: layout.ratio(),
+        left_tab_id: layout.left_tab_id(),
+        right_tab_id: layout.right_tab_id(),
+        pane_tab_ids: layout.pane_tab_ids.clone(),
+        pane_ratios: layout.pane_ratios.clone(),
+        orientation: layout.orientation.as_str().to_string(),
+        last_split: layout.last_split.clone(),
+        // 隐私站点的关闭记录只保留在内存中
+        closed_tabs: CLOSED_TABS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| !is_private_site(&t.site_id))
+            .cloned()
+            .collect(),
+    };
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    if config.session == session {
+        return;
+    }
+    config.session = session;
+    let _ = save_config(&config);
+}
+
+/// 拖动分隔条等高频操作：延迟写入会话，连续调用只落盘最后一次
+static SESSION_PERSIST_SEQ: AtomicU64 = AtomicU64::new(0);
+const SESSION_PERSIST_DEBOUNCE: Duration = Duration::from_millis(500);
+
+fn schedule_persist_session() {
+    let seq = SESSION_PERSIST_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SESSION_PERSIST_DEBOUNCE).await;
+        if SESSION_PERSIST_SEQ.load(Ordering::SeqCst) == seq {
+            persist_session();
+        }
+    });
+}
+
+/// 启动时恢复上次的 Tab 与布局：丢弃站点已不存在的 Tab，只为可见的 Tab 创建 Webview，
+/// 其余 Tab 在首次切换时再创建。成功恢复可见状态时返回 true。
+fn restore_session(app: &tauri::AppHandle) -> bool {
+    let session = APP_CONFIG.lock().unwrap().session.clone();
+
+    {
+        // 已停用站点的 Tab 一并丢弃
+        let site_ids: HashSet<String> = APP_CONFIG
+            .lock()
+            .unwrap()
+            .sites
+            .iter()
+            .filter(|s| s.enabled)
+            .map(|s| s.id.clone())
+            .collect();
+        let mut state = APP_STATE.lock().unwrap();
+        for tab in &session.tabs {
+            if site_ids.contains(&tab.site_id) && !site_ids.contains(&tab.tab_id) {
+                state.tabs.insert(tab.tab_id.clone(), tab.site_id.clone());
+            } else {
+                println!("[session] 丢弃失效 Tab: {}", tab.tab_id);
+            }
+        }
+    }
+
+    let valid = |tab_id: &str| -> Option<(String, String)> {
+        let tab_id = tab_id.trim().to_string();
+        let site_id = get_tab_site_id(&tab_id).ok()?;
+        Some((tab_id, site_id))
+    };
+
+    let active = valid(&session.active_tab_id);
+    {
+        let layout = &mut APP_STATE.lock().unwrap().layout;
+        layout.orientation = SplitOrientation::parse(&session.orientation).unwrap_or_default();
+        layout.split_ratio = session.ratio.clamp(0.2, 0.8);
+        layout.last_split = session.last_split.clone();
+    }
+    {
+        let site_ids: HashSet<String> = APP_CONFIG
+            .lock()
+            .unwrap()
+            .sites
+            .iter()
+            .map(|s| s.id.clone())
+            .collect();
+        let mut closed = CLOSED_TABS.lock().unwrap();
+        *closed = session
+            .closed_tabs
+            .iter()
+            .filter(|t| site_ids.contains(&t.site_id))
+            .take(MAX_CLOSED_TABS)
+            .map(|t| ClosedTab {
+                closed_at: time::Timestamp::from_secs(t.closed_at)
+                    .normalized()
+                    .unwrap_or_else(time::Timestamp::now)
+                    .as_secs(),
+                ..t.clone()
+            })
+            .collect();
+    }
+    let mut restored = false;
+    if session.layout_mode == "split" {
+        // 旧配置只有 left/right，按两分屏恢复
+        let (pane_ids, pane_ratios) = if session.pane_tab_ids.is_empty() {
+            let ratio = session.ratio.clamp(0.2, 0.8);
+            (
+                session
+                    .left_tab_id
+                    .iter()
+                    .chain(session.right_tab_id.iter())
+                    .cloned()
+                    .collect::<Vec<_>>(),
+                vec![ratio, 1.0 - ratio],
+            )
+        } else {
+            (session.pane_tab_ids.clone(), session.pane_ratios.clone())
+        };
+        let mut panes: Vec<(String, String)> = Vec::new();
+        let mut ratios: Vec<f64> = Vec::new();
+        for (index, tab_id) in pane_ids.iter().take(MAX_SPLIT_PANES).enumerate() {
+            if let Some(pane) = valid(tab_id) {
+                panes.push(pane);
+                ratios.push(pane_ratios.get(index).copied().unwrap_or(0.0));
+            }
+        }
+        if !panes.is_empty() {
+            let panes_ok = panes
+                .iter()
+                .all(|(tab_id, site_id)| ensure_tab_webview(app, tab_id, site_id).is_ok());
+            if panes_ok {
+                let (active_tab, active_site) = active
+                    .clone()
+                    .filter(|(id, _)| panes.iter().any(|(pane, _)| pane == id))
+                    .or(panes.first().cloned())
+                    .unwrap_or_default();
+                let mut state = APP_STATE.lock().unwrap();
+                state
+                    .layout
+                    .set_split(panes.iter().map(|(id, _)| id.clone()).collect(), &ratios);
+                state.set_active(&active_tab, &active_site);
+                restored = true;
+            }
+        }
+    }
+
+    if !restored {
+        if let Some((tab_id, site_id)) = active {
+            if ensure_tab_webview(app, &tab_id, &site_id).is_ok() {
+                APP_STATE.lock().unwrap().set_active(&tab_id, &site_id);
+                restored = true;
+            }
+        }
+    }
+
+    if restored {
+        let _ = resize_webviews_inner(app, true);
+    }
+    persist_session();
+    restored
+}
+
+fn restore_last_active_state(app: &tauri::AppHandle) {
+    if restore_session(app) {
+        return;
+    }
+
+    let site_id = {
+        let config = APP_CONFIG.lock().unwrap();
+        config.last_active_site_id.clone()
+    };
+
+    if site_id.trim().is_empty() {
+        return;
+    }
+
+    if get_site_by_id(&site_id).is_err() {
+        clear_last_active();
+        return;
+    }
+
+    {
+        let mut state = APP_STATE.lock().unwrap();
+        state.layout.set_single();
+        state.set_active(&site_id, &site_id);
+    }
+
+    if ensure_tab_webview(app, &site_id, &site_id).is_err() {
+        APP_STATE.lock().unwrap().clear_active();
+        clear_last_active();
+        return;
+    }
+
+    let _ = resize_webviews_inner(app, true);
+}
+
+fn ensure_tab_webview(app: &tauri::AppHandle, tab_id: &str, site_id: &str) -> Result<(), String> {
+    let window = get_main_window(app)?;
+    let (position, size) = calculate_webview_bounds(&window);
+    let webview_label = format!("ai_{}", tab_id);
+
+    println!(
+        "[ensure_tab_webview] tab_id={} site_id={} label={}",
+        tab_id, site_id, webview_label
+    );
+
+    if is_tab_detached(tab_id) {
+        return Err("该 Tab 已在独立窗口中打开".to_string());
+    }
+
+    let view_exists = APP_STATE.lock().unwrap().views.contains(tab_id);
+    if view_exists {
+        if let Some(webview) = app.get_webview(&webview_label) {
+            let _ = webview.set_position(position);
+            let _ = webview.set_size(size);
+            touch_tab(tab_id);
+            return Ok(());
+        }
+
+        APP_STATE.lock().unwrap().views.remove(tab_id);
+    }
+
+    let site = get_enabled_site_by_id(site_id)?;
+    let _ = app.emit("webview-loading", site_id);
+
+    let data_dir = get_tab_data_dir(site_id, tab_id);
+    println!("[ensure_tab_webview] data_dir={}", data_dir.display());
+    let _ = std::fs::create_dir_all(&data_dir);
+
+    let url = site_url::parse_site_url(&site.url)?;
+
+    mark_last_requested_url(tab_id, &url);
+
+    let anti_detection_script = {
+        let config = APP_CONFIG.lock().unwrap();
+        site_anti_detection_script(&site, &config.anti_detection, &config.language)
+    };
+
+    let app_handle = app.clone();
+    let site_id_clone = site_id.to_string();
+    let tab_id_clone = tab_id.to_string();
+
+    let webview_builder = WebviewBuilder::new(&webview_label, WebviewUrl::External(url))
+        .user_agent(USER_AGENT)
+        .initialization_script(anti_detection_script)
+        .initialization_script(build_site_init_script(&site.init_script))
+        .initialization_script(PAGE_TIMING_SCRIPT)
+        .initialization_script(BRIDGE_PROBE_SCRIPT)
+        .initialization_script(PAGE_TITLE_SCRIPT)
+        .initialization_script(ZOOM_KEYS_SCRIPT)
+        .initialization_script(tab_mute_script(is_tab_muted(tab_id)))
+        .data_directory(data_dir)
+        .on_new_window({
+            let app_handle = app.clone();
+            let tab_id = tab_id.to_string();
+            let site_id = site_id.to_string();
+            move |url, _features| handle_tab_new_window(&app_handle, &tab_id, &site_id, url)
+        })
+        .on_download({
+            let app_handle = app.clone();
+            let tab_id = tab_id.to_string();
+            let site_id = site_id.to_string();
+            move |_webview, event| {
+                match event {
+                    tauri::webview::DownloadEvent::Requested { url, destination } => {
+                        let dir = match resolve_download_dir() {
+                            Ok(dir) => dir,
+                            Err(e) => {
+                                println!("[download] 下载目录不可用: {}", e);
+                                return false;
+                            }
+                        };
+                        *destination =
+                            downloads::start(&app_handle, &tab_id, &site_id, &url, destination, &dir);
+                    }
+                    tauri::webview::DownloadEvent::Finished { url, path, success } => {
+                        downloads::finish(&app_handle, &url, path, success);
+                    }
+                    _ => {}
+                }
+                true
+            }
+        })
+        .on_page_load(move |webview, payload| match payload.event() {
+            PageLoadEvent::Started => {
+                println!("[{}] 页面开始加载", webview.label());
+                mark_last_requested_url(&tab_id_clone, payload.url());
+                invalidate_extraction_cache(&tab_id_clone);
+                let load_seq = reset_bridge_state(&tab_id_clone);
+                update_tab_page_state(&tab_id_clone, |state| {
+                    state.loading = true;
+                    state.load_error = None;
+                    state.url = payload.url().to_string();
+                    state.started_url = payload.url().to_string();
+                });
+                schedule_page_load_timeout(
+                    app_handle.clone(),
+                    tab_id_clone.clone(),
+                    site_id_clone.clone(),
+                    load_seq,
+                );
+            }
+            PageLoadEvent::Finished => {
+                let failed = update_tab_page_state(&tab_id_clone, |state| {
+                    state.loading = false;
+                    let failed = is_failed_load(payload.url(), &state.started_url);
+                    if !failed {
+                        state.url = payload.url().to_string();
+                        state.load_error = None;
+                    }
+                    failed
+                });
+                if failed {
+                    let error_url = payload.url().as_str().to_string();
+                    let retry_url = read_tab_runtime(&tab_id_clone, |runtime| {
+                        runtime.last_requested_url.clone()
+                    })
+                    .flatten()
+                    .unwrap_or_else(|| error_url.clone());
+                    let should_retry = register_retry_attempt(&tab_id_clone, &retry_url);
+                    let message = if should_retry {
+                        format!("页面加载失败，准备重试，error_url={}", error_url)
+                    } else {
+                        format!("页面加载失败，重试后仍失败，error_url={}", error_url)
+                    };
+                    append_webview_error_log(&tab_id_clone, &site_id_clone, &retry_url, &message);
+
+                    if should_retry {
+                        let app_handle = app_handle.clone();
+                        let tab_id = tab_id_clone.clone();
+                        let retry_url_clone = retry_url.clone();
+                        let site_id_for_spawn = site_id_clone.clone();
+                        tauri::async_runtime::spawn(async move {
+                            tokio::time::sleep(Duration::from_secs(2)).await;
+                            let webview_label = format!("ai_{}", tab_id);
+                            if let Some(child) = app_handle.get_webview(&webview_label) {
+                                if let Ok(url) = retry_url_clone.parse::<tauri::Url>() {
+                                    let _ = child.navigate(url);
+                                } else {
+                                    let message = "重试 URL 解析失败".to_string();
+                                    append_webview_error_log(
+                                        &tab_id,
+                                        &site_id_for_spawn,
+                                        &retry_url_clone,
+                                        &message,
+                                    );
+                                    report_webview_load_failure(
+                                        &app_handle,
+                                        &tab_id,
+                                        &site_id_for_spawn,
+                                        &retry_url_clone,
+                                        message,
+                                    );
+                                }
+                            }
+                        });
+                    } else {
+                        report_webview_load_failure(
+                            &app_handle,
+                            &tab_id_clone,
+                            &site_id_clone,
+                            &retry_url,
+                            message,
+                        );
+                    }
+                    return;
+                }
+
+                println!("[{}] 页面加载完成", webview.label());
+                if !NATIVE_ZOOM_SUPPORTED.load(Ordering::Relaxed) {
+                    apply_tab_zoom(&webview, tab_zoom(&tab_id_clone, &site_id_clone));
+                }
+                // 初始化脚本在创建 Webview 时生成，之后切换的静音状态在每次加载完成后补上
+                let _ = webview.eval(tab_mute_script(is_tab_muted(&tab_id_clone)));
+                check_site_redirect(&app_handle, &site_id_clone, payload.url());
+                touch_tab(&tab_id_clone);
+                reset_retry_state(&tab_id_clone);
+                schedule_bridge_timeout(tab_id_clone.clone());
+                let _ = app_handle.emit("webview-loaded", &site_id_clone);
+            }
+        });
+
+    struct CreatingViewGuard(String);
+    impl Drop for CreatingViewGuard {
+        fn drop(&mut self) {
+            APP_STATE.lock().unwrap().creating_views.remove(&self.0);
+        }
+    }
+
+    // add_child 期间可能同步触发 Resized 事件，此处不能持有 APP_STATE 锁
+    println!("[ensure_tab_webview] add_child start label={}", webview_label);
+    APP_STATE
+        .lock()
+        .unwrap()
+        .creating_views
+        .insert(tab_id.to_string());
+    let _guard = CreatingViewGuard(tab_id.to_string());
+    window
+        .add_child(
+            webview_builder,
+            LogicalPosition::new(position.x, position.y),
+            LogicalSize::new(size.width, size.height),
+        )
+        .map_err(|e| format!("添加 Webview 失败: {}", e))?;
+    println!("[ensure_tab_webview] add_child done label={}", webview_label);
+    let zoom = tab_zoom(tab_id, site_id);
+    if zoom != 1.0 {
+        if let Some(webview) = app.get_webview(&webview_label) {
+            apply_tab_zoom(&webview, zoom);
+        }
+    }
+
+    APP_STATE.lock().unwrap().views.insert(tab_id.to_string());
+    update_tab_runtime(tab_id, |runtime| runtime.hibernated = false);
+    touch_tab(tab_id);
+    Ok(())
+}
+
+// ============================================================================
+// 子页面弹窗（window.open / target=_blank）
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+struct PopupBlockedPayload {
+    tab_id: String,
+    site_id: String,
+    url: String,
+    reason: String,
+}
+
+fn emit_popup_blocked(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    site_id: &str,
+    url: &tauri::Url,
+    reason: String,
+) {
+    println!("[popup] 已拦截 tab_id={} url={}: {}", tab_id, url, reason);
+    let _ = app.emit(
+        "popup-blocked",
+        PopupBlockedPayload {
+            tab_id: tab_id.to_string(),
+            site_id: site_id.to_string(),
+            url: url.to_string(),
+            reason,
+        },
+    );
+}
+
+/// 弹窗是否与打开它的页面或站点配置的地址属于同一可注册域（如 `gemini.google.com` 打开
+/// `accounts.google.com`）；about:blank 弹窗由打开者随后写入或跳转，视为同站
+fn is_same_site_popup(tab_id: &str, site_id: &str, url: &tauri::Url) -> bool {
+    if url.as_str() == "about:blank" {
+        return true;
+    }
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let page_url = read_tab_runtime(tab_id, |runtime| runtime.page.url.clone())
+        .filter(|url| !url.is_empty());
+    let site_url = get_site_by_id(site_id).ok().map(|site| site.url);
+    page_url
+        .into_iter()
+        .chain(site_url)
+        .filter_map(|raw| site_url::parse_site_url(&raw).ok())
+        .any(|base| site_url::same_registrable_domain(&base, url))
+}
+
+/// 子 Webview 请求打开新窗口：同站弹窗照常打开，其余按站点的弹窗策略处理。
+/// Windows 上在独立线程调用，其余平台在事件循环中调用，因此新建 Tab 放到异步任务中
+fn handle_tab_new_window(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    site_id: &str,
+    url: tauri::Url,
+) -> tauri::webview::NewWindowResponse<tauri::Wry> {
+    use tauri::webview::NewWindowResponse;
+
+    if is_same_site_popup(tab_id, site_id, &url) {
+        return NewWindowResponse::Allow;
+    }
+    if !matches!(url.scheme(), "http" | "https") {
+        emit_popup_blocked(
+            app,
+            tab_id,
+            site_id,
+            &url,
+            format!("不支持的链接类型: {}", url.scheme()),
+        );
+        return NewWindowResponse::Deny;
+    }
+    let policy = get_site_by_id(site_id)
+        .map(|site| site.popup_policy)
+        .unwrap_or_else(|_| default_popup_policy());
+    match policy.as_str() {
+        "new_tab" => {
+            let app = app.clone();
+            let tab_id = tab_id.to_string();
+            let site_id = site_id.to_string();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = open_popup_in_new_tab(&app, &site_id, &url).await {
+                    emit_popup_blocked(
+                        &app,
+                        &tab_id,
+                        &site_id,
+                        &url,
+                        format!("在新 Tab 中打开失败: {}", e),
+                    );
+                }
+            });
+        }
+        "block" => emit_popup_blocked(app, tab_id, site_id, &url, "站点弹窗策略为拦截".to_string()),
+        _ => {
+            if let Err(e) = app.opener().open_url(url.as_str(), None::<&str>) {
+                emit_popup_blocked(app, tab_id, site_id, &url, format!("打开浏览器失败: {}", e));
+            }
+        }
+    }
+    NewWindowResponse::Deny
+}
+
+/// 为站点新建 Tab 并切换过去，随后导航到弹窗地址
+async fn open_popup_in_new_tab(
+    app: &tauri::AppHandle,
+    site_id: &str,
+    url: &tauri::Url,
+) -> Result<(), String> {
+    let tab_id = create_tab_inner(site_id)?;
+    switch_tab_inner(app.clone(), tab_id.clone()).await?;
+    if let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) {
+        mark_last_requested_url(&tab_id, url);
+        child
+            .navigate(url.clone())
+            .map_err(|e| format!("导航失败: {}", e))?;
+    }
+    persist_session();
+    let _ = app.emit("view-switched", site_id);
+    Ok(())
+}
+
+fn tab_ids_for_site(site_id: &str) -> Vec<String> {
+    let mut ids: Vec<String> = vec![site_id.to_string()];
+    for (tab_id, mapped_site) in APP_STATE.lock().unwrap().tabs.iter() {
+        if mapped_site == site_id {
+            ids.push(tab_id.clone());
+        }
+    }
+    ids
+}
+
+fn destroy_tab_webview(app: &tauri::AppHandle, tab_id: &str) {
+    let webview_label = format!("ai_{}", tab_id);
+    if let Some(webview) = app.get_webview(&webview_label) {
+        let _ = webview.close();
+    }
+    if let Some(window) = forget_tab_webview(tab_id).and_then(|label| app.get_window(&label)) {
+        let _ = window.destroy();
+    }
+}
+
+/// Webview 关闭后的记录清理：从 `views` 移除并重置运行时状态（Tab 映射、缩放与静音保留），
+/// 返回 Tab 所在独立窗口的 label
+fn forget_tab_webview(tab_id: &str) -> Option<String> {
+    APP_STATE.lock().unwrap().views.remove(tab_id);
+    reset_tab_runtime(tab_id).and_then(|runtime| runtime.detached_window)
+}
+
+fn close_tab_webview(app: &tauri::AppHandle, tab_id: &str) {
+    destroy_tab_webview(app, tab_id);
+    APP_STATE.lock().unwrap().tabs.remove(tab_id);
+    // 休眠的 Tab 仍保留缩放与静音，关闭后才丢弃
+    TAB_RUNTIME.lock().unwrap().remove(tab_id);
+}
+
+/// 页面加载失败（重试后仍失败）：通知前端并关闭 Webview，Tab 保留错误原因供 Tab 栏显示
+fn report_webview_load_failure(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    site_id: &str,
+    url: &str,
+    message: String,
+) {
+    let payload = WebviewLoadFailedPayload {
+        tab_id: tab_id.to_string(),
+        site_id: site_id.to_string(),
+        url: url.to_string(),
+        message: message.clone(),
+    };
+    let _ = app.emit("webview-load-failed", payload);
+    handle_webview_load_failure(app, tab_id, site_id);
+    // 关闭 Webview 会清空页面状态，之后再写入错误原因
+    update_tab_page_state(tab_id, |state| state.load_error = Some(message));
+}
+
+/// 开始加载后超时仍未完成：通知前端并标记错误，但不关闭 Webview（页面可能稍后加载完成）
+fn schedule_page_load_timeout(app: tauri::AppHandle, tab_id: String, site_id: String, load_seq: u64) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PAGE_LOAD_TIMEOUT).await;
+        let message = format!("页面加载超时（{}s）", PAGE_LOAD_TIMEOUT.as_secs());
+        let url = {
+            let mut runtimes = TAB_RUNTIME.lock().unwrap();
+            match runtimes.get_mut(&tab_id) {
+                Some(runtime) if runtime.bridge.load_seq == load_seq && runtime.page.loading => {
+                    let state = &mut runtime.page;
+                    state.loading = false;
+                    state.load_error = Some(message.clone());
+                    state.url.clone()
+                }
+                _ => return,
+            }
+        };
+        append_webview_error_log(&tab_id, &site_id, &url, &message);
+        let payload = WebviewLoadFailedPayload {
+            tab_id,
+            site_id,
+            url,
+            message,
+        };
+        let _ = app.emit("webview-load-failed", payload);
+    });
+}
+
+fn handle_webview_load_failure(app: &tauri::AppHandle, tab_id: &str, site_id: &str) {
+    destroy_tab_webview(app, tab_id);
+
+    let mut next_active: Option<String> = None;
+    let mut clear_current = false;
+
+    {
+        let mut state = APP_STATE.lock().unwrap();
+        let active_matches = state.active_tab == tab_id;
+        let current_matches = state.current_view == site_id;
+        let layout = &mut state.layout;
+        match layout.mode {
+            LayoutMode::Single => {
+                if active_matches || current_matches {
+                    clear_current = true;
+                }
+            }
+            LayoutMode::Split => {
+                if layout.remove_pane(tab_id) {
+                    if layout.pane_tab_ids.len() < 2 {
+                        next_active = layout.left_tab_id();
+                        layout.set_single();
+                    } else if active_matches {
+                        next_active = layout.left_tab_id();
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(next_tab_id) = next_active {
+        let site = get_tab_site_id(&next_tab_id).ok();
+        APP_STATE
+            .lock()
+            .unwrap()
+            .set_active(&next_tab_id, site.as_deref().unwrap_or_default());
+        match site {
+            Some(site) => update_last_active(&next_tab_id, &site),
+            None => clear_last_active(),
+        }
+    } else if clear_current {
+        APP_STATE.lock().unwrap().clear_active();
+        clear_last_active();
+    }
+
+    let _ = resize_webviews_inner(app, true);
+}
+
+/// 站点展示顺序：置顶站点（按 pinned 顺序）在前，其余按 site_order，
+/// 最后补齐未出现在 site_order 中的站点；忽略不存在的 id 并去重
+fn ordered_site_ids(config: &AppConfig) -> Vec<String> {
+    site_ids_in_order(config, false)
+}
+
+/// 站点展示顺序；`include_disabled` 为 false 时跳过已停用的站点
+fn site_ids_in_order(config: &AppConfig, include_disabled: bool) -> Vec<String> {
+    let existing: HashSet<&str> = config
+        .sites
+        .iter()
+        .filter(|s| include_disabled || s.enabled)
+        .map(|s| s.id.as_str())
+        .collect();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut ordered: Vec<String> = Vec::new();
+
+    let candidates = config
+        .pinned_site_ids
+        .iter()
+        .chain(config.site_order.iter())
+        .chain(config.sites.iter().map(|s| &s.id));
+    for id in candidates {
+        if existing.contains(id.as_str()) && seen.insert(id.as_str()) {
+            ordered.push(id.clone());
+        }
+    }
+    ordered
+}
+
+fn first_site_id_excluding(exclude_site_id: &str) -> Option<String> {
+    let config = APP_CONFIG.lock().unwrap();
+    ordered_site_ids(&config)
+        .into_iter()
+        .find(|id| id != exclude_site_id)
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// 获取应用配置
+#[tauri::command]
+fn get_config(webview: tauri::Webview) -> Result<AppConfig, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    // 注意：不要把 API Key 暴露给前端/远程页面
+    let mut cfg = APP_CONFIG.lock().unwrap().clone();
+    cfg.ai_api_key.clear();
+    for profile in cfg.ai_api_profiles.iter_mut() {
+        profile.key.clear();
+    }
+    cfg.sites = cfg
+        .sites
+        .iter()
+        .map(|s| localized_site(s, &cfg.language))
+        .collect();
+    cfg.locked = is_locked(&cfg);
+    Ok(cfg)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn set_ai_api_settings(
+    webview: tauri::Webview,
+    base_url: String,
+    model: String,
+    api_key: String,
+    clear_key: Option<bool>,
+    provider: Option<AiProvider>,
+    auth: Option<AiAuthMode>,
+    deployment: Option<String>,
+    api_version: Option<String>,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_ai_api_settings")?;
+    let model = model.trim().to_string();
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    let provider = provider.unwrap_or(config.ai_api_provider);
+    if provider == AiProvider::Azure && base_url.trim().is_empty() {
+        return Err("Azure OpenAI 需要填写资源地址（如 https://xxx.openai.azure.com）".into());
+    }
+    config.ai_api_provider = provider;
+    if let Some(deployment) = deployment {
+        config.ai_api_deployment = deployment.trim().to_string();
+    }
+    if let Some(api_version) = api_version {
+        config.ai_api_version = api_version.trim().to_string();
+    }
+    if let Some(auth) = auth {
+        config.ai_api_auth = auth;
+    }
+    config.ai_api_base_url = normalize_ai_base_url(&base_url, provider);
+    config.ai_api_model = model;
+    let api_key_trimmed = api_key.trim().to_string();
+    if !api_key_trimmed.is_empty() || clear_key == Some(true) {
+        store_ai_api_key(&mut config, &api_key_trimmed);
+    }
+    if !api_key_trimmed.is_empty() {
+        config.demo_mode = false;
+    }
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 测试连接的单个请求超时
+const AI_CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 本地模型首次请求需要加载模型，chat completion 的测试超时放宽
+const AI_CONNECTION_TEST_LOCAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 失败时返回的响应体最大字符数
+const AI_CONNECTION_TEST_BODY_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+struct AiConnectionTestResult {
+    /// 服务可达（收到了 HTTP 响应）
+    reachable: bool,
+    /// API Key 被接受
+    auth_ok: bool,
+    /// 模型可用；未配置 Model 时为 None
+    model_found: Option<bool>,
+    /// `GET {base}/models` 的往返耗时
+    latency_ms: u64,
+    /// 失败时服务返回的原始响应体（或连接错误）
+    error: Option<String>,
+}
+
+impl AiConnectionTestResult {
+    fn fail(&mut self, detail: String) {
+        self.error = Some(detail.chars().take(AI_CONNECTION_TEST_BODY_CHARS).collect());
+    }
+}
+
+/// 测试 AI 服务连接：`GET {base}/models`，配置了 Model 时再发一次 1 token 的 chat completion。
+/// 参数为空时使用已保存的设置（便于保存前测试）
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn test_ai_connection(
+    webview: tauri::Webview,
+    base_url: Option<String>,
+    model: Option<String>,
+    api_key: Option<String>,
+    provider: Option<AiProvider>,
+    auth: Option<AiAuthMode>,
+    deployment: Option<String>,
+    api_version: Option<String>,
+) -> Result<AiConnectionTestResult, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let config = APP_CONFIG.lock().unwrap().clone();
+    let mut settings = resolve_ai_settings(&config)?;
+    if let Some(provider) = provider {
+        settings.provider = provider;
+    }
+    if let Some(auth) = auth {
+        settings.auth_mode = auth;
+    }
+    if let Some(deployment) = deployment {
+        settings.deployment = deployment.trim().to_string();
+    }
+    if let Some(api_version) = api_version {
+        settings.api_version = api_version.trim().to_string();
+    }
+    if let Some(base_url) = base_url {
+        settings.base_url = normalize_ai_base_url(&base_url, settings.provider);
+    }
+    if let Some(model) = model {
+        settings.model = model.trim().to_string();
+    }
+    if let Some(api_key) = api_key
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+    {
+        settings.api_key = api_key;
+    }
+    if settings.missing_key() {
+        return Err(AppError::ApiKeyMissing);
+    }
+
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ConnectionTest);
+    let mut result = AiConnectionTestResult {
+        reachable: false,
+        auth_ok: false,
+        model_found: None,
+        latency_ms: 0,
+        error: None,
+    };
+
+    let started = std::time::Instant::now();
+    let resp = ai_client::get(
+        &audit,
+        &settings.models_url(),
+        settings.auth(),
+        Some(AI_CONNECTION_TEST_TIMEOUT),
+    )
+    .await;
+    result.latency_ms = started.elapsed().as_millis() as u64;
+    let resp = match resp {
+        Ok(resp) => resp,
+        Err(e) => {
+            result.fail(format!("无法连接 AI 服务: {}", e));
+            return Ok(result);
+        }
+    };
+    result.reachable = true;
+    let status = resp.status();
+    match status.as_u16() {
+        200..=299 => {
+            result.auth_ok = true;
+            if !settings.model.is_empty() {
+                result.model_found = resp
+                    .json::<serde_json::Value>()
+                    .await
+                    .ok()
+                    .and_then(|v| parse_model_ids(&v))
+                    .map(|ids| ids.contains(&settings.model));
+            }
+        }
+        401 | 403 => {
+            let body = resp.text().await.unwrap_or_default();
+            result.fail(format!("API 返回错误 {}: {}", status, body));
+            return Ok(result);
+        }
+        // 部分兼容服务没有 /models，改由 chat completion 验证
+        404 | 405 => {}
+        _ => {
+            let body = resp.text().await.unwrap_or_default();
+            result.fail(format!("API 返回错误 {}: {}", status, body));
+            return Ok(result);
+        }
+    }
+
+    if settings.missing_model() {
+        if !result.auth_ok {
+            result.fail("服务未提供模型列表接口，请填写 Model 后再测试".to_string());
+        }
+        return Ok(result);
+    }
+
+    let (url, body) = settings.chat_request(None, "ping", Some(1), false);
+    let timeout = if settings.is_local() {
+        AI_CONNECTION_TEST_LOCAL_TIMEOUT
+    } else {
+        AI_CONNECTION_TEST_TIMEOUT
+    };
+    let resp = ai_client::post_json(&audit, &url, settings.auth(), &body, Some(timeout)).await;
+    match resp {
+        Ok(resp) if resp.status().is_success() => {
+            result.auth_ok = true;
+            result.model_found = Some(true);
+        }
+        Ok(resp) => {
+            let status = resp.status();
+            match status.as_u16() {
+                401 | 403 => result.auth_ok = false,
+                // Key 已被接受，服务按模型名拒绝了请求
+                400 | 404 => {
+                    result.auth_ok = true;
+                    result.model_found = Some(false);
+                }
+                _ => {}
+            }
+            let body = resp.text().await.unwrap_or_default();
+            result.fail(format!("API 返回错误 {}: {}", status, body));
+        }
+        Err(e) => result.fail(format!("请求失败: {}", e)),
+    }
+    Ok(result)
+}
+
+/// 模型列表缓存时长
+const AI_MODELS_CACHE_TTL_SECS: u64 = 5 * 60;
+
+struct CachedModelList {
+    /// base_url 与 Key 的指纹，任一变化即失效
+    fingerprint: u64,
+    fetched_at: u64,
+    models: Vec<String>,
+}
+
+static AI_MODELS_CACHE: Lazy<Mutex<Option<CachedModelList>>> = Lazy::new(|| Mutex::new(None));
+
+fn model_list_fingerprint(settings: &ResolvedAiSettings) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    settings.base_url.hash(&mut hasher);
+    settings.api_key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 宽松解析模型列表：兼容 `{"data":[{"id":..}]}`、`{"models":[{"name":..}]}` 与裸数组，
+/// 元素可以是对象（id / name / model 字段）或字符串；无法识别时返回 None
+fn parse_model_ids(value: &serde_json::Value) -> Option<Vec<String>> {
+    let items = match value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(map) => ["data", "models", "result"]
+            .iter()
+            .find_map(|key| map.get(*key)?.as_array())?,
+        _ => return None,
+    };
+    let mut ids: Vec<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            serde_json::Value::String(id) => Some(id.clone()),
+            serde_json::Value::Object(obj) => ["id", "name", "model"]
+                .iter()
+                .find_map(|key| obj.get(*key)?.as_str())
+                .map(|id| id.to_string()),
+            _ => None,
+        })
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    Some(ids)
+}
+
+/// 获取当前服务可用的模型 id（已排序），结果缓存 AI_MODELS_CACHE_TTL_SECS；`refresh` 为 true 时忽略缓存
+#[tauri::command]
+async fn list_ai_models(
+    webview: tauri::Webview,
+    refresh: Option<bool>,
+) -> Result<Vec<String>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let config = APP_CONFIG.lock().unwrap().clone();
+    let settings = resolve_ai_settings(&config)?;
+    if settings.missing_key() {
+        return Err("未配置 API Key，请先在设置中填写后再获取模型列表".into());
+    }
+    let fingerprint = model_list_fingerprint(&settings);
+    if refresh != Some(true) {
+        if let Some(cached) = AI_MODELS_CACHE.lock().unwrap().as_ref() {
+            if cached.fingerprint == fingerprint
+                && now_ts().saturating_sub(cached.fetched_at) < AI_MODELS_CACHE_TTL_SECS
+            {
+                return Ok(cached.models.clone());
+            }
+        }
+    }
+
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ModelList);
+    let resp = ai_client::get(
+        &audit,
+        &settings.models_url(),
+        settings.auth(),
+        Some(AI_CONNECTION_TEST_TIMEOUT),
+    )
+    .await
+    .map_err(|e| format!("无法连接 AI 服务: {}", e))?;
+    let status = resp.status();
+    match status.as_u16() {
+        200..=299 => {}
+        404 | 405 => return Err("该服务未提供模型列表接口，请手动填写 Model".into()),
+        code => {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(AppError::from_api_status(code, body));
+        }
+    }
+    let value: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("解析模型列表失败: {}", e))?;
+    let models = parse_model_ids(&value).ok_or_else(|| "无法识别的模型列表格式".to_string())?;
+
+    *AI_MODELS_CACHE.lock().unwrap() = Some(CachedModelList {
+        fingerprint,
+        fetched_at: now_ts(),
+        models: models.clone(),
+    });
+    Ok(models)
+}
+
+/// 开启/关闭演示模式
+#[tauri::command]
+fn set_demo_mode(webview: tauri::Webview, enabled: bool) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_demo_mode")?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.demo_mode = enabled;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 获取 API Key 存储状态（不返回 Key 本身）
+#[tauri::command]
+fn get_ai_api_key_status(webview: tauri::Webview) -> Result<AiApiKeyStatus, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let config = APP_CONFIG.lock().unwrap().clone();
+    let has_key = !resolve_ai_api_key(&config)?.trim().is_empty();
+    Ok(AiApiKeyStatus {
+        backend: secret_store::backend().name().to_string(),
+        in_keyring: config.ai_api_key_in_keyring,
+        has_key,
+        demo_mode: config.demo_mode,
+    })
+}
+
+// ============================================================================
+// AI 服务配置档案
+// ============================================================================
+
+#[tauri::command]
+fn list_ai_profiles(webview: tauri::Webview) -> Result<Vec<AiApiProfileInfo>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let config = APP_CONFIG.lock().unwrap();
+    Ok(config
+        .ai_api_profiles
+        .iter()
+        .map(|p| ai_profile_info(p, &config.active_ai_profile_id))
+        .collect())
+}
+
+/// 新建或更新档案：`id` 为空时新建；`api_key` 为空且未要求清除时保留原 Key
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn upsert_ai_profile(
+    webview: tauri::Webview,
+    id: Option<String>,
+    name: String,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    clear_key: Option<bool>,
+    provider: Option<AiProvider>,
+    auth: Option<AiAuthMode>,
+    deployment: Option<String>,
+    api_version: Option<String>,
+) -> Result<AiApiProfileInfo, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("upsert_ai_profile")?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("档案名称不能为空".into());
+    }
+    let api_key = api_key.unwrap_or_default().trim().to_string();
+    let id = id.map(|s| s.trim().to_string()).unwrap_or_default();
+    let is_new = id.is_empty();
+
+    // 先在锁外构建档案：写入密钥环可能阻塞（系统弹出解锁框），不能持有 APP_CONFIG
+    let mut profile = if is_new {
+        AiApiProfile {
+            id: format!("aip_{}", Uuid::new_v4().to_string().split('-').next().unwrap()),
+            ..Default::default()
+        }
+    } else {
+        APP_CONFIG
+            .lock()
+            .unwrap()
+            .ai_api_profiles
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| "档案不存在".to_string())?
+    };
+    profile.name = name;
+    if let Some(provider) = provider {
+        profile.provider = provider;
+    }
+    if profile.provider == AiProvider::Azure && base_url.trim().is_empty() {
+        return Err("Azure OpenAI 需要填写资源地址（如 https://xxx.openai.azure.com）".into());
+    }
+    if let Some(auth) = auth {
+        profile.auth = auth;
+    }
+    if let Some(deployment) = deployment {
+        profile.deployment = deployment.trim().to_string();
+    }
+    if let Some(api_version) = api_version {
+        profile.api_version = api_version.trim().to_string();
+    }
+    profile.base_url = normalize_ai_base_url(&base_url, profile.provider);
+    profile.model = model.trim().to_string();
+    let key_changed = !api_key.is_empty() || clear_key == Some(true);
+    if key_changed {
+        store_ai_profile_key(&mut profile, &api_key);
+    }
+
+    let saved = commit_ai_profile(&profile, is_new);
+    if saved.is_err() && is_new && key_changed {
+        // 新档案未保存成功，清理刚写入密钥环的 Key
+        store_ai_profile_key(&mut profile, "");
+    }
+    saved
+}
+
+/// 把档案写入配置副本并保存，保存成功后才替换内存中的配置
+fn commit_ai_profile(profile: &AiApiProfile, is_new: bool) -> Result<AiApiProfileInfo, AppError> {
+    let mut config = APP_CONFIG.lock().unwrap();
+    let mut updated = config.clone();
+    if is_new {
+        updated.ai_api_profiles.push(profile.clone());
+    } else {
+        let existing = updated
+            .ai_api_profiles
+            .iter_mut()
+            .find(|p| p.id == profile.id)
+            .ok_or_else(|| "档案不存在".to_string())?;
+        *existing = profile.clone();
+    }
+    save_config(&updated)?;
+    let info = ai_profile_info(profile, &updated.active_ai_profile_id);
+    *config = updated;
+    Ok(info)
+}
+
+#[tauri::command]
+fn delete_ai_profile(webview: tauri::Webview, id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("delete_ai_profile")?;
+    let mut profile = {
+        let mut config = APP_CONFIG.lock().unwrap();
+        let index = config
+            .ai_api_profiles
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| "档案不存在".to_string())?;
+        let mut updated = config.clone();
+        let profile = updated.ai_api_profiles.remove(index);
+        if updated.active_ai_profile_id == id {
+            updated.active_ai_profile_id.clear();
+        }
+        save_config(&updated)?;
+        *config = updated;
+        profile
+    };
+    // 配置已保存后再删除密钥环中的 Key，且不持有 APP_CONFIG
+    store_ai_profile_key(&mut profile, "");
+    Ok(())
+}
+
+/// 选中档案；传空字符串表示不使用档案（回退到默认 AI 设置）
+#[tauri::command]
+fn set_active_ai_profile(webview: tauri::Webview, id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_active_ai_profile")?;
+    let id = id.trim().to_string();
+    let mut config = APP_CONFIG.lock().unwrap();
+    if !id.is_empty() && !config.ai_api_profiles.iter().any(|p| p.id == id) {
+        return Err("档案不存在".into());
+    }
+    config.active_ai_profile_id = id;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_active_project(webview: tauri::Webview, project_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.active_project_id = project_id;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_language(webview: tauri::Webview, language: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_language")?;
+    let lang = language.trim().to_string();
+    if lang.is_empty() {
+        return Err("language 不能为空".into());
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.language = lang;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_summary_prompt_template(webview: tauri::Webview, template: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_summary_prompt_template")?;
+    let trimmed = template.trim().to_string();
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.summary_prompt_template = if trimmed.is_empty() {
+        default_summary_prompt_template()
+    } else {
+        trimmed
+    };
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 提示词模板可用的变量列表（设置界面据此展示变量标签）
+#[tauri::command]
+fn get_prompt_variables(webview: tauri::Webview) -> Result<Vec<PromptVariable>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(PROMPT_VARIABLES
+        .iter()
+        .map(|&(name, description)| PromptVariable { name, description })
+        .collect())
+}
+
+#[tauri::command]
+fn list_prompt_templates(webview: tauri::Webview) -> Result<Vec<PromptTemplate>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(APP_CONFIG.lock().unwrap().prompt_templates.clone())
+}
+
+/// 新建或更新命名提示词模板：`id` 为空时新建
+#[tauri::command]
+fn upsert_prompt_template(
+    webview: tauri::Webview,
+    id: Option<String>,
+    name: String,
+    body: String,
+) -> Result<PromptTemplate, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("upsert_prompt_template")?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("模板名称不能为空".into());
+    }
+    let body = body.trim().to_string();
+    if body.is_empty() {
+        return Err("模板内容不能为空".into());
+    }
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    let id = id.map(|s| s.trim().to_string()).unwrap_or_default();
+    let template = if id.is_empty() {
+        let template = PromptTemplate {
+            id: format!("tpl_{}", Uuid::new_v4().to_string().split('-').next().unwrap()),
+            name,
+            body,
+        };
+        config.prompt_templates.push(template.clone());
+        template
+    } else {
+        let existing = config
+            .prompt_templates
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| "提示词模板不存在".to_string())?;
+        existing.name = name;
+        existing.body = body;
+        existing.clone()
+    };
+    save_config(&config)?;
+    Ok(template)
+}
+
+/// 删除命名提示词模板；引用它的站点回退到站点自定义模板或全局模板
+#[tauri::command]
+fn delete_prompt_template(webview: tauri::Webview, id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("delete_prompt_template")?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    let before = config.prompt_templates.len();
+    config.prompt_templates.retain(|t| t.id != id);
+    if config.prompt_templates.len() == before {
+        return Err("提示词模板不存在".into());
+    }
+    for site in config.sites.iter_mut() {
+        if site.prompt_template_id == id {
+            site.prompt_template_id.clear();
+        }
+    }
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 设置总结请求的 temperature、输出上限与系统提示词（系统提示词为空时使用默认值）
+#[tauri::command]
+fn set_ai_request_options(
+    webview: tauri::Webview,
+    temperature: f64,
+    max_tokens: Option<i64>,
+    system_prompt: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_ai_request_options")?;
+    if !(0.0..=2.0).contains(&temperature) {
+        return Err("temperature 需在 0.0 到 2.0 之间".into());
+    }
+    let max_tokens = match max_tokens {
+        None => None,
+        Some(n) if n > 0 => Some(u32::try_from(n).map_err(|_| "max_tokens 过大".to_string())?),
+        Some(_) => return Err("max_tokens 必须为正整数".into()),
+    };
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.ai_api_temperature = temperature;
+    config.ai_api_max_tokens = max_tokens;
+    config.ai_system_prompt = system_prompt.trim().to_string();
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 列出项目（不含回收站）
+///
+/// - `tag` 不为空时只列出带该标签的项目（不区分大小写）
+/// - `sort_by`：updated（默认）| created | title；`descending` 默认按时间降序、按标题升序
+/// - 标题按转小写后的字符串逐字节比较：ASCII 不区分大小写，中文按 Unicode 码位排列
+///   （不做拼音排序），同名时再按更新时间降序
+/// - `offset`/`limit` 分页；都不传时返回全部（与旧版本一致）
+#[tauri::command]
+fn list_projects(
+    webview: tauri::Webview,
+    tag: Option<String>,
+    sort_by: Option<String>,
+    descending: Option<bool>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<ProjectSummary>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let sort_by = sort_by
+        .as_deref()
+        .map(ProjectSortBy::parse)
+        .transpose()?
+        .unwrap_or_default();
+    let descending = descending.unwrap_or(sort_by != ProjectSortBy::Title);
+
+    let mut projects = project_store::list_index();
+    projects.retain(|p| !p.is_trashed());
+    if let Some(tag) = tag
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+    {
+        projects.retain(|p| p.tags.iter().any(|t| t.to_lowercase() == tag));
+    }
+    projects.sort_by(|a, b| {
+        let order = match sort_by {
+            ProjectSortBy::Updated => a.updated_at.cmp(&b.updated_at),
+            ProjectSortBy::Created => a.created_at.cmp(&b.created_at),
+            ProjectSortBy::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        };
+        let order = if descending { order.reverse() } else { order };
+        order.then_with(|| b.updated_at.cmp(&a.updated_at))
+    });
+
+    let language = APP_CONFIG.lock().unwrap().language.clone();
+    let now = time::Timestamp::now();
+    Ok(projects
+        .into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|p| ProjectSummary {
+            notes_chars: p.notes_chars,
+            summary_chars: p.summary_chars,
+            id: p.id,
+            title: p.title,
+            created_at: p.created_at,
+            updated_at: p.updated_at,
+            updated_label: p.updated_at.relative(now, &language),
+            tags: p.tags,
+        })
+        .collect())
+}
+
+/// 全部项目中用到的标签（不区分大小写去重，按字母排序）
+#[tauri::command]
+fn list_all_tags(webview: tauri::Webview) -> Result<Vec<String>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let mut tags: BTreeMap<String, String> = BTreeMap::new();
+    for project in project_store::list_index()
+        .into_iter()
+        .filter(|p| !p.is_trashed())
+    {
+        for tag in project.tags {
+            tags.entry(tag.to_lowercase()).or_insert(tag);
+        }
+    }
+    Ok(tags.into_values().collect())
+}
+
+#[tauri::command]
+fn set_project_tags(
+    webview: tauri::Webview,
+    project_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_project_tags")?;
+    let tags = normalize_project_tags(tags)?;
+    update_project_field(&project_id, |p| p.tags = tags.clone())?;
+    Ok(tags)
+}
+
+#[tauri::command]
+fn get_project(webview: tauri::Webview, project_id: String) -> Result<ProjectContext, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    load_live_project(&project_id).ok_or_else(|| AppError::from("项目不存在"))
+}
+
+/// 把项目渲染为 Markdown：标题、时间、总结、笔记、来源与历次总结
+fn render_project_markdown(
+    project: &ProjectContext,
+    site_names: &HashMap<String, String>,
+    language: &str,
+) -> String {
+    let zh = language.starts_with("zh");
+    let label = |zh_text: &'static str, en_text: &'static str| if zh { zh_text } else { en_text };
+    let site_name = |site_id: &str| {
+        site_names
+            .get(site_id)
+            .cloned()
+            .unwrap_or_else(|| site_id.to_string())
+    };
+
+    let mut md = format!("# {}\n\n", project.title.trim());
+    md.push_str(&format!(
+        "- {}: {}\n- {}: {}\n",
+        label("创建时间", "Created"),
+        project.created_at.to_local_iso8601(),
+        label("更新时间", "Updated"),
+        project.updated_at.to_local_iso8601()
+    ));
+
+    let mut section = |heading: &str, body: &str| {
+        if !body.trim().is_empty() {
+            md.push_str(&format!("\n## {}\n\n{}\n", heading, body.trim()));
+        }
+    };
+    section(label("总结", "Summary"), &project.summary);
+    section(label("笔记", "Notes"), &project.notes);
+
+    if !project.sources.is_empty() {
+        md.push_str(&format!("\n## {}\n\n", label("来源", "Sources")));
+        for source in &project.sources {
+            let title = if source.title.trim().is_empty() {
+                &source.url
+            } else {
+                &source.title
+            };
+            md.push_str(&format!(
+                "- [{}]({}) — {}\n",
+                title.trim(),
+                source.url,
+                source.site_name
+            ));
+        }
+    }
+
+    if !project.entries.is_empty() {
+        md.push_str(&format!("\n## {}\n", label("历次总结", "History")));
+        for entry in &project.entries {
+            let sites: Vec<String> = if entry.site_ids.is_empty() {
+                std::iter::once(entry.site_id.as_str())
+                    .filter(|id| !id.is_empty())
+                    .map(site_name)
+                    .collect()
+            } else {
+                entry.site_ids.iter().map(|id| site_name(id)).collect()
+            };
+            md.push_str(&format!(
+                "\n### {} {}",
+                entry.created_at.local_date(),
+                entry.created_at.local_time()
+            ));
+            if !sites.is_empty() {
+                md.push_str(&format!(" · {}", sites.join(" / ")));
+            }
+            md.push_str(&format!("\n\n{}\n", entry.summary.trim()));
+            if !entry.source_excerpt.trim().is_empty() {
+                md.push_str(&format!(
+                    "\n**{}**\n\n",
+                    label("原文摘录", "Source excerpt")
+                ));
+                for line in entry.source_excerpt.trim().lines() {
+                    md.push_str(&format!("> {}\n", line));
+                }
+            }
+        }
+    }
+    md
+}
+
+/// 由项目标题生成安全的文件名：只保留 ASCII 字母数字、`-` 与 `_`，
+/// 其余字符（含中文等非 ASCII 字符）去掉；结果为空时使用 `project-<id 前 8 位>`
+fn markdown_export_file_name(project: &ProjectContext) -> String {
+    let mut name = String::new();
+    for c in project.title.trim().chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            name.push(c);
+        } else if (c.is_whitespace() || c == '.') && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name: String = name.trim_matches('-').chars().take(60).collect();
+    if name.is_empty() {
+        format!("project-{}.md", project.id.chars().take(8).collect::<String>())
+    } else {
+        format!("{}.md", name)
+    }
+}
+
+/// 默认导出位置：文档目录（无则下载目录、主目录）下的安全文件名，已存在时追加序号
+fn default_markdown_export_path(project: &ProjectContext) -> Result<PathBuf, String> {
+    let dirs = directories::UserDirs::new().ok_or("无法获取用户目录")?;
+    let dir = dirs
+        .document_dir()
+        .or_else(|| dirs.download_dir())
+        .unwrap_or_else(|| dirs.home_dir())
+        .to_path_buf();
+    let file_name = markdown_export_file_name(project);
+    let stem = file_name.trim_end_matches(".md");
+    let mut path = dir.join(&file_name);
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).md", stem, n));
+        n += 1;
+    }
+    Ok(path)
+}
+
+/// 导出项目为 Markdown 文件，返回写入的路径；未指定路径时写入文档目录
+#[tauri::command]
+fn export_project_markdown(
+    webview: tauri::Webview,
+    project_id: String,
+    path: Option<String>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let project = load_live_project(&project_id).ok_or_else(|| AppError::from("项目不存在"))?;
+    let (language, site_names) = {
+        let config = APP_CONFIG.lock().unwrap();
+        let names: HashMap<String, String> = config
+            .sites
+            .iter()
+            .map(|s| (s.id.clone(), localized_site(s, &config.language).name))
+            .collect();
+        (config.language.clone(), names)
+    };
+    let path = match path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => default_markdown_export_path(&project)?,
+    };
+    let content = render_project_markdown(&project, &site_names, &language);
+    write_file_atomic(&path, content.as_bytes())
+        .map_err(|e| format!("写入 Markdown 失败: {}", e))?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+fn create_project(webview: tauri::Webview, title: String) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("create_project")?;
+    let language = APP_CONFIG.lock().unwrap().language.clone();
+    let id = new_project_id();
+    let title = if title.trim().is_empty() {
+        default_project_title(&language)
+    } else {
+        title.trim().to_string()
+    };
+    project_store::save_project(&ProjectContext::new(id.clone(), title))?;
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.active_project_id = id.clone();
+    let _ = save_config(&config);
+
+    Ok(id)
+}
+
+/// 兼容旧接口：依次改名、更新笔记与总结（标题为空时不改名）
+#[tauri::command]
+fn update_project(
+    webview: tauri::Webview,
+    project_id: String,
+    title: String,
+    notes: String,
+    summary: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("update_project")?;
+    if !title.trim().is_empty() {
+        rename_project_inner(&project_id, &title)?;
+    }
+    update_project_notes_inner(&project_id, notes)?;
+    update_project_summary_inner(&project_id, summary)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn rename_project(
+    webview: tauri::Webview,
+    project_id: String,
+    title: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("rename_project")?;
+    rename_project_inner(&project_id, &title).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn update_project_notes(
+    webview: tauri::Webview,
+    project_id: String,
+    notes: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("update_project_notes")?;
+    update_project_notes_inner(&project_id, notes).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn update_project_summary(
+    webview: tauri::Webview,
+    project_id: String,
+    summary: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("update_project_summary")?;
+    update_project_summary_inner(&project_id, summary).map_err(AppError::from)
+}
+
+/// 在写锁内只修改项目的一个字段并更新 `updated_at`，不会覆盖其他字段的并发修改
+fn update_project_field(
+    project_id: &str,
+    f: impl FnOnce(&mut ProjectContext),
+) -> Result<(), String> {
+    project_store::update_project(project_id, |p| {
+        if p.is_trashed() {
+            return Err("项目不存在".to_string());
+        }
+        f(p);
+        p.updated_at = time::Timestamp::now();
+        Ok(())
+    })
+}
+
+fn rename_project_inner(project_id: &str, title: &str) -> Result<(), String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("项目名称不能为空".to_string());
+    }
+    update_project_field(project_id, |p| p.title = title.to_string())
+}
+
+fn update_project_notes_inner(project_id: &str, notes: String) -> Result<(), String> {
+    update_project_field(project_id, |p| p.notes = notes)
+}
+
+fn update_project_summary_inner(project_id: &str, summary: String) -> Result<(), String> {
+    update_project_field(project_id, |p| p.summary = summary)
+}
+
+/// 写入一次总结记录（`append_notes` 时把原文追加到笔记末尾），不覆盖用户正在编辑的笔记与标题
+fn record_project_summary(
+    project: &mut ProjectContext,
+    site_id: &str,
+    source: &str,
+    summary: &str,
+    entry_limit: usize,
+    append_notes: bool,
+) {
+    project.append_entry(site_id, source, summary, entry_limit);
+    if append_notes {
+        if !project.notes.trim().is_empty() {
+            project.notes.push_str("\n\n");
+        }
+        project.notes.push_str(source.trim());
+    }
+}
+
+#[tauri::command]
+fn delete_project(webview: tauri::Webview, project_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    permissions::authorize(
+        permissions::InvokeSurface::MainWebview,
+        "delete_project",
+        &project_id,
+        None,
+    )?;
+    delete_project_inner(&project_id)
+}
+
+/// 把项目移到回收站（可在 TRASH_RETENTION_SECS 内恢复）
+fn delete_project_inner(project_id: &str) -> Result<(), AppError> {
+    ensure_unlocked("delete_project")?;
+    project_store::update_project(project_id, |p| {
+        if p.is_trashed() {
+            return Err("项目不存在".to_string());
+        }
+        p.deleted_at = Some(time::Timestamp::now());
+        Ok(())
+    })?;
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    if config.active_project_id == project_id {
+        config.active_project_id = String::new();
+        let _ = save_config(&config);
+    }
+    Ok(())
+}
+
+/// 回收站中的项目
+#[derive(Debug, Clone, Serialize)]
+struct TrashedProject {
+    id: String,
+    title: String,
+    deleted_at: time::Timestamp,
+    /// 到期自动清除的时间
+    purge_at: time::Timestamp,
+}
+
+#[tauri::command]
+fn list_trashed_projects(webview: tauri::Webview) -> Result<Vec<TrashedProject>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let mut trashed: Vec<TrashedProject> = project_store::list_index()
+        .into_iter()
+        .filter_map(|p| {
+            let deleted_at = p.deleted_at?;
+            Some(TrashedProject {
+                id: p.id,
+                title: p.title,
+                deleted_at,
+                purge_at: time::Timestamp::from_secs(deleted_at.as_secs() + TRASH_RETENTION_SECS),
+            })
+        })
+        .collect();
+    trashed.sort_by_key(|p| std::cmp::Reverse(p.deleted_at));
+    Ok(trashed)
+}
+
+#[tauri::command]
+fn restore_project(webview: tauri::Webview, project_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("restore_project")?;
+    project_store::update_project(&project_id, |p| {
+        if !p.is_trashed() {
+            return Err("回收站中没有该项目".to_string());
+        }
+        p.deleted_at = None;
+        Ok(())
+    })
+    .map_err(AppError::from)
+}
+
+/// 从回收站中永久删除项目
+#[tauri::command]
+fn purge_project(webview: tauri::Webview, project_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    permissions::authorize(
+        permissions::InvokeSurface::MainWebview,
+        "purge_project",
+        &project_id,
+        None,
+    )?;
+    ensure_unlocked("purge_project")?;
+    let trashed = project_store::list_index()
+        .iter()
+        .any(|e| e.id == project_id && e.is_trashed());
+    if !trashed {
+        return Err("回收站中没有该项目".into());
+    }
+    project_store::remove_project(&project_id)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAiChatResponse {
+    choices: Vec<OpenAiChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
+}
+
+/// chat completions 的 `usage`（部分兼容服务不返回）
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct OpenAiUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+impl From<OpenAiUsage> for usage::TokenUsage {
+    fn from(u: OpenAiUsage) -> Self {
+        Self {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAiChoice {
+    message: OpenAiMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAiMessage {
+    content: String,
+}
+
+/// 流式响应（SSE）中的一个 `data:` 事件
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAiStreamChunk {
+    #[serde(default)]
+    choices: Vec<OpenAiStreamChoice>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+    /// `stream_options.include_usage` 时最后一个事件携带
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAiStreamChoice {
+    #[serde(default)]
+    delta: OpenAiStreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct OpenAiStreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Anthropic Messages API 的响应
+#[derive(Debug, Clone, Deserialize)]
+struct AnthropicMessageResponse {
+    #[serde(default)]
+    content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnthropicContentBlock {
+    #[serde(default)]
+    text: String,
+}
+
+/// `summary-estimate` 事件：提取完成、发送请求前的文本规模
+#[derive(Debug, Clone, Serialize)]
+struct SummaryEstimatePayload {
+    request_id: String,
+    #[serde(flatten)]
+    estimate: SummaryEstimate,
+    max_chars: Option<u32>,
+}
+
+/// `summary-retrying` 事件：`attempt` 为即将开始的第几次尝试
+#[derive(Debug, Clone, Serialize)]
+struct SummaryRetryingPayload {
+    request_id: String,
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SummaryChunkPayload {
+    request_id: String,
+    delta: String,
+}
+
+/// 流式总结的事件通道：增量文本通过 `summary-chunk` 事件推送给前端
+struct SummaryStream {
+    app: tauri::AppHandle,
+    request_id: String,
+    /// 调用方的总耗时上限，重试等待不会超过剩余时间
+    deadline: Option<tokio::time::Instant>,
+}
+
+/// 规范化前端传入的总结 request_id，缺省时生成新的
+fn summary_request_id(request_id: Option<String>) -> String {
+    request_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+impl SummaryStream {
+    fn new(app: &tauri::AppHandle, request_id: &str) -> Self {
+        Self {
+            app: app.clone(),
+            request_id: request_id.to_string(),
+            deadline: None,
+        }
+    }
+
+    fn with_deadline(mut self, deadline: tokio::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    fn emit_retrying(&self, attempt: u32, delay: Duration, reason: &str) {
+        let _ = self.app.emit(
+            "summary-retrying",
+            SummaryRetryingPayload {
+                request_id: self.request_id.clone(),
+                attempt,
+                max_attempts: SUMMARY_MAX_ATTEMPTS,
+                delay_ms: delay.as_millis() as u64,
+                reason: reason.to_string(),
+            },
+        );
+    }
+
+    fn emit(&self, delta: &str) {
+        let _ = self.app.emit(
+            "summary-chunk",
+            SummaryChunkPayload {
+                request_id: self.request_id.clone(),
+                delta: delta.to_string(),
+            },
+        );
+    }
+}
+
+/// 读取 Anthropic Messages 响应（非流式），有推送通道时整体推送一次
+async fn read_anthropic_message(
+    resp: ai_client::AuditedResponse,
+    stream: Option<&SummaryStream>,
+) -> Result<(String, usage::TokenUsage), String> {
+    let data = resp
+        .json::<AnthropicMessageResponse>()
+        .await
+        .map_err(|e| format!("解析响应失败: {}", e))?;
+    let content: String = data.content.iter().map(|block| block.text.as_str()).collect();
+    if let Some(stream) = stream {
+        stream.emit(&content);
+    }
+    let usage = data.usage.unwrap_or_default();
+    Ok((
+        content,
+        usage::TokenUsage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+        },
+    ))
+}
+
+/// 读取 chat completions 响应：SSE 流逐段推送；服务端忽略 `stream` 时按普通 JSON 解析。
+/// 同时返回 token 用量（服务端未返回时为 0）
+async fn read_chat_completion(
+    mut resp: ai_client::AuditedResponse,
+    stream: Option<&SummaryStream>,
+) -> Result<(String, usage::TokenUsage), String> {
+    let is_sse = resp
+        .content_type()
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+
+    if !is_sse {
+        let data = resp
+            .json::<OpenAiChatResponse>()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        let content = data
+            .choices
+            .first()
+            .map(|c| c.message.content.clone())
+            .unwrap_or_default();
+        if let Some(stream) = stream {
+            stream.emit(&content);
+        }
+        return Ok((content, data.usage.unwrap_or_default().into()));
+    }
+
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut content = String::new();
+    let mut token_usage = usage::TokenUsage::default();
+    // finish_reason 之后可能还有携带 usage 的事件，读到 [DONE] 或流结束为止
+    let mut finished = false;
+    let mut done = false;
+    while !done {
+        // 网络中断必须作为错误返回，避免把截断的内容当成完整总结
+        let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| format!("读取流式响应失败: {}", e))?
+        else {
+            break;
+        };
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                finished = true;
+                done = true;
+                break;
+            }
+            let event = serde_json::from_str::<OpenAiStreamChunk>(data)
+                .map_err(|e| format!("解析流式响应失败: {}", e))?;
+            if let Some(error) = event.error {
+                return Err(format!("API 返回错误: {}", error));
+            }
+            if let Some(u) = event.usage {
+                token_usage = u.into();
+            }
+            for choice in event.choices {
+                if let Some(delta) = choice.delta.content.filter(|d| !d.is_empty()) {
+                    content.push_str(&delta);
+                    if let Some(stream) = stream {
+                        stream.emit(&delta);
+                    }
+                }
+                if choice.finish_reason.is_some() {
+                    finished = true;
+                }
+            }
+        }
+    }
+
+    if !finished {
+        return Err("流式响应意外中断，总结可能不完整".to_string());
+    }
+    Ok((content, token_usage))
+}
+
+/// 总结文本；传入 `request_id` 时增量内容以同一 id 通过 `summary-chunk` 事件推送
+#[tauri::command]
+async fn summarize_text(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    text: String,
+    site_id: Option<String>,
+    request_id: Option<String>,
+    template_id: Option<String>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    let request_id = summary_request_id(request_id);
+    let stream = SummaryStream::new(&app, &request_id);
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ManualSummarize);
+    run_cancellable_summary(
+        &app,
+        &request_id,
+        summarize_text_inner(text, site_id, template_id, Some(&stream), audit),
+    )
+    .await
+}
+
+/// 总结核心逻辑（不依赖调用方 Webview，权限校验由命令入口负责）
+async fn summarize_text_inner(
+    text: String,
+    site_id: Option<String>,
+    template_id: Option<String>,
+    stream: Option<&SummaryStream>,
+    audit: ai_client::ApiCallContext,
+) -> Result<String, AppError> {
+    let config = APP_CONFIG.lock().unwrap().clone();
+    let template = resolve_summary_template(&config, site_id.as_deref(), template_id.as_deref());
+    let context = PromptContext::resolve(&config, site_id.as_deref(), &config.active_project_id);
+    let prompt = build_summary_prompt(
+        &template,
+        language_label(&config.language),
+        &text,
+        &context,
+    );
+
+    let private = site_id
+        .as_deref()
+        .is_some_and(|id| config.sites.iter().any(|s| s.id == id && s.private));
+    let audit = if private {
+        audit.private_site()
+    } else {
+        audit.with_site(site_id)
+    };
+    request_summary(prompt, &text, stream, audit).await
+}
+
+/// 总结请求的最大尝试次数（仅 429 / 5xx / 连接失败会重试）
+const SUMMARY_MAX_ATTEMPTS: u32 = 3;
+
+/// 指数退避的基础间隔与单次等待上限
+const SUMMARY_RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const SUMMARY_RETRY_MAX_DELAY: Duration = Duration::from_secs(20);
+
+/// 第 `attempt` 次失败后的等待时间：基础间隔 × 2^(attempt-1)，再加至多一半的随机抖动
+fn summary_retry_backoff(attempt: u32) -> Duration {
+    let base = SUMMARY_RETRY_BASE_DELAY.as_millis() as u64 * (1u64 << (attempt - 1).min(6));
+    let jitter = (Uuid::new_v4().as_u128() % (base as u128 / 2 + 1)) as u64;
+    Duration::from_millis(base + jitter).min(SUMMARY_RETRY_MAX_DELAY)
+}
+
+/// 一次总结请求的结果种类（决定是否重试）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SummaryAttemptOutcome {
+    /// 收到响应：状态码与 `Retry-After`
+    Response {
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    /// 连接失败或超时
+    Transient,
+    /// 其他请求错误
+    Fatal,
+}
+
+/// 第 `attempt` 次请求后的重试等待时间，不重试时为 None：
+/// 429 / 5xx 优先遵循 `Retry-After`（要求等待超过 SUMMARY_RETRY_MAX_DELAY 时不重试），
+/// 未给出时与连接失败一样按退避策略
+fn summary_retry_delay(outcome: SummaryAttemptOutcome, attempt: u32) -> Option<Duration> {
+    if attempt >= SUMMARY_MAX_ATTEMPTS {
+        return None;
+    }
+    match outcome {
+        SummaryAttemptOutcome::Response {
+            status,
+            retry_after,
+        } if status == 429 || (500..600).contains(&status) => match retry_after {
+            Some(delay) => (delay <= SUMMARY_RETRY_MAX_DELAY).then_some(delay),
+            None => Some(summary_retry_backoff(attempt)),
+        },
+        SummaryAttemptOutcome::Transient => Some(summary_retry_backoff(attempt)),
+        _ => None,
+    }
+}
+
+/// 发送总结请求；429 / 5xx / 连接失败时按退避策略重试（优先遵循 `Retry-After`），
+/// 等待时间超出调用方剩余时间时不再重试
+async fn send_summary_request(
+    settings: &ResolvedAiSettings,
+    url: &str,
+    body: &serde_json::Value,
+    stream: Option<&SummaryStream>,
+    audit: &ai_client::ApiCallContext,
+) -> Result<ai_client::AuditedResponse, AppError> {
+    let mut attempt = 1;
+    loop {
+        let outcome = ai_client::post_json(audit, url, settings.auth(), body, None).await;
+        let (kind, reason) = match &outcome {
+            Ok(resp) => (
+                SummaryAttemptOutcome::Response {
+                    status: resp.status().as_u16(),
+                    retry_after: resp.retry_after(),
+                },
+                format!("HTTP {}", resp.status().as_u16()),
+            ),
+            Err(e) if e.is_connect() || e.is_timeout() => {
+                (SummaryAttemptOutcome::Transient, e.to_string())
+            }
+            Err(e) => (SummaryAttemptOutcome::Fatal, e.to_string()),
+        };
+        let retry = summary_retry_delay(kind, attempt)
+            .filter(|delay| {
+                stream
+                    .and_then(|s| s.deadline)
+                    .is_none_or(|deadline| tokio::time::Instant::now() + *delay < deadline)
+            })
+            .map(|delay| (delay, reason));
+        let Some((delay, reason)) = retry else {
+            return outcome.map_err(|e| AppError::Network(e.to_string()));
+        };
+        drop(outcome);
+        attempt += 1;
+        println!(
+            "[summary] 第 {} 次请求失败（{}），{}ms 后重试",
+            attempt - 1,
+            reason,
+            delay.as_millis()
+        );
+        if let Some(stream) = stream {
+            stream.emit_retrying(attempt, delay, &reason);
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// 发送已渲染好的总结提示词并读取结果；演示模式下由 `source`（原文）生成模拟总结
+async fn request_summary(
+    prompt: String,
+    source: &str,
+    stream: Option<&SummaryStream>,
+    audit: ai_client::ApiCallContext,
+) -> Result<String, AppError> {
+    let config = APP_CONFIG.lock().unwrap().clone();
+    if config.demo_mode {
+        let summary = ai_client::demo_summary(&audit, source, &config.language);
+        if let Some(stream) = stream {
+            for piece in summary.split_inclusive('\n') {
+                stream.emit(piece);
+            }
+        }
+        return Ok(summary);
+    }
+    let settings = resolve_ai_settings(&config)?;
+    if settings.missing_key() {
+        return Err(AppError::ApiKeyMissing);
+    }
+    if settings.missing_model() {
+        return Err("未配置 Model".into());
+    }
+
+    let system_prompt = config.ai_system_prompt.trim();
+    let system_prompt = if system_prompt.is_empty() {
+        DEFAULT_AI_SYSTEM_PROMPT
+    } else {
+        system_prompt
+    };
+    let (url, mut body) = settings.chat_request(
+        Some(system_prompt),
+        &prompt,
+        config.ai_api_max_tokens,
+        stream.is_some(),
+    );
+    let temperature = match settings.provider {
+        AiProvider::Anthropic => config.ai_api_temperature.min(1.0),
+        _ => config.ai_api_temperature,
+    };
+    body["temperature"] = temperature.into();
+
+    let resp = send_summary_request(&settings, &url, &body, stream, &audit).await?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(AppError::from_api_status(status.as_u16(), text));
+    }
+
+    let (content, token_usage) = match settings.provider {
+        AiProvider::OpenAi | AiProvider::Azure => read_chat_completion(resp, stream).await?,
+        AiProvider::Anthropic => read_anthropic_message(resp, stream).await?,
+    };
+
+    if content.trim().is_empty() {
+        return Err("API 返回空内容".into());
+    }
+
+    let model = if settings.model.trim().is_empty() {
+        settings.azure_deployment()
+    } else {
+        settings.model.as_str()
+    };
+    usage::record(audit.site_id(), model, token_usage);
+
+    Ok(content)
+}
+
+/// 项目的总结记录（最新的在前）
+#[tauri::command]
+fn list_project_entries(
+    webview: tauri::Webview,
+    project_id: String,
+) -> Result<Vec<ContextEntry>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let project = load_live_project(&project_id).ok_or_else(|| "项目不存在".to_string())?;
+    let mut entries = project.entries;
+    entries.reverse();
+    Ok(entries)
+}
+
+/// 删除一条总结记录；删除的是最近一次时 `summary` 回退到上一条
+#[tauri::command]
+fn delete_project_entry(
+    webview: tauri::Webview,
+    project_id: String,
+    entry_id: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("delete_project_entry")?;
+    project_store::update_project(&project_id, |project| {
+        let index = project
+            .entries
+            .iter()
+            .position(|e| e.id == entry_id)
+            .ok_or_else(|| "总结记录不存在".to_string())?;
+        project.entries.remove(index);
+        if index == project.entries.len() {
+            project.summary = project
+                .entries
+                .last()
+                .map(|e| e.summary.clone())
+                .unwrap_or_default();
+        }
+        project.updated_at = time::Timestamp::now();
+        Ok(())
+    })
+    .map_err(AppError::from)
+}
+
+/// 设置提取与总结的超时（秒，5–600）；传 None 恢复默认值。
+/// `append_notes` 为 None 时保持原设置
+#[tauri::command]
+fn set_summary_options(
+    webview: tauri::Webview,
+    extract_timeout_secs: Option<u32>,
+    total_timeout_secs: Option<u32>,
+    append_notes: Option<bool>,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_summary_options")?;
+    for secs in [extract_timeout_secs, total_timeout_secs].into_iter().flatten() {
+        if !SUMMARY_TIMEOUT_RANGE_SECS.contains(&secs) {
+            return Err(AppError::from(format!(
+                "超时需在 {} 到 {} 秒之间",
+                SUMMARY_TIMEOUT_RANGE_SECS.start(),
+                SUMMARY_TIMEOUT_RANGE_SECS.end()
+            )));
+        }
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.summary_extract_timeout_secs = extract_timeout_secs;
+    config.summary_total_timeout_secs = total_timeout_secs;
+    if let Some(append_notes) = append_notes {
+        config.summary_append_notes = append_notes;
+    }
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 设置总结完成/失败时是否发送桌面通知
+#[tauri::command]
+fn set_notification_settings(
+    webview: tauri::Webview,
+    notify_on_summary: bool,
+    notify_on_summary_failure: bool,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_notification_settings")?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.notify_on_summary = notify_on_summary;
+    config.notify_on_summary_failure = notify_on_summary_failure;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 站点页面下载的保存目录（配置为空时为系统下载文件夹，无则主目录），不存在时创建
+fn resolve_download_dir() -> Result<PathBuf, String> {
+    let configured = APP_CONFIG.lock().unwrap().download_dir.trim().to_string();
+    let dir = if configured.is_empty() {
+        let dirs = directories::UserDirs::new().ok_or("无法获取用户目录")?;
+        dirs.download_dir()
+            .unwrap_or_else(|| dirs.home_dir())
+            .to_path_buf()
+    } else {
+        PathBuf::from(configured)
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建下载目录 {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// 设置站点页面下载的保存目录；传 None 或空字符串时恢复为系统下载文件夹
+#[tauri::command]
+fn set_download_dir(webview: tauri::Webview, dir: Option<String>) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_download_dir")?;
+    let dir = dir.map(|d| d.trim().to_string()).unwrap_or_default();
+    if !dir.is_empty() {
+        let path = std::path::Path::new(&dir);
+        if !path.is_absolute() {
+            return Err("下载目录必须是绝对路径".into());
+        }
+        if !path.is_dir() {
+            return Err(format!("目录不存在: {}", dir).into());
+        }
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.download_dir = dir;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 最近的下载（新的在前，只保存在内存中）
+#[tauri::command]
+fn list_recent_downloads(
+    webview: tauri::Webview,
+) -> Result<Vec<downloads::DownloadRecord>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(downloads::recent())
+}
+
+/// 用系统默认程序打开已完成的下载（只允许打开最近下载列表中的文件）
+#[tauri::command]
+fn open_download(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let path = PathBuf::from(path.trim());
+    if !downloads::is_completed_download(&path) {
+        return Err("不是本应用下载的文件".into());
+    }
+    if !path.is_file() {
+        return Err(format!("文件不存在: {}", path.display()).into());
+    }
+    app.opener()
+        .open_path(path.display().to_string(), None::<&str>)
+        .map_err(|e| format!("打开文件失败: {}", e).into())
+}
+
+/// 设置 AI 服务请求的 HTTP 代理；传 None 或空字符串时沿用环境变量
+///
+/// 地址在保存前校验并立即重建共享客户端，无效地址不会写入配置。
+#[tauri::command]
+fn set_http_proxy(webview: tauri::Webview, url: Option<String>) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_http_proxy")?;
+    let url = url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty());
+    ai_client::set_proxy(url.as_deref())?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.http_proxy = url;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 更新全局反检测设置；注入脚本因此变化的站点关闭已有 Webview，下次切换时按新设置重建
+#[tauri::command]
+fn set_anti_detection_config(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    settings: AntiDetectionConfig,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_anti_detection_config")?;
+    let settings = normalize_anti_detection_config(settings)?;
+    let changed_site_ids: Vec<String> = {
+        let mut config = APP_CONFIG.lock().unwrap();
+        if config.anti_detection == settings {
+            return Ok(());
+        }
+        let changed = config
+            .sites
+            .iter()
+            .filter(|s| {
+                site_anti_detection_script(s, &config.anti_detection, &config.language)
+                    != site_anti_detection_script(s, &settings, &config.language)
+            })
+            .map(|s| s.id.clone())
+            .collect();
+        config.anti_detection = settings;
+        save_config(&config)?;
+        changed
+    };
+    for site_id in &changed_site_ids {
+        close_site_webviews(&app, site_id);
+    }
+    reconcile_site_tabs(&app);
+    Ok(())
+}
+
+/// 设置总结当前 Tab 的提取字符上限（None 或 0 表示不限制）
+#[tauri::command]
+fn set_summary_max_chars(webview: tauri::Webview, max_chars: Option<u32>) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_summary_max_chars")?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.summary_max_chars = max_chars.filter(|&n| n > 0);
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 设置每个项目保留的总结记录条数（1–1000），超出的旧记录在下次写入时清理
+#[tauri::command]
+fn set_project_entry_limit(webview: tauri::Webview, limit: u32) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_project_entry_limit")?;
+    if !(1..=1000).contains(&limit) {
+        return Err("保留条数需在 1 到 1000 之间".into());
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.project_entry_limit = limit;
+    save_config(&config)?;
+    Ok(())
+}
+
+fn ensure_active_project_id() -> Result<String, String> {
+    let mut config = APP_CONFIG.lock().unwrap();
+    if !config.active_project_id.trim().is_empty() {
+        return Ok(config.active_project_id.clone());
+    }
+
+    // 持有 APP_CONFIG 期间检查并创建，并发调用不会各自创建默认项目
+    if let Some(first) = project_store::list_index()
+        .into_iter()
+        .find(|p| !p.is_trashed())
+    {
+        config.active_project_id = first.id.clone();
+        let _ = save_config(&config);
+        return Ok(first.id);
+    }
+
+    let title = default_project_title(&config.language);
+    let id = new_project_id();
+    project_store::save_project(&ProjectContext::new(id.clone(), title))?;
+
+    config.active_project_id = id.clone();
+    let _ = save_config(&config);
+    Ok(id)
+}
+
+#[tauri::command]
+async fn aihub_submit_page_text(request_id: String, token: String, text: String) -> Result<(), AppError> {
+    let pending = PENDING_EXTRACTS.lock().unwrap().remove(&request_id);
+    if let Some(p) = pending {
+        if p.token != token {
+            return Ok(());
+        }
+        if text.len() > p.max_bytes {
+            let error = format!(
+                "页面文本过大（{} MB），超过 {} MB 上限",
+                text.len() / (1024 * 1024),
+                p.max_bytes / (1024 * 1024)
+            );
+            let _ = p.tx.send(Err(error.clone()));
+            return Err(error.into());
+        }
+        let _ = p.tx.send(Ok(text));
+    }
+    Ok(())
+}
+
+/// 在子 Webview 中求值 `expr`（可返回 Promise），结果转为字符串后经
+/// `aihub_submit_page_text` 回传；超时返回 `Ok(None)`
+async fn eval_page_text(
+    child: &tauri::Webview,
+    expr: &str,
+    timeout: Duration,
+) -> Result<Option<String>, String> {
+    eval_page_text_limited(child, expr, timeout, MAX_SUBMITTED_PAGE_TEXT_BYTES).await
+}
+
+/// 同 `eval_page_text`，回传内容的大小上限为 `max_bytes`（如截图）
+async fn eval_page_text_limited(
+    child: &tauri::Webview,
+    expr: &str,
+    timeout: Duration,
+    max_bytes: usize,
+) -> Result<Option<String>, String> {
+    let request = PendingExtractRequest::register(max_bytes);
+    let js = format!(
+        r#"(async () => {{
+  try {{
+    const text = String(await ({expr}) ?? '');
+    await window.__TAURI__.core.invoke('aihub_submit_page_text', {{ requestId: '{rid}', token: '{tok}', text }});
+  }} catch (e) {{
+    try {{
+      await window.__TAURI__.core.invoke('aihub_submit_page_text', {{ requestId: '{rid}', token: '{tok}', text: '' }});
+    }} catch (_) {{}}
+  }}
+}})();"#,
+        expr = expr,
+        rid = request.request_id,
+        tok = request.token
+    );
+
+    child
+        .eval(&js)
+        .map_err(|e| format!("执行页面脚本失败: {}", e))?;
+
+    request.wait(timeout).await
+}
+
+// ============================================================================
+// 未发送输入检测（破坏性操作前确认）
+// ============================================================================
+
+/// 需要用户确认（`AppError::UnsentInput`）转为字符串时的前缀，后接 JSON：
+/// `{"tab_id": "...", "draft_length": n}`
+const UNSENT_INPUT_ERROR_PREFIX: &str = "[unsent_input]";
+
+/// 探测输入框的等待上限（页面无响应时视为没有草稿）
+const UNSENT_INPUT_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// 通用输入框选择器（站点未配置时使用）
+const GENERIC_INPUT_SELECTOR: &str = "textarea, [contenteditable='true']";
+
+/// 站点输入框选择器：站点配置优先，其次内置站点默认值
+fn input_selector_for_site(site: &AiSite) -> String {
+    if !site.input_selector.trim().is_empty() {
+        return site.input_selector.trim().to_string();
+    }
+    let builtin = match site.id.as_str() {
+        "deepseek" => "textarea#chat-input",
+        "openai" => "#prompt-textarea",
+        "doubao" => "textarea[data-testid='chat_input_input']",
+        "qianwen" => "textarea",
+        _ => "",
+    };
+    builtin.to_string()
+}
+
+/// 站点对话内容选择器：站点配置优先，其次内置站点默认值
+fn extract_selector_for_site(site: &AiSite) -> String {
+    if !site.extract_selector.trim().is_empty() {
+        return site.extract_selector.trim().to_string();
+    }
+    let builtin = match site.id.as_str() {
+        "deepseek" => "#root [class*='message'], .ds-markdown",
+        "openai" => "main [data-message-author-role]",
+        "doubao" => "[data-testid='message_text_content']",
+        "qianwen" => "[class*='questionItem'], [class*='answerItem']",
+        _ => "",
+    };
+    builtin.to_string()
+}
+
+/// 生成提取页面文本的脚本：拼接选择器匹配元素的 innerText，匹配为空时回退到 body
+fn page_text_extraction_script(selector: &str) -> String {
+    let selector = serde_json::to_string(selector).unwrap_or_else(|_| "\"\"".to_string());
+    format!(
+        r#"(() => {{
+  const fallback = () => document?.body?.innerText || '';
+  const sel = {selector};
+  if (!sel) return fallback();
+  let nodes = [];
+  try {{ nodes = Array.from(document.querySelectorAll(sel)); }} catch (_) {{ return fallback(); }}
+  const parts = nodes
+    .filter((el) => !nodes.some((other) => other !== el && other.contains(el)))
+    .map((el) => (el.innerText || '').trim())
+    .filter((t) => t.length > 0);
+  return parts.length > 0 ? parts.join('\n\n') : fallback();
+}})()"#
+    )
+}
+
+/// Tab 对应站点的页面文本提取脚本
+fn page_text_extraction_script_for_tab(tab_id: &str) -> String {
+    let selector = get_tab_site_id(tab_id)
+        .and_then(|site_id| get_site_by_id(&site_id))
+        .map(|site| extract_selector_for_site(&site))
+        .unwrap_or_default();
+    page_text_extraction_script(&selector)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UnsentInputProbe {
+    tab_id: String,
+    has_unsent: bool,
+    draft_length: usize,
+}
+
+/// 读取 Tab 输入框中尚未发送的内容（Webview 不存在或无响应时返回空）
+async fn probe_unsent_input(app: &tauri::AppHandle, tab_id: &str) -> UnsentInputProbe {
+    let empty = UnsentInputProbe {
+        tab_id: tab_id.to_string(),
+        has_unsent: false,
+        draft_length: 0,
+    };
+    let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) else {
+        return empty;
+    };
+    let selector = get_tab_site_id(tab_id)
+        .and_then(|site_id| get_site_by_id(&site_id))
+        .map(|site| input_selector_for_site(&site))
+        .unwrap_or_default();
+    let selectors = serde_json::to_string(&[selector.as_str(), GENERIC_INPUT_SELECTOR])
+        .unwrap_or_else(|_| "[]".to_string());
+    let expr = format!(
+        r#"(() => {{
+  for (const sel of {selectors}) {{
+    if (!sel) continue;
+    let el = null;
+    try {{ el = document.querySelector(sel); }} catch (_) {{ continue; }}
+    if (!el) continue;
+    const value = typeof el.value === 'string' ? el.value : (el.innerText || '');
+    return value.trim();
+  }}
+  return '';
+}})()"#
+    );
+
+    match eval_page_text(&child, &expr, UNSENT_INPUT_PROBE_TIMEOUT).await {
+        Ok(Some(draft)) => {
+            let draft_length = draft.chars().count();
+            UnsentInputProbe {
+                has_unsent: draft_length > 0,
+                draft_length,
+                ..empty
+            }
+        }
+        Ok(None) => {
+            println!("[unsent_input] tab_id={} 探测超时", tab_id);
+            empty
+        }
+        Err(e) => {
+            println!("[unsent_input] tab_id={} 探测失败: {}", tab_id, e);
+            empty
+        }
+    }
+}
+
+/// `check_unsaved` 为 true 时，任一 Tab 有未发送内容则返回需确认的错误
+async fn ensure_no_unsent_input(
+    app: &tauri::AppHandle,
+    tab_ids: &[String],
+    check_unsaved: Option<bool>,
+) -> Result<(), AppError> {
+    if check_unsaved != Some(true) {
+        return Ok(());
+    }
+    for tab_id in tab_ids {
+        let probe = probe_unsent_input(app, tab_id).await;
+        if probe.has_unsent {
+            let detail = serde_json::json!({
+                "tab_id": probe.tab_id,
+                "draft_length": probe.draft_length,
+            });
+            return Err(AppError::UnsentInput(detail));
+        }
+    }
+    Ok(())
+}
+
+/// 查询 Tab 输入框是否有未发送的内容
+#[tauri::command]
+async fn tab_has_unsent_input(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+) -> Result<UnsentInputProbe, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(probe_unsent_input(&app, &tab_id).await)
+}
+
+// ============================================================================
+// 填入输入框（注入项目上下文 / 广播提示词）
+// ============================================================================
+
+/// 注入脚本的等待上限
+const INJECT_CONTEXT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 广播时单个 Tab 的等待上限（含等待输入框出现）
+const BROADCAST_TAB_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 填入脚本等待输入框出现的上限（新建的 Webview 页面可能仍在渲染）
+const INPUT_WAIT_MS: u64 = 5000;
+
+/// 站点发送按钮选择器：站点配置优先，其次内置站点默认值；为空时在输入框上模拟回车
+fn submit_selector_for_site(site: &AiSite) -> String {
+    if !site.submit_selector.trim().is_empty() {
+        return site.submit_selector.trim().to_string();
+    }
+    let builtin = match site.id.as_str() {
+        "openai" => "button[data-testid='send-button'], #composer-submit-button",
+        "doubao" => "button[data-testid='chat_input_send_button'], #flow-end-msg-send",
+        _ => "",
+    };
+    builtin.to_string()
+}
+
+/// 生成把 `text` 填入 Tab 输入框的脚本，结果为 `ok` / `not_found` / `submit_failed`。
+/// `submit` 为 Some 时填入后再点击发送按钮（选择器为空或找不到时在输入框上模拟回车）
+fn fill_input_script(tab_id: &str, text: &str, submit: Option<&str>) -> String {
+    let selector = get_tab_site_id(tab_id)
+        .and_then(|site_id| get_site_by_id(&site_id))
+        .map(|site| input_selector_for_site(&site))
+        .unwrap_or_default();
+    let selectors = serde_json::to_string(&[selector.as_str(), GENERIC_INPUT_SELECTOR])
+        .unwrap_or_else(|_| "[]".to_string());
+    // 文本与选择器经 JSON 编码后嵌入脚本，引号、反引号与换行都不会破坏脚本
+    let text = serde_json::to_string(text).unwrap_or_else(|_| "\"\"".to_string());
+    let submit = match submit {
+        Some(selector) => serde_json::to_string(selector).unwrap_or_else(|_| "\"\"".to_string()),
+        None => "null".to_string(),
+    };
+    // textarea/input 通过原型上的 value setter 赋值（React 等框架才能感知），
+    // contenteditable 通过 insertText 写入
+    format!(
+        r#"(async () => {{
+  const text = {text};
+  const submit = {submit};
+  const find = () => {{
+    for (const sel of {selectors}) {{
+      if (!sel) continue;
+      try {{
+        const el = document.querySelector(sel);
+        if (el) return el;
+      }} catch (_) {{}}
+    }}
+    return null;
+  }};
+  let el = find();
+  for (let waited = 0; !el && waited < {wait}; waited += 200) {{
+    await new Promise((r) => setTimeout(r, 200));
+    el = find();
+  }}
+  if (!el) return 'not_found';
+  el.focus();
+  if (el instanceof HTMLTextAreaElement || el instanceof HTMLInputElement) {{
+    const proto = el instanceof HTMLTextAreaElement ? HTMLTextAreaElement.prototype : HTMLInputElement.prototype;
+    const setter = Object.getOwnPropertyDescriptor(proto, 'value')?.set;
+    if (setter) setter.call(el, text); else el.value = text;
+  }} else {{
+    const range = document.createRange();
+    range.selectNodeContents(el);
+    const selection = window.getSelection();
+    selection?.removeAllRanges();
+    selection?.addRange(range);
+    let inserted = false;
+    try {{ inserted = document.execCommand('insertText', false, text); }} catch (_) {{}}
+    if (!inserted) el.textContent = text;
+  }}
+  el.dispatchEvent(new InputEvent('input', {{ bubbles: true, inputType: 'insertText', data: text }}));
+  el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+  if (submit === null) return 'ok';
+
+  // 等待站点根据输入启用发送按钮
+  await new Promise((r) => setTimeout(r, 300));
+  if (submit) {{
+    let button = null;
+    try {{ button = document.querySelector(submit); }} catch (_) {{}}
+    if (button) {{
+      if (button.disabled) return 'submit_failed';
+      button.click();
+      return 'ok';
+    }}
+  }}
+  const init = {{ key: 'Enter', code: 'Enter', keyCode: 13, which: 13, bubbles: true, cancelable: true }};
+  el.dispatchEvent(new KeyboardEvent('keydown', init));
+  el.dispatchEvent(new KeyboardEvent('keypress', init));
+  el.dispatchEvent(new KeyboardEvent('keyup', init));
+  return 'ok';
+}})()"#,
+        wait = INPUT_WAIT_MS
+    )
+}
+
+/// 把当前项目的总结填入 Tab 的输入框（只填入、不发送）；
+/// 输入框找不到时返回错误，由界面提示改用剪贴板
+#[tauri::command]
+async fn inject_context_to_tab(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    prefix: Option<String>,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let active_project_id = APP_CONFIG.lock().unwrap().active_project_id.clone();
+    let summary = load_live_project(&active_project_id)
+        .map(|p| p.summary)
+        .unwrap_or_default();
+    if summary.trim().is_empty() {
+        return Err("当前项目还没有可注入的总结".into());
+    }
+    let text = match prefix.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(prefix) => format!("{}\n\n{}", prefix, summary.trim()),
+        None => summary.trim().to_string(),
+    };
+
+    let child = tab_child_webview(&app, &tab_id)?;
+    if tab_bridge_ok(&tab_id) == Some(false) {
+        return Err(BRIDGE_BLOCKED_ERROR.into());
+    }
+    let expr = fill_input_script(&tab_id, &text, None);
+    match eval_page_text(&child, &expr, INJECT_CONTEXT_TIMEOUT).await? {
+        Some(result) if result == "ok" => Ok(()),
+        Some(result) if result == "not_found" => {
+            Err("未找到输入框，请复制总结后手动粘贴".into())
+        }
+        Some(_) => Err("注入失败，请复制总结后手动粘贴".into()),
+        None => Err("页面无响应，请复制总结后手动粘贴".into()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BroadcastTabResult {
+    ok: bool,
+    error: Option<String>,
+}
+
+/// 向单个 Tab 填入（并可选发送）提示词
+async fn broadcast_to_tab(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    prompt: &str,
+    submit: bool,
+) -> Result<(), String> {
+    let site_id = get_tab_site_id(tab_id)?;
+    ensure_tab_webview(app, tab_id, &site_id)?;
+    let child = tab_child_webview(app, tab_id)?;
+    if tab_bridge_ok(tab_id) == Some(false) {
+        return Err(BRIDGE_BLOCKED_ERROR.to_string());
+    }
+    let submit_selector = submit.then(|| {
+        get_site_by_id(&site_id)
+            .map(|site| submit_selector_for_site(&site))
+            .unwrap_or_default()
+    });
+    let expr = fill_input_script(tab_id, prompt, submit_selector.as_deref());
+    match eval_page_text(&child, &expr, BROADCAST_TAB_TIMEOUT).await? {
+        Some(result) if result == "ok" => Ok(()),
+        Some(result) if result == "not_found" => Err("未找到输入框".to_string()),
+        Some(result) if result == "submit_failed" => Err("发送按钮不可用".to_string()),
+        Some(_) => Err("填入失败".to_string()),
+        None => Err("页面无响应（可能仍在加载）".to_string()),
+    }
+}
+
+/// 把同一条提示词填入多个 Tab（`submit` 为 true 时同时发送）；
+/// 各 Tab 并行执行，按 Tab 返回结果，单个失败不影响其他 Tab
+#[tauri::command]
+async fn broadcast_prompt(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_ids: Vec<String>,
+    prompt: String,
+    submit: bool,
+) -> Result<HashMap<String, BroadcastTabResult>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    if prompt.trim().is_empty() {
+        return Err("提示词不能为空".into());
+    }
+
+    let mut tasks = Vec::new();
+    for tab_id in tab_ids.into_iter().collect::<HashSet<_>>() {
+        let app = app.clone();
+        let prompt = prompt.clone();
+        tasks.push(tauri::async_runtime::spawn(async move {
+            let result = broadcast_to_tab(&app, &tab_id, &prompt, submit).await;
+            (tab_id, result)
+        }));
+    }
+
+    let mut results = HashMap::new();
+    for task in tasks {
+        let Ok((tab_id, result)) = task.await else {
+            continue;
+        };
+        if let Err(e) = &result {
+            println!("[broadcast] tab_id={} 失败: {}", tab_id, e);
+        }
+        results.insert(
+            tab_id,
+            BroadcastTabResult {
+                ok: result.is_ok(),
+                error: result.err(),
+            },
+        );
+    }
+    Ok(results)
+}
+
+// ============================================================================
+// 页面前进/后退
+// ============================================================================
+
+/// 读取页面导航状态的等待上限
+const NAVIGATION_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TabNavigationState {
+    #[serde(default)]
+    can_go_back: bool,
+    #[serde(default)]
+    can_go_forward: bool,
+    #[serde(default)]
+    url: String,
+}
+
+fn tab_child_webview(app: &tauri::AppHandle, tab_id: &str) -> Result<tauri::Webview, AppError> {
+    app.get_webview(&format!("ai_{}", tab_id))
+        .ok_or_else(|| AppError::WebviewNotFound(tab_id.to_string()))
+}
+
+/// 优先使用 Navigation API（Chromium/WebView2）；不支持时用 history.length 估计能否后退，
+/// 前进状态未知时按不可前进处理
+async fn probe_tab_navigation(app: &tauri::AppHandle, tab_id: &str) -> Result<TabNavigationState, String> {
+    let child = tab_child_webview(app, tab_id)?;
+    let expr = r#"JSON.stringify((() => {
+  const nav = window.navigation;
+  if (nav && typeof nav.canGoBack === 'boolean') {
+    return { can_go_back: nav.canGoBack, can_go_forward: nav.canGoForward, url: location.href };
+  }
+  return { can_go_back: history.length > 1, can_go_forward: false, url: location.href };
+})())"#;
+    let raw = eval_page_text(&child, expr, NAVIGATION_PROBE_TIMEOUT)
+        .await?
+        .ok_or_else(|| "页面无响应".to_string())?;
+    serde_json::from_str(&raw).map_err(|_| "页面未返回导航状态".to_string())
+}
+
+/// 获取 Tab 的前进/后退状态（由页面自身回报）
+#[tauri::command]
+async fn get_tab_navigation_state(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+) -> Result<TabNavigationState, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    probe_tab_navigation(&app, &tab_id).await.map_err(AppError::from)
+}
+
+// ============================================================================
+// 当前页面地址（复制链接 / 在系统浏览器中打开）
+// ============================================================================
+
+/// 只接受可交给外部浏览器的 http(s) 地址
+fn external_page_url(raw: &str) -> Option<String> {
+    tauri::Url::parse(raw.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|url| url.to_string())
+}
+
+/// Tab 当前页面地址：优先由页面回报 `location.href`（单页应用的 pushState 地址也能取到）；
+/// Webview 未创建、页面阻止脚本或无响应时退回 Webview 记录的地址，再退回站点配置的地址
+async fn current_tab_url(app: &tauri::AppHandle, tab_id: &str) -> Result<String, AppError> {
+    let site_id = get_tab_site_id(tab_id)?;
+    if let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) {
+        if tab_bridge_ok(tab_id) != Some(false) {
+            if let Ok(Some(href)) =
+                eval_page_text(&child, "location.href", NAVIGATION_PROBE_TIMEOUT).await
+            {
+                if let Some(url) = external_page_url(&href) {
+                    return Ok(url);
+                }
+            }
+        }
+        if let Some(url) = child
+            .url()
+            .ok()
+            .and_then(|url| external_page_url(url.as_str()))
+        {
+            return Ok(url);
+        }
+    }
+    let site = get_site_by_id(&site_id)?;
+    external_page_url(&site.url).ok_or_else(|| "当前页面没有可用的网址".into())
+}
+
+/// 返回 Tab 当前页面地址，供前端复制到剪贴板
+#[tauri::command]
+async fn copy_tab_url(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    current_tab_url(&app, &tab_id).await
+}
+
+/// 在系统默认浏览器中打开 Tab 当前页面（如内嵌 Webview 中无法完成的 OAuth 登录）
+#[tauri::command]
+async fn open_tab_in_browser(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let url = current_tab_url(&app, &tab_id).await?;
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| format!("打开浏览器失败: {}", e).into())
+}
+
+// ============================================================================
+// 页面截图
+// ============================================================================
+
+/// 截图脚本的等待上限（页面元素很多时内联样式较慢）
+const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 截图 data URL 的大小上限（字节）
+const MAX_SCREENSHOT_DATA_URL_BYTES: usize = 64 * 1024 * 1024;
+
+async fn capture_tab_png(app: &tauri::AppHandle, tab_id: &str) -> Result<Vec<u8>, AppError> {
+    get_tab_site_id(tab_id)?;
+    let child = tab_child_webview(app, tab_id)?;
+    if tab_bridge_ok(tab_id) == Some(false) {
+        return Err("该站点阻止了页面脚本通信，无法截图".into());
+    }
+    let data_url = eval_page_text_limited(
+        &child,
+        screenshot::CAPTURE_SCRIPT,
+        SCREENSHOT_TIMEOUT,
+        MAX_SCREENSHOT_DATA_URL_BYTES,
+    )
+    .await?
+    .ok_or_else(|| AppError::from("截图超时"))?;
+    if data_url.is_empty() {
+        return Err("页面截图失败（页面可能阻止了渲染）".into());
+    }
+    screenshot::decode_png_data_url(&data_url).map_err(AppError::from)
+}
+
+/// 默认截图位置：图片目录（无则下载目录、主目录）下带时间的文件名，已存在时追加序号
+fn default_screenshot_path() -> Result<PathBuf, String> {
+    let dirs = directories::UserDirs::new().ok_or("无法获取用户目录")?;
+    let dir = dirs
+        .picture_dir()
+        .or_else(|| dirs.download_dir())
+        .unwrap_or_else(|| dirs.home_dir())
+        .to_path_buf();
+    let now = time::Timestamp::now();
+    let stem = format!(
+        "AI Hub {} {}",
+        now.local_date(),
+        now.local_time().replace(':', ".")
+    );
+    let mut path = dir.join(format!("{}.png", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).png", stem, n));
+        n += 1;
+    }
+    Ok(path)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScreenshotSavedPayload {
+    path: String,
+    tab_ids: Vec<String>,
+}
+
+/// 截取 Tab 可见区域并保存为 PNG，返回保存路径；未指定路径时保存到图片目录。
+/// `capture_split` 为 true 且处于分屏时截取所有窗格并从左到右拼接
+#[tauri::command]
+async fn capture_tab_screenshot(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    path: Option<String>,
+    capture_split: Option<bool>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let split = capture_split.unwrap_or(false) && APP_STATE.lock().unwrap().layout.is_split();
+    let tab_ids = if split {
+        layout_visible_tab_ids()
+    } else {
+        vec![tab_id]
+    };
+    let mut images = Vec::with_capacity(tab_ids.len());
+    for tab_id in &tab_ids {
+        images.push(capture_tab_png(&app, tab_id).await?);
+    }
+    let png = if images.len() == 1 {
+        images.pop().unwrap_or_default()
+    } else {
+        screenshot::stitch_horizontally(&images)?
+    };
+    let path = match path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => default_screenshot_path()?,
+    };
+    write_file_atomic(&path, &png).map_err(|e| format!("写入截图失败: {}", e))?;
+    let path = path.display().to_string();
+    let _ = app.emit(
+        "screenshot-saved",
+        ScreenshotSavedPayload {
+            path: path.clone(),
+            tab_ids,
+        },
+    );
+    Ok(path)
+}
+
+// ============================================================================
+// 导出 PDF
+// ============================================================================
+//
+// Tauri 尚未暴露 WebView2 PrintToPdf / WKWebView createPDF，无法直接写出 PDF 文件；
+// 目前注入打印样式后打开系统打印对话框，由用户选择“另存为 PDF”（对话框中默认位置不受控制）。
+
+/// 导出前等待页面加载完成的上限
+const PRINT_LOAD_WAIT: Duration = Duration::from_secs(30);
+
+/// 打印样式：常用页边距，并让滚动容器展开以打印完整对话
+const PRINT_STYLE_SCRIPT: &str = r#"(() => {
+  if (!document.getElementById('__aihub_print_style')) {
+    const style = document.createElement('style');
+    style.id = '__aihub_print_style';
+    style.textContent = '@page { margin: 12mm; } @media print { html, body { height: auto !important; overflow: visible !important; } * { overflow: visible !important; max-height: none !important; } }';
+    (document.head || document.documentElement).appendChild(style);
+  }
+  return '';
+})()"#;
+
+#[derive(Debug, Clone, Serialize)]
+struct PrintToPdfResult {
+    /// 已写入的 PDF 路径；只打开了打印对话框时为 None
+    path: Option<String>,
+    /// 已打开系统打印对话框，需由用户在对话框中保存
+    print_dialog_opened: bool,
+}
+
+/// 校验导出路径：不能为空、扩展名为 .pdf、所在目录已存在
+fn validate_pdf_path(path: &str) -> Result<PathBuf, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("导出路径不能为空".to_string());
+    }
+    let path = PathBuf::from(path);
+    let is_pdf = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    if !is_pdf {
+        return Err("导出文件的扩展名必须是 .pdf".to_string());
+    }
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() && !dir.is_dir() => {
+            Err(format!("目录不存在: {}", dir.display()))
+        }
+        _ => Ok(path),
+    }
+}
+
+/// 导出前的参数检查：先校验路径，再确认 Tab 存在并返回其站点 id（都不会创建 Webview）
+fn resolve_print_target(tab_id: &str, path: &str) -> Result<String, AppError> {
+    validate_pdf_path(path)?;
+    get_tab_site_id(tab_id)
+}
+
+/// 等待 Tab 页面加载完成（超时后按当前内容继续）
+async fn wait_tab_page_loaded(tab_id: &str, timeout: Duration) {
+    let deadline = std::time::Instant::now() + timeout;
+    while std::time::Instant::now() < deadline {
+        let loading = read_tab_runtime(tab_id, |runtime| runtime.page.loading).unwrap_or(false);
+        if !loading {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// 把 Tab 的完整页面导出为 PDF。平台接口可用前退回打开打印对话框（`print_dialog_opened`），
+/// 无法打印时返回 `unsupported`
+#[tauri::command]
+async fn print_tab_to_pdf(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    path: String,
+) -> Result<PrintToPdfResult, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let site_id = resolve_print_target(&tab_id, &path)?;
+    let created = app.get_webview(&format!("ai_{}", tab_id)).is_none();
+    ensure_tab_webview(&app, &tab_id, &site_id)?;
+    let child = tab_child_webview(&app, &tab_id)?;
+    if created {
+        // 新建的 Webview 要等开始加载后才有加载状态
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    wait_tab_page_loaded(&tab_id, PRINT_LOAD_WAIT).await;
+
+    let _ = child.eval(PRINT_STYLE_SCRIPT);
+    // macOS 的 WKWebView 忽略 window.print()，改用原生打印；其余平台 window.print() 可用
+    let printed = if cfg!(target_os = "macos") {
+        child.print().map_err(|e| e.to_string())
+    } else {
+        child.eval("window.print()").map_err(|e| e.to_string())
+    };
+    if let Err(e) = printed {
+        return Err(AppError::Unsupported(format!(
+            "当前平台无法导出 PDF: {}",
+            e
+        )));
+    }
+    Ok(PrintToPdfResult {
+        path: None,
+        print_dialog_opened: true,
+    })
+}
+
+// ============================================================================
+// 页面缩放
+// ============================================================================
+
+const MIN_TAB_ZOOM: f64 = 0.5;
+const MAX_TAB_ZOOM: f64 = 3.0;
+
+/// Ctrl + =/- 依次经过的缩放档位（与常见浏览器一致）
+const ZOOM_LEVELS: &[f64] = &[
+    0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0,
+];
+
+/// 平台不支持原生缩放（如 macOS 11 以下）时改用 CSS zoom，并在每次页面加载完成后重新应用
+static NATIVE_ZOOM_SUPPORTED: AtomicBool = AtomicBool::new(true);
+
+fn tab_zoom(tab_id: &str, site_id: &str) -> f64 {
+    if let Some(zoom) = read_tab_runtime(tab_id, |runtime| runtime.zoom).flatten() {
+        return zoom;
+    }
+    get_site_by_id(site_id)
+        .map(|site| site.zoom.clamp(MIN_TAB_ZOOM, MAX_TAB_ZOOM))
+        .unwrap_or_else(|_| default_zoom())
+}
+
+fn apply_tab_zoom(webview: &tauri::Webview, zoom: f64) {
+    if NATIVE_ZOOM_SUPPORTED.load(Ordering::Relaxed) {
+        match webview.set_zoom(zoom) {
+            Ok(()) => return,
+            Err(e) => {
+                println!("[zoom] 原生缩放不可用，改用 CSS zoom: {}", e);
+                NATIVE_ZOOM_SUPPORTED.store(false, Ordering::Relaxed);
+            }
+        }
+    }
+    let _ = webview.eval(format!(
+        "document.documentElement && (document.documentElement.style.zoom = '{}');",
+        zoom
+    ));
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TabZoomChangedPayload {
+    tab_id: String,
+    site_id: String,
+    zoom: f64,
+}
+
+/// 最近一次调度的缩放保存序号
+static ZOOM_PERSIST_SEQ: AtomicU64 = AtomicU64::new(0);
+const ZOOM_PERSIST_DEBOUNCE: Duration = Duration::from_millis(800);
+
+/// 站点默认缩放变化后延迟写入配置文件，连续调整（快捷键连按、Ctrl+滚轮）只写最后一次
+fn schedule_zoom_persist() {
+    let seq = ZOOM_PERSIST_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(ZOOM_PERSIST_DEBOUNCE).await;
+        if ZOOM_PERSIST_SEQ.load(Ordering::SeqCst) != seq {
+            return;
+        }
+        let config = APP_CONFIG.lock().unwrap();
+        if let Err(e) = save_config(&config) {
+            println!("[zoom] 保存站点缩放失败: {}", e);
+        }
+    });
+}
+
+/// 设置 Tab 缩放并立即生效；未锁定时同时记为站点默认缩放（该站点之后新建的 Tab 沿用），
+/// 配置文件延迟保存。锁定模式下只改变当前 Tab。返回实际生效的比例
+fn set_tab_zoom_inner(app: &tauri::AppHandle, tab_id: &str, zoom: f64) -> Result<f64, AppError> {
+    if !zoom.is_finite() {
+        return Err("缩放比例无效".into());
+    }
+    let zoom = zoom.clamp(MIN_TAB_ZOOM, MAX_TAB_ZOOM);
+    let site_id = get_tab_site_id(tab_id)?;
+    update_tab_runtime(tab_id, |runtime| runtime.zoom = Some(zoom));
+    if let Some(webview) = app.get_webview(&format!("ai_{}", tab_id)) {
+        apply_tab_zoom(&webview, zoom);
+    }
+    if ensure_unlocked("set_tab_zoom").is_ok() {
+        let changed = {
+            let mut config = APP_CONFIG.lock().unwrap();
+            match config.sites.iter_mut().find(|s| s.id == site_id) {
+                Some(site) if site.zoom != zoom => {
+                    site.zoom = zoom;
+                    true
+                }
+                _ => false,
+            }
+        };
+        if changed {
+            schedule_zoom_persist();
+        }
+    }
+    let _ = app.emit(
+        "tab-zoom-changed",
+        TabZoomChangedPayload {
+            tab_id: tab_id.to_string(),
+            site_id,
+            zoom,
+        },
+    );
+    Ok(zoom)
+}
+
+/// 设置 Tab 页面缩放（0.5–3.0，超出范围时取边界值）
+#[tauri::command]
+fn set_tab_zoom(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    factor: f64,
+) -> Result<f64, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    set_tab_zoom_inner(&app, &tab_id, factor)
+}
+
+/// 获取 Tab 当前的页面缩放
+#[tauri::command]
+fn get_tab_zoom(webview: tauri::Webview, tab_id: String) -> Result<f64, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let site_id = get_tab_site_id(&tab_id)?;
+    Ok(tab_zoom(&tab_id, &site_id))
+}
+
+/// 子页面的缩放快捷键（仅允许 ai_* Webview 调整自己所属的 Tab）：
+/// `direction` 为 1/-1 时放大/缩小一档，0 时恢复 100%
+#[tauri::command]
+fn aihub_zoom_step(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    direction: i32,
+) -> Result<f64, AppError> {
+    let tab_id = webview
+        .label()
+        .strip_prefix("ai_")
+        .ok_or_else(|| "Not allowed".to_string())?
+        .to_string();
+    let site_id = get_tab_site_id(&tab_id)?;
+    let current = tab_zoom(&tab_id, &site_id);
+    let next = match direction.signum() {
+        1 => ZOOM_LEVELS
+            .iter()
+            .copied()
+            .find(|level| *level > current + 0.001)
+            .unwrap_or(MAX_TAB_ZOOM),
+        -1 => ZOOM_LEVELS
+            .iter()
+            .rev()
+            .copied()
+            .find(|level| *level < current - 0.001)
+            .unwrap_or(MIN_TAB_ZOOM),
+        _ => 1.0,
+    };
+    set_tab_zoom_inner(&app, &tab_id, next)
+}
+
+// ============================================================================
+// Tab 静音
+// ============================================================================
+//
+// Tauri 没有提供 Webview 静音接口，这里通过注入脚本实现：
+// 静音当前与之后插入的 <audio>/<video>（MutationObserver + 拦截 play），并取消语音朗读。
+// 只恢复由脚本静音的元素，页面自身的静音设置不受影响。限制：Web Audio（AudioContext）不在覆盖范围内。
+
+/// 静音脚本：初始化脚本与切换时共用，`__MUTED__` 替换为 true/false
+const TAB_MUTE_SCRIPT: &str = r#"(() => {
+  const muted = __MUTED__;
+  let state = window.__aihubMute;
+  if (!state) {
+    state = window.__aihubMute = { muted, owned: new WeakSet() };
+    const apply = (el) => {
+      if (state.muted) {
+        if (!el.muted) {
+          el.muted = true;
+          state.owned.add(el);
+        }
+      } else if (state.owned.has(el)) {
+        el.muted = false;
+        state.owned.delete(el);
+      }
+    };
+    state.applyAll = () => document.querySelectorAll('audio, video').forEach(apply);
+    const play = HTMLMediaElement.prototype.play;
+    HTMLMediaElement.prototype.play = function (...args) {
+      apply(this);
+      return play.apply(this, args);
+    };
+    if (window.speechSynthesis) {
+      const speak = window.speechSynthesis.speak.bind(window.speechSynthesis);
+      window.speechSynthesis.speak = (utterance) => {
+        if (!state.muted) speak(utterance);
+      };
+    }
+    const observe = () => {
+      new MutationObserver((mutations) => {
+        if (!state.muted) return;
+        for (const m of mutations) {
+          for (const node of m.addedNodes) {
+            if (node instanceof HTMLMediaElement) apply(node);
+            else if (node.querySelectorAll) node.querySelectorAll('audio, video').forEach(apply);
+          }
+        }
+      }).observe(document.documentElement, { childList: true, subtree: true });
+      state.applyAll();
+    };
+    if (document.documentElement) observe();
+    else document.addEventListener('DOMContentLoaded', observe, { once: true });
+  }
+  state.muted = muted;
+  if (document.documentElement) state.applyAll();
+  if (muted && window.speechSynthesis) window.speechSynthesis.cancel();
+})();"#;
+
+fn tab_mute_script(muted: bool) -> String {
+    TAB_MUTE_SCRIPT.replace("__MUTED__", if muted { "true" } else { "false" })
+}
+
+fn is_tab_muted(tab_id: &str) -> bool {
+    read_tab_runtime(tab_id, |runtime| runtime.muted).unwrap_or(false)
+}
+
+fn set_tab_muted_inner(app: &tauri::AppHandle, tab_id: &str, muted: bool) {
+    update_tab_runtime(tab_id, |runtime| runtime.muted = muted);
+    if let Some(webview) = app.get_webview(&format!("ai_{}", tab_id)) {
+        let _ = webview.eval(tab_mute_script(muted));
+    }
+    let _ = app.emit("tab-page-updated", tab_id);
+}
+
+/// 静音/取消静音 Tab（休眠或重新创建 Webview 后仍保持）
+#[tauri::command]
+fn set_tab_muted(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    muted: bool,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    get_tab_site_id(&tab_id)?;
+    set_tab_muted_inner(&app, &tab_id, muted);
+    Ok(())
+}
+
+/// 一键静音/取消静音所有 Tab（含休眠的 Tab），返回处理的 Tab 数
+#[tauri::command]
+fn mute_all_tabs(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    muted: bool,
+) -> Result<usize, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    // 与 get_tabs_state 相同的 Tab 范围
+    let mut tab_ids = APP_STATE.lock().unwrap().known_tab_ids();
+    tab_ids.extend(hibernated_tab_ids());
+    for tab_id in &tab_ids {
+        set_tab_muted_inner(&app, tab_id, muted);
+    }
+    Ok(tab_ids.len())
+}
+
+// ============================================================================
+// 页内查找
+// ============================================================================
+
+/// 单次查找最多标记的匹配数
+const FIND_MAX_MATCHES: usize = 1000;
+
+/// 查找脚本的等待上限
+const FIND_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 清除查找高亮与注入的样式
+const FIND_CLEAR_SCRIPT: &str = r#"(() => {
+  if (window.CSS && CSS.highlights) {
+    CSS.highlights.delete('aihub-find');
+    CSS.highlights.delete('aihub-find-current');
+  }
+  const style = document.getElementById('__aihub_find_style');
+  if (style) style.remove();
+  if (window.__aihubFind && window.__aihubFind.usedSelection) {
+    const sel = window.getSelection();
+    if (sel) sel.removeAllRanges();
+  }
+  window.__aihubFind = undefined;
+  return '';
+})()"#;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FindInPageResult {
+    #[serde(default)]
+    match_count: usize,
+    /// 当前匹配序号（从 1 开始，无匹配时为 0）
+    #[serde(default)]
+    current_index: usize,
+}
+
+/// 生成查找脚本：同一关键词再次查找时移动到下一个/上一个匹配。
+/// 支持 CSS Custom Highlight API 时以 `::highlight` 样式标出所有匹配（不改动页面 DOM），
+/// 否则退回到选中当前匹配
+fn find_in_page_script(query: &str, forward: bool) -> String {
+    let query = serde_json::to_string(query).unwrap_or_else(|_| "\"\"".to_string());
+    format!(
+        r#"(() => {{
+  const q = {query};
+  const forward = {forward};
+  const st = window.__aihubFind || (window.__aihubFind = {{ query: '', ranges: [], index: -1, usedSelection: false }});
+  const hasHighlight = typeof Highlight === 'function' && window.CSS && CSS.highlights;
+  if (!document.getElementById('__aihub_find_style')) {{
+    const style = document.createElement('style');
+    style.id = '__aihub_find_style';
+    style.textContent = '::highlight(aihub-find){{background:#fde68a;color:inherit}}::highlight(aihub-find-current){{background:#f97316;color:#fff}}';
+    (document.head || document.documentElement).appendChild(style);
+  }}
+  const stale = st.ranges.some((r) => r.collapsed);
+  if (st.query !== q || stale) {{
+    st.query = q;
+    st.index = -1;
+    st.ranges = [];
+    const needle = q.toLowerCase();
+    const walker = document.createTreeWalker(document.body || document.documentElement, NodeFilter.SHOW_TEXT, {{
+      acceptNode: (n) => {{
+        const p = n.parentElement;
+        if (!p || p.closest('script,style,noscript')) return NodeFilter.FILTER_REJECT;
+        return NodeFilter.FILTER_ACCEPT;
+      }}
+    }});
+    outer: for (let n = walker.nextNode(); n; n = walker.nextNode()) {{
+      const text = n.data.toLowerCase();
+      for (let i = text.indexOf(needle); i !== -1; i = text.indexOf(needle, i + needle.length)) {{
+        const r = document.createRange();
+        r.setStart(n, i);
+        r.setEnd(n, i + needle.length);
+        st.ranges.push(r);
+        if (st.ranges.length >= {max}) break outer;
+      }}
+    }}
+  }}
+  const total = st.ranges.length;
+  if (total === 0) {{
+    if (hasHighlight) {{
+      CSS.highlights.delete('aihub-find');
+      CSS.highlights.delete('aihub-find-current');
+    }}
+    return JSON.stringify({{ match_count: 0, current_index: 0 }});
+  }}
+  st.index = st.index < 0 ? (forward ? 0 : total - 1) : (st.index + (forward ? 1 : -1) + total) % total;
+  const current = st.ranges[st.index];
+  if (hasHighlight) {{
+    CSS.highlights.set('aihub-find', new Highlight(...st.ranges));
+    CSS.highlights.set('aihub-find-current', new Highlight(current));
+  }} else {{
+    const sel = window.getSelection();
+    if (sel) {{
+      sel.removeAllRanges();
+      sel.addRange(current);
+      st.usedSelection = true;
+    }}
+  }}
+  const el = current.startContainer.parentElement;
+  if (el) el.scrollIntoView({{ block: 'center' }});
+  return JSON.stringify({{ match_count: total, current_index: st.index + 1 }});
+}})()"#,
+        max = FIND_MAX_MATCHES,
+    )
+}
+
+/// 在 Tab 页面中查找（Tauri 未提供原生查找 API，使用注入脚本实现）；
+/// 关键词为空时清除高亮
+#[tauri::command]
+async fn find_in_page(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    query: String,
+    forward: bool,
+) -> Result<FindInPageResult, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let child = tab_child_webview(&app, &tab_id)?;
+    if query.is_empty() {
+        child
+            .eval(FIND_CLEAR_SCRIPT)
+            .map_err(|e| format!("执行页面脚本失败: {}", e))?;
+        return Ok(FindInPageResult::default());
+    }
+    let raw = eval_page_text(&child, &find_in_page_script(&query, forward), FIND_TIMEOUT)
+        .await?
+        .ok_or_else(|| "页面无响应".to_string())?;
+    serde_json::from_str(&raw).map_err(|_| AppError::from("页面未返回查找结果"))
+}
+
+/// 结束页内查找并清除高亮
+#[tauri::command]
+fn stop_find_in_page(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    // Webview 已关闭时高亮随页面一起销毁
+    let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) else {
+        return Ok(());
+    };
+    child
+        .eval(FIND_CLEAR_SCRIPT)
+        .map_err(|e| AppError::from(format!("执行页面脚本失败: {}", e)))
+}
+
+/// Tab 页面后退
+#[tauri::command]
+fn tab_go_back(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    tab_child_webview(&app, &tab_id)?
+        .eval("history.back()")
+        .map_err(|e| AppError::from(format!("后退失败: {}", e)))
+}
+
+/// Tab 页面前进
+#[tauri::command]
+fn tab_go_forward(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    tab_child_webview(&app, &tab_id)?
+        .eval("history.forward()")
+        .map_err(|e| AppError::from(format!("前进失败: {}", e)))
+}
+
+/// 标记“当前活跃 Tab”（用于 split 模式下的“总结当前对话”）
+#[tauri::command]
+fn set_active_tab_id(webview: tauri::Webview, tab_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    if tab_id.trim().is_empty() {
+        return Ok(());
+    }
+    APP_STATE.lock().unwrap().active_tab = tab_id.clone();
+    if let Ok(site_id) = get_tab_site_id(&tab_id) {
+        update_last_active(&tab_id, &site_id);
+    }
+    Ok(())
+}
+
+/// 总结当前 Tab 并保存到项目
+///
+/// - 默认写入当前项目
+/// - `project_id`：写入指定项目，不改变当前项目
+/// - `create_project_title`：为本次总结新建项目；`activate_project` 为 true 时同时设为当前项目
+/// - 目标项目在提取页面之前校验，不存在时直接失败
+#[tauri::command]
+async fn summarize_active_tab(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    request_id: Option<String>,
+    template_id: Option<String>,
+    project_id: Option<String>,
+    create_project_title: Option<String>,
+    activate_project: Option<bool>,
+) -> Result<ActiveTabSummary, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    let target = SummaryTarget::resolve(
+        project_id,
+        create_project_title,
+        activate_project.unwrap_or(false),
+    )?;
+    let request_id = summary_request_id(request_id);
+    run_cancellable_summary(
+        &app,
+        &request_id,
+        summarize_active_tab_inner(app.clone(), None, &request_id, template_id, target),
+    )
+    .await
+}
+
+/// “总结当前 Tab”的结果保存到哪个项目
+#[derive(Debug, Clone)]
+enum SummaryTarget {
+    /// 当前项目（没有时创建默认项目）
+    ActiveProject,
+    Project(String),
+    NewProject {
+        title: String,
+        activate: bool,
+    },
+}
+
+impl SummaryTarget {
+    fn resolve(
+        project_id: Option<String>,
+        create_project_title: Option<String>,
+        activate: bool,
+    ) -> Result<Self, String> {
+        let project_id = project_id
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        match (project_id, create_project_title) {
+            (Some(_), Some(_)) => {
+                Err("project_id 与 create_project_title 不能同时指定".to_string())
+            }
+            (Some(id), None) => {
+                if load_live_project(&id).is_none() {
+                    return Err("项目不存在".to_string());
+                }
+                Ok(SummaryTarget::Project(id))
+            }
+            (None, Some(title)) => {
+                let title = title.trim();
+                if title.is_empty() {
+                    return Err("项目名称不能为空".to_string());
+                }
+                ensure_storage_writable()?;
+                Ok(SummaryTarget::NewProject {
+                    title: title.to_string(),
+                    activate,
+                })
+            }
+            (None, None) => Ok(SummaryTarget::ActiveProject),
+        }
+    }
+}
+
+// ============================================================================
+// 提取文本缓存（重新总结时不再提取页面）
+// ============================================================================
+
+/// 单条缓存的文本上限，超过时不缓存
+const EXTRACTION_CACHE_MAX_BYTES: usize = 1024 * 1024;
+
+/// 缓存的最长保留时间，过期后视为没有缓存
+const EXTRACTION_CACHE_TTL_SECS: u64 = 30 * 60;
+
+/// Tab 最近一次提取的页面文本；页面开始导航或 Tab 关闭时清除
+#[derive(Debug, Clone)]
+struct CachedExtraction {
+    site_id: String,
+    url: String,
+    text: String,
+    extracted_at: time::Timestamp,
+}
+
+fn cache_extraction(tab_id: &str, site_id: &str, url: String, text: &str) {
+    let extraction = (text.len() <= EXTRACTION_CACHE_MAX_BYTES).then(|| CachedExtraction {
+        site_id: site_id.to_string(),
+        url,
+        text: text.to_string(),
+        extracted_at: time::Timestamp::now(),
+    });
+    update_tab_runtime(tab_id, |runtime| runtime.extraction = extraction);
+}
+
+fn invalidate_extraction_cache(tab_id: &str) {
+    if let Some(runtime) = TAB_RUNTIME.lock().unwrap().get_mut(tab_id) {
+        runtime.extraction = None;
+    }
+}
+
+/// 未过期的缓存
+fn cached_extraction(tab_id: &str) -> Option<CachedExtraction> {
+    let mut runtimes = TAB_RUNTIME.lock().unwrap();
+    let cache = &mut runtimes.get_mut(tab_id)?.extraction;
+    let entry = cache.as_ref()?;
+    if now_ts().saturating_sub(entry.extracted_at.as_secs()) > EXTRACTION_CACHE_TTL_SECS {
+        *cache = None;
+        return None;
+    }
+    Some(entry.clone())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ResummarizeResult {
+    summary: String,
+    /// 提取时的页面地址
+    url: String,
+    extracted_at: time::Timestamp,
+    /// 缓存的时长（秒），供界面提示内容可能已过时
+    age_secs: u64,
+}
+
+/// 用当前 Tab 最近一次提取的文本重新总结（不再提取页面，也不保存到项目）；
+/// 没有缓存（未总结过、页面已导航或缓存过期）时返回错误
+#[tauri::command]
+async fn resummarize_last_extraction(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    template_id: Option<String>,
+    request_id: Option<String>,
+) -> Result<ResummarizeResult, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    let tab_id = APP_STATE.lock().unwrap().focused_tab_id();
+    let cached = cached_extraction(&tab_id)
+        .ok_or_else(|| AppError::from("当前 Tab 没有可复用的提取内容，请重新总结"))?;
+    let request_id = summary_request_id(request_id);
+    let stream = SummaryStream::new(&app, &request_id);
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ManualSummarize)
+        .with_tab(Some(tab_id));
+    let summary = run_cancellable_summary(
+        &app,
+        &request_id,
+        summarize_text_inner(
+            cached.text,
+            Some(cached.site_id),
+            template_id,
+            Some(&stream),
+            audit,
+        ),
+    )
+    .await?;
+    Ok(ResummarizeResult {
+        summary,
+        url: cached.url,
+        extracted_at: cached.extracted_at,
+        age_secs: now_ts().saturating_sub(cached.extracted_at.as_secs()),
+    })
+}
+
+// ============================================================================
+// 页面文本提取
+// ============================================================================
+
+/// `extract_tab_text` 返回的字符数上限，超过时截断
+const EXTRACT_TAB_TEXT_MAX_CHARS: usize = 500_000;
+
+struct TabExtraction {
+    text: String,
+    url: String,
+}
+
+/// 确保 Tab 的 Webview 存在后提取页面文本（按站点的提取选择器）；
+/// 等待中的提取请求由 `eval_page_text` 在返回或被取消时清理
+async fn extract_tab_text_inner(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    site_id: &str,
+    timeout: Duration,
+) -> Result<TabExtraction, AppError> {
+    ensure_tab_webview(app, tab_id, site_id)?;
+    let child = app
+        .get_webview(&format!("ai_{}", tab_id))
+        .ok_or_else(|| AppError::WebviewNotFound(tab_id.to_string()))?;
+    if tab_bridge_ok(tab_id) == Some(false) {
+        return Err(BRIDGE_BLOCKED_ERROR.into());
+    }
+    let text = eval_page_text(
+        &child,
+        &page_text_extraction_script_for_tab(tab_id),
+        timeout,
+    )
+    .await?
+    .ok_or_else(|| extract_timeout_error(timeout))?;
+    if text.trim().is_empty() {
+        return Err("未能提取到页面文本（可能被站点限制或页面未加载完成）".into());
+    }
+    Ok(TabExtraction {
+        text,
+        url: child.url().map(|u| u.to_string()).unwrap_or_default(),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExtractedTabText {
+    tab_id: String,
+    site_id: String,
+    text: String,
+    /// `text` 的字符数（截断后）
+    length: usize,
+    url: String,
+    /// 原文超过 EXTRACT_TAB_TEXT_MAX_CHARS 个字符，`text` 只保留开头部分
+    truncated: bool,
+}
+
+/// 只提取页面文本，不调用 AI 服务；`tab_id` 为空时提取当前 Tab
+#[tauri::command]
+async fn extract_tab_text(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    tab_id: Option<String>,
+) -> Result<ExtractedTabText, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let tab_id = tab_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| APP_STATE.lock().unwrap().focused_tab_id());
+    if tab_id.is_empty() {
+        return Err("没有可提取的页面".into());
+    }
+    let site_id = get_tab_site_id(&tab_id)?;
+    let timeout = summary_extract_timeout(&APP_CONFIG.lock().unwrap());
+    let extraction = extract_tab_text_inner(&app, &tab_id, &site_id, timeout).await?;
+    let truncated = extraction
+        .text
+        .chars()
+        .nth(EXTRACT_TAB_TEXT_MAX_CHARS)
+        .is_some();
+    let text: String = if truncated {
+        extraction
+            .text
+            .chars()
+            .take(EXTRACT_TAB_TEXT_MAX_CHARS)
+            .collect()
+    } else {
+        extraction.text
+    };
+    Ok(ExtractedTabText {
+        length: text.chars().count(),
+        tab_id,
+        site_id,
+        text,
+        url: extraction.url,
+        truncated,
+    })
+}
+
+/// 总结当前 Tab 的结果
+#[derive(Debug, Clone, Serialize)]
+struct ActiveTabSummary {
+    summary: String,
+    /// 保存总结的项目
+    project_id: String,
+    /// 是否为本次总结新建的项目
+    project_created: bool,
+}
+
+// ============================================================================
+// 总结取消
+// ============================================================================
+
+/// 进行中的总结：request_id → 取消令牌
+static SUMMARY_CANCEL_TOKENS: Lazy<Mutex<HashMap<String, CancellationToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+struct SummaryRequestPayload {
+    request_id: String,
+}
+
+/// 以可取消方式执行总结：开始时发出 `summary-started`；被 `cancel_summary` 取消时直接丢弃
+/// future（页面提取等待与 HTTP 请求随之中止，进行中的 Tab 标记由 guard 清除），
+/// 并发出 `summary-cancelled`
+async fn run_cancellable_summary<Fut, T>(
+    app: &tauri::AppHandle,
+    request_id: &str,
+    fut: Fut,
+) -> Result<T, AppError>
+where
+    Fut: std::future::Future<Output = Result<T, AppError>>,
+{
+    struct CancelTokenGuard(String);
+    impl Drop for CancelTokenGuard {
+        fn drop(&mut self) {
+            SUMMARY_CANCEL_TOKENS.lock().unwrap().remove(&self.0);
+        }
+    }
+
+    let token = CancellationToken::new();
+    SUMMARY_CANCEL_TOKENS
+        .lock()
+        .unwrap()
+        .insert(request_id.to_string(), token.clone());
+    let _guard = CancelTokenGuard(request_id.to_string());
+    let payload = SummaryRequestPayload {
+        request_id: request_id.to_string(),
+    };
+    let _ = app.emit("summary-started", payload.clone());
+
+    match token.run_until_cancelled(fut).await {
+        Some(result) => result,
+        None => {
+            println!("[summary] 已取消 request_id={}", request_id);
+            let _ = app.emit("summary-cancelled", payload);
+            Err(AppError::Cancelled)
+        }
+    }
+}
+
+/// 取消进行中的总结（`request_id` 也可以是总结任务的 job id）
+#[tauri::command]
+fn cancel_summary(webview: tauri::Webview, app: tauri::AppHandle, request_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let token = SUMMARY_CANCEL_TOKENS.lock().unwrap().get(&request_id).cloned();
+    if let Some(token) = token {
+        token.cancel();
+        return Ok(());
+    }
+    jobs::cancel_job(&request_id).map_err(|_| "总结不存在或已结束".to_string())?;
+    let _ = app.emit("summary-cancelled", SummaryRequestPayload { request_id });
+    Ok(())
+}
+
+/// 以后台任务方式执行“总结当前 Tab”，返回 job id（进度与结果通过 `job-updated` 事件通知前端）
+///
+/// 供前端命令、深链接、自动化 API 等入口共用；调用方负责先做 `ensure_summarize_allowed` 检查
+pub(crate) fn trigger_summarize_active_tab(
+    app: &tauri::AppHandle,
+    template_id: Option<String>,
+) -> String {
+    let app_for_job = app.clone();
+    jobs::start_job(app, "summarize_active_tab", move |job| async move {
+        summarize_active_tab_inner(
+            app_for_job,
+            Some(&job),
+            job.id(),
+            template_id,
+            SummaryTarget::ActiveProject,
+        )
+        .await
+        .map(|result| result.summary)
+    })
+}
+
+fn extract_timeout_error(timeout: Duration) -> AppError {
+    AppError::ExtractionTimeout {
+        secs: timeout.as_secs(),
+    }
+}
+
+fn total_timeout_error(budget: Duration) -> AppError {
+    AppError::SummaryTimeout {
+        secs: budget.as_secs(),
+    }
+}
+
+/// 占用若干 Tab 的“总结进行中”标记（全部可用时才占用），drop 时清除
+struct SummaryInProgressGuard {
+    tab_ids: Vec<String>,
+}
+
+impl SummaryInProgressGuard {
+    fn acquire(tab_ids: &[String]) -> Result<Self, String> {
+        let mut in_progress = SUMMARIES_IN_PROGRESS.lock().unwrap();
+        if tab_ids.iter().any(|id| in_progress.contains(id)) {
+            return Err("该页面正在总结中，请稍候…".to_string());
+        }
+        in_progress.extend(tab_ids.iter().cloned());
+        Ok(SummaryInProgressGuard {
+            tab_ids: tab_ids.to_vec(),
+        })
+    }
+}
+
+impl Drop for SummaryInProgressGuard {
+    fn drop(&mut self) {
+        let mut in_progress = SUMMARIES_IN_PROGRESS.lock().unwrap();
+        for tab_id in &self.tab_ids {
+            in_progress.remove(tab_id);
+        }
+    }
+}
+
+/// `job` 不为空时按阶段上报进度（后台任务模式）；`template_id` 指定命名提示词模板
+async fn summarize_active_tab_inner(
+    app: tauri::AppHandle,
+    job: Option<&jobs::JobHandle>,
+    request_id: &str,
+    template_id: Option<String>,
+    target: SummaryTarget,
+) -> Result<ActiveTabSummary, AppError> {
+    let report = |progress: f64, message: &str| {
+        if let Some(job) = job {
+            job.progress(progress, message);
+        }
+    };
+
+    let tab_id = APP_STATE.lock().unwrap().focused_tab_id();
+
+    if tab_id.trim().is_empty() {
+        return Err("没有可总结的页面".into());
+    }
+
+    let _guard = SummaryInProgressGuard::acquire(std::slice::from_ref(&tab_id))?;
+
+    let (budget, extract_timeout) = {
+        let config = APP_CONFIG.lock().unwrap().clone();
+        (
+            summary_total_timeout(&config),
+            summary_extract_timeout(&config),
+        )
+    };
+    let deadline = tokio::time::Instant::now() + budget;
+    let result = tokio::time::timeout(
+        budget,
+        async {
+
+    let site_id = get_tab_site_id(&tab_id).unwrap_or_else(|_| tab_id.clone());
+    report(0.2, "提取页面内容");
+    let TabExtraction {
+        text: extracted,
+        url: page_url,
+    } = extract_tab_text_inner(&app, &tab_id, &site_id, extract_timeout).await?;
+    cache_extraction(&tab_id, &site_id, page_url, &extracted);
+
+    let estimate = SummaryEstimate::of(&extracted);
+    let max_chars = APP_CONFIG.lock().unwrap().summary_max_chars;
+    let _ = app.emit(
+        "summary-estimate",
+        SummaryEstimatePayload {
+            request_id: request_id.to_string(),
+            estimate,
+            max_chars,
+        },
+    );
+    check_summary_size(estimate, max_chars)?;
+
+    report(0.5, "生成总结");
+    let stream = SummaryStream::new(&app, request_id).with_deadline(deadline);
+    let feature = if job.is_some() {
+        ai_client::ApiFeature::SummarizeJob
+    } else {
+        ai_client::ApiFeature::ManualSummarize
+    };
+    let audit_project_id = match &target {
+        SummaryTarget::ActiveProject => Some(APP_CONFIG.lock().unwrap().active_project_id.clone()),
+        SummaryTarget::Project(id) => Some(id.clone()),
+        SummaryTarget::NewProject { .. } => None,
+    };
+    let audit = ai_client::ApiCallContext::new(feature)
+        .with_tab(Some(tab_id.clone()))
+        .with_project(audit_project_id);
+    let summary = summarize_text_inner(
+        extracted.clone(),
+        Some(site_id.clone()),
+        template_id,
+        Some(&stream),
+        audit,
+    )
+    .await?;
+
+    report(0.9, "保存到项目");
+
+    let (language, entry_limit, append_notes) = {
+        let config = APP_CONFIG.lock().unwrap();
+        (
+            config.language.clone(),
+            config.project_entry_limit as usize,
+            config.summary_append_notes,
+        )
+    };
+    let record = |project: &mut ProjectContext| {
+        record_project_summary(
+            project,
+            &site_id,
+            &extracted,
+            &summary,
+            entry_limit,
+            append_notes,
+        );
+        Ok(())
+    };
+    let (project_id, project_created) = match target {
+        SummaryTarget::ActiveProject => {
+            let project_id = ensure_active_project_id()?;
+            let create =
+                || ProjectContext::new(project_id.clone(), default_project_title(&language));
+            project_store::upsert_project(&project_id, create, record)?;
+            (project_id, false)
+        }
+        SummaryTarget::Project(project_id) => {
+            project_store::update_project(&project_id, record)?;
+            (project_id, false)
+        }
+        SummaryTarget::NewProject { title, activate } => {
+            let mut project = ProjectContext::new(new_project_id(), title);
+            record(&mut project)?;
+            project_store::save_project(&project)?;
+            if activate {
+                let mut config = APP_CONFIG.lock().unwrap();
+                config.active_project_id = project.id.clone();
+                let _ = save_config(&config);
+            }
+            (project.id, true)
+        }
+    };
+
+    Ok::<_, AppError>(ActiveTabSummary {
+        summary,
+        project_id,
+        project_created,
+    })
+        },
+    )
+    .await;
+
+    let result = match result {
+        Ok(res) => res,
+        Err(_) => Err(total_timeout_error(budget)),
+    };
+    notify_summary_result(&app, &result);
+    result
+}
+
+/// 总结当前 Tab 结束后的桌面通知：主窗口在前台时不发送，点击通知前置主窗口
+fn notify_summary_result(app: &tauri::AppHandle, result: &Result<ActiveTabSummary, AppError>) {
+    let (notify_success, notify_failure) = {
+        let config = APP_CONFIG.lock().unwrap();
+        (config.notify_on_summary, config.notify_on_summary_failure)
+    };
+    let Ok(window) = get_main_window(app) else {
+        return;
+    };
+    if window.is_focused().unwrap_or(false) {
+        return;
+    }
+    let (title, body) = match result {
+        Ok(result) if notify_success => (
+            format!("总结已保存到「{}」", project_title(&result.project_id)),
+            result.summary.clone(),
+        ),
+        Err(e) if notify_failure => (format!("总结失败（{}）", e.code()), e.message()),
+        _ => return,
+    };
+    notify::show(app, &title, &body, move || {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    });
+}
+
+// ============================================================================
+// 多 Tab 合并总结
+// ============================================================================
+
+/// 同时提取的 Tab 数量上限
+const MULTI_SOURCE_EXTRACT_CONCURRENCY: usize = 3;
+
+
+/// 多来源总结提示词（占位符同总结模板）
+const MULTI_SOURCE_SUMMARY_PROMPT: &str =
+    "以下内容来自多个 AI 对话页面，每段以“【来源 n｜站点｜标题】”开头。\n\
+请综合所有来源，整理出可直接用于后续对话的上下文：\n\
+1. 共同的目标、事实与约束\n\
+2. 各来源之间的分歧或矛盾（注明来源编号）\n\
+3. 已确定的结论与仍待解决的问题\n\
+要点后用 [n] 标注出处。输出语言：{language}\n\n{text}";
+
+#[derive(Debug, Clone, Serialize)]
+struct MultiSourceProgressPayload {
+    job_id: String,
+    tab_id: String,
+    site_id: String,
+    /// extracting / done / failed / skipped
+    status: String,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MultiSourceFailure {
+    tab_id: String,
+    site_id: String,
+    error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MultiSourceSummaryResult {
+    project_id: String,
+    summary: String,
+    /// 演示模式生成的模拟总结
+    demo: bool,
+    sources: Vec<ProjectSource>,
+    /// 提取失败或被跳过的 Tab（不影响其余 Tab 的总结）
+    failures: Vec<MultiSourceFailure>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedPage {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    text: String,
+}
+
+fn emit_multi_source_progress(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    tab_id: &str,
+    site_id: &str,
+    status: &str,
+    error: Option<String>,
+) {
+    let _ = app.emit(
+        "summarize-all-progress",
+        MultiSourceProgressPayload {
+            job_id: job_id.to_string(),
+            tab_id: tab_id.to_string(),
+            site_id: site_id.to_string(),
+            status: status.to_string(),
+            error,
+        },
+    );
+}
+
+/// 提取单个 Tab 的标题、地址与正文
+async fn extract_tab_source(
+    app: &tauri::AppHandle,
+    tab_id: &str,
+    site_id: &str,
+) -> Result<(ProjectSource, String), AppError> {
+    let child = app
+        .get_webview(&format!("ai_{}", tab_id))
+        .ok_or_else(|| "页面未加载".to_string())?;
+    let expr = format!(
+        "JSON.stringify({{ title: document.title || '', text: {} }})",
+        page_text_extraction_script_for_tab(tab_id)
+    );
+    let timeout = summary_extract_timeout(&APP_CONFIG.lock().unwrap());
+    let raw = eval_page_text(&child, &expr, timeout)
+        .await?
+        .ok_or_else(|| extract_timeout_error(timeout))?;
+    let page: ExtractedPage =
+        serde_json::from_str(&raw).map_err(|_| "页面脚本未返回有效结果".to_string())?;
+    if page.text.trim().is_empty() {
+        return Err("未能提取到页面文本".into());
+    }
+    let site_name = get_site_by_id(site_id)
+        .map(|s| s.name)
+        .unwrap_or_else(|_| site_id.to_string());
+    let source = ProjectSource {
+        tab_id: tab_id.to_string(),
+        site_id: site_id.to_string(),
+        site_name,
+        title: page.title.trim().to_string(),
+        url: child.url().map(|u| u.to_string()).unwrap_or_default(),
+    };
+    Ok((source, page.text))
+}
+
+/// 提取所有已打开 Tab（并发受限、单 Tab 超时），合并为带来源标注的文本后总结，
+/// 结果作为一条记录保存到项目。单个 Tab 失败只记入结果，不中断整体
+async fn summarize_all_open_tabs_inner(
+    app: tauri::AppHandle,
+    job: &jobs::JobHandle,
+    project_id: Option<String>,
+) -> Result<MultiSourceSummaryResult, AppError> {
+    let project_id = match project_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+    {
+        Some(id) => {
+            if load_live_project(&id).is_none() {
+                return Err("项目不存在".into());
+            }
+            id
+        }
+        None => ensure_active_project_id()?,
+    };
+
+    let tab_ids = APP_STATE.lock().unwrap().known_tab_ids();
+    let mut tabs: Vec<(String, String)> = tab_ids
+        .into_iter()
+        .filter_map(|tab_id| {
+            get_tab_site_id(&tab_id)
+                .ok()
+                .map(|site_id| (tab_id, site_id))
+        })
+        .collect();
+    tabs.sort();
+    if tabs.is_empty() {
+        return Err("没有已打开的 Tab".into());
+    }
+    let tab_ids: Vec<String> = tabs.iter().map(|(tab_id, _)| tab_id.clone()).collect();
+    let _guard = SummaryInProgressGuard::acquire(&tab_ids)?;
+
+    let total = tabs.len();
+    let mut failures: Vec<MultiSourceFailure> = Vec::new();
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(
+        MULTI_SOURCE_EXTRACT_CONCURRENCY,
+    ));
+    // 任务被取消（future 被丢弃）时一并取消尚未完成的提取
+    let cancel = CancellationToken::new();
+    let _cancel_guard = cancel.clone().drop_guard();
+    let mut tasks = Vec::new();
+    for (tab_id, site_id) in tabs {
+        if tab_bridge_ok(&tab_id) == Some(false) {
+            emit_multi_source_progress(
+                &app,
+                job.id(),
+                &tab_id,
+                &site_id,
+                "skipped",
+                Some(BRIDGE_BLOCKED_ERROR.to_string()),
+            );
+            failures.push(MultiSourceFailure {
+                tab_id,
+                site_id,
+                error: BRIDGE_BLOCKED_ERROR.to_string(),
+            });
+            continue;
+        }
+        let app = app.clone();
+        let semaphore = semaphore.clone();
+        let cancel = cancel.clone();
+        let job_id = job.id().to_string();
+        tasks.push(tauri::async_runtime::spawn(async move {
+            let result = cancel
+                .run_until_cancelled(async {
+                    let _permit = semaphore.acquire().await.map_err(|e| e.to_string())?;
+                    emit_multi_source_progress(
+                        &app,
+                        &job_id,
+                        &tab_id,
+                        &site_id,
+                        "extracting",
+                        None,
+                    );
+                    extract_tab_source(&app, &tab_id, &site_id).await
+                })
+                .await
+                .unwrap_or_else(|| Err("已取消".into()));
+            (tab_id, site_id, result)
+        }));
+    }
+
+    job.progress(0.1, "提取页面内容");
+    let mut sources: Vec<(ProjectSource, String)> = Vec::new();
+    for task in tasks {
+        let (tab_id, site_id, result) = task.await.map_err(|e| format!("提取任务异常: {}", e))?;
+        match result {
+            Ok(source) => {
+                emit_multi_source_progress(&app, job.id(), &tab_id, &site_id, "done", None);
+                sources.push(source);
+            }
+            Err(error) => {
+                println!("[summarize_all] tab_id={} 提取失败: {}", tab_id, error);
+                emit_multi_source_progress(
+                    &app,
+                    job.id(),
+                    &tab_id,
+                    &site_id,
+                    "failed",
+                    Some(error.to_string()),
+                );
+                failures.push(MultiSourceFailure {
+                    tab_id,
+                    site_id,
+                    error: error.to_string(),
+                });
+            }
+        }
+        let finished = sources.len() + failures.len();
+        job.progress(
+            0.1 + 0.5 * finished as f64 / total as f64,
+            &format!("已提取 {}/{}", finished, total),
+        );
+    }
+    if sources.is_empty() {
+        return Err("所有 Tab 均提取失败".into());
+    }
+
+    let corpus = sources
+        .iter()
+        .enumerate()
+        .map(|(index, (source, text))| {
+            let title = if source.title.is_empty() {
+                "-"
+            } else {
+                &source.title
+            };
+            format!(
+                "【来源 {}｜{}｜{}】\n{}\n\n{}",
+                index + 1,
+                source.site_name,
+                title,
+                source.url,
+                text.trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n");
+
+    job.progress(0.6, "生成总结");
+    let (language, context) = {
+        let config = APP_CONFIG.lock().unwrap();
+        let context = PromptContext::resolve(&config, None, &project_id);
+        (config.language.clone(), context)
+    };
+    let prompt = build_summary_prompt(
+        MULTI_SOURCE_SUMMARY_PROMPT,
+        language_label(&language),
+        &corpus,
+        &context,
+    );
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::SummarizeJob)
+        .with_project(Some(project_id.clone()));
+    let audit = if sources.iter().any(|(s, _)| is_private_site(&s.site_id)) {
+        audit.private_site()
+    } else {
+        audit
+    };
+    // 流式增量以 job id 作为 request_id
+    let stream = SummaryStream::new(&app, job.id());
+    let summary = request_summary(prompt, &corpus, Some(&stream), audit).await?;
+
+    job.progress(0.9, "保存到项目");
+    let sources: Vec<ProjectSource> = sources.into_iter().map(|(source, _)| source).collect();
+    let entry_limit = APP_CONFIG.lock().unwrap().project_entry_limit as usize;
+    project_store::update_project(&project_id, |project| {
+        project.append_entry("", &corpus, &summary, entry_limit);
+        project.notes = corpus;
+        project.sources = sources.clone();
+        Ok(())
+    })?;
+
+    Ok(MultiSourceSummaryResult {
+        project_id,
+        summary,
+        demo: APP_CONFIG.lock().unwrap().demo_mode,
+        sources,
+        failures,
+    })
+}
+
+/// 以后台任务方式合并总结所有已打开的 Tab，立即返回 job id；
+/// 每个 Tab 的进度通过 `summarize-all-progress` 事件推送，可用 `cancel_job` 取消
+#[tauri::command]
+fn summarize_all_open_tabs(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    project_id: Option<String>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    let app_for_job = app.clone();
+    Ok(jobs::start_job(
+        &app,
+        "summarize_all_open_tabs",
+        move |job| async move { summarize_all_open_tabs_inner(app_for_job, &job, project_id).await },
+    ))
+}
+
+// ============================================================================
+// 分屏对比总结
+// ============================================================================
+
+/// 对比总结提示词（{text} 为两个分屏各自的总结）
+const SPLIT_COMPARISON_PROMPT: &str =
+    "以下是两个 AI 针对同一问题的回答总结，分别以“【A｜站点】”“【B｜站点】”开头。\n\
+请对比两者并输出：\n\
+1. 共识：两者一致的结论与事实\n\
+2. 分歧：观点或事实不一致之处（注明 A/B）\n\
+3. 最佳回答：哪一方更好及理由，必要时给出合并后的结论\n\
+输出语言：{language}\n\n{text}";
+
+/// 提取并总结单个分屏（供对比总结并行调用）
+async fn summarize_split_pane(
+    app: tauri::AppHandle,
+    tab_id: String,
+    site_id: String,
+    project_id: String,
+) -> Result<(ProjectSource, String), AppError> {
+    if tab_bridge_ok(&tab_id) == Some(false) {
+        return Err(BRIDGE_BLOCKED_ERROR.into());
+    }
+    ensure_tab_webview(&app, &tab_id, &site_id)?;
+    let (source, text) = extract_tab_source(&app, &tab_id, &site_id).await?;
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ManualSummarize)
+        .with_tab(Some(tab_id))
+        .with_project(Some(project_id));
+    let summary = summarize_text_inner(text, Some(site_id), None, None, audit).await?;
+    Ok((source, summary))
+}
+
+async fn summarize_split_comparison_inner(
+    app: tauri::AppHandle,
+    request_id: &str,
+) -> Result<String, AppError> {
+    let panes = {
+        let layout = &APP_STATE.lock().unwrap().layout;
+        if !matches!(layout.mode, LayoutMode::Split) {
+            return Err("当前不是分屏布局，无法对比总结".into());
+        }
+        layout.pane_tab_ids.clone()
+    };
+    if panes.len() != 2 {
+        return Err("对比总结仅支持两个分屏".into());
+    }
+
+    let _guard = SummaryInProgressGuard::acquire(&panes)?;
+    let config = APP_CONFIG.lock().unwrap().clone();
+    let budget = summary_total_timeout(&config);
+    // 两个分屏的总结并行进行，之后再生成对比，共两轮请求
+    let budget = budget * 2;
+    let deadline = tokio::time::Instant::now() + budget;
+    let project_id = ensure_active_project_id()?;
+
+    let result = tokio::time::timeout(budget, async {
+        let mut tasks = Vec::new();
+        for tab_id in &panes {
+            let site_id = get_tab_site_id(tab_id)?;
+            tasks.push(tauri::async_runtime::spawn(summarize_split_pane(
+                app.clone(),
+                tab_id.clone(),
+                site_id,
+                project_id.clone(),
+            )));
+        }
+        let mut sides = Vec::new();
+        for (label, task) in ["A", "B"].into_iter().zip(tasks) {
+            let (source, summary) = task
+                .await
+                .map_err(|e| format!("总结任务异常: {}", e))?
+                .map_err(|e| format!("分屏 {} 总结失败: {}", label, e))?;
+            sides.push((label, source, summary));
+        }
+
+        let corpus = sides
+            .iter()
+            .map(|(label, source, summary)| {
+                format!(
+                    "【{}｜{}】\n{}\n\n{}",
+                    label,
+                    source.site_name,
+                    source.url,
+                    summary.trim()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        let (language, context) = {
+            let config = APP_CONFIG.lock().unwrap();
+            let context = PromptContext::resolve(&config, None, &project_id);
+            (config.language.clone(), context)
+        };
+        let prompt = build_summary_prompt(
+            SPLIT_COMPARISON_PROMPT,
+            language_label(&language),
+            &corpus,
+            &context,
+        );
+        let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ManualSummarize)
+            .with_project(Some(project_id.clone()));
+        let audit = if sides.iter().any(|(_, s, _)| is_private_site(&s.site_id)) {
+            audit.private_site()
+        } else {
+            audit
+        };
+        let stream = SummaryStream::new(&app, request_id).with_deadline(deadline);
+        let comparison = request_summary(prompt, &corpus, Some(&stream), audit).await?;
+
+        let site_ids: Vec<String> = sides.iter().map(|(_, s, _)| s.site_id.clone()).collect();
+        let entry_limit = APP_CONFIG.lock().unwrap().project_entry_limit as usize;
+        project_store::update_project(&project_id, |project| {
+            project.append_entry("", &corpus, &comparison, entry_limit);
+            if let Some(entry) = project.entries.last_mut() {
+                entry.site_ids = site_ids;
+            }
+            project.notes = corpus;
+            project.sources = sides.into_iter().map(|(_, source, _)| source).collect();
+            Ok(())
+        })?;
+        Ok(comparison)
+    })
+    .await;
+
+    match result {
+        Ok(res) => res,
+        Err(_) => Err(total_timeout_error(budget)),
+    }
+}
+
+/// 分屏对比：分别提取并总结两个分屏，再生成对比（共识、分歧、最佳回答），
+/// 结果作为一条记录保存到当前项目（记录两个来源站点）
+#[tauri::command]
+async fn summarize_split_comparison(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    request_id: Option<String>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    let request_id = summary_request_id(request_id);
+    run_cancellable_summary(
+        &app,
+        &request_id,
+        summarize_split_comparison_inner(app.clone(), &request_id),
+    )
+    .await
+}
+
+// ============================================================================
+// 页面加载性能
+// ============================================================================
+
+/// 每个站点保留的最近加载样本数
+const PAGE_TIMING_WINDOW: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+struct PageTimingSample {
+    tab_id: String,
+    url: String,
+    dom_content_loaded_ms: Option<f64>,
+    load_ms: Option<f64>,
+    first_contentful_paint_ms: Option<f64>,
+    anti_detection_mode: String,
+    recorded_at: u64,
+}
+
+/// 站点 → 最近加载样本（滚动窗口）
+static PAGE_TIMINGS: Lazy<Mutex<HashMap<String, VecDeque<PageTimingSample>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+struct PageTimingStats {
+    anti_detection_mode: String,
+    samples: usize,
+    avg_dom_content_loaded_ms: Option<f64>,
+    avg_load_ms: Option<f64>,
+    avg_first_contentful_paint_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SitePerformance {
+    site_id: String,
+    samples: Vec<PageTimingSample>,
+    /// 按反检测模式分组的均值，便于 A/B 对比
+    stats: Vec<PageTimingStats>,
+}
+
+fn sanitize_timing_ms(value: Option<f64>) -> Option<f64> {
+    // 只接受 0 ~ 10 分钟内的有限值，防止页面上报异常数据
+    value.filter(|v| v.is_finite() && *v > 0.0 && *v <= 600_000.0)
+}
+
+fn average(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    let (sum, count) = values
+        .flatten()
+        .fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f64)
+    }
+}
+
+fn page_timing_stats(samples: &VecDeque<PageTimingSample>) -> Vec<PageTimingStats> {
+    let mut modes: Vec<String> = Vec::new();
+    for sample in samples {
+        if !modes.contains(&sample.anti_detection_mode) {
+            modes.push(sample.anti_detection_mode.clone());
+        }
+    }
+    modes
+        .into_iter()
+        .map(|mode| {
+            let group: Vec<&PageTimingSample> = samples
+                .iter()
+                .filter(|s| s.anti_detection_mode == mode)
+                .collect();
+            PageTimingStats {
+                samples: group.len(),
+                avg_dom_content_loaded_ms: average(group.iter().map(|s| s.dom_content_loaded_ms)),
+                avg_load_ms: average(group.iter().map(|s| s.load_ms)),
+                avg_first_contentful_paint_ms: average(
+                    group.iter().map(|s| s.first_contentful_paint_ms),
+                ),
+                anti_detection_mode: mode,
+            }
+        })
+        .collect()
+}
+
+/// 站点阻止页面脚本调用后端时的错误（附带改用剪贴板总结的建议）
+const BRIDGE_BLOCKED_ERROR: &str =
+    "该站点阻止了内容提取（CSP/沙箱限制）。请在页面中复制对话内容，粘贴到项目笔记后使用“总结文本”";
+
+/// Tab 当前页面的标题、地址与加载状态
+#[derive(Debug, Clone, Default)]
+struct TabPageState {
+    title: String,
+    url: String,
+    loading: bool,
+    /// 最近一次加载失败的原因（重新开始加载或加载成功时清除）
+    load_error: Option<String>,
+    /// 最近一次开始加载的地址（判断停在 about:blank 是否为加载失败）
+    started_url: String,
+}
+
+fn update_tab_page_state<R>(tab_id: &str, update: impl FnOnce(&mut TabPageState) -> R) -> R {
+    update_tab_runtime(tab_id, |runtime| update(&mut runtime.page))
+}
+
+/// 子页面上报标题与地址（仅允许 ai_* Webview 上报自己所属的 Tab）
+#[tauri::command]
+fn aihub_report_title(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    title: String,
+    url: String,
+) -> Result<(), AppError> {
+    let tab_id = webview
+        .label()
+        .strip_prefix("ai_")
+        .ok_or_else(|| "Not allowed".to_string())?
+        .to_string();
+    let title: String = title.trim().chars().take(256).collect();
+    let url: String = url.chars().take(2048).collect();
+    update_tab_page_state(&tab_id, |state| {
+        state.title = title;
+        state.url = url;
+    });
+    let _ = app.emit("tab-page-updated", tab_id);
+    Ok(())
+}
+
+/// 子页面回报脚本桥接可用（仅允许 ai_* Webview 回报自己所属的 Tab）
+#[tauri::command]
+fn aihub_report_bridge(webview: tauri::Webview, url: String) -> Result<(), AppError> {
+    let tab_id = webview
+        .label()
+        .strip_prefix("ai_")
+        .ok_or_else(|| "Not allowed".to_string())?
+        .to_string();
+    println!("[bridge] tab_id={} ok url={}", tab_id, url.chars().take(256).collect::<String>());
+    update_tab_runtime(&tab_id, |runtime| runtime.bridge.ok = Some(true));
+    Ok(())
+}
+
+/// 子页面回应存活检测（仅允许 ai_* Webview 回应自己所属的 Tab）
+#[tauri::command]
+fn aihub_report_alive(webview: tauri::Webview, seq: u64) -> Result<(), AppError> {
+    let tab_id = webview
+        .label()
+        .strip_prefix("ai_")
+        .ok_or_else(|| "Not allowed".to_string())?
+        .to_string();
+    update_tab_runtime(&tab_id, |runtime| {
+        let state = &mut runtime.bridge;
+        state.pong_seq = state.pong_seq.max(seq);
+        state.unresponsive = false;
+    });
+    Ok(())
+}
+
+/// 子页面上报加载耗时（仅允许 ai_* Webview 上报自己所属的 Tab）
+#[tauri::command]
+fn aihub_report_page_timing(
+    webview: tauri::Webview,
+    url: String,
+    dom_content_loaded_ms: Option<f64>,
+    load_ms: Option<f64>,
+    first_contentful_paint_ms: Option<f64>,
+) -> Result<(), AppError> {
+    let tab_id = webview
+        .label()
+        .strip_prefix("ai_")
+        .ok_or_else(|| "Not allowed".to_string())?
+        .to_string();
+    let site_id = get_tab_site_id(&tab_id)?;
+    let site = get_site_by_id(&site_id)?;
+    if site.private {
+        return Ok(());
+    }
+
+    let sample = PageTimingSample {
+        tab_id,
+        url: url.chars().take(2048).collect(),
+        dom_content_loaded_ms: sanitize_timing_ms(dom_content_loaded_ms),
+        load_ms: sanitize_timing_ms(load_ms),
+        first_contentful_paint_ms: sanitize_timing_ms(first_contentful_paint_ms),
+        anti_detection_mode: effective_anti_detection_mode(&site).to_string(),
+        recorded_at: now_ts(),
+    };
+
+    let mut timings = PAGE_TIMINGS.lock().unwrap();
+    let window = timings.entry(site_id).or_default();
+    window.push_back(sample);
+    while window.len() > PAGE_TIMING_WINDOW {
+        window.pop_front();
+    }
+    Ok(())
+}
+
+/// 获取站点最近的加载耗时样本与统计
+#[tauri::command]
+fn get_site_performance(webview: tauri::Webview, site_id: String) -> Result<SitePerformance, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let timings = PAGE_TIMINGS.lock().unwrap();
+    let samples = timings.get(&site_id).cloned().unwrap_or_default();
+    Ok(SitePerformance {
+        site_id,
+        stats: page_timing_stats(&samples),
+        samples: samples.into_iter().collect(),
+    })
+}
+
+// ============================================================================
+// 诊断信息
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+struct SitePerformanceSummary {
+    site_id: String,
+    stats: Vec<PageTimingStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Diagnostics {
+    storage: StorageStatus,
+    site_performance: Vec<SitePerformanceSummary>,
+    /// 最近 7 天使用 API Key 的调用汇总
+    api_audit_daily: Vec<ai_client::ApiAuditDaily>,
+    /// 生成时间（本地时区 ISO-8601）
+    generated_at: String,
+    /// 启动以来自检累计修正次数
+    self_check_corrections: usize,
+    /// 最近一次自检报告
+    last_self_check: Option<SelfCheckReport>,
+}
+
+#[tauri::command]
+fn get_diagnostics(webview: tauri::Webview) -> Result<Diagnostics, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let mut site_performance: Vec<SitePerformanceSummary> = PAGE_TIMINGS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(site_id, samples)| SitePerformanceSummary {
+            site_id: site_id.clone(),
+            stats: page_timing_stats(samples),
+        })
+        .collect();
+    site_performance.sort_by(|a, b| a.site_id.cmp(&b.site_id));
+    Ok(Diagnostics {
+        storage: storage_status(),
+        site_performance,
+        api_audit_daily: ai_client::daily_rollup(7),
+        generated_at: time::Timestamp::now().to_local_iso8601(),
+        self_check_corrections: SELF_CHECK_CORRECTIONS.load(Ordering::SeqCst),
+        last_self_check: LAST_SELF_CHECK.lock().unwrap().clone(),
+    })
+}
+
+/// 最近 `days` 天（1–3650）总结请求的 token 用量，按模型与站点汇总
+#[tauri::command]
+fn get_usage_stats(webview: tauri::Webview, days: u32) -> Result<usage::UsageStats, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    if !(1..=3650).contains(&days) {
+        return Err("天数需在 1 到 3650 之间".into());
+    }
+    Ok(usage::stats(days))
+}
+
+/// 各站点的使用统计（激活次数、累计前台秒数、最后使用时间），按站点 id 索引
+#[tauri::command]
+fn get_site_stats(
+    webview: tauri::Webview,
+) -> Result<BTreeMap<String, site_stats::SiteStats>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(site_stats::snapshot())
+}
+
+/// 清空站点使用统计
+#[tauri::command]
+fn reset_site_stats(webview: tauri::Webview) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("reset_site_stats")?;
+    site_stats::reset();
+    Ok(())
+}
+
+/// 查询使用 API Key 的调用记录（range: 24h / 7d / 30d / all，默认 7d）
+#[tauri::command]
+fn get_api_audit(
+    webview: tauri::Webview,
+    range: Option<String>,
+) -> Result<Vec<ai_client::ApiAuditEntry>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let range = range.unwrap_or_else(|| "7d".to_string());
+    let window_secs: u64 = match range.as_str() {
+        "24h" => 24 * 60 * 60,
+        "7d" => 7 * 24 * 60 * 60,
+        "30d" => 30 * 24 * 60 * 60,
+        "all" => u64::MAX,
+        other => return Err(format!("不支持的时间范围: {}", other).into()),
+    };
+    Ok(ai_client::entries_since(now_ts().saturating_sub(window_secs)))
+}
+
+// ============================================================================
+// 总结预检（不消耗 completion token）
+// ============================================================================
+
+/// 预检总耗时上限内的单项超时
+const PREFLIGHT_PROVIDER_TIMEOUT: Duration = Duration::from_secs(2);
+const PREFLIGHT_WEBVIEW_TIMEOUT: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Serialize)]
+struct PreflightCheck {
+    /// 检查项：config / provider / project / webview / extraction
+    id: &'static str,
+    ok: bool,
+    /// 失败时的错误码（如 `missing_api_key`），供前端给出对应的修复入口
+    code: Option<&'static str>,
+    message: String,
+}
+
+impl PreflightCheck {
+    fn pass(id: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            ok: true,
+            code: None,
+            message: message.into(),
+        }
+    }
+
+    fn fail(id: &'static str, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            ok: false,
+            code: Some(code),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PreflightReport {
+    ok: bool,
+    /// 演示模式（不检查 AI 服务）
+    demo: bool,
+    checks: Vec<PreflightCheck>,
+}
+
+/// 用 `GET {base_url}/models` 验证服务可达与 Key 有效（不产生 completion）
+async fn preflight_provider_check(settings: ResolvedAiSettings) -> PreflightCheck {
+    let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::Preflight);
+    let resp = ai_client::get(
+        &audit,
+        &settings.models_url(),
+        settings.auth(),
+        Some(PREFLIGHT_PROVIDER_TIMEOUT),
+    )
+    .await;
+    match resp {
+        Ok(resp) => match resp.status().as_u16() {
+            200..=299 => PreflightCheck::pass("provider", "服务可达，Key 有效"),
+            401 | 403 => PreflightCheck::fail(
+                "provider",
+                "provider_auth_failed",
+                format!("服务拒绝了 API Key（{}）", resp.status()),
+            ),
+            // 部分兼容服务没有 /models，能收到响应即视为可达
+            404 | 405 => PreflightCheck::pass("provider", "服务可达（未提供模型列表接口）"),
+            _ => PreflightCheck::fail(
+                "provider",
+                "provider_error",
+                format!("服务返回异常状态 {}", resp.status()),
+            ),
+        },
+        Err(e) if e.is_timeout() => {
+            PreflightCheck::fail("provider", "provider_timeout", "连接 AI 服务超时")
+        }
+        Err(e) => PreflightCheck::fail(
+            "provider",
+            "provider_unreachable",
+            format!("无法连接 AI 服务: {}", e),
+        ),
+    }
+}
+
+/// 检查目标 Tab 的 Webview 是否存活并可提取文本（只返回长度）
+async fn preflight_webview_checks(app: &tauri::AppHandle, tab_id: Option<String>) -> Vec<PreflightCheck> {
+    let tab_id = tab_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| APP_STATE.lock().unwrap().focused_tab_id());
+    if tab_id.is_empty() {
+        return vec![PreflightCheck::fail("webview", "no_target_tab", "没有可总结的页面")];
+    }
+    let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) else {
+        return vec![PreflightCheck::fail(
+            "webview",
+            "webview_not_loaded",
+            "页面尚未打开，请先切换到该 Tab",
+        )];
+    };
+
+    if tab_bridge_ok(&tab_id) == Some(false) {
+        return vec![PreflightCheck::fail(
+            "webview",
+            "bridge_blocked",
+            BRIDGE_BLOCKED_ERROR,
+        )];
+    }
+
+    let expr = format!(
+        "String(({}).length)",
+        page_text_extraction_script_for_tab(&tab_id)
+    );
+    match eval_page_text(&child, &expr, PREFLIGHT_WEBVIEW_TIMEOUT).await {
+        Ok(Some(len)) => {
+            let len = len.trim().parse::<usize>().unwrap_or(0);
+            let extraction = if len > 0 {
+                PreflightCheck::pass("extraction", format!("可提取 {} 个字符", len))
+            } else {
+                PreflightCheck::fail(
+                    "extraction",
+                    "extraction_empty",
+                    "页面没有可提取的文本（可能未加载完成）",
+                )
+            };
+            vec![PreflightCheck::pass("webview", "页面响应正常"), extraction]
+        }
+        Ok(None) => vec![PreflightCheck::fail(
+            "webview",
+            "webview_unresponsive",
+            "页面无响应",
+        )],
+        Err(e) => vec![PreflightCheck::fail("webview", "webview_eval_failed", e)],
+    }
+}
+
+/// 一次性检查总结链路：配置、AI 服务、项目、页面 Webview 与文本提取
+#[tauri::command]
+async fn preflight_summary_check(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: Option<String>,
+) -> Result<PreflightReport, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let mut checks: Vec<PreflightCheck> = Vec::new();
+    let config = APP_CONFIG.lock().unwrap().clone();
+
+    // 1. 配置
+    let provider_task = if config.demo_mode {
+        checks.push(PreflightCheck::pass("config", "演示模式，无需 API Key"));
+        checks.push(PreflightCheck::pass("provider", "演示模式，不连接 AI 服务"));
+        None
+    } else {
+        match resolve_ai_settings(&config) {
+            Err(e) => {
+                checks.push(PreflightCheck::fail("config", "api_key_unreadable", e));
+                None
+            }
+            Ok(settings) if settings.missing_key() => {
+                checks.push(PreflightCheck::fail(
+                    "config",
+                    "missing_api_key",
+                    "未配置 API Key",
+                ));
+                None
+            }
+            Ok(settings) if settings.missing_model() => {
+                checks.push(PreflightCheck::fail(
+                    "config",
+                    "missing_model",
+                    "未配置 Model",
+                ));
+                None
+            }
+            Ok(settings) => match tauri::Url::parse(&settings.base_url) {
+                Err(e) => {
+                    checks.push(PreflightCheck::fail(
+                        "config",
+                        "invalid_base_url",
+                        format!("Base URL 无效: {}", e),
+                    ));
+                    None
+                }
+                Ok(_) => {
+                    checks.push(PreflightCheck::pass("config", "配置完整"));
+                    // 2. AI 服务与页面检查并行进行
+                    Some(tauri::async_runtime::spawn(preflight_provider_check(
+                        settings,
+                    )))
+                }
+            },
+        }
+    };
+
+    // 3. 项目：只判断能否写入，不在预检中创建项目
+    if STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+        checks.push(PreflightCheck::fail(
+            "project",
+            "storage_read_only",
+            "配置目录被其他进程占用，总结无法保存",
+        ));
+    } else {
+        let projects = project_store::list_index();
+        let message = match projects.iter().find(|p| p.id == config.active_project_id) {
+            Some(p) => format!("保存到项目「{}」", p.title),
+            None => "将保存到默认项目".to_string(),
+        };
+        checks.push(PreflightCheck::pass("project", message));
+    }
+
+    // 4. 页面
+    checks.extend(preflight_webview_checks(&app, tab_id).await);
+
+    if let Some(task) = provider_task {
+        match task.await {
+            Ok(check) => checks.push(check),
+            Err(e) => checks.push(PreflightCheck::fail(
+                "provider",
+                "provider_unreachable",
+                e.to_string(),
+            )),
+        }
+    }
+
+    Ok(PreflightReport {
+        ok: checks.iter().all(|c| c.ok),
+        demo: config.demo_mode,
+        checks,
+    })
+}
+
+// ============================================================================
+// 后台任务命令
+// ============================================================================
+
+/// 以后台任务方式执行“总结当前 Tab”，立即返回 job id
+#[tauri::command]
+fn start_summarize_active_tab(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    template_id: Option<String>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    Ok(trigger_summarize_active_tab(&app, template_id))
+}
+
+/// 以后台任务方式总结给定文本，立即返回 job id
+#[tauri::command]
+fn start_summarize_text(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    text: String,
+    site_id: Option<String>,
+    template_id: Option<String>,
+) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_summarize_allowed()?;
+    let app_for_job = app.clone();
+    Ok(jobs::start_job(&app, "summarize_text", move |job| async move {
+        job.progress(0.1, "生成总结");
+        // 流式增量以 job id 作为 request_id
+        let stream = SummaryStream::new(&app_for_job, job.id());
+        let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::SummarizeJob);
+        summarize_text_inner(text, site_id, template_id, Some(&stream), audit).await
+    }))
+}
+
+#[tauri::command]
+fn get_job(webview: tauri::Webview, job_id: String) -> Result<jobs::JobRecord, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    jobs::get_job(&job_id).ok_or_else(|| AppError::from("任务不存在"))
+}
+
+#[tauri::command]
+fn list_jobs(webview: tauri::Webview) -> Result<Vec<jobs::JobRecord>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(jobs::list_jobs())
+}
+
+#[tauri::command]
+fn cancel_job(webview: tauri::Webview, job_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    jobs::cancel_job(&job_id).map_err(AppError::from)
+}
+
+/// 获取所有 AI 站点列表（按排序顺序）；默认不含已停用的站点，`include_disabled` 为 true 时一并返回
+#[tauri::command]
+fn get_ai_sites(
+    webview: tauri::Webview,
+    include_disabled: Option<bool>,
+) -> Result<Vec<AiSite>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let config = APP_CONFIG.lock().unwrap();
+    let sites = site_ids_in_order(&config, include_disabled.unwrap_or(false))
+        .iter()
+        .filter_map(|id| config.sites.iter().find(|s| &s.id == id))
+        .map(|s| localized_site(s, &config.language))
+        .collect();
+    Ok(sites)
+}
+
+/// 获取站点展示顺序（置顶在前），供前端直接使用
+#[tauri::command]
+fn get_ordered_site_ids(webview: tauri::Webview) -> Result<Vec<String>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let config = APP_CONFIG.lock().unwrap();
+    Ok(ordered_site_ids(&config))
+}
+
+/// 获取当前活跃的视图 ID
+#[tauri::command]
+fn get_current_view(webview: tauri::Webview) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(APP_STATE.lock().unwrap().current_view.clone())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TabInfo {
+    tab_id: String,
+    site_id: String,
+    /// 所属站点为隐私站点（前端低调显示）
+    private: bool,
+    /// 页面能否回传提取内容（None = 未加载或尚未确定）
+    bridge_ok: Option<bool>,
+    /// 页面标题与当前地址（Webview 未创建时为站点名称与配置地址）
+    title: String,
+    current_url: String,
+    loading: bool,
+    /// 最近一次加载失败的原因（Tab 栏显示错误标记）
+    load_error: Option<String>,
+    /// 已休眠（Webview 已关闭，切回时重新加载）
+    hibernated: bool,
+    /// 已移到独立窗口（主窗口 Tab 栏可隐藏）
+    detached: bool,
+    /// 已静音
+    muted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TabsStateResponse {
+    active_tab_id: String,
+    mode: String,
+    ratio: f64,
+    left_tab_id: Option<String>,
+    right_tab_id: Option<String>,
+    /// 分屏中从左到右（或从上到下）的 Tab 及其占比（用于渲染分隔条）
+    pane_tab_ids: Vec<String>,
+    pane_ratios: Vec<f64>,
+    /// horizontal / vertical
+    orientation: String,
+    /// 可恢复的上次分屏布局（没有或其中 Tab 已关闭时为 null）
+    last_split: Option<LastSplit>,
+    tabs: Vec<TabInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct WebviewLoadFailedPayload {
+    tab_id: String,
+    site_id: String,
+    url: String,
+    message: String,
+}
+
+/// 获取当前 Tabs 状态（用于前端渲染 TabBar/分屏）
+#[tauri::command]
+fn get_tabs_state(webview: tauri::Webview) -> Result<TabsStateResponse, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let (layout, active_tab_id, mut tab_ids) = {
+        let state = APP_STATE.lock().unwrap();
+        let mut tab_ids = state.known_tab_ids();
+        if !state.current_view.is_empty() {
+            tab_ids.insert(state.current_view.clone());
+        }
+        (state.layout.clone(), state.active_tab.clone(), tab_ids)
+    };
+    let hibernated_tabs: HashSet<String> = hibernated_tab_ids().into_iter().collect();
+    tab_ids.extend(hibernated_tabs.iter().cloned());
+
+    let language = APP_CONFIG.lock().unwrap().language.clone();
+    let mut tabs: Vec<TabInfo> = Vec::new();
+    for tab_id in tab_ids {
+        if let Ok(site_id) = get_tab_site_id(&tab_id) {
+            let bridge_ok = tab_bridge_ok(&tab_id);
+            let private = is_private_site(&site_id);
+            let page = read_tab_runtime(&tab_id, |runtime| runtime.page.clone()).unwrap_or_default();
+            let site = get_site_by_id(&site_id)
+                .ok()
+                .map(|s| localized_site(&s, &language));
+            let title = if page.title.is_empty() {
+                site.as_ref().map(|s| s.name.clone()).unwrap_or_default()
+            } else {
+                page.title
+            };
+            let current_url = if page.url.is_empty() {
+                site.map(|s| s.url).unwrap_or_default()
+            } else {
+                page.url
+            };
+            let hibernated = hibernated_tabs.contains(&tab_id);
+            let detached = is_tab_detached(&tab_id);
+            let muted = is_tab_muted(&tab_id);
+            tabs.push(TabInfo {
+                tab_id,
+                site_id,
+                private,
+                bridge_ok,
+                title,
+                current_url,
+                loading: page.loading,
+                load_error: page.load_error,
+                hibernated,
+                detached,
+                muted,
+            });
+        }
+    }
+    tabs.sort_by(|a, b| a.tab_id.cmp(&b.tab_id));
+
+    Ok(TabsStateResponse {
+        active_tab_id,
+        mode: match layout.mode {
+            LayoutMode::Single => "single".to_string(),
+            LayoutMode::Split => "split".to_string(),
+        },
+        ratio: layout.ratio(),
+        left_tab_id: layout.left_tab_id(),
+        right_tab_id: layout.right_tab_id(),
+        orientation: layout.orientation.as_str().to_string(),
+        last_split: layout.restorable_last_split(),
+        pane_tab_ids: layout.pane_tab_ids,
+        pane_ratios: layout.pane_ratios,
+        tabs,
+    })
+}
+
+/// 创建一个新 Tab（默认共享站点登录：同站点共用 data directory）
+#[tauri::command]
+fn create_tab(webview: tauri::Webview, site_id: String) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    create_tab_inner(&site_id).map_err(AppError::from)
+}
+
+fn create_tab_inner(site_id: &str) -> Result<String, String> {
+    println!("[create_tab] site_id={}", site_id);
+    let tab_id = format!(
+        "{}_{}",
+        site_id,
+        Uuid::new_v4().to_string().split('-').next().unwrap()
+    );
+    {
+        // 持有配置锁完成校验与登记：remove_site 要么在此之前删除站点（此处校验失败），
+        // 要么在此之后删除并由 reconcile_site_tabs 清理该映射
+        let config = APP_CONFIG.lock().unwrap();
+        APP_STATE
+            .lock()
+            .unwrap()
+            .register_tab(&config, site_id, &tab_id)?;
+    }
+    persist_session();
+    Ok(tab_id)
+}
+
+impl AppState {
+    /// 校验站点存在且启用后登记 Tab 映射；调用方持有配置锁
+    fn register_tab(
+        &mut self,
+        config: &AppConfig,
+        site_id: &str,
+        tab_id: &str,
+    ) -> Result<(), String> {
+        match config.sites.iter().find(|s| s.id == site_id) {
+            None => return Err(format!("站点不存在: {}", site_id)),
+            Some(site) if !site.enabled => return Err(format!("站点已停用: {}", site_id)),
+            Some(_) => {}
+        }
+        self.tabs.insert(tab_id.to_string(), site_id.to_string());
+        Ok(())
+    }
+
+    /// 所属站点已不存在的 Tab，以及既不是主 Tab 也没有映射的 Webview
+    fn stale_site_tabs(&self, site_ids: &HashSet<String>) -> Vec<String> {
+        let mut stale: Vec<String> = self
+            .tabs
+            .iter()
+            .filter(|(_, site_id)| !site_ids.contains(*site_id))
+            .map(|(tab_id, _)| tab_id.clone())
+            .collect();
+        let orphan_views: Vec<String> = self
+            .views
+            .iter()
+            .filter(|tab_id| {
+                !stale.contains(tab_id)
+                    && !site_ids.contains(*tab_id)
+                    && !self.tabs.contains_key(*tab_id)
+            })
+            .cloned()
+            .collect();
+        stale.extend(orphan_views);
+        stale
+    }
+
+    /// 摘除所属站点已不存在的 Tab：从清单与映射中移除并修正布局，返回被摘除的 Tab
+    /// （其 Webview 由调用方关闭）
+    fn remove_stale_site_tabs(&mut self, site_ids: &HashSet<String>) -> Vec<String> {
+        let stale = self.stale_site_tabs(site_ids);
+        for tab_id in &stale {
+            self.views.remove(tab_id);
+            self.tabs.remove(tab_id);
+        }
+        self.detach_tabs(&stale);
+        stale
+    }
+
+    /// 从分屏与活跃 Tab 中摘除已关闭的 Tab，分屏全部摘除时回到单视图
+    fn detach_tabs(&mut self, tab_ids: &[String]) {
+        for tab_id in tab_ids {
+            self.layout.remove_pane(tab_id);
+        }
+        if self.layout.is_split() && self.layout.pane_tab_ids.is_empty() {
+            self.layout.set_single();
+        }
+        if tab_ids.contains(&self.active_tab) {
+            self.clear_active();
+        }
+    }
+}
+
+// ============================================================================
+// 布局事务（switch_tab / set_layout）
+// ============================================================================
+//
+// 多步切换按“先校验与执行 Webview 副作用，最后写入内存状态”的顺序进行。
+// 若中途出错返回或命令 future 被丢弃（如前端刷新），`LayoutTransaction` 在 drop 时
+// 调度一次对账，保证布局状态与实际存在的 Webview 收敛一致。
+
+struct LayoutTransaction {
+    on_abort: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl LayoutTransaction {
+    /// 未提交即被丢弃时在主线程上对账
+    fn begin(app: &tauri::AppHandle) -> Self {
+        let app = app.clone();
+        Self::with_abort(move || {
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || reconcile_layout_state(&handle));
+        })
+    }
 
-    struct WebviewCreateGuard;
-    impl Drop for WebviewCreateGuard {
-        fn drop(&mut self) {
-            WEBVIEW_CREATE_IN_PROGRESS.fetch_sub(1, Ordering::SeqCst);
+    fn with_abort(on_abort: impl FnOnce() + Send + 'static) -> Self {
+        Self {
+            on_abort: Some(Box::new(on_abort)),
         }
     }
 
-    println!("[ensure_tab_webview] add_child start label={}", webview_label);
-    WEBVIEW_CREATE_IN_PROGRESS.fetch_add(1, Ordering::SeqCst);
-    let _guard = WebviewCreateGuard;
-    window
-        .add_child(
-            webview_builder,
-            LogicalPosition::new(position.x, position.y),
-            LogicalSize::new(size.width, size.height),
-        )
-        .map_err(|e| format!("添加 Webview 失败: {}", e))?;
-    println!("[ensure_tab_webview] add_child done label={}", webview_label);
-
-    CREATED_VIEWS.lock().unwrap().insert(tab_id.to_string(), true);
-    touch_tab(tab_id);
-    Ok(())
+    fn commit(mut self) {
+        self.on_abort = None;
+    }
 }
 
-fn tab_ids_for_site(site_id: &str) -> Vec<String> {
-    let mut ids: Vec<String> = vec![site_id.to_string()];
-    for (tab_id, mapped_site) in TAB_SITE_MAP.lock().unwrap().iter() {
-        if mapped_site == site_id {
-            ids.push(tab_id.clone());
+impl Drop for LayoutTransaction {
+    fn drop(&mut self) {
+        if let Some(on_abort) = self.on_abort.take() {
+            println!("[layout] 切换未完成，调度对账");
+            on_abort();
         }
     }
-    ids
 }
 
-fn destroy_tab_webview(app: &tauri::AppHandle, tab_id: &str) {
-    let webview_label = format!("ai_{}", tab_id);
-    if let Some(webview) = app.get_webview(&webview_label) {
-        let _ = webview.close();
+impl AppState {
+    /// 按实际存在的 Webview 修正清单与布局：移除不存在的 Webview 与引用它们的分屏，
+    /// 分屏全部失效时回到单视图；返回需要重建 Webview 的活跃 Tab
+    fn reconcile_views(&mut self, is_live: impl Fn(&str) -> bool) -> Option<String> {
+        self.views.retain(|tab_id| is_live(tab_id));
+        let AppState { views, layout, .. } = self;
+        if layout.is_split() {
+            let dead: Vec<String> = layout
+                .pane_tab_ids
+                .iter()
+                .filter(|id| !views.contains(*id))
+                .cloned()
+                .collect();
+            for tab_id in dead {
+                layout.remove_pane(&tab_id);
+            }
+            if layout.pane_tab_ids.is_empty() {
+                layout.set_single();
+            }
+        }
+        (!self.active_tab.is_empty() && !self.views.contains(&self.active_tab))
+            .then(|| self.active_tab.clone())
     }
-    CREATED_VIEWS.lock().unwrap().remove(tab_id);
-    LAST_USED_AT.lock().unwrap().remove(tab_id);
-    LAST_REQUESTED_URL.lock().unwrap().remove(tab_id);
-    RETRY_STATE.lock().unwrap().remove(tab_id);
-}
-
-fn close_tab_webview(app: &tauri::AppHandle, tab_id: &str) {
-    destroy_tab_webview(app, tab_id);
-    TAB_SITE_MAP.lock().unwrap().remove(tab_id);
 }
 
-fn handle_webview_load_failure(app: &tauri::AppHandle, tab_id: &str, site_id: &str) {
-    destroy_tab_webview(app, tab_id);
-
-    let active = ACTIVE_TAB_ID.lock().unwrap().clone();
-    let current = CURRENT_VIEW.lock().unwrap().clone();
-
-    let mut next_active: Option<String> = None;
-    let mut clear_current = false;
+/// 对账：移除清单中已不存在的 Webview，修正布局中引用的失效 Tab，然后重新布局
+fn reconcile_layout_state(app: &tauri::AppHandle) {
+    let active = APP_STATE
+        .lock()
+        .unwrap()
+        .reconcile_views(|tab_id| app.get_webview(&format!("ai_{}", tab_id)).is_some());
 
-    {
-        let mut layout = LAYOUT_STATE.lock().unwrap();
-        match layout.mode {
-            LayoutMode::Single => {
-                if active == tab_id || current == site_id {
-                    clear_current = true;
-                }
+    if let Some(active) = active {
+        // 活跃 Tab 没有 Webview：尝试重建，失败则清空
+        match get_tab_site_id(&active) {
+            Ok(site_id) if ensure_tab_webview(app, &active, &site_id).is_ok() => {
+                APP_STATE.lock().unwrap().current_view = site_id;
             }
-            LayoutMode::Split => {
-                let mut left = layout.left_tab_id.clone();
-                let mut right = layout.right_tab_id.clone();
+            _ => APP_STATE.lock().unwrap().clear_active(),
+        }
+    }
 
-                if left.as_deref() == Some(tab_id) {
-                    left = None;
-                }
-                if right.as_deref() == Some(tab_id) {
-                    right = None;
-                }
+    let _ = resize_webviews_inner(app, !ACTIVE_VIEW_SUPPRESSED.load(Ordering::SeqCst));
+}
 
-                if left.is_none() || right.is_none() {
-                    next_active = left.clone().or(right.clone());
-                    layout.mode = LayoutMode::Single;
-                    layout.left_tab_id = None;
-                    layout.right_tab_id = None;
-                } else {
-                    layout.left_tab_id = left;
-                    layout.right_tab_id = right;
-                }
+/// 按布局应当显示的 Tab（单视图为活跃 Tab，分屏为各窗格）
+fn layout_visible_tab_ids() -> Vec<String> {
+    let state = APP_STATE.lock().unwrap();
+    match state.layout.mode {
+        LayoutMode::Single => {
+            let tab_id = state.focused_tab_id();
+            if tab_id.is_empty() {
+                Vec::new()
+            } else {
+                vec![tab_id]
             }
         }
+        LayoutMode::Split => state.layout.pane_tab_ids.clone(),
     }
+}
 
-    if let Some(next_tab_id) = next_active {
-        *ACTIVE_TAB_ID.lock().unwrap() = next_tab_id.clone();
-        if let Ok(site) = get_tab_site_id(&next_tab_id) {
-            *CURRENT_VIEW.lock().unwrap() = site.clone();
-            update_last_active(&next_tab_id, &site);
-        } else {
-            *CURRENT_VIEW.lock().unwrap() = String::new();
-            clear_last_active();
-        }
-    } else if clear_current {
-        *ACTIVE_TAB_ID.lock().unwrap() = String::new();
-        *CURRENT_VIEW.lock().unwrap() = String::new();
-        clear_last_active();
+#[derive(Debug, Clone, Serialize)]
+struct TabsChangedPayload {
+    removed_tab_ids: Vec<String>,
+}
+
+/// 站点变更后的一致性检查：关闭所属站点已不存在的 Tab（Tab 映射与已创建的 Webview），
+/// 修正布局后通过 `tabs-state-changed` 事件通知前端刷新
+fn reconcile_site_tabs(app: &tauri::AppHandle) {
+    let site_ids: HashSet<String> = APP_CONFIG
+        .lock()
+        .unwrap()
+        .sites
+        .iter()
+        .map(|s| s.id.clone())
+        .collect();
+    let stale = APP_STATE.lock().unwrap().remove_stale_site_tabs(&site_ids);
+    if stale.is_empty() {
+        return;
     }
+    println!("[reconcile] 关闭站点已删除的 Tab: {}", stale.join(","));
 
-    let _ = resize_webviews_inner(app, true);
+    for tab_id in &stale {
+        close_tab_webview(app, tab_id);
+    }
+
+    reconcile_layout_state(app);
+    persist_session();
+    let _ = app.emit(
+        "tabs-state-changed",
+        TabsChangedPayload {
+            removed_tab_ids: stale,
+        },
+    );
 }
 
-fn first_site_id_excluding(exclude_site_id: &str) -> Option<String> {
-    let config = APP_CONFIG.lock().unwrap();
-    let existing: HashSet<String> = config.sites.iter().map(|s| s.id.clone()).collect();
-    let mut seen: HashSet<String> = HashSet::new();
+// ============================================================================
+// 定期自检
+// ============================================================================
+//
+// 长时间运行后内存状态可能与实际 Webview 脱节（Webview 悄然退出、布局引用休眠的 Tab 等），
+// 自检定期修正这些偏差；每项修正都会记录日志并计入诊断信息
 
-    for id in &config.pinned_site_ids {
-        if !existing.contains(id) || id == exclude_site_id {
-            continue;
+/// 自检间隔（Webview 创建或总结进行中时跳过本轮）
+const SELF_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// 启动以来自检累计修正的次数
+static SELF_CHECK_CORRECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+static LAST_SELF_CHECK: Lazy<Mutex<Option<SelfCheckReport>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+struct SelfCheckReport {
+    ran_at: u64,
+    /// 本轮所做的修正（为空表示状态一致）
+    corrections: Vec<String>,
+    /// 启动以来累计修正次数
+    total_corrections: usize,
+}
+
+fn self_check_busy() -> bool {
+    !APP_STATE.lock().unwrap().creating_views.is_empty()
+        || !SUMMARIES_IN_PROGRESS.lock().unwrap().is_empty()
+}
+
+/// 执行一轮自检（需在主线程调用）
+fn run_self_check_pass(app: &tauri::AppHandle) -> SelfCheckReport {
+    let mut corrections: Vec<String> = Vec::new();
+
+    // 1. 清单中 Webview 已不存在的记录
+    let dead_views: Vec<String> = {
+        let views = &mut APP_STATE.lock().unwrap().views;
+        let dead: Vec<String> = views
+            .iter()
+            .filter(|tab_id| app.get_webview(&format!("ai_{}", tab_id)).is_none())
+            .cloned()
+            .collect();
+        for tab_id in &dead {
+            views.remove(tab_id);
         }
-        if seen.insert(id.clone()) {
-            return Some(id.clone());
+        dead
+    };
+    for tab_id in dead_views {
+        corrections.push(format!("移除已失效的 Webview 记录: {}", tab_id));
+    }
+
+    // 2. 休眠记录中已关闭的 Tab
+    let stale_hibernated: Vec<String> = {
+        let tabs = APP_STATE.lock().unwrap().tabs.clone();
+        let mut runtimes = TAB_RUNTIME.lock().unwrap();
+        let stale: Vec<String> = runtimes
+            .iter()
+            .filter(|(tab_id, runtime)| runtime.hibernated && !tabs.contains_key(*tab_id))
+            .map(|(tab_id, _)| tab_id.clone())
+            .collect();
+        for tab_id in &stale {
+            runtimes.remove(tab_id);
         }
+        stale
+    };
+    for tab_id in stale_hibernated {
+        corrections.push(format!("移除已关闭 Tab 的休眠记录: {}", tab_id));
     }
 
-    for id in &config.site_order {
-        if !existing.contains(id) || id == exclude_site_id {
-            continue;
-        }
-        if seen.insert(id.clone()) {
-            return Some(id.clone());
-        }
+    // 3. 站点已删除的 Tab
+    let tabs_before: HashSet<String> = APP_STATE.lock().unwrap().tabs.keys().cloned().collect();
+    reconcile_site_tabs(app);
+    let tabs_after: HashSet<String> = APP_STATE.lock().unwrap().tabs.keys().cloned().collect();
+    for tab_id in tabs_before.difference(&tabs_after) {
+        corrections.push(format!("关闭站点已删除的 Tab: {}", tab_id));
     }
 
-    for site in &config.sites {
-        if site.id == exclude_site_id {
+    // 4. 布局中应显示的 Tab 没有 Webview（已休眠或意外退出）：重建，失败则由对账移出布局
+    for tab_id in layout_visible_tab_ids() {
+        if is_tab_detached(&tab_id) || app.get_webview(&format!("ai_{}", tab_id)).is_some() {
             continue;
         }
-        if seen.insert(site.id.clone()) {
-            return Some(site.id.clone());
+        let Ok(site_id) = get_tab_site_id(&tab_id) else {
+            continue;
+        };
+        match ensure_tab_webview(app, &tab_id, &site_id) {
+            Ok(()) => corrections.push(format!("重建布局中缺失的 Webview: {}", tab_id)),
+            Err(e) => corrections.push(format!(
+                "布局中的 Tab 无法恢复，已移出布局: {} ({})",
+                tab_id, e
+            )),
         }
     }
 
-    None
-}
+    // 5. 对账并按当前布局重新设置位置、大小与可见性
+    reconcile_layout_state(app);
 
-// ============================================================================
-// Tauri Commands
-// ============================================================================
+    // 6. 等待方已放弃或已过期的页面提取请求
+    let dropped_extracts = sweep_pending_extracts(&mut PENDING_EXTRACTS.lock().unwrap());
+    if dropped_extracts > 0 {
+        corrections.push(format!("清理已失效的提取请求: {} 个", dropped_extracts));
+    }
 
-/// 获取应用配置
-#[tauri::command]
-fn get_config(webview: tauri::Webview) -> Result<AppConfig, String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+    // 7. 过期的后台任务记录（常规清理，不计为修正）
+    jobs::prune_expired_jobs();
+
+    for correction in &corrections {
+        println!("[self-check] {}", correction);
     }
-    // 注意：不要把 API Key 暴露给前端/远程页面
-    let mut cfg = APP_CONFIG.lock().unwrap().clone();
-    cfg.ai_api_key.clear();
-    Ok(cfg)
+    let total_corrections =
+        SELF_CHECK_CORRECTIONS.fetch_add(corrections.len(), Ordering::SeqCst) + corrections.len();
+    let report = SelfCheckReport {
+        ran_at: now_ts(),
+        corrections,
+        total_corrections,
+    };
+    *LAST_SELF_CHECK.lock().unwrap() = Some(report.clone());
+    report
+}
+
+/// 定时自检
+fn start_self_check_timer(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SELF_CHECK_INTERVAL).await;
+            if self_check_busy() {
+                println!("[self-check] Webview 创建或总结进行中，跳过本轮");
+                continue;
+            }
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                run_self_check_pass(&handle);
+            });
+        }
+    });
 }
 
+/// 立即执行一轮自检并返回修正报告
 #[tauri::command]
-fn set_ai_api_settings(
+async fn run_self_check(
     webview: tauri::Webview,
-    base_url: String,
-    model: String,
-    api_key: String,
-    clear_key: Option<bool>,
-) -> Result<(), String> {
+    app: tauri::AppHandle,
+) -> Result<SelfCheckReport, AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let base_url_trimmed = base_url.trim().trim_end_matches('/').to_string();
-    let base_url = if base_url_trimmed.is_empty() {
-        "https://api.openai.com/v1".to_string()
-    } else {
-        base_url_trimmed
-    };
-    let model = model.trim().to_string();
+    if self_check_busy() {
+        return Err("正在创建 Webview 或生成总结，请稍后再试".into());
+    }
+    let (tx, rx) = oneshot::channel();
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(run_self_check_pass(&handle));
+    })
+    .map_err(|e| format!("自检调度失败: {}", e))?;
+    rx.await.map_err(|_| AppError::from("自检未完成"))
+}
 
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.ai_api_base_url = base_url;
-    config.ai_api_model = model;
-    let api_key_trimmed = api_key.trim().to_string();
-    if !api_key_trimmed.is_empty() || clear_key == Some(true) {
-        config.ai_api_key = api_key_trimmed;
+/// 切换到指定 Tab（进入单视图模式）
+async fn switch_tab_inner(app: tauri::AppHandle, tab_id: String) -> Result<(), String> {
+    if focus_detached_window(&app, &tab_id) {
+        return Ok(());
     }
-    save_config(&config)?;
+    let site_id = get_tab_site_id(&tab_id)?;
+    println!("[switch_tab] tab_id={} site_id={}", tab_id, site_id);
+
+    let txn = LayoutTransaction::begin(&app);
+    ensure_tab_webview(&app, &tab_id, &site_id)?;
+
+    // Webview 已就绪，再一次性写入内存状态
+    {
+        let mut state = APP_STATE.lock().unwrap();
+        state.layout.set_single();
+        state.set_active(&tab_id, &site_id);
+    }
+
+    resize_webviews_inner(&app, true)?;
+    txn.commit();
+
+    upsert_recent_site(&site_id);
+    record_site_activation(&site_id);
+    update_last_active(&tab_id, &site_id);
+    persist_session();
+    touch_tab(&tab_id);
+    gc_idle_webviews(&app);
     Ok(())
 }
 
 #[tauri::command]
-fn set_active_project(webview: tauri::Webview, project_id: String) -> Result<(), String> {
+async fn switch_tab(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.active_project_id = project_id;
-    save_config(&config)?;
-    Ok(())
+    switch_tab_inner(app, tab_id).await.map_err(AppError::from)
 }
 
+/// 手动休眠 Tab（不能休眠当前显示的 Tab 或分屏中的 Tab）；`check_unsaved` 为 true 时输入框有未发送内容则需确认
 #[tauri::command]
-fn set_language(webview: tauri::Webview, language: String) -> Result<(), String> {
+async fn hibernate_tab(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    check_unsaved: Option<bool>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let lang = language.trim().to_string();
-    if lang.is_empty() {
-        return Err("language 不能为空".to_string());
+    get_tab_site_id(&tab_id)?;
+    if is_tab_visible(&tab_id) {
+        return Err("不能休眠正在显示的 Tab".into());
     }
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.language = lang;
-    save_config(&config)?;
+    if !APP_STATE.lock().unwrap().views.contains(&tab_id) {
+        return Ok(());
+    }
+    ensure_no_unsent_input(&app, std::slice::from_ref(&tab_id), check_unsaved).await?;
+    hibernate_tab_webview(&app, &tab_id);
     Ok(())
 }
 
+/// 重新加载失败的 Tab：导航到站点配置的地址；Webview 已因加载失败关闭时重新创建并切换过去
 #[tauri::command]
-fn set_summary_prompt_template(webview: tauri::Webview, template: String) -> Result<(), String> {
+async fn retry_tab_load(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let trimmed = template.trim().to_string();
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.summary_prompt_template = if trimmed.is_empty() {
-        default_summary_prompt_template()
-    } else {
-        trimmed
+    let site_id = get_tab_site_id(&tab_id)?;
+    reset_retry_state(&tab_id);
+    let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) else {
+        return switch_tab_inner(app, tab_id).await.map_err(AppError::from);
     };
-    save_config(&config)?;
-    Ok(())
+    let site = get_site_by_id(&site_id)?;
+    let url = site_url::parse_site_url(&site.url)?;
+    mark_last_requested_url(&tab_id, &url);
+    child
+        .navigate(url)
+        .map_err(|e| AppError::from(format!("重新加载失败: {}", e)))
 }
 
-#[tauri::command]
-fn list_projects(webview: tauri::Webview) -> Result<Vec<ProjectSummary>, String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+/// 可以重启的 Tab 所属站点（独立窗口中的 Tab 不支持重启）
+fn restartable_tab_site_id(tab_id: &str) -> Result<String, AppError> {
+    let site_id = get_tab_site_id(tab_id)?;
+    if is_tab_detached(tab_id) {
+        return Err("该 Tab 已在独立窗口中打开".into());
     }
-    let mut projects = load_contexts();
-    projects.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-    Ok(projects
-        .into_iter()
-        .map(|p| ProjectSummary {
-            id: p.id,
-            title: p.title,
-            updated_at: p.updated_at,
-        })
-        .collect())
+    Ok(site_id)
 }
 
+/// 重启 Tab 的 Webview（渲染进程崩溃或白屏时使用）：强制关闭后按原数据目录重建，登录状态保留
 #[tauri::command]
-fn get_project(webview: tauri::Webview, project_id: String) -> Result<ProjectContext, String> {
+async fn restart_tab(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let projects = load_contexts();
-    projects
-        .into_iter()
-        .find(|p| p.id == project_id)
-        .ok_or_else(|| "项目不存在".to_string())
-}
+    let site_id = restartable_tab_site_id(&tab_id)?;
+    println!("[restart_tab] tab_id={} site_id={}", tab_id, site_id);
+    destroy_tab_webview(&app, &tab_id);
 
-#[tauri::command]
-fn create_project(webview: tauri::Webview, title: String) -> Result<String, String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+    // close 异步生效：等旧 Webview 注销后再用同一 label 创建
+    let label = format!("ai_{}", tab_id);
+    for _ in 0..20 {
+        if app.get_webview(&label).is_none() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    if app.get_webview(&label).is_some() {
+        return Err("旧 Webview 尚未关闭，请稍后重试".into());
     }
-    let mut projects = load_contexts();
-    let id = format!("proj_{}", Uuid::new_v4().to_string().split('-').next().unwrap());
-    let ts = now_ts();
-    projects.push(ProjectContext {
-        id: id.clone(),
-        title: if title.trim().is_empty() {
-            "默认项目".to_string()
-        } else {
-            title.trim().to_string()
-        },
-        notes: String::new(),
-        summary: String::new(),
-        created_at: ts,
-        updated_at: ts,
-    });
-    save_contexts(&projects)?;
-
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.active_project_id = id.clone();
-    let _ = save_config(&config);
 
-    Ok(id)
+    ensure_tab_webview(&app, &tab_id, &site_id)?;
+    resize_webviews_inner(&app, true)?;
+    Ok(())
 }
 
+/// 设置布局（single / split / split3）
+/// - split：兼容 left/right 两分屏；传入 pane_tab_ids 时按列表从左到右排列 N 个分屏
+/// - split3：三分屏，需通过 pane_tab_ids 传入 3 个 Tab
+/// - orientation：horizontal（左右）/ vertical（上下），缺省沿用当前方向；
+///   已处于分屏且未传 Tab 时只切换方向，不重建 Webview
+/// - 单视图下以 split 调用且未传 Tab 时，恢复上次退出分屏前的布局（其 Tab 须仍然存在）
 #[tauri::command]
-fn update_project(
+#[allow(clippy::too_many_arguments)]
+async fn set_layout(
     webview: tauri::Webview,
-    project_id: String,
-    title: String,
-    notes: String,
-    summary: String,
-) -> Result<(), String> {
+    app: tauri::AppHandle,
+    mode: String,
+    ratio: Option<f64>,
+    left_tab_id: Option<String>,
+    right_tab_id: Option<String>,
+    pane_tab_ids: Option<Vec<String>>,
+    pane_ratios: Option<Vec<f64>>,
+    orientation: Option<String>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
-    }
-    let mut projects = load_contexts();
-    let mut found = false;
-    for p in projects.iter_mut() {
-        if p.id != project_id {
-            continue;
-        }
-        found = true;
-        p.title = if title.trim().is_empty() { p.title.clone() } else { title.trim().to_string() };
-        p.notes = notes;
-        p.summary = summary;
-        p.updated_at = now_ts();
-        break;
+        return Err(AppError::NotAllowed);
     }
-    if !found {
-        return Err("项目不存在".to_string());
+    let orientation = orientation
+        .as_deref()
+        .map(SplitOrientation::parse)
+        .transpose()?;
+    let effects = AppLayoutEffects { app };
+    if mode == "single" {
+        println!("[set_layout] mode=single");
+        return apply_single_layout(&effects);
     }
-    save_contexts(&projects)?;
-    Ok(())
-}
 
-#[tauri::command]
-fn delete_project(webview: tauri::Webview, project_id: String) -> Result<(), String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+    if mode != "split" && mode != "split3" {
+        return Err("mode 仅支持 single|split|split3".into());
     }
-    let mut projects = load_contexts();
-    let before = projects.len();
-    projects.retain(|p| p.id != project_id);
-    if projects.len() == before {
-        return Err("项目不存在".to_string());
+
+    let trimmed = |id: String| {
+        let id = id.trim().to_string();
+        if id.is_empty() {
+            None
+        } else {
+            Some(id)
+        }
+    };
+    let mut panes: Vec<String> = match pane_tab_ids {
+        Some(ids) => ids.into_iter().filter_map(trimmed).collect(),
+        None => left_tab_id
+            .and_then(trimmed)
+            .into_iter()
+            .chain(right_tab_id.and_then(trimmed))
+            .collect(),
+    };
+    let mut restored_ratios: Option<Vec<f64>> = None;
+    let mut orientation = orientation;
+    if panes.is_empty() {
+        let layout = APP_STATE.lock().unwrap().layout.clone();
+        if layout.is_split() {
+            // 仅切换方向：沿用当前分屏
+            if orientation.is_some() {
+                panes = layout.pane_tab_ids.clone();
+            }
+        } else if let Some(last) = layout.restorable_last_split() {
+            println!("[set_layout] 恢复上次分屏: {}", last.pane_tab_ids.join(","));
+            panes = last.pane_tab_ids;
+            restored_ratios = Some(last.pane_ratios);
+            if orientation.is_none() {
+                orientation = SplitOrientation::parse(&last.orientation).ok();
+            }
+        }
     }
-    save_contexts(&projects)?;
 
-    let mut config = APP_CONFIG.lock().unwrap();
-    if config.active_project_id == project_id {
-        config.active_project_id = String::new();
-        let _ = save_config(&config);
+    if panes.is_empty() {
+        return Err("至少需要选择一个 Tab".into());
     }
-    Ok(())
-}
+    if mode == "split3" && panes.len() != 3 {
+        return Err("三分屏需要选择 3 个 Tab".into());
+    }
+    if panes.len() > MAX_SPLIT_PANES {
+        return Err(format!("最多支持 {} 个分屏", MAX_SPLIT_PANES).into());
+    }
+    let unique: HashSet<&String> = panes.iter().collect();
+    if unique.len() != panes.len() {
+        return Err(if panes.len() == 2 {
+            "左右 Tab 不能相同".into()
+        } else {
+            "分屏 Tab 不能重复".into()
+        });
+    }
+    println!("[set_layout] mode={} panes={}", mode, panes.join(","));
+
+    // 不要在创建/添加 Webview 时持有 APP_STATE 锁，避免与 WindowEvent::Resized 产生死锁
+    let desired_ratios = {
+        let layout = &APP_STATE.lock().unwrap().layout;
+        if let Some(ratios) = pane_ratios.or(restored_ratios) {
+            ratios
+        } else if panes.len() == 2 {
+            let ratio = ratio.unwrap_or(layout.ratio()).clamp(0.2, 0.8);
+            vec![ratio, 1.0 - ratio]
+        } else if layout.pane_tab_ids.len() == panes.len() {
+            layout.pane_ratios.clone()
+        } else {
+            Vec::new()
+        }
+    };
 
-#[derive(Debug, Clone, Deserialize)]
-struct OpenAiChatResponse {
-    choices: Vec<OpenAiChoice>,
+    apply_split_layout(&effects, panes, &desired_ratios, orientation).await
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct OpenAiChoice {
-    message: OpenAiMessage,
+/// set_layout 的副作用层：Tab 解析、Webview 创建、重新布局与提交后的收尾。
+/// 布局切换的顺序（先校验、再创建 Webview、最后写入状态并提交事务）只在
+/// `apply_single_layout` / `apply_split_layout` 中实现一次
+trait LayoutEffects {
+    fn state(&self) -> std::sync::MutexGuard<'_, AppState>;
+    fn tab_site_id(&self, tab_id: &str) -> Result<String, AppError>;
+    fn begin_transaction(&self) -> LayoutTransaction;
+    /// 确保 Tab 的 Webview 存在
+    async fn ensure_webview(&self, tab_id: &str, site_id: &str) -> Result<(), AppError>;
+    fn resize(&self) -> Result<(), AppError>;
+    /// 布局已提交：持久化会话、刷新窗格 Tab 的活跃时间并回收空闲 Webview
+    fn committed(&self, pane_tab_ids: &[String]);
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct OpenAiMessage {
-    content: String,
+struct AppLayoutEffects {
+    app: tauri::AppHandle,
 }
 
-#[tauri::command]
-async fn summarize_text(webview: tauri::Webview, text: String, site_id: Option<String>) -> Result<String, String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
-    }
-    let config = APP_CONFIG.lock().unwrap().clone();
-    if config.ai_api_key.trim().is_empty() {
-        return Err("未配置 API Key".to_string());
+impl LayoutEffects for AppLayoutEffects {
+    fn state(&self) -> std::sync::MutexGuard<'_, AppState> {
+        APP_STATE.lock().unwrap()
     }
-    if config.ai_api_model.trim().is_empty() {
-        return Err("未配置 Model".to_string());
-    }
-
-    let base_url = config.ai_api_base_url.trim().trim_end_matches('/').to_string();
-    let url = format!("{}/chat/completions", base_url);
 
-    let mut template = config.summary_prompt_template.clone();
-    if let Some(id) = site_id.as_deref() {
-        if let Some(site) = config.sites.iter().find(|s| s.id == id) {
-            if !site.summary_prompt_override.trim().is_empty() {
-                template = site.summary_prompt_override.clone();
-            }
-        }
+    fn tab_site_id(&self, tab_id: &str) -> Result<String, AppError> {
+        get_tab_site_id(tab_id)
     }
-    if template.trim().is_empty() {
-        template = default_summary_prompt_template();
-    }
-    let prompt = build_summary_prompt(&template, language_label(&config.language), &text);
-
-    let body = serde_json::json!({
-        "model": config.ai_api_model,
-        "messages": [
-            { "role": "system", "content": "你是一个擅长提炼上下文与约束的助手。" },
-            { "role": "user", "content": prompt }
-        ],
-        "temperature": 0.2
-    });
-
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(url)
-        .header(CONTENT_TYPE, "application/json")
-        .header(AUTHORIZATION, format!("Bearer {}", config.ai_api_key))
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {}", e))?;
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(format!("API 返回错误 {}: {}", status, text));
+    fn begin_transaction(&self) -> LayoutTransaction {
+        LayoutTransaction::begin(&self.app)
     }
 
-    let data = resp
-        .json::<OpenAiChatResponse>()
-        .await
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+    async fn ensure_webview(&self, tab_id: &str, site_id: &str) -> Result<(), AppError> {
+        Ok(ensure_tab_webview(&self.app, tab_id, site_id)?)
+    }
 
-    let content = data
-        .choices
-        .get(0)
-        .map(|c| c.message.content.clone())
-        .unwrap_or_default();
+    fn resize(&self) -> Result<(), AppError> {
+        Ok(resize_webviews_inner(&self.app, true)?)
+    }
 
-    if content.trim().is_empty() {
-        return Err("API 返回空内容".to_string());
+    fn committed(&self, pane_tab_ids: &[String]) {
+        persist_session();
+        for tab_id in pane_tab_ids {
+            touch_tab(tab_id);
+        }
+        gc_idle_webviews(&self.app);
     }
+}
 
-    Ok(content)
+fn apply_single_layout(effects: &impl LayoutEffects) -> Result<(), AppError> {
+    let txn = effects.begin_transaction();
+    effects.state().layout.set_single();
+    effects.resize()?;
+    txn.commit();
+    effects.committed(&[]);
+    Ok(())
 }
 
-fn ensure_active_project_id() -> Result<String, String> {
-    let mut config = APP_CONFIG.lock().unwrap();
-    if !config.active_project_id.trim().is_empty() {
-        return Ok(config.active_project_id.clone());
-    }
+/// 切换到分屏：`panes` 已去重并校验数量
+async fn apply_split_layout(
+    effects: &impl LayoutEffects,
+    panes: Vec<String>,
+    desired_ratios: &[f64],
+    orientation: Option<SplitOrientation>,
+) -> Result<(), AppError> {
+    // 先校验所有 Tab，再执行副作用，避免只创建了一部分就失败
+    let pane_sites: Vec<String> = panes
+        .iter()
+        .map(|id| effects.tab_site_id(id))
+        .collect::<Result<_, _>>()?;
 
-    let mut projects = load_contexts();
-    if let Some(first) = projects.first() {
-        config.active_project_id = first.id.clone();
-        let _ = save_config(&config);
-        return Ok(first.id.clone());
-    }
-
-    let id = format!("proj_{}", Uuid::new_v4().to_string().split('-').next().unwrap());
-    let ts = now_ts();
-    projects.push(ProjectContext {
-        id: id.clone(),
-        title: "默认项目".to_string(),
-        notes: String::new(),
-        summary: String::new(),
-        created_at: ts,
-        updated_at: ts,
-    });
-    save_contexts(&projects)?;
+    let txn = effects.begin_transaction();
+    for (tab_id, site_id) in panes.iter().zip(&pane_sites) {
+        effects.ensure_webview(tab_id, site_id).await?;
+    }
 
-    config.active_project_id = id.clone();
-    let _ = save_config(&config);
-    Ok(id)
-}
+    // 兼容：当前视图仍为“当前主站点”，优先 active tab 的站点
+    let active_tab_id = effects.state().active_tab.clone();
+    let next_current_view = effects
+        .tab_site_id(&active_tab_id)
+        .ok()
+        .or(pane_sites.first().cloned());
 
-#[tauri::command]
-async fn aihub_submit_page_text(request_id: String, token: String, text: String) -> Result<(), String> {
-    let pending = PENDING_EXTRACTS.lock().unwrap().remove(&request_id);
-    if let Some(p) = pending {
-        if p.token != token {
-            return Ok(());
+    {
+        let mut state = effects.state();
+        state.layout.set_split(panes.clone(), desired_ratios);
+        if let Some(orientation) = orientation {
+            state.layout.orientation = orientation;
+        }
+        if let Some(site) = next_current_view {
+            state.current_view = site;
         }
-        let _ = p.tx.send(text);
     }
+
+    effects.resize()?;
+    txn.commit();
+    effects.committed(&panes);
     Ok(())
 }
 
-/// 标记“当前活跃 Tab”（用于 split 模式下的“总结当前对话”）
+/// 拖动分隔条时实时调整分屏占比：只更新占比并重新摆放现有 Webview，
+/// 不重新解析 Tab，也不切换可见性。`divider` 缺省为第一条分隔条（两分屏时即左右/上下占比）
 #[tauri::command]
-fn set_active_tab_id(webview: tauri::Webview, tab_id: String) -> Result<(), String> {
+fn set_split_ratio(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    ratio: f64,
+    divider: Option<usize>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
-    }
-    if tab_id.trim().is_empty() {
-        return Ok(());
-    }
-    *ACTIVE_TAB_ID.lock().unwrap() = tab_id.clone();
-    if let Ok(site_id) = get_tab_site_id(&tab_id) {
-        update_last_active(&tab_id, &site_id);
+        return Err(AppError::NotAllowed);
     }
+    APP_STATE
+        .lock()
+        .unwrap()
+        .layout
+        .move_divider(divider.unwrap_or(0), ratio)?;
+    resize_webviews_inner(&app, false)?;
+    schedule_persist_session();
     Ok(())
 }
 
+/// 交换左右（上下）两个分屏：只交换位置并重新摆放现有 Webview，分隔条位置不变
 #[tauri::command]
-async fn summarize_active_tab(app: tauri::AppHandle, webview: tauri::Webview) -> Result<String, String> {
+fn swap_split_panes(webview: tauri::Webview, app: tauri::AppHandle) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-
-    if SUMMARY_IN_PROGRESS
-        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-        .is_err()
     {
-        return Err("总结正在进行中，请稍候…".to_string());
-    }
-
-    struct SummaryInProgressGuard;
-    impl Drop for SummaryInProgressGuard {
-        fn drop(&mut self) {
-            SUMMARY_IN_PROGRESS.store(false, Ordering::SeqCst);
+        let layout = &mut APP_STATE.lock().unwrap().layout;
+        if !layout.is_split() {
+            return Err("当前不是分屏布局".into());
         }
-    }
-    let _guard = SummaryInProgressGuard;
-
-    let result = tokio::time::timeout(
-        std::time::Duration::from_secs(60),
-        async {
-    let tab_id = {
-        let active = ACTIVE_TAB_ID.lock().unwrap().clone();
-        if !active.is_empty() {
-            active
-        } else {
-            CURRENT_VIEW.lock().unwrap().clone()
+        if layout.pane_tab_ids.len() < 2 {
+            return Err("分屏缺少左侧或右侧 Tab".into());
         }
-    };
-
-    if tab_id.trim().is_empty() {
-        return Err("没有可总结的页面".to_string());
+        layout.pane_tab_ids.swap(0, 1);
     }
+    resize_webviews_inner(&app, false)?;
+    persist_session();
+    Ok(())
+}
 
-    let site_id = get_tab_site_id(&tab_id).unwrap_or_else(|_| tab_id.clone());
-    ensure_tab_webview(&app, &tab_id, &site_id)?;
+// ============================================================================
+// 最近关闭的 Tab
+// ============================================================================
 
-    let webview_label = format!("ai_{}", tab_id);
-    let child = app
-        .get_webview(&webview_label)
-        .ok_or_else(|| "Webview 不存在".to_string())?;
+/// 最多记录的已关闭 Tab 数量
+const MAX_CLOSED_TABS: usize = 10;
 
-    let request_id = Uuid::new_v4().to_string();
-    let token = Uuid::new_v4().to_string();
-    let (tx, rx) = oneshot::channel::<String>();
-    PENDING_EXTRACTS
-        .lock()
-        .unwrap()
-        .insert(request_id.clone(), PendingExtract { token: token.clone(), tx });
+/// 最近关闭的 Tab（新的在前）
+static CLOSED_TABS: Lazy<Mutex<Vec<ClosedTab>>> = Lazy::new(|| Mutex::new(Vec::new()));
 
-    let js = format!(
-        r#"(async () => {{
-  try {{
-    const text = document?.body?.innerText || '';
-    await window.__TAURI__.core.invoke('aihub_submit_page_text', {{ requestId: '{rid}', token: '{tok}', text }});
-  }} catch (e) {{
-    try {{
-      await window.__TAURI__.core.invoke('aihub_submit_page_text', {{ requestId: '{rid}', token: '{tok}', text: '' }});
-    }} catch (_) {{}}
-  }}
-}})();"#,
-        rid = request_id,
-        tok = token
+/// 记录即将关闭的 Tab（须在销毁 Webview 之前调用，以便取到最后的页面地址）
+fn remember_closed_tab(tab_id: &str, site_id: &str) {
+    let url = read_tab_runtime(tab_id, |runtime| {
+        Some(runtime.page.url.clone())
+            .filter(|url| !url.is_empty())
+            .or_else(|| runtime.last_requested_url.clone())
+    })
+    .flatten()
+    .unwrap_or_default();
+    let mut closed = CLOSED_TABS.lock().unwrap();
+    closed.retain(|t| t.tab_id != tab_id);
+    closed.insert(
+        0,
+        ClosedTab {
+            tab_id: tab_id.to_string(),
+            site_id: site_id.to_string(),
+            url,
+            closed_at: now_ts(),
+        },
     );
+    closed.truncate(MAX_CLOSED_TABS);
+}
 
-    child.eval(&js).map_err(|e| format!("执行提取脚本失败: {}", e))?;
+/// 最近关闭的 Tab（新的在前）
+#[tauri::command]
+fn list_closed_tabs(webview: tauri::Webview) -> Result<Vec<ClosedTab>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(CLOSED_TABS.lock().unwrap().clone())
+}
 
-    let extracted = match tokio::time::timeout(std::time::Duration::from_secs(20), rx).await {
-        Ok(res) => res.map_err(|_| "提取失败".to_string())?,
-        Err(_) => {
-            PENDING_EXTRACTS.lock().unwrap().remove(&request_id);
-            return Err("提取超时".to_string());
+/// 重新打开最近关闭的 Tab：恢复 Tab 映射（沿用原 tab_id，保留其独立的数据目录），
+/// 切换过去并导航到关闭时的页面。返回重新打开的 tab_id
+#[tauri::command]
+async fn reopen_closed_tab(webview: tauri::Webview, app: tauri::AppHandle) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let closed = loop {
+        let Some(closed) = ({
+            let mut stack = CLOSED_TABS.lock().unwrap();
+            (!stack.is_empty()).then(|| stack.remove(0))
+        }) else {
+            return Err("没有最近关闭的 Tab".into());
+        };
+        // 站点已被删除的记录直接跳过
+        if get_site_by_id(&closed.site_id).is_ok() {
+            break closed;
         }
     };
+    println!("[reopen_closed_tab] tab_id={} site_id={}", closed.tab_id, closed.site_id);
 
-    if extracted.trim().is_empty() {
-        return Err("未能提取到页面文本（可能被站点限制或页面未加载完成）".to_string());
-    }
+    let is_main_tab = closed.tab_id == closed.site_id;
+    let already_open = {
+        let state = APP_STATE.lock().unwrap();
+        state.views.contains(&closed.tab_id) || state.tabs.contains_key(&closed.tab_id)
+    };
+    let tab_id = if is_main_tab {
+        closed.tab_id.clone()
+    } else if already_open {
+        create_tab_inner(&closed.site_id)?
+    } else {
+        APP_STATE
+            .lock()
+            .unwrap()
+            .tabs
+            .insert(closed.tab_id.clone(), closed.site_id.clone());
+        closed.tab_id.clone()
+    };
 
-    // 总结（内部调用，避免再次经过 invoke 参数校验）
-    let summary = summarize_text(webview, extracted.clone(), Some(site_id.clone())).await?;
+    switch_tab_inner(app.clone(), tab_id.clone()).await?;
 
-    // 保存到 active project（覆盖 notes/summary）
-    let project_id = ensure_active_project_id()?;
-    let mut projects = load_contexts();
-    let ts = now_ts();
-    let mut found = false;
-    for p in projects.iter_mut() {
-        if p.id != project_id {
-            continue;
+    let target = tauri::Url::parse(&closed.url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"));
+    if let (Some(url), Some(child)) = (target, app.get_webview(&format!("ai_{}", tab_id))) {
+        if child.url().ok().as_ref() != Some(&url) {
+            mark_last_requested_url(&tab_id, &url);
+            child.navigate(url).map_err(|e| format!("导航失败: {}", e))?;
         }
-        found = true;
-        p.notes = extracted;
-        p.summary = summary.clone();
-        p.updated_at = ts;
-        break;
-    }
-    if !found {
-        projects.push(ProjectContext {
-            id: project_id,
-            title: "默认项目".to_string(),
-            notes: String::new(),
-            summary: summary.clone(),
-            created_at: ts,
-            updated_at: ts,
-        });
-    }
-    let _ = save_contexts(&projects);
-
-    Ok(summary)
-        },
-    )
-    .await;
-
-    match result {
-        Ok(res) => res,
-        Err(_) => Err("总结超时（60s）".to_string()),
     }
+    persist_session();
+    Ok(tab_id)
 }
 
-/// 获取所有 AI 站点列表（按排序顺序）
+/// 关闭一个 Tab
 #[tauri::command]
-fn get_ai_sites(webview: tauri::Webview) -> Result<Vec<AiSite>, String> {
+async fn close_tab(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    check_unsaved: Option<bool>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let config = APP_CONFIG.lock().unwrap();
-    let mut sites: Vec<AiSite> = Vec::new();
-    let mut seen: HashSet<String> = HashSet::new();
+    ensure_no_unsent_input(&app, std::slice::from_ref(&tab_id), check_unsaved).await?;
+    let closed_site_id = get_tab_site_id(&tab_id).unwrap_or_else(|_| tab_id.clone());
+    remember_closed_tab(&tab_id, &closed_site_id);
+    close_tab_webview(&app, &tab_id);
+    remove_tab_from_layout(app, &tab_id, &closed_site_id).await.map_err(AppError::from)
+}
 
-    // 按 site_order 排序
-    for id in &config.site_order {
-        if !seen.insert(id.clone()) {
-            continue;
-        }
-        if let Some(site) = config.sites.iter().find(|s| &s.id == id) {
-            sites.push(site.clone());
-        }
+/// Tab 离开主窗口（关闭或移到独立窗口）后修正布局：
+/// 单视图下回到列表第一个站点，分屏只剩一个时退回单视图
+async fn remove_tab_from_layout(
+    app: tauri::AppHandle,
+    tab_id: &str,
+    closed_site_id: &str,
+) -> Result<(), String> {
+    // 注意：不要在 await 时持有 MutexGuard（否则 future 非 Send）
+    #[derive(Debug)]
+    enum CloseFallback {
+        None,
+        SwitchToTab(String),
+        SwitchToFirstSite(String),
+        ClearToEmpty,
     }
 
-    // 添加不在 order 中的站点
-    for site in &config.sites {
-        if seen.insert(site.id.clone()) {
-            sites.push(site.clone());
+    // 先于 APP_STATE 取得（需要读取配置）
+    let first_site_id = first_site_id_excluding(closed_site_id);
+    let fallback = {
+        let mut state = APP_STATE.lock().unwrap();
+        let AppState {
+            active_tab, layout, ..
+        } = &mut *state;
+        match layout.mode {
+            LayoutMode::Single => {
+                if active_tab == tab_id {
+                    // 关闭当前显示的 Tab：回到“列表第一个站点”（排除被关闭站点）
+                    if let Some(site_id) = first_site_id {
+                        CloseFallback::SwitchToFirstSite(site_id)
+                    } else {
+                        CloseFallback::ClearToEmpty
+                    }
+                } else {
+                    CloseFallback::None
+                }
+            }
+            LayoutMode::Split => {
+                let removed = layout.remove_pane(tab_id);
+                if layout.pane_tab_ids.len() < 2 {
+                    // 只剩一个分屏时退回单视图
+                    let remaining = layout.left_tab_id();
+                    layout.set_single();
+                    if let Some(tab) = remaining {
+                        CloseFallback::SwitchToTab(tab)
+                    } else {
+                        CloseFallback::None
+                    }
+                } else {
+                    // 其余分屏保持不变，被关闭的若是活跃 Tab 则把焦点交给最左侧分屏
+                    if removed && active_tab == tab_id {
+                        if let Some(first) = layout.left_tab_id() {
+                            *active_tab = first;
+                        }
+                    }
+                    CloseFallback::None
+                }
+            }
+        }
+    };
+
+    match fallback {
+        CloseFallback::None => {}
+        CloseFallback::ClearToEmpty => {
+            APP_STATE.lock().unwrap().clear_active();
+            clear_last_active();
+        }
+        CloseFallback::SwitchToFirstSite(site_id) => {
+            APP_STATE.lock().unwrap().clear_active();
+            switch_view_inner(app.clone(), site_id).await?;
+            return Ok(());
+        }
+        CloseFallback::SwitchToTab(tab) => {
+            switch_tab_inner(app.clone(), tab).await?;
+            return Ok(());
         }
     }
 
-    Ok(sites)
+    resize_webviews_inner(&app, true)?;
+    persist_session();
+    Ok(())
 }
 
-/// 获取当前活跃的视图 ID
-#[tauri::command]
-fn get_current_view(webview: tauri::Webview) -> Result<String, String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
-    }
-    Ok(CURRENT_VIEW.lock().unwrap().clone())
+// ============================================================================
+// 独立窗口
+// ============================================================================
+
+/// 独立窗口的初始大小（逻辑像素）
+const DETACHED_WINDOW_SIZE: (f64, f64) = (1100.0, 800.0);
+
+fn detached_window_label(tab_id: &str) -> Option<String> {
+    read_tab_runtime(tab_id, |runtime| runtime.detached_window.clone()).flatten()
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct TabInfo {
-    tab_id: String,
-    site_id: String,
+fn is_tab_detached(tab_id: &str) -> bool {
+    detached_window_label(tab_id).is_some()
+}
+
+/// 已移到独立窗口的 Tab
+fn detached_tab_ids() -> Vec<String> {
+    tab_runtime_ids(|runtime| runtime.detached_window.is_some())
+}
+
+/// 已休眠的 Tab
+fn hibernated_tab_ids() -> Vec<String> {
+    tab_runtime_ids(|runtime| runtime.hibernated)
+}
+
+/// Tab 在独立窗口中时把该窗口提到前台
+fn focus_detached_window(app: &tauri::AppHandle, tab_id: &str) -> bool {
+    let label = detached_window_label(tab_id);
+    let Some(window) = label.and_then(|label| app.get_window(&label)) else {
+        return false;
+    };
+    let _ = window.unminimize();
+    let _ = window.set_focus();
+    true
 }
 
+// ============================================================================
+// 全局快捷键
+// ============================================================================
+
 #[derive(Debug, Clone, Serialize)]
-struct TabsStateResponse {
-    active_tab_id: String,
-    mode: String,
-    ratio: f64,
-    left_tab_id: Option<String>,
-    right_tab_id: Option<String>,
-    tabs: Vec<TabInfo>,
+struct ShortcutInfo {
+    action: String,
+    /// 未分配时为 None
+    accelerator: Option<String>,
+    /// 已在系统中注册成功（平台不支持或被其他程序占用时为 false）
+    registered: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct WebviewLoadFailedPayload {
-    tab_id: String,
-    site_id: String,
-    url: String,
-    message: String,
+struct ShortcutsResponse {
+    /// 当前平台能否注册全局快捷键
+    supported: bool,
+    shortcuts: Vec<ShortcutInfo>,
+}
+
+/// 快捷键触发（由全局快捷键插件在事件循环上调用，实际操作放到异步任务中执行）
+fn handle_shortcut_action(app: &tauri::AppHandle, action: &str) {
+    let app = app.clone();
+    let action = action.to_string();
+    tauri::async_runtime::spawn(async move {
+        let Ok(window) = get_main_window(&app) else {
+            return;
+        };
+        if action == shortcuts::TOGGLE_WINDOW_ACTION {
+            let in_front = window.is_visible().unwrap_or(false)
+                && window.is_focused().unwrap_or(false)
+                && !window.is_minimized().unwrap_or(false);
+            if in_front {
+                let _ = window.hide();
+            } else {
+                let _ = window.show();
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+            return;
+        }
+        let Some(index) = shortcuts::site_index(&action) else {
+            return;
+        };
+        let site_id = APP_CONFIG.lock().unwrap().site_order.get(index).cloned();
+        let Some(site_id) = site_id else {
+            return;
+        };
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        if let Err(e) = switch_view_inner(app.clone(), site_id).await {
+            println!("[shortcuts] 切换站点失败: {}", e);
+        }
+    });
+}
+
+/// 获取全部快捷键动作及其分配情况
+#[tauri::command]
+fn get_shortcuts(webview: tauri::Webview) -> Result<ShortcutsResponse, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let bindings = APP_CONFIG.lock().unwrap().shortcuts.clone();
+    let shortcuts = shortcuts::known_actions()
+        .into_iter()
+        .map(|action| ShortcutInfo {
+            accelerator: bindings.get(&action).cloned(),
+            registered: shortcuts::is_registered(&action),
+            action,
+        })
+        .collect();
+    Ok(ShortcutsResponse {
+        supported: shortcuts::is_supported(),
+        shortcuts,
+    })
+}
+
+/// 设置动作的快捷键（如 `Ctrl+Shift+Space`）；传 None 或空字符串取消分配
+///
+/// 快捷键与其他动作冲突、格式无效或被其他程序占用时返回错误，配置保持不变。
+#[tauri::command]
+fn set_shortcut(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    action: String,
+    accelerator: Option<String>,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_shortcut")?;
+    if !shortcuts::is_known_action(&action) {
+        return Err(format!("未知的快捷键动作: {}", action).into());
+    }
+    let accelerator = accelerator
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .map(|a| shortcuts::Accelerator::parse(&a))
+        .transpose()?;
+
+    let previous = APP_CONFIG.lock().unwrap().shortcuts.clone();
+    let mut bindings = previous.clone();
+    match accelerator {
+        Some(accelerator) => {
+            if let Some(other) = shortcuts::find_conflict(&bindings, &action, &accelerator) {
+                return Err(format!("快捷键 {} 已分配给 {}", accelerator, other).into());
+            }
+            bindings.insert(action, accelerator.to_string());
+        }
+        None => {
+            bindings.remove(&action);
+        }
+    }
+
+    // 注册时不持有配置锁：插件在主线程上执行注册
+    if let Err(e) = shortcuts::apply(&app, &bindings) {
+        let _ = shortcuts::apply(&app, &previous);
+        return Err(e.into());
+    }
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.shortcuts = bindings;
+    save_config(&config)?;
+    Ok(())
 }
 
-/// 获取当前 Tabs 状态（用于前端渲染 TabBar/分屏）
+/// 把 Tab 移到独立窗口：Webview 整体移过去（数据目录与页面状态不变），
+/// 主窗口按关闭 Tab 的规则修正布局；独立窗口关闭时 Tab 移回主窗口
 #[tauri::command]
-fn get_tabs_state(webview: tauri::Webview) -> Result<TabsStateResponse, String> {
+async fn detach_tab(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let layout = LAYOUT_STATE.lock().unwrap().clone();
-    let active_tab_id = ACTIVE_TAB_ID.lock().unwrap().clone();
-
-    let mut tab_ids: std::collections::HashSet<String> = CREATED_VIEWS.lock().unwrap().keys().cloned().collect();
-    tab_ids.extend(TAB_SITE_MAP.lock().unwrap().keys().cloned());
-    let current_site = CURRENT_VIEW.lock().unwrap().clone();
-    if !current_site.is_empty() {
-        tab_ids.insert(current_site);
+    if focus_detached_window(&app, &tab_id) {
+        return Ok(());
     }
-
-    let mut tabs: Vec<TabInfo> = Vec::new();
-    for tab_id in tab_ids {
-        if let Ok(site_id) = get_tab_site_id(&tab_id) {
-            tabs.push(TabInfo { tab_id, site_id });
-        }
+    let site_id = get_tab_site_id(&tab_id)?;
+    let site = get_site_by_id(&site_id)?;
+    // 休眠或尚未打开的 Tab 先在主窗口创建
+    ensure_tab_webview(&app, &tab_id, &site_id)?;
+    let child = tab_child_webview(&app, &tab_id)?;
+
+    let language = APP_CONFIG.lock().unwrap().language.clone();
+    let window_label = format!("detached_{}", tab_id);
+    let window = tauri::WindowBuilder::new(&app, &window_label)
+        .title(localized_site(&site, &language).name)
+        .inner_size(DETACHED_WINDOW_SIZE.0, DETACHED_WINDOW_SIZE.1)
+        .build()
+        .map_err(|e| format!("创建窗口失败: {}", e))?;
+    if let Err(e) = child.reparent(&window) {
+        let _ = window.destroy();
+        return Err(format!("移动 Webview 失败: {}", e).into());
     }
-    tabs.sort_by(|a, b| a.tab_id.cmp(&b.tab_id));
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let size = window.inner_size().unwrap_or_default().to_logical::<f64>(scale);
+    let _ = child.set_position(LogicalPosition::new(0.0, 0.0));
+    let _ = child.set_size(size);
+    let _ = child.set_auto_resize(true);
+    let _ = child.show();
+    update_tab_runtime(&tab_id, |runtime| runtime.detached_window = Some(window_label));
+
+    let app_for_window = app.clone();
+    let tab_for_window = tab_id.clone();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::CloseRequested { .. } = event {
+            reattach_tab(&app_for_window, &tab_for_window);
+        }
+    });
 
-    Ok(TabsStateResponse {
-        active_tab_id,
-        mode: match layout.mode {
-            LayoutMode::Single => "single".to_string(),
-            LayoutMode::Split => "split".to_string(),
+    remove_tab_from_layout(app.clone(), &tab_id, &site_id).await?;
+    let _ = app.emit(
+        "tabs-state-changed",
+        TabsChangedPayload {
+            removed_tab_ids: Vec::new(),
         },
-        ratio: layout.ratio,
-        left_tab_id: layout.left_tab_id,
-        right_tab_id: layout.right_tab_id,
-        tabs,
-    })
+    );
+    Ok(())
 }
 
-/// 创建一个新 Tab（默认共享站点登录：同站点共用 data directory）
-#[tauri::command]
-fn create_tab(webview: tauri::Webview, site_id: String) -> Result<String, String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+/// 独立窗口关闭：Webview 移回主窗口并隐藏（不打乱当前布局）；移回失败时按休眠处理，
+/// Tab 本身保留
+fn reattach_tab(app: &tauri::AppHandle, tab_id: &str) {
+    let was_detached = TAB_RUNTIME
+        .lock()
+        .unwrap()
+        .get_mut(tab_id)
+        .and_then(|runtime| runtime.detached_window.take())
+        .is_some();
+    if !was_detached {
+        return;
     }
-    let _ = get_site_by_id(&site_id)?;
-    println!("[create_tab] site_id={}", site_id);
-    let tab_id = format!(
-        "{}_{}",
-        site_id,
-        Uuid::new_v4().to_string().split('-').next().unwrap()
+    let reattached = match (get_main_window(app), app.get_webview(&format!("ai_{}", tab_id))) {
+        (Ok(main_window), Some(child)) => {
+            let _ = child.set_auto_resize(false);
+            child.reparent(&main_window).is_ok()
+        }
+        _ => false,
+    };
+    if reattached {
+        touch_tab(tab_id);
+        let _ = resize_webviews_inner(app, true);
+    } else {
+        hibernate_tab_webview(app, tab_id);
+    }
+    let _ = app.emit(
+        "tabs-state-changed",
+        TabsChangedPayload {
+            removed_tab_ids: Vec::new(),
+        },
     );
-    TAB_SITE_MAP.lock().unwrap().insert(tab_id.clone(), site_id);
-    Ok(tab_id)
 }
 
-/// 切换到指定 Tab（进入单视图模式）
-async fn switch_tab_inner(app: tauri::AppHandle, tab_id: String) -> Result<(), String> {
-    let site_id = get_tab_site_id(&tab_id)?;
-    println!("[switch_tab] tab_id={} site_id={}", tab_id, site_id);
+/// 切换视图（核心功能）
+async fn switch_view_inner(app: tauri::AppHandle, site_id: String) -> Result<(), String> {
+    // 站点切换默认使用主 Tab（tab_id == site_id）并进入单视图模式
+    let _ = get_enabled_site_by_id(&site_id)?;
 
     {
-        let mut layout = LAYOUT_STATE.lock().unwrap();
-        layout.mode = LayoutMode::Single;
-        layout.left_tab_id = None;
-        layout.right_tab_id = None;
+        let mut state = APP_STATE.lock().unwrap();
+        state.layout.set_single();
+        state.active_tab = site_id.clone();
     }
-
-    *ACTIVE_TAB_ID.lock().unwrap() = tab_id.clone();
-    ensure_tab_webview(&app, &tab_id, &site_id)?;
+    ensure_tab_webview(&app, &site_id, &site_id)?;
     resize_webviews_inner(&app, true)?;
 
-    *CURRENT_VIEW.lock().unwrap() = site_id.clone();
+    APP_STATE.lock().unwrap().current_view = site_id.clone();
     upsert_recent_site(&site_id);
-    update_last_active(&tab_id, &site_id);
-    touch_tab(&tab_id);
+    record_site_activation(&site_id);
+    update_last_active(&site_id, &site_id);
+    persist_session();
+    touch_tab(&site_id);
     gc_idle_webviews(&app);
     Ok(())
 }
 
 #[tauri::command]
-async fn switch_tab(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), String> {
+async fn switch_view(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    switch_tab_inner(app, tab_id).await
+    switch_view_inner(app, site_id).await.map_err(AppError::from)
 }
 
-/// 设置布局（single/split）
+/// 刷新站点的所有 Tab；`force` 为 true 时绕过缓存（见 hard_reload_tab）
 #[tauri::command]
-async fn set_layout(
+async fn refresh_view(
     webview: tauri::Webview,
     app: tauri::AppHandle,
-    mode: String,
-    ratio: Option<f64>,
-    left_tab_id: Option<String>,
-    right_tab_id: Option<String>,
-) -> Result<(), String> {
+    site_id: String,
+    check_unsaved: Option<bool>,
+    force: Option<bool>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    if mode == "single" {
-        println!("[set_layout] mode=single");
-        {
-            let mut layout = LAYOUT_STATE.lock().unwrap();
-            layout.mode = LayoutMode::Single;
-            layout.left_tab_id = None;
-            layout.right_tab_id = None;
-        }
+    let views: Vec<String> = APP_STATE.lock().unwrap().views.iter().cloned().collect();
+    let views: Vec<String> = views
+        .into_iter()
+        .filter(|tab_id| get_tab_site_id(tab_id).ok().as_deref() == Some(site_id.as_str()))
+        .collect();
+    ensure_no_unsent_input(&app, &views, check_unsaved).await?;
+    let force = force.unwrap_or(false);
+    let mut recreated = false;
+    for tab_id in views {
+        let webview_label = format!("ai_{}", tab_id);
+        let Some(webview) = app.get_webview(&webview_label) else {
+            continue;
+        };
+        recreated |= reload_tab(&app, webview, &tab_id, &site_id, force).await?;
+    }
+    if recreated {
         resize_webviews_inner(&app, true)?;
-        gc_idle_webviews(&app);
-        return Ok(());
     }
 
-    if mode != "split" {
-        return Err("mode 仅支持 single|split".to_string());
+    Ok(())
+}
+
+/// 只刷新一个 Tab（分屏中的其他同站点 Tab 不受影响）；Webview 尚未创建或已休眠时报错
+#[tauri::command]
+async fn refresh_tab(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    tab_id: String,
+    check_unsaved: Option<bool>,
+    force: Option<bool>,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let site_id = get_tab_site_id(&tab_id)?;
+    let child = app
+        .get_webview(&format!("ai_{}", tab_id))
+        .ok_or_else(|| AppError::WebviewNotFound(tab_id.clone()))?;
+    ensure_no_unsent_input(&app, std::slice::from_ref(&tab_id), check_unsaved).await?;
+    if reload_tab(&app, child, &tab_id, &site_id, force.unwrap_or(false)).await? {
+        resize_webviews_inner(&app, true)?;
     }
+    Ok(())
+}
 
-    let left = left_tab_id.and_then(|id| {
-        let trimmed = id.trim().to_string();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed)
-        }
-    });
-    let right = right_tab_id.and_then(|id| {
-        let trimmed = id.trim().to_string();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed)
+/// 重新加载一个 Tab 并通知前端显示加载状态；返回是否重建了布局中可见的 Webview（调用方需重新布局）
+async fn reload_tab(
+    app: &tauri::AppHandle,
+    child: tauri::Webview,
+    tab_id: &str,
+    site_id: &str,
+    force: bool,
+) -> Result<bool, String> {
+    if force && !is_tab_detached(tab_id) {
+        // 重建时由 ensure_tab_webview 发送 webview-loading
+        let visible = layout_visible_tab_ids().iter().any(|id| id == tab_id);
+        hard_reload_tab(app, child, tab_id, site_id, visible).await?;
+        return Ok(visible);
+    }
+    let _ = app.emit("webview-loading", site_id);
+    child
+        .eval("window.location.reload()")
+        .map_err(|e| format!("刷新失败: {}", e))?;
+    Ok(false)
+}
+
+/// 绕过缓存重新加载（`location.reload(true)` 在现代内核中无效）：先在页面内清空 Cache Storage，
+/// 关闭 Webview 后删除 profile 中的 HTTP 缓存，再用同一数据目录重建，Cookie 与登录状态保留。
+/// 不在当前布局中的 Tab 只关闭（与休眠相同），切回时重新加载
+async fn hard_reload_tab(
+    app: &tauri::AppHandle,
+    child: tauri::Webview,
+    tab_id: &str,
+    site_id: &str,
+    visible: bool,
+) -> Result<(), String> {
+    if let Some(script) = site_data_page_script("cache") {
+        let _ = eval_page_text(&child, script, SITE_DATA_SCRIPT_TIMEOUT).await;
+    }
+    if visible {
+        destroy_tab_webview(app, tab_id);
+    } else {
+        hibernate_tab_webview(app, tab_id);
+    }
+    // 内核进程可能尚未释放缓存文件，删除失败时仍照常重建
+    if let Some(paths) = site_data_profile_paths(&get_tab_data_dir(site_id, tab_id), "cache") {
+        if let Err(e) = remove_profile_paths(&paths) {
+            println!("[refresh] tab_id={} 删除 HTTP 缓存失败: {}", tab_id, e);
         }
-    });
+    }
+    if visible {
+        ensure_tab_webview(app, tab_id, site_id)?;
+    }
+    Ok(())
+}
 
-    if left.is_none() && right.is_none() {
-        return Err("至少需要选择一个 Tab".to_string());
+/// Tab 回到站点首页（站点配置的 URL，保留 query 与 fragment）
+#[tauri::command]
+fn tab_go_home(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
     }
+    let site_id = get_tab_site_id(&tab_id)?;
+    let site = get_site_by_id(&site_id)?;
+    let url = site_url::parse_site_url(&site.url)?;
+    let child = app
+        .get_webview(&format!("ai_{}", tab_id))
+        .ok_or_else(|| AppError::WebviewNotFound(tab_id.clone()))?;
+    mark_last_requested_url(&tab_id, &url);
+    child.navigate(url).map_err(|e| format!("导航失败: {}", e))?;
+    Ok(())
+}
 
-    if let (Some(ref l), Some(ref r)) = (&left, &right) {
-        println!("[set_layout] mode=split left={} right={}", l, r);
-        if l == r {
-            return Err("左右 Tab 不能相同".to_string());
-        }
-    } else {
-        println!(
-            "[set_layout] mode=split left={} right={}",
-            left.clone().unwrap_or_else(|| "-".to_string()),
-            right.clone().unwrap_or_else(|| "-".to_string())
-        );
+// ============================================================================
+// 破坏性操作预览（只读）
+// ============================================================================
+
+/// 最多保留的预览快照数量
+const MAX_PREVIEW_SNAPSHOTS: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+struct ProjectSourceRef {
+    project_id: String,
+    title: String,
+}
+
+/// 清除缓存/删除站点将影响的内容
+#[derive(Debug, Clone, Serialize)]
+struct SiteDestructivePreview {
+    snapshot_id: String,
+    /// clear_view_cache / remove_site
+    action: String,
+    site_id: String,
+    /// 将被关闭的已打开 Tab
+    open_tab_ids: Vec<String>,
+    /// 站点主 profile 目录占用的字节数
+    profile_bytes: u64,
+    /// 额外 Tab 的独立 profile 目录占用的字节数（两种操作都不会删除）
+    tab_profile_bytes: u64,
+    /// 是否删除主 profile 目录（删除站点只移除配置，不删除磁盘数据）
+    deletes_profile: bool,
+    /// 以该站点页面作为总结来源的项目
+    referencing_projects: Vec<ProjectSourceRef>,
+}
+
+impl SiteDestructivePreview {
+    /// 与执行结果相关的部分是否一致（profile 大小随浏览持续变化，不参与比较）
+    fn same_effect(&self, other: &SiteDestructivePreview) -> bool {
+        let projects = |p: &SiteDestructivePreview| -> Vec<String> {
+            p.referencing_projects.iter().map(|r| r.project_id.clone()).collect()
+        };
+        self.open_tab_ids == other.open_tab_ids && projects(self) == projects(other)
     }
+}
+
+static PREVIEW_SNAPSHOTS: Lazy<Mutex<VecDeque<SiteDestructivePreview>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
 
-    // 不要在创建/添加 Webview 时持有 LAYOUT_STATE 锁，避免与 WindowEvent::Resized 产生死锁
-    let desired_ratio = {
-        let layout = LAYOUT_STATE.lock().unwrap();
-        ratio.unwrap_or(layout.ratio).clamp(0.2, 0.8)
+/// 目录占用的字节数（不跟随符号链接，读取失败的条目忽略）
+fn dir_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
     };
+    entries
+        .flatten()
+        .map(|entry| match entry.path().symlink_metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) if meta.is_file() => meta.len(),
+            _ => 0,
+        })
+        .sum()
+}
 
-    if let Some(ref left_id) = left {
-        let left_site = get_tab_site_id(left_id)?;
-        ensure_tab_webview(&app, left_id, &left_site)?;
-        touch_tab(left_id);
-    }
+// ============================================================================
+// 存储占用
+// ============================================================================
 
-    if let Some(ref right_id) = right {
-        let right_site = get_tab_site_id(right_id)?;
-        ensure_tab_webview(&app, right_id, &right_site)?;
-        touch_tab(right_id);
-    }
+/// 已删除站点遗留目录的汇总项 id
+const ORPHANED_STORAGE_ID: &str = "orphaned";
 
-    {
-        let mut layout = LAYOUT_STATE.lock().unwrap();
-        layout.mode = LayoutMode::Split;
-        layout.ratio = desired_ratio;
-        layout.left_tab_id = left.clone();
-        layout.right_tab_id = right.clone();
-    }
+#[derive(Debug, Clone, Serialize)]
+struct SiteStorageUsage {
+    site_id: String,
+    bytes: u64,
+    /// 额外 Tab 的独立 profile 数（不含主 Tab）
+    tab_profile_count: usize,
+}
 
-    resize_webviews_inner(&app, true)?;
+#[derive(Debug, Clone, Serialize)]
+struct StorageUsageReport {
+    /// 按占用从大到小；已删除站点遗留的目录汇总为 `orphaned`
+    sites: Vec<SiteStorageUsage>,
+    total_bytes: u64,
+}
+
+/// 子目录名（遍历根目录下的站点或 Tab 目录）
+fn child_dir_names(path: &std::path::Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .collect()
+}
+
+fn compute_storage_usage(site_ids: &[String]) -> StorageUsageReport {
+    let mut usage: HashMap<String, SiteStorageUsage> = site_ids
+        .iter()
+        .map(|id| {
+            (
+                id.clone(),
+                SiteStorageUsage {
+                    site_id: id.clone(),
+                    bytes: 0,
+                    tab_profile_count: 0,
+                },
+            )
+        })
+        .collect();
+    let mut orphaned = SiteStorageUsage {
+        site_id: ORPHANED_STORAGE_ID.to_string(),
+        bytes: 0,
+        tab_profile_count: 0,
+    };
 
-    // 兼容：CURRENT_VIEW 仍返回“当前主站点”，优先 active tab 的站点
-    if let Ok(active_site) = get_tab_site_id(&ACTIVE_TAB_ID.lock().unwrap().clone()) {
-        *CURRENT_VIEW.lock().unwrap() = active_site;
-    } else if let Some(left_id) = left {
-        if let Ok(site) = get_tab_site_id(&left_id) {
-            *CURRENT_VIEW.lock().unwrap() = site;
+    let data_root = get_data_root();
+    for name in child_dir_names(&data_root) {
+        let bytes = dir_size(&data_root.join(&name));
+        match usage.get_mut(&name) {
+            Some(site) => site.bytes += bytes,
+            None => orphaned.bytes += bytes,
         }
-    } else if let Some(right_id) = right {
-        if let Ok(site) = get_tab_site_id(&right_id) {
-            *CURRENT_VIEW.lock().unwrap() = site;
+    }
+    let tab_root = get_tab_data_root();
+    for name in child_dir_names(&tab_root) {
+        let site_dir = tab_root.join(&name);
+        let bytes = dir_size(&site_dir);
+        let tab_profiles = child_dir_names(&site_dir).len();
+        match usage.get_mut(&name) {
+            Some(site) => {
+                site.bytes += bytes;
+                site.tab_profile_count += tab_profiles;
+            }
+            None => {
+                orphaned.bytes += bytes;
+                orphaned.tab_profile_count += tab_profiles;
+            }
         }
     }
 
-    gc_idle_webviews(&app);
-
-    Ok(())
+    let mut sites: Vec<SiteStorageUsage> = usage.into_values().collect();
+    if orphaned.bytes > 0 || orphaned.tab_profile_count > 0 {
+        sites.push(orphaned);
+    }
+    sites.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.site_id.cmp(&b.site_id)));
+    StorageUsageReport {
+        total_bytes: sites.iter().map(|s| s.bytes).sum(),
+        sites,
+    }
 }
 
-/// 关闭一个 Tab
-#[tauri::command]
-async fn close_tab(webview: tauri::Webview, app: tauri::AppHandle, tab_id: String) -> Result<(), String> {
-    if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
-    }
-    let closed_site_id = get_tab_site_id(&tab_id).unwrap_or_else(|_| tab_id.clone());
-    close_tab_webview(&app, &tab_id);
+#[derive(Debug, Clone, Serialize)]
+struct RemovedProfileDir {
+    site_id: String,
+    path: String,
+    bytes: u64,
+}
 
-    // 注意：不要在 await 时持有 MutexGuard（否则 future 非 Send）
-    #[derive(Debug)]
-    enum CloseFallback {
-        None,
-        SwitchToTab(String),
-        SwitchToFirstSite(String),
-        ClearToEmpty,
-    }
+#[derive(Debug, Clone, Serialize)]
+struct OrphanCleanupReport {
+    removed: Vec<RemovedProfileDir>,
+    freed_bytes: u64,
+    /// 删除失败的目录（通常仍被 Webview 进程占用）
+    failed: Vec<String>,
+}
 
-    let fallback = {
-        let mut layout = LAYOUT_STATE.lock().unwrap();
-        match layout.mode {
-            LayoutMode::Single => {
-                let active_tab_id = ACTIVE_TAB_ID.lock().unwrap().clone();
-                if active_tab_id == tab_id {
-                    // 关闭当前显示的 Tab：回到“列表第一个站点”（排除被关闭站点）
-                    if let Some(site_id) = first_site_id_excluding(&closed_site_id) {
-                        CloseFallback::SwitchToFirstSite(site_id)
-                    } else {
-                        CloseFallback::ClearToEmpty
-                    }
-                } else {
-                    CloseFallback::None
-                }
+/// 删除 `webviews/<id>` 与 `webviews_tabs/<id>` 中属于 `site_ids` 的目录；
+/// 调用方负责保证这些站点已不在配置中
+fn remove_orphaned_profiles(site_ids: &[String]) -> OrphanCleanupReport {
+    let mut report = OrphanCleanupReport {
+        removed: Vec::new(),
+        freed_bytes: 0,
+        failed: Vec::new(),
+    };
+    for root in [get_data_root(), get_tab_data_root()] {
+        for site_id in site_ids {
+            let path = root.join(site_id);
+            if !path.is_dir() {
+                continue;
             }
-            LayoutMode::Split => {
-                let left = layout.left_tab_id.clone();
-                let right = layout.right_tab_id.clone();
-                if left.as_deref() == Some(tab_id.as_str()) {
-                    layout.left_tab_id = None;
-                }
-                if right.as_deref() == Some(tab_id.as_str()) {
-                    layout.right_tab_id = None;
-                }
-
-                if layout.left_tab_id.is_none() || layout.right_tab_id.is_none() {
-                    let remaining = layout.left_tab_id.clone().or(layout.right_tab_id.clone());
-                    layout.mode = LayoutMode::Single;
-                    layout.left_tab_id = None;
-                    layout.right_tab_id = None;
-                    if let Some(tab) = remaining {
-                        CloseFallback::SwitchToTab(tab)
-                    } else {
-                        CloseFallback::None
-                    }
-                } else {
-                    CloseFallback::None
+            let bytes = dir_size(&path);
+            match fs::remove_dir_all(&path) {
+                Ok(()) => {
+                    report.freed_bytes += bytes;
+                    report.removed.push(RemovedProfileDir {
+                        site_id: site_id.clone(),
+                        path: path.display().to_string(),
+                        bytes,
+                    });
                 }
+                Err(e) => report.failed.push(format!("{}: {}", path.display(), e)),
             }
         }
-    };
+    }
+    report
+}
 
-    match fallback {
-        CloseFallback::None => {}
-        CloseFallback::ClearToEmpty => {
-            *ACTIVE_TAB_ID.lock().unwrap() = String::new();
-            *CURRENT_VIEW.lock().unwrap() = String::new();
-            clear_last_active();
-        }
-        CloseFallback::SwitchToFirstSite(site_id) => {
-            *ACTIVE_TAB_ID.lock().unwrap() = String::new();
-            *CURRENT_VIEW.lock().unwrap() = String::new();
-            switch_view_inner(app.clone(), site_id).await?;
-            return Ok(());
-        }
-        CloseFallback::SwitchToTab(tab) => {
-            switch_tab_inner(app.clone(), tab).await?;
-            return Ok(());
-        }
+/// 清理已删除站点遗留的 profile 目录（现有站点及其额外 Tab 的目录不会被删除）
+#[tauri::command]
+async fn cleanup_orphaned_profiles(webview: tauri::Webview) -> Result<OrphanCleanupReport, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("cleanup_orphaned_profiles")?;
+    // 只读实例的配置可能落后于主实例，不能据此判断目录是否遗留
+    ensure_storage_writable()?;
+    let existing: HashSet<String> = APP_CONFIG
+        .lock()
+        .unwrap()
+        .sites
+        .iter()
+        .map(|s| s.id.clone())
+        .collect();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut orphaned: Vec<String> = child_dir_names(&get_data_root())
+            .into_iter()
+            .chain(child_dir_names(&get_tab_data_root()))
+            .filter(|name| !existing.contains(name))
+            .collect();
+        orphaned.sort();
+        orphaned.dedup();
+        remove_orphaned_profiles(&orphaned)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))
+}
 
-    resize_webviews_inner(&app, true)?;
-    Ok(())
+/// 统计各站点 profile 的磁盘占用（在阻塞线程中遍历目录）
+#[tauri::command]
+async fn get_storage_usage(webview: tauri::Webview) -> Result<StorageUsageReport, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let site_ids: Vec<String> = APP_CONFIG
+        .lock()
+        .unwrap()
+        .sites
+        .iter()
+        .map(|s| s.id.clone())
+        .collect();
+    tauri::async_runtime::spawn_blocking(move || compute_storage_usage(&site_ids))
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
 }
 
-/// 切换视图（核心功能）
-async fn switch_view_inner(app: tauri::AppHandle, site_id: String) -> Result<(), String> {
-    // 站点切换默认使用主 Tab（tab_id == site_id）并进入单视图模式
-    let _ = get_site_by_id(&site_id)?;
+fn build_site_destructive_preview(action: &str, site_id: &str) -> Result<SiteDestructivePreview, String> {
+    let site = get_site_by_id(site_id)?;
+    if action == "remove_site" && site.builtin {
+        return Err("无法删除内置站点".to_string());
+    }
+    let known = APP_STATE.lock().unwrap().known_tab_ids();
+    let mut open_tab_ids: Vec<String> = tab_ids_for_site(site_id)
+        .into_iter()
+        .filter(|id| known.contains(id))
+        .collect();
+    open_tab_ids.sort();
+
+    let proj_dirs = directories::ProjectDirs::from("com", "aihub", "AIHub")
+        .expect("Could not get project directories");
+    let profile_bytes = dir_size(&get_data_dir(site_id));
+    let tab_profile_bytes = dir_size(&proj_dirs.data_dir().join("webviews_tabs").join(site_id));
+
+    let referencing_projects = project_store::load_all()
+        .into_iter()
+        .filter(|p| p.sources.iter().any(|s| s.site_id == site_id))
+        .map(|p| ProjectSourceRef {
+            project_id: p.id,
+            title: p.title,
+        })
+        .collect();
 
-    {
-        let mut layout = LAYOUT_STATE.lock().unwrap();
-        layout.mode = LayoutMode::Single;
-        layout.left_tab_id = None;
-        layout.right_tab_id = None;
+    let preview = SiteDestructivePreview {
+        snapshot_id: Uuid::new_v4().simple().to_string(),
+        action: action.to_string(),
+        site_id: site_id.to_string(),
+        open_tab_ids,
+        profile_bytes,
+        tab_profile_bytes,
+        deletes_profile: action == "clear_view_cache",
+        referencing_projects,
+    };
+    let mut snapshots = PREVIEW_SNAPSHOTS.lock().unwrap();
+    snapshots.push_back(preview.clone());
+    while snapshots.len() > MAX_PREVIEW_SNAPSHOTS {
+        snapshots.pop_front();
     }
+    Ok(preview)
+}
 
-    *ACTIVE_TAB_ID.lock().unwrap() = site_id.clone();
-    ensure_tab_webview(&app, &site_id, &site_id)?;
-    resize_webviews_inner(&app, true)?;
-
-    *CURRENT_VIEW.lock().unwrap() = site_id.clone();
-    upsert_recent_site(&site_id);
-    update_last_active(&site_id, &site_id);
-    touch_tab(&site_id);
-    gc_idle_webviews(&app);
-    Ok(())
+/// 执行前校验预览快照：状态与预览时不同则返回包含新旧预览的错误，由前端提示后重新确认
+fn ensure_preview_current(
+    action: &str,
+    site_id: &str,
+    snapshot_id: Option<String>,
+) -> Result<(), AppError> {
+    let Some(snapshot_id) = snapshot_id.filter(|id| !id.trim().is_empty()) else {
+        return Ok(());
+    };
+    let previous = {
+        let mut snapshots = PREVIEW_SNAPSHOTS.lock().unwrap();
+        let index = snapshots
+            .iter()
+            .position(|p| p.snapshot_id == snapshot_id && p.action == action && p.site_id == site_id)
+            .ok_or_else(|| "预览已过期，请重新预览".to_string())?;
+        snapshots.remove(index).unwrap()
+    };
+    let current = build_site_destructive_preview(action, site_id)?;
+    if previous.same_effect(&current) {
+        return Ok(());
+    }
+    Err(AppError::PreviewChanged {
+        previous: serde_json::to_value(&previous).unwrap_or_default(),
+        current: serde_json::to_value(&current).unwrap_or_default(),
+    })
 }
 
+/// 预览清除站点缓存的影响（不做任何修改）
 #[tauri::command]
-async fn switch_view(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), String> {
+fn preview_clear_view_cache(webview: tauri::Webview, site_id: String) -> Result<SiteDestructivePreview, AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    switch_view_inner(app, site_id).await
+    build_site_destructive_preview("clear_view_cache", &site_id).map_err(AppError::from)
 }
 
-/// 刷新当前视图
+/// 预览删除站点的影响（不做任何修改）
 #[tauri::command]
-fn refresh_view(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), String> {
+fn preview_remove_site(webview: tauri::Webview, site_id: String) -> Result<SiteDestructivePreview, AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
-    }
-    let views: Vec<String> = CREATED_VIEWS.lock().unwrap().keys().cloned().collect();
-    for tab_id in views {
-        if get_tab_site_id(&tab_id).ok().as_deref() != Some(site_id.as_str()) {
-            continue;
-        }
-        let webview_label = format!("ai_{}", tab_id);
-        if let Some(webview) = app.get_webview(&webview_label) {
-            webview
-                .eval("window.location.reload()")
-                .map_err(|e| format!("刷新失败: {}", e))?;
-        }
+        return Err(AppError::NotAllowed);
     }
-
-    Ok(())
+    build_site_destructive_preview("remove_site", &site_id).map_err(AppError::from)
 }
 
-/// 清除站点缓存
+/// 清除站点缓存；传入预览的 `snapshot_id` 时，若预览后状态有变化则不执行
 #[tauri::command]
-fn clear_view_cache(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), String> {
+async fn clear_view_cache(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    site_id: String,
+    check_unsaved: Option<bool>,
+    snapshot_id: Option<String>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    permissions::authorize(
+        permissions::InvokeSurface::MainWebview,
+        "clear_view_cache",
+        &site_id,
+        None,
+    )?;
+    ensure_preview_current("clear_view_cache", &site_id, snapshot_id)?;
+    clear_view_cache_inner(&app, &site_id, check_unsaved).await
+}
+
+/// 清除站点缓存：关闭所有 Tab 后按全部类型清除各 Tab 的 profile 数据，再删除站点数据目录
+async fn clear_view_cache_inner(
+    app: &tauri::AppHandle,
+    site_id: &str,
+    check_unsaved: Option<bool>,
+) -> Result<(), AppError> {
+    ensure_unlocked("clear_view_cache")?;
+    let tab_ids = tab_ids_for_site(site_id);
+    ensure_no_unsent_input(app, &tab_ids, check_unsaved).await?;
     // 关闭该站点下所有 Tab Webview（含主 Tab）
-    for tab_id in tab_ids_for_site(&site_id) {
-        close_tab_webview(&app, &tab_id);
+    for tab_id in tab_ids {
+        close_tab_webview(app, &tab_id);
     }
 
+    let all_kinds: Vec<String> = SITE_DATA_KINDS.iter().map(|k| k.to_string()).collect();
+    clear_site_data_inner(app, site_id, &all_kinds).await?;
+
     // 删除数据目录
-    let data_dir = get_data_dir(&site_id);
+    let data_dir = get_data_dir(site_id);
     if data_dir.exists() {
         let _ = std::fs::remove_dir_all(&data_dir);
     }
 
     // 如果是当前视图，清除状态
-    let current = CURRENT_VIEW.lock().unwrap().clone();
-    if current == site_id {
-        *CURRENT_VIEW.lock().unwrap() = String::new();
-        *ACTIVE_TAB_ID.lock().unwrap() = String::new();
+    let was_current = {
+        let mut state = APP_STATE.lock().unwrap();
+        let was_current = state.current_view == site_id;
+        if was_current {
+            state.clear_active();
+        }
+        was_current
+    };
+    if was_current {
         clear_last_active();
     }
 
     Ok(())
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct ClearCookiesTabResult {
+    tab_id: String,
+    ok: bool,
+    /// 清除的 Cookie 数（删除 Cookie 文件时无法计数，为 None）
+    cleared: Option<usize>,
+    /// webview：通过已打开的 Webview 删除；profile_file：删除未打开 Tab 的 Cookie 文件
+    method: &'static str,
+    error: Option<String>,
+}
+
+/// Webview profile 目录中的 Cookie 存储文件（各平台内核的固定位置）
+fn cookie_store_files(data_dir: &std::path::Path) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        let network = data_dir.join("EBWebView").join("Default").join("Network");
+        vec![network.join("Cookies"), network.join("Cookies-journal")]
+    } else if cfg!(target_os = "linux") {
+        vec![data_dir.join("cookies")]
+    } else {
+        // macOS 的 WKWebView 不使用自定义数据目录，Cookie 只能通过 Webview 删除
+        Vec::new()
+    }
+}
+
+/// 删除一个 Webview 中的全部 Cookie（每个 Tab 的 profile 独立，不会影响其他站点）
+async fn clear_webview_cookies(child: tauri::Webview) -> Result<usize, String> {
+    // Windows 上读取 Cookie 不能在主线程/同步命令中进行（WebView2 会死锁）
+    tauri::async_runtime::spawn_blocking(move || {
+        let cookies = child.cookies().map_err(|e| format!("读取 Cookie 失败: {}", e))?;
+        let count = cookies.len();
+        for cookie in cookies {
+            child
+                .delete_cookie(cookie)
+                .map_err(|e| format!("删除 Cookie 失败: {}", e))?;
+        }
+        Ok(count)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 只清除站点（含所有 Tab）的 Cookie，保留 localStorage、缓存等其余数据
+#[tauri::command]
+async fn clear_site_cookies(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    site_id: String,
+) -> Result<Vec<ClearCookiesTabResult>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("clear_site_cookies")?;
+    get_site_by_id(&site_id)?;
+
+    let mut results = Vec::new();
+    for tab_id in tab_ids_for_site(&site_id) {
+        if let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) {
+            let result = clear_webview_cookies(child).await;
+            results.push(ClearCookiesTabResult {
+                ok: result.is_ok(),
+                cleared: result.as_ref().ok().copied(),
+                method: "webview",
+                error: result.err(),
+                tab_id,
+            });
+            continue;
+        }
+
+        let files = cookie_store_files(&get_tab_data_dir(&site_id, &tab_id));
+        let error = if files.is_empty() {
+            Some("当前平台不支持清除未打开 Tab 的 Cookie，请先打开该 Tab".to_string())
+        } else {
+            files
+                .iter()
+                .filter(|path| path.exists())
+                .find_map(|path| std::fs::remove_file(path).err())
+                .map(|e| format!("删除 Cookie 文件失败: {}", e))
+        };
+        results.push(ClearCookiesTabResult {
+            tab_id,
+            ok: error.is_none(),
+            cleared: None,
+            method: "profile_file",
+            error,
+        });
+    }
+    Ok(results)
+}
+
+// ============================================================================
+// 按类型清除站点数据
+// ============================================================================
+
+/// 可单独清除的站点数据类型
+const SITE_DATA_KINDS: &[&str] = &["cookies", "cache", "service_workers", "local_storage", "indexeddb"];
+
+/// 页面内清除数据的脚本超时
+const SITE_DATA_SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+struct SiteDataKindResult {
+    tab_id: String,
+    kind: String,
+    /// ok / not_supported / failed
+    status: &'static str,
+    /// webview：通过已打开的 Webview 清除；profile_files：删除未打开 Tab 的 profile 文件
+    method: &'static str,
+    detail: Option<String>,
+}
+
+/// 各类数据在 profile 目录中的位置（None = 当前平台无法通过文件清除）
+/// - Windows（WebView2）：`EBWebView/Default` 下的对应目录
+/// - Linux（WebKitGTK）：base data directory 下的对应目录；HTTP 缓存位于系统共享缓存目录，不支持
+/// - macOS（WKWebView）不使用自定义数据目录，均不支持
+fn site_data_profile_paths(data_dir: &std::path::Path, kind: &str) -> Option<Vec<PathBuf>> {
+    if kind == "cookies" {
+        let files = cookie_store_files(data_dir);
+        return (!files.is_empty()).then_some(files);
+    }
+    if cfg!(target_os = "windows") {
+        let profile = data_dir.join("EBWebView").join("Default");
+        let dirs: &[&str] = match kind {
+            "cache" => &["Cache", "Code Cache", "GPUCache", "Service Worker/CacheStorage"],
+            "service_workers" => &["Service Worker"],
+            "local_storage" => &["Local Storage", "Session Storage"],
+            "indexeddb" => &["IndexedDB"],
+            _ => return None,
+        };
+        Some(dirs.iter().map(|d| profile.join(d)).collect())
+    } else if cfg!(target_os = "linux") {
+        let dirs: &[&str] = match kind {
+            "service_workers" => &["serviceworkers"],
+            "local_storage" => &["localstorage"],
+            "indexeddb" => &["databases/indexeddb"],
+            _ => return None,
+        };
+        Some(dirs.iter().map(|d| data_dir.join(d)).collect())
+    } else {
+        None
+    }
+}
+
+/// 在已打开的页面中清除对应数据的脚本（仅作用于页面当前 origin）
+fn site_data_page_script(kind: &str) -> Option<&'static str> {
+    match kind {
+        "cache" => Some(
+            "(async () => { if (!window.caches) return 'not_supported'; const keys = await caches.keys(); await Promise.all(keys.map((k) => caches.delete(k))); return 'ok'; })()",
+        ),
+        "service_workers" => Some(
+            "(async () => { if (!navigator.serviceWorker) return 'not_supported'; const regs = await navigator.serviceWorker.getRegistrations(); await Promise.all(regs.map((r) => r.unregister())); return 'ok'; })()",
+        ),
+        "local_storage" => Some("(() => { localStorage.clear(); sessionStorage.clear(); return 'ok'; })()"),
+        "indexeddb" => Some(
+            "(async () => { if (!indexedDB.databases) return 'not_supported'; const dbs = await indexedDB.databases(); await Promise.all(dbs.map((d) => new Promise((resolve) => { const req = indexedDB.deleteDatabase(d.name); req.onsuccess = req.onerror = req.onblocked = () => resolve(); }))); return 'ok'; })()",
+        ),
+        _ => None,
+    }
+}
+
+/// 删除 profile 中的文件或目录（不存在视为成功）
+fn remove_profile_paths(paths: &[PathBuf]) -> Result<(), String> {
+    for path in paths {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else if path.exists() {
+            std::fs::remove_file(path)
+        } else {
+            Ok(())
+        };
+        result.map_err(|e| format!("删除 {} 失败: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+async fn clear_tab_data_kind(
+    app: &tauri::AppHandle,
+    site_id: &str,
+    tab_id: &str,
+    kind: &str,
+) -> SiteDataKindResult {
+    let mut result = SiteDataKindResult {
+        tab_id: tab_id.to_string(),
+        kind: kind.to_string(),
+        status: "ok",
+        method: "webview",
+        detail: None,
+    };
+
+    if let Some(child) = app.get_webview(&format!("ai_{}", tab_id)) {
+        let outcome = if kind == "cookies" {
+            clear_webview_cookies(child).await.map(|count| Some(format!("{} 个 Cookie", count)))
+        } else {
+            let script = site_data_page_script(kind).unwrap_or_default();
+            match eval_page_text(&child, script, SITE_DATA_SCRIPT_TIMEOUT).await {
+                Ok(Some(text)) if text == "ok" => Ok(None),
+                Ok(Some(text)) if text == "not_supported" => {
+                    result.status = "not_supported";
+                    Ok(Some("页面环境不支持该 API".to_string()))
+                }
+                Ok(Some(_)) => Err("页面脚本执行失败".to_string()),
+                Ok(None) => Err("页面无响应".to_string()),
+                Err(e) => Err(e),
+            }
+        };
+        match outcome {
+            Ok(detail) => result.detail = detail,
+            Err(e) => {
+                result.status = "failed";
+                result.detail = Some(e);
+            }
+        }
+        return result;
+    }
+
+    result.method = "profile_files";
+    match site_data_profile_paths(&get_tab_data_dir(site_id, tab_id), kind) {
+        None => {
+            result.status = "not_supported";
+            result.detail = Some("当前平台不支持清除未打开 Tab 的此类数据，请先打开该 Tab".to_string());
+        }
+        Some(paths) => {
+            if let Err(e) = remove_profile_paths(&paths) {
+                result.status = "failed";
+                result.detail = Some(e);
+            }
+        }
+    }
+    result
+}
+
+/// 对站点的所有 Tab 按类型清除数据；单个类型不支持或失败不影响其余类型
+async fn clear_site_data_inner(
+    app: &tauri::AppHandle,
+    site_id: &str,
+    kinds: &[String],
+) -> Result<Vec<SiteDataKindResult>, String> {
+    get_site_by_id(site_id)?;
+    for kind in kinds {
+        if !SITE_DATA_KINDS.contains(&kind.as_str()) {
+            return Err(format!("不支持的数据类型: {}", kind));
+        }
+    }
+    let mut results = Vec::new();
+    for tab_id in tab_ids_for_site(site_id) {
+        for kind in kinds {
+            results.push(clear_tab_data_kind(app, site_id, &tab_id, kind).await);
+        }
+    }
+    Ok(results)
+}
+
+/// 按类型清除站点数据（cookies / cache / service_workers / local_storage / indexeddb），
+/// 返回每个 Tab、每种类型的结果（ok / not_supported / failed）
+#[tauri::command]
+async fn clear_site_data(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    site_id: String,
+    kinds: Vec<String>,
+) -> Result<Vec<SiteDataKindResult>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("clear_site_data")?;
+    if kinds.is_empty() {
+        return Err("请至少选择一种数据类型".into());
+    }
+    clear_site_data_inner(&app, &site_id, &kinds).await.map_err(AppError::from)
+}
+
 /// 打开开发者工具
 #[tauri::command]
-fn open_devtools(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), String> {
+fn open_devtools(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let active_tab_id = ACTIVE_TAB_ID.lock().unwrap().clone();
+    let active_tab_id = APP_STATE.lock().unwrap().active_tab.clone();
     let tab_id = if !active_tab_id.is_empty()
         && get_tab_site_id(&active_tab_id).ok().as_deref() == Some(site_id.as_str())
     {
@@ -1942,7 +11142,7 @@ fn open_devtools(webview: tauri::Webview, app: tauri::AppHandle, site_id: String
             main_wv.open_devtools();
             return Ok(());
         }
-        return Err("Webview 不存在".to_string());
+        Err(AppError::WebviewNotFound(tab_id))
     }
 
     #[cfg(not(debug_assertions))]
@@ -1950,15 +11150,15 @@ fn open_devtools(webview: tauri::Webview, app: tauri::AppHandle, site_id: String
         let _ = app;
         let _ = webview_label;
         let _ = site_id;
-        return Err("Devtools 仅在开发模式可用".to_string());
+        Err("Devtools 仅在开发模式可用".to_string())
     }
 }
 
 /// 设置侧边栏宽度（拖拽调整时调用）
 #[tauri::command]
-fn set_sidebar_width(webview: tauri::Webview, app: tauri::AppHandle, width: f64) -> Result<(), String> {
+fn set_sidebar_width(webview: tauri::Webview, app: tauri::AppHandle, width: f64) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
     // 更新配置中的侧边栏宽度
     {
@@ -1972,71 +11172,97 @@ fn set_sidebar_width(webview: tauri::Webview, app: tauri::AppHandle, width: f64)
     }
 
     // 立即更新所有 Webview 位置
-    resize_webviews_bounds_only(app)
+    resize_webviews_bounds_only(app).map_err(AppError::from)
 }
 
 /// 更新所有 Webview 尺寸（窗口调整大小时调用）
 #[tauri::command]
-fn resize_webviews(webview: tauri::Webview, app: tauri::AppHandle) -> Result<(), String> {
+fn resize_webviews(webview: tauri::Webview, app: tauri::AppHandle) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    resize_webviews_inner(&app, true)
+    resize_webviews_inner(&app, true).map_err(AppError::from)
 }
 
 fn resize_webviews_bounds_only(app: tauri::AppHandle) -> Result<(), String> {
     resize_webviews_inner(&app, false)
 }
 
+/// 最近一次调度的窗口尺寸更新序号
+static RESIZE_SEQ: AtomicU64 = AtomicU64::new(0);
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// 拖动调整窗口大小时的 Resized 事件：延迟更新 Webview 位置，连续事件只处理最后一次
+///
+/// 更新在后台任务中执行，不在窗口事件回调内调用 Webview API。
+fn schedule_resize_webviews(app: tauri::AppHandle) {
+    let seq = RESIZE_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(RESIZE_DEBOUNCE).await;
+        if RESIZE_SEQ.load(Ordering::SeqCst) == seq {
+            let _ = resize_webviews_bounds_only(app);
+        }
+    });
+}
+
 fn resize_webviews_inner(app: &tauri::AppHandle, apply_visibility: bool) -> Result<(), String> {
     let window = get_main_window(app)?;
     let (content_pos, content_size) = calculate_webview_bounds(&window);
 
-    let layout = LAYOUT_STATE.lock().unwrap().clone();
-    let active_tab_id = ACTIVE_TAB_ID.lock().unwrap().clone();
-    let current_site_id = CURRENT_VIEW.lock().unwrap().clone();
+    let (layout, focused_tab_id, current_site_id, views) = {
+        let state = APP_STATE.lock().unwrap();
+        (
+            state.layout.clone(),
+            state.focused_tab_id(),
+            state.current_view.clone(),
+            state.views.clone(),
+        )
+    };
 
     let mut visible: HashMap<String, (LogicalPosition<f64>, LogicalSize<f64>)> = HashMap::new();
 
     match layout.mode {
         LayoutMode::Single => {
-            let tab_id = if !active_tab_id.is_empty() {
-                active_tab_id
-            } else {
-                current_site_id
-            };
-            if !tab_id.is_empty() {
-                visible.insert(tab_id, (content_pos, content_size));
+            if !focused_tab_id.is_empty() {
+                visible.insert(focused_tab_id, (content_pos, content_size));
             }
         }
         LayoutMode::Split => {
-            if let (Some(left_tab), Some(right_tab)) = (layout.left_tab_id, layout.right_tab_id) {
-                let ratio = layout.ratio.clamp(0.2, 0.8);
-                let left_width = (content_size.width * ratio).max(100.0);
-                let right_width = (content_size.width - left_width).max(100.0);
-
-                visible.insert(
-                    left_tab,
-                    (
-                        LogicalPosition::new(content_pos.x, content_pos.y),
-                        LogicalSize::new(left_width, content_size.height),
-                    ),
-                );
-                visible.insert(
-                    right_tab,
-                    (
-                        LogicalPosition::new(content_pos.x + left_width, content_pos.y),
-                        LogicalSize::new(right_width, content_size.height),
-                    ),
-                );
+            if !layout.pane_tab_ids.is_empty() {
+                let vertical = layout.orientation == SplitOrientation::Vertical;
+                let sizes = if vertical {
+                    pane_sizes(content_size.height, &layout.pane_ratios, MIN_PANE_HEIGHT)
+                } else {
+                    pane_sizes(content_size.width, &layout.pane_ratios, MIN_PANE_WIDTH)
+                };
+                let mut offset = 0.0;
+                for (tab_id, size) in layout.pane_tab_ids.iter().zip(sizes) {
+                    let bounds = if vertical {
+                        (
+                            LogicalPosition::new(content_pos.x, content_pos.y + offset),
+                            LogicalSize::new(content_size.width, size),
+                        )
+                    } else {
+                        (
+                            LogicalPosition::new(content_pos.x + offset, content_pos.y),
+                            LogicalSize::new(size, content_size.height),
+                        )
+                    };
+                    visible.insert(tab_id.clone(), bounds);
+                    offset += size;
+                }
             } else if !current_site_id.is_empty() {
                 visible.insert(current_site_id, (content_pos, content_size));
             }
         }
     }
 
-    let views = CREATED_VIEWS.lock().unwrap().clone();
-    for (tab_id, _) in views {
+    let detached: HashSet<String> = detached_tab_ids().into_iter().collect();
+    for tab_id in views {
+        // 独立窗口中的 Webview 由其窗口自行铺满
+        if detached.contains(&tab_id) {
+            continue;
+        }
         let webview_label = format!("ai_{}", tab_id);
         if let Some(webview) = app.get_webview(&webview_label) {
             if let Some((pos, size)) = visible.get(&tab_id) {
@@ -2060,22 +11286,43 @@ fn resize_webviews_inner(app: &tauri::AppHandle, apply_visibility: bool) -> Resu
 // 自定义站点和配置命令
 // ============================================================================
 
+/// 查找 URL 规范化后相同的站点（用于去重）
+fn find_site_with_same_url<'a>(
+    config: &'a AppConfig,
+    url: &tauri::Url,
+    exclude_site_id: Option<&str>,
+) -> Option<&'a AiSite> {
+    config.sites.iter().find(|s| {
+        if Some(s.id.as_str()) == exclude_site_id {
+            return false;
+        }
+        site_url::parse_site_url(&s.url)
+            .map(|existing| site_url::normalized_eq(&existing, url))
+            .unwrap_or(false)
+    })
+}
+
 /// 添加自定义站点
 #[tauri::command]
-fn add_site(webview: tauri::Webview, name: String, url: String, icon: String) -> Result<AiSite, String> {
+fn add_site(webview: tauri::Webview, name: String, url: String, icon: String) -> Result<AiSite, AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("add_site")?;
+    let parsed = site_url::parse_site_url(&url)?;
     let new_site = AiSite {
         id: format!("custom_{}", Uuid::new_v4().to_string().split('-').next().unwrap()),
         name,
-        url,
+        url: url.trim().to_string(),
         icon,
         builtin: false,
-        summary_prompt_override: String::new(),
+        ..Default::default()
     };
 
     let mut config = APP_CONFIG.lock().unwrap();
+    if let Some(dup) = find_site_with_same_url(&config, &parsed, None) {
+        return Err(format!("已存在相同 URL 的站点: {}", dup.name).into());
+    }
     config.sites.push(new_site.clone());
     config.site_order.push(new_site.id.clone());
     save_config(&config)?;
@@ -2083,8 +11330,103 @@ fn add_site(webview: tauri::Webview, name: String, url: String, icon: String) ->
     Ok(new_site)
 }
 
+/// 书签导入中未创建站点的条目
+#[derive(Debug, Clone, Serialize)]
+struct BookmarkImportEntry {
+    title: String,
+    url: String,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BookmarkImportReport {
+    imported: Vec<AiSite>,
+    /// 与已有站点（或同一文件中更早的书签）地址相同
+    skipped: Vec<BookmarkImportEntry>,
+    /// 地址不是 http/https 等无法作为站点的书签
+    invalid: Vec<BookmarkImportEntry>,
+}
+
+/// 从浏览器导出的书签 HTML 批量添加自定义站点；`folder_name` 指定时只导入该文件夹（含子文件夹）
+#[tauri::command]
+fn import_sites_from_bookmarks(
+    webview: tauri::Webview,
+    path: String,
+    folder_name: Option<String>,
+) -> Result<BookmarkImportReport, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("import_sites_from_bookmarks")?;
+    let content =
+        fs::read_to_string(path.trim()).map_err(|e| format!("读取书签文件失败: {}", e))?;
+    let bookmarks = bookmarks::parse(&content, folder_name.as_deref())?;
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    let report = add_bookmark_sites(&mut config, bookmarks);
+    if !report.imported.is_empty() {
+        save_config(&config)?;
+    }
+    Ok(report)
+}
+
+/// 把书签逐个加为自定义站点（追加到 `site_order`），地址无效或重复的记入报告
+fn add_bookmark_sites(
+    config: &mut AppConfig,
+    bookmarks: Vec<bookmarks::Bookmark>,
+) -> BookmarkImportReport {
+    let mut report = BookmarkImportReport {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        invalid: Vec::new(),
+    };
+    for bookmark in bookmarks {
+        let parsed = match site_url::parse_site_url(&bookmark.url) {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                report.invalid.push(BookmarkImportEntry {
+                    title: bookmark.title,
+                    url: bookmark.url,
+                    reason,
+                });
+                continue;
+            }
+        };
+        // 已导入的站点也在 config.sites 中，文件内的重复书签同样被跳过
+        if let Some(dup) = find_site_with_same_url(config, &parsed, None) {
+            report.skipped.push(BookmarkImportEntry {
+                reason: format!("已存在相同 URL 的站点: {}", dup.name),
+                title: bookmark.title,
+                url: bookmark.url,
+            });
+            continue;
+        }
+        let name = if bookmark.title.is_empty() {
+            parsed.host_str().unwrap_or_default().to_string()
+        } else {
+            bookmark.title
+        };
+        let site = AiSite {
+            id: format!(
+                "custom_{}",
+                Uuid::new_v4().to_string().split('-').next().unwrap()
+            ),
+            name,
+            url: bookmark.url,
+            icon: bookmark.icon.unwrap_or_else(|| "custom".to_string()),
+            builtin: false,
+            ..Default::default()
+        };
+        config.sites.push(site.clone());
+        config.site_order.push(site.id.clone());
+        report.imported.push(site);
+    }
+    report
+}
+
 /// 更新站点（支持内置与自定义站点的基本信息编辑）
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn update_site(
     webview: tauri::Webview,
     app: tauri::AppHandle,
@@ -2093,69 +11435,310 @@ fn update_site(
     url: String,
     icon: String,
     summary_prompt_override: Option<String>,
-) -> Result<(), String> {
+    anti_detection_mode: Option<String>,
+    extract_selector: Option<String>,
+    private: Option<bool>,
+    prompt_template_id: Option<String>,
+    input_selector: Option<String>,
+    submit_selector: Option<String>,
+    init_script: Option<String>,
+    popup_policy: Option<String>,
+    disable_anti_detection: Option<bool>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("update_site")?;
+    let parsed = site_url::parse_site_url(&url)?;
+    let url = url.trim().to_string();
+    if let Some(mode) = anti_detection_mode.as_deref() {
+        if !ANTI_DETECTION_MODES.contains(&mode) {
+            return Err(format!("不支持的反检测模式: {}", mode).into());
+        }
+    }
+    if let Some(script) = init_script.as_deref() {
+        validate_site_init_script(script)?;
     }
-    let (old_url, new_url, config_snapshot) = {
+    if let Some(policy) = popup_policy.as_deref() {
+        if !POPUP_POLICIES.contains(&policy) {
+            return Err(format!("不支持的弹窗策略: {}", policy).into());
+        }
+    }
+    let (needs_rebuild, became_private, config_snapshot) = {
         let mut config = APP_CONFIG.lock().unwrap();
+        if let Some(template_id) = prompt_template_id.as_deref().map(str::trim) {
+            if !template_id.is_empty()
+                && !config.prompt_templates.iter().any(|t| t.id == template_id)
+            {
+                return Err("提示词模板不存在".into());
+            }
+        }
+        if let Some(dup) = find_site_with_same_url(&config, &parsed, Some(&site_id)) {
+            return Err(format!("已存在相同 URL 的站点: {}", dup.name).into());
+        }
         let site = config
             .sites
             .iter_mut()
             .find(|s| s.id == site_id)
-            .ok_or_else(|| "站点不存在".to_string())?;
+            .ok_or_else(|| AppError::SiteNotFound(site_id.clone()))?;
 
         let old_url = site.url.clone();
-        site.name = name;
+        // 内置名称未被修改（提交的是任一语言下的显示名）时保留 key，否则视为用户改名
+        if site.name_key.is_empty() || !i18n::matches_any_language(&site.name_key, &name) {
+            site.name_key.clear();
+            site.name = name;
+        }
         site.url = url;
         site.icon = icon;
         if let Some(override_template) = summary_prompt_override {
             site.summary_prompt_override = override_template;
         }
-        let new_url = site.url.clone();
-
-        (old_url, new_url, config.clone())
+        if let Some(template_id) = prompt_template_id {
+            site.prompt_template_id = template_id.trim().to_string();
+        }
+        if let Some(selector) = extract_selector {
+            site.extract_selector = selector.trim().to_string();
+        }
+        if let Some(selector) = input_selector {
+            site.input_selector = selector.trim().to_string();
+        }
+        if let Some(selector) = submit_selector {
+            site.submit_selector = selector.trim().to_string();
+        }
+        let old_mode = effective_anti_detection_mode(site).to_string();
+        if let Some(mode) = anti_detection_mode {
+            site.anti_detection_mode = mode;
+        }
+        if let Some(disabled) = disable_anti_detection {
+            site.disable_anti_detection = disabled;
+        }
+        let old_init_script = site.init_script.clone();
+        if let Some(script) = init_script {
+            site.init_script = script;
+        }
+        // 弹窗策略在打开新窗口时读取，无需重建 Webview
+        if let Some(policy) = popup_policy {
+            site.popup_policy = policy;
+        }
+        let needs_rebuild = old_url != site.url
+            || old_mode != effective_anti_detection_mode(site)
+            || old_init_script != site.init_script;
+        let became_private = private == Some(true) && !site.private;
+        if let Some(private) = private {
+            site.private = private;
+        }
+        if became_private {
+            // 新标记为隐私的站点：同时清除已有的最近使用记录
+            config.recent_site_ids.retain(|id| id != &site_id);
+        }
+
+        (needs_rebuild, became_private, config.clone())
+    };
+
+    save_config(&config_snapshot)?;
+
+    if became_private {
+        PAGE_TIMINGS.lock().unwrap().remove(&site_id);
+        let tab_ids = tab_ids_for_site(&site_id);
+        ai_client::pseudonymize_site(&site_id, &tab_ids);
+        usage::pseudonymize_site(&site_id);
+        site_stats::forget_site(&site_id);
+    }
+
+    // 若 URL、反检测模式或站点初始化脚本变更，为确保生效，关闭已有 Webview，等待下次切换时重建
+    if needs_rebuild {
+        close_site_webviews(&app, &site_id);
+        OBSERVED_SITE_URLS.lock().unwrap().remove(&site_id);
+    }
+
+    reconcile_site_tabs(&app);
+    Ok(())
+}
+
+/// 关闭站点的所有 Tab Webview（下次切换时按最新配置重建）；站点正在显示时回到空白状态
+fn close_site_webviews(app: &tauri::AppHandle, site_id: &str) {
+    for tab_id in tab_ids_for_site(site_id) {
+        close_tab_webview(app, &tab_id);
+    }
+
+    let (current, active_tab) = {
+        let state = APP_STATE.lock().unwrap();
+        (state.current_view.clone(), state.active_tab.clone())
+    };
+    if current == site_id
+        || (!active_tab.is_empty() && get_tab_site_id(&active_tab).ok().as_deref() == Some(site_id))
+    {
+        {
+            let mut state = APP_STATE.lock().unwrap();
+            state.clear_active();
+            state.layout = LayoutState::default();
+        }
+        clear_last_active();
+    }
+}
+
+// ============================================================================
+// 站点地址跳转检测
+// ============================================================================
+
+/// 各站点本次运行中首次成功加载后的最终地址（每个站点只记录一次）
+static OBSERVED_SITE_URLS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+struct SiteUrlOutdatedPayload {
+    site_id: String,
+    configured_url: String,
+    observed_url: String,
+}
+
+/// 站点首次加载完成后检查是否被跳转到其他可注册域（配置的地址可能已永久迁移）
+fn check_site_redirect(app: &tauri::AppHandle, site_id: &str, final_url: &tauri::Url) {
+    {
+        let mut observed = OBSERVED_SITE_URLS.lock().unwrap();
+        if observed.contains_key(site_id) {
+            return;
+        }
+        observed.insert(site_id.to_string(), final_url.to_string());
+    }
+    let Ok(site) = get_site_by_id(site_id) else {
+        return;
+    };
+    let Ok(configured) = site_url::parse_site_url(&site.url) else {
+        return;
+    };
+    if site_url::same_registrable_domain(&configured, final_url) {
+        return;
+    }
+    println!(
+        "[site_url] 站点 {} 的地址 {} 跳转到了 {}",
+        site_id, site.url, final_url
+    );
+    let _ = app.emit(
+        "site-url-outdated",
+        SiteUrlOutdatedPayload {
+            site_id: site_id.to_string(),
+            configured_url: site.url,
+            observed_url: final_url.to_string(),
+        },
+    );
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SiteRuntimeState {
+    site_id: String,
+    /// 本次运行中首次加载后的实际地址（尚未加载过为 None）
+    observed_url: Option<String>,
+    /// 实际地址与配置地址不在同一可注册域
+    url_outdated: bool,
+}
+
+/// 获取各站点的运行时状态（实际地址等）
+#[tauri::command]
+fn get_sites_runtime_state(webview: tauri::Webview) -> Result<Vec<SiteRuntimeState>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let sites = APP_CONFIG.lock().unwrap().sites.clone();
+    let observed = OBSERVED_SITE_URLS.lock().unwrap().clone();
+    Ok(sites
+        .into_iter()
+        .map(|site| {
+            let observed_url = observed.get(&site.id).cloned();
+            let url_outdated = match (
+                site_url::parse_site_url(&site.url),
+                observed_url.as_deref().map(site_url::parse_site_url),
+            ) {
+                (Ok(configured), Some(Ok(actual))) => {
+                    !site_url::same_registrable_domain(&configured, &actual)
+                }
+                _ => false,
+            };
+            SiteRuntimeState {
+                site_id: site.id,
+                observed_url,
+                url_outdated,
+            }
+        })
+        .collect())
+}
+
+/// 把站点地址更新为实际跳转到的地址（与 update_site 相同的校验），并关闭该站点的 Webview
+#[tauri::command]
+fn accept_observed_url(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<String, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("accept_observed_url")?;
+    let observed = OBSERVED_SITE_URLS
+        .lock()
+        .unwrap()
+        .get(&site_id)
+        .cloned()
+        .ok_or_else(|| "该站点尚未加载，没有可用的实际地址".to_string())?;
+    let parsed = site_url::parse_site_url(&observed)?;
+    let config_snapshot = {
+        let mut config = APP_CONFIG.lock().unwrap();
+        if let Some(dup) = find_site_with_same_url(&config, &parsed, Some(&site_id)) {
+            return Err(format!("已存在相同 URL 的站点: {}", dup.name).into());
+        }
+        let site = config
+            .sites
+            .iter_mut()
+            .find(|s| s.id == site_id)
+            .ok_or_else(|| AppError::SiteNotFound(site_id.clone()))?;
+        if site.url == observed {
+            return Ok(observed);
+        }
+        site.url = observed.clone();
+        config.clone()
     };
-
     save_config(&config_snapshot)?;
 
-    // 若 URL 变更，为确保生效，关闭已有 Webview，等待下次切换时按新 URL 重建
-    if old_url != new_url {
-        for tab_id in tab_ids_for_site(&site_id) {
-            close_tab_webview(&app, &tab_id);
-        }
-
-        let current = CURRENT_VIEW.lock().unwrap().clone();
-        let active_tab = ACTIVE_TAB_ID.lock().unwrap().clone();
-        if current == site_id
-            || (!active_tab.is_empty()
-                && get_tab_site_id(&active_tab).ok().as_deref() == Some(site_id.as_str()))
-        {
-            *CURRENT_VIEW.lock().unwrap() = String::new();
-            *ACTIVE_TAB_ID.lock().unwrap() = String::new();
-            *LAYOUT_STATE.lock().unwrap() = LayoutState::default();
-            clear_last_active();
-        }
-    }
-
-    Ok(())
+    close_site_webviews(&app, &site_id);
+    OBSERVED_SITE_URLS.lock().unwrap().remove(&site_id);
+    reconcile_site_tabs(&app);
+    Ok(observed)
 }
 
-/// 删除自定义站点
+/// 删除自定义站点；传入预览的 `snapshot_id` 时，若预览后状态有变化则不执行
 #[tauri::command]
-fn remove_site(webview: tauri::Webview, app: tauri::AppHandle, site_id: String) -> Result<(), String> {
+fn remove_site(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    site_id: String,
+    snapshot_id: Option<String>,
+    purge_data: Option<bool>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    permissions::authorize(
+        permissions::InvokeSurface::MainWebview,
+        "remove_site",
+        &site_id,
+        None,
+    )?;
+    ensure_preview_current("remove_site", &site_id, snapshot_id)?;
+    remove_site_inner(&app, site_id, purge_data.unwrap_or(false))
+}
+
+/// 删除站点；`purge_data` 为 true 时在后台删除其 profile 目录（失败的部分可由
+/// cleanup_orphaned_profiles 再次清理）
+fn remove_site_inner(
+    app: &tauri::AppHandle,
+    site_id: String,
+    purge_data: bool,
+) -> Result<(), AppError> {
+    ensure_unlocked("remove_site")?;
     let mut config = APP_CONFIG.lock().unwrap();
 
     // 检查是否为内置站点
     if let Some(site) = config.sites.iter().find(|s| s.id == site_id) {
         if site.builtin {
-            return Err("无法删除内置站点".to_string());
+            return Err("无法删除内置站点".into());
         }
     } else {
-        return Err("站点不存在".to_string());
+        return Err(AppError::SiteNotFound(site_id));
     }
 
     // 删除站点
@@ -2172,27 +11755,80 @@ fn remove_site(webview: tauri::Webview, app: tauri::AppHandle, site_id: String)
     // 关闭对应的 Webview
     drop(config);
     for tab_id in tab_ids_for_site(&site_id) {
-        close_tab_webview(&app, &tab_id);
+        close_tab_webview(app, &tab_id);
     }
 
     // 如果是当前视图，清除状态
-    let current = CURRENT_VIEW.lock().unwrap().clone();
-    if current == site_id {
-        *CURRENT_VIEW.lock().unwrap() = String::new();
-        *ACTIVE_TAB_ID.lock().unwrap() = String::new();
-        *LAYOUT_STATE.lock().unwrap() = LayoutState::default();
+    let was_current = {
+        let mut state = APP_STATE.lock().unwrap();
+        let was_current = state.current_view == site_id;
+        if was_current {
+            state.clear_active();
+            state.layout = LayoutState::default();
+        }
+        was_current
+    };
+    if was_current {
         clear_last_active();
     }
 
+    reconcile_site_tabs(app);
+    site_stats::forget_site(&site_id);
+
+    if purge_data {
+        tauri::async_runtime::spawn_blocking(move || {
+            let report = remove_orphaned_profiles(&[site_id]);
+            for path in &report.failed {
+                println!("[profiles] 删除站点数据失败: {}", path);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// 停用/启用站点：停用时关闭该站点的全部 Tab，配置与排序位置保留，重新启用后回到原位置
+#[tauri::command]
+fn set_site_enabled(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    site_id: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_site_enabled")?;
+    {
+        let mut config = APP_CONFIG.lock().unwrap();
+        let site = config
+            .sites
+            .iter_mut()
+            .find(|s| s.id == site_id)
+            .ok_or_else(|| AppError::SiteNotFound(site_id.clone()))?;
+        if site.enabled == enabled {
+            return Ok(());
+        }
+        site.enabled = enabled;
+        if !enabled && config.last_active_site_id == site_id {
+            config.last_active_site_id.clear();
+            config.last_active_tab_id.clear();
+        }
+        save_config(&config)?;
+    }
+    if !enabled {
+        close_site_webviews(&app, &site_id);
+        persist_session();
+    }
     Ok(())
 }
 
 /// 更新站点排序
 #[tauri::command]
-fn update_sites_order(webview: tauri::Webview, order: Vec<String>) -> Result<(), String> {
+fn update_sites_order(webview: tauri::Webview, order: Vec<String>) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("update_sites_order")?;
     let mut config = APP_CONFIG.lock().unwrap();
     let existing: HashSet<String> = config.sites.iter().map(|s| s.id.clone()).collect();
 
@@ -2207,6 +11843,13 @@ fn update_sites_order(webview: tauri::Webview, order: Vec<String>) -> Result<(),
         }
         next.push(id);
     }
+    // 前端通常只传启用的站点：未传入的停用站点按原下标插回，保持重新启用后的位置
+    for (index, id) in config.site_order.iter().enumerate() {
+        let disabled = config.sites.iter().any(|s| &s.id == id && !s.enabled);
+        if disabled && seen.insert(id.clone()) {
+            next.insert(index.min(next.len()), id.clone());
+        }
+    }
     for site in &config.sites {
         if seen.insert(site.id.clone()) {
             next.push(site.id.clone());
@@ -2220,13 +11863,14 @@ fn update_sites_order(webview: tauri::Webview, order: Vec<String>) -> Result<(),
 
 /// 置顶/取消置顶站点
 #[tauri::command]
-fn toggle_pin_site(webview: tauri::Webview, site_id: String, pinned: bool) -> Result<(), String> {
+fn toggle_pin_site(webview: tauri::Webview, site_id: String, pinned: bool) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("toggle_pin_site")?;
     let mut config = APP_CONFIG.lock().unwrap();
     if !config.sites.iter().any(|s| s.id == site_id) {
-        return Err("站点不存在".to_string());
+        return Err(AppError::SiteNotFound(site_id));
     }
 
     config.pinned_site_ids.retain(|id| id != &site_id);
@@ -2240,10 +11884,11 @@ fn toggle_pin_site(webview: tauri::Webview, site_id: String, pinned: bool) -> Re
 
 /// 更新置顶站点顺序（仅组内排序）
 #[tauri::command]
-fn update_pinned_sites_order(webview: tauri::Webview, order: Vec<String>) -> Result<(), String> {
+fn update_pinned_sites_order(webview: tauri::Webview, order: Vec<String>) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("update_pinned_sites_order")?;
     let mut config = APP_CONFIG.lock().unwrap();
     let existing: std::collections::HashSet<String> =
         config.sites.iter().map(|s| s.id.clone()).collect();
@@ -2268,10 +11913,11 @@ fn update_pinned_sites_order(webview: tauri::Webview, order: Vec<String>) -> Res
 
 /// 清空最近使用列表
 #[tauri::command]
-fn clear_recent_sites(webview: tauri::Webview) -> Result<(), String> {
+fn clear_recent_sites(webview: tauri::Webview) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("clear_recent_sites")?;
     let mut config = APP_CONFIG.lock().unwrap();
     config.recent_site_ids.clear();
     save_config(&config)?;
@@ -2280,10 +11926,11 @@ fn clear_recent_sites(webview: tauri::Webview) -> Result<(), String> {
 
 /// 重置导航栏数据（排序/置顶/最近），保留站点本身
 #[tauri::command]
-fn reset_navigation(webview: tauri::Webview) -> Result<(), String> {
+fn reset_navigation(webview: tauri::Webview) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
+    ensure_unlocked("reset_navigation")?;
     let mut config = APP_CONFIG.lock().unwrap();
 
     // 同步清理 sites 重复项（避免侧边栏重复）
@@ -2320,48 +11967,640 @@ fn reset_navigation(webview: tauri::Webview) -> Result<(), String> {
     Ok(())
 }
 
-/// 设置主题
+// ============================================================================
+// 配置导入/导出
+// ============================================================================
+
+/// 配置包格式版本（不兼容变更时递增）
+const CONFIG_BUNDLE_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ConfigBundle {
+    schema_version: u32,
+    exported_at: u64,
+    config: AppConfig,
+    #[serde(default)]
+    contexts: Vec<ProjectContext>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConfigImportReport {
+    sites_added: usize,
+    sites_updated: usize,
+    /// 未通过校验而跳过的站点（`index` 为在配置包站点列表中的下标）
+    site_errors: Vec<SiteImportError>,
+    projects_added: usize,
+    projects_updated: usize,
+}
+
+/// 按给定顺序重建 site_order：去重、移除不存在项，并补齐遗漏的站点
+fn rebuild_site_order(config: &mut AppConfig, order: &[String]) {
+    let existing: HashSet<String> = config.sites.iter().map(|s| s.id.clone()).collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut next: Vec<String> = Vec::new();
+    for id in order {
+        if existing.contains(id) && seen.insert(id.clone()) {
+            next.push(id.clone());
+        }
+    }
+    for site in &config.sites {
+        if seen.insert(site.id.clone()) {
+            next.push(site.id.clone());
+        }
+    }
+    config.site_order = next;
+}
+
+/// 导出配置与项目上下文为单个 JSON 文件（默认不包含 API Key）
 #[tauri::command]
-fn set_theme(webview: tauri::Webview, theme: String) -> Result<(), String> {
+fn export_config_bundle(
+    webview: tauri::Webview,
+    path: String,
+    include_secrets: Option<bool>,
+) -> Result<(), AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
     }
-    let mut config = APP_CONFIG.lock().unwrap();
-    config.theme = theme;
-    save_config(&config)?;
+    // 锁定模式下不允许导出 API Key
+    if include_secrets == Some(true) {
+        ensure_unlocked("export_config_bundle")?;
+    }
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("导出路径不能为空".into());
+    }
+
+    let mut config = APP_CONFIG.lock().unwrap().clone();
+    config.ai_api_key = if include_secrets == Some(true) {
+        resolve_ai_api_key(&config)?
+    } else {
+        String::new()
+    };
+    config.ai_api_key_in_keyring = false;
+    for profile in config.ai_api_profiles.iter_mut() {
+        profile.key = if include_secrets == Some(true) {
+            resolve_ai_profile_key(profile)?
+        } else {
+            String::new()
+        };
+        profile.key_in_keyring = false;
+    }
+    let bundle = ConfigBundle {
+        schema_version: CONFIG_BUNDLE_SCHEMA_VERSION,
+        exported_at: now_ts(),
+        config,
+        contexts: project_store::load_all(),
+    };
+    let content = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("序列化配置包失败: {}", e))?;
+    write_file_atomic(std::path::Path::new(path), content.as_bytes())
+        .map_err(|e| format!("写入配置包失败: {}", e))?;
     Ok(())
 }
 
-/// 显示/隐藏当前活跃的子 Webview（用于在主 UI 上方显示弹窗）
+/// 从配置包导入：合并自定义站点与项目，保留本机内置站点
 #[tauri::command]
-fn set_active_view_visible(webview: tauri::Webview, app: tauri::AppHandle, visible: bool) -> Result<(), String> {
+fn import_config_bundle(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<ConfigImportReport, AppError> {
     if !is_main_invoker_webview(&webview) {
-        return Err("Not allowed".to_string());
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("import_config_bundle")?;
+    let content = fs::read_to_string(path.trim()).map_err(|e| format!("读取配置包失败: {}", e))?;
+
+    // 先只解析版本号，避免未知版本的结构差异导致误导性的解析错误
+    let version = serde_json::from_str::<serde_json::Value>(&content)
+        .map_err(|e| format!("配置包不是有效的 JSON: {}", e))?
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| "配置包缺少 schema_version".to_string())?;
+    if version != CONFIG_BUNDLE_SCHEMA_VERSION as u64 {
+        return Err(format!("不支持的配置包版本: {}", version).into());
+    }
+    let bundle = serde_json::from_str::<ConfigBundle>(&content)
+        .map_err(|e| format!("解析配置包失败: {}", e))?;
+
+    let mut report = ConfigImportReport {
+        sites_added: 0,
+        sites_updated: 0,
+        site_errors: Vec::new(),
+        projects_added: 0,
+        projects_updated: 0,
+    };
+
+    // 项目：按 id 合并，保留较新的一份
+    for incoming in bundle.contexts {
+        match project_store::load_project(&incoming.id) {
+            Some(existing) if incoming.updated_at <= existing.updated_at => {}
+            Some(_) => {
+                project_store::save_project(&incoming)?;
+                report.projects_updated += 1;
+            }
+            None => {
+                project_store::save_project(&incoming)?;
+                report.projects_added += 1;
+            }
+        }
     }
-    let layout = LAYOUT_STATE.lock().unwrap().clone();
-    let active_tab_id = ACTIVE_TAB_ID.lock().unwrap().clone();
-    let current_site_id = CURRENT_VIEW.lock().unwrap().clone();
+    let project_ids: HashSet<String> =
+        project_store::list_index().into_iter().map(|e| e.id).collect();
 
-    let mut targets: Vec<String> = Vec::new();
-    match layout.mode {
-        LayoutMode::Single => {
-            if !active_tab_id.is_empty() {
-                targets.push(active_tab_id);
-            } else if !current_site_id.is_empty() {
-                targets.push(current_site_id);
+    let imported = bundle.config;
+    let mut config = APP_CONFIG.lock().unwrap();
+    let mut merged = merge_bundle_sites(&mut config, imported.sites);
+    report.sites_added = merged.report.added;
+    report.sites_updated = merged.report.updated;
+    report.site_errors = std::mem::take(&mut merged.report.errors);
+    rebuild_site_order(&mut config, &imported.site_order);
+
+    let existing: HashSet<String> = config.sites.iter().map(|s| s.id.clone()).collect();
+    let mut seen_pinned: HashSet<String> = HashSet::new();
+    config.pinned_site_ids = imported
+        .pinned_site_ids
+        .into_iter()
+        .filter(|id| existing.contains(id) && seen_pinned.insert(id.clone()))
+        .collect();
+
+    config.theme = imported.theme;
+    config.sidebar_width = imported.sidebar_width;
+    config.sidebar_expanded_width = imported.sidebar_expanded_width;
+    config.language = imported.language;
+    config.summary_prompt_template = imported.summary_prompt_template;
+    config.prompt_templates = imported.prompt_templates;
+    config.ai_api_temperature = imported.ai_api_temperature;
+    config.ai_api_max_tokens = imported.ai_api_max_tokens;
+    config.summary_max_chars = imported.summary_max_chars;
+    config.summary_extract_timeout_secs = imported.summary_extract_timeout_secs;
+    config.summary_total_timeout_secs = imported.summary_total_timeout_secs;
+    config.summary_append_notes = imported.summary_append_notes;
+    config.notify_on_summary = imported.notify_on_summary;
+    config.notify_on_summary_failure = imported.notify_on_summary_failure;
+    config.ai_system_prompt = imported.ai_system_prompt;
+    config.ai_api_provider = imported.ai_api_provider;
+    config.ai_api_auth = imported.ai_api_auth;
+    config.ai_api_base_url = imported.ai_api_base_url;
+    config.ai_api_model = imported.ai_api_model;
+    config.ai_api_deployment = imported.ai_api_deployment;
+    config.ai_api_version = imported.ai_api_version;
+    if !imported.ai_api_key.trim().is_empty() {
+        store_ai_api_key(&mut config, imported.ai_api_key.trim());
+    }
+    // AI 档案：按 id 合并；导入包中没有 Key 时保留本地已有的 Key
+    for incoming in imported.ai_api_profiles {
+        let key = incoming.key.trim().to_string();
+        let index = match config.ai_api_profiles.iter().position(|p| p.id == incoming.id) {
+            Some(i) => {
+                let existing = &mut config.ai_api_profiles[i];
+                existing.name = incoming.name;
+                existing.provider = incoming.provider;
+                existing.auth = incoming.auth;
+                existing.base_url = incoming.base_url;
+                existing.model = incoming.model;
+                existing.deployment = incoming.deployment;
+                existing.api_version = incoming.api_version;
+                i
+            }
+            None => {
+                config.ai_api_profiles.push(AiApiProfile {
+                    key: String::new(),
+                    key_in_keyring: false,
+                    ..incoming
+                });
+                config.ai_api_profiles.len() - 1
             }
+        };
+        if !key.is_empty() {
+            store_ai_profile_key(&mut config.ai_api_profiles[index], &key);
         }
-        LayoutMode::Split => {
-            if let Some(left) = layout.left_tab_id {
-                targets.push(left);
+    }
+    if config
+        .ai_api_profiles
+        .iter()
+        .any(|p| p.id == imported.active_ai_profile_id)
+    {
+        config.active_ai_profile_id = imported.active_ai_profile_id;
+    }
+    if project_ids.contains(&imported.active_project_id) {
+        config.active_project_id = imported.active_project_id;
+    }
+    save_config(&config)?;
+    drop(config);
+
+    apply_merged_site_import(&app, &merged);
+    Ok(report)
+}
+
+/// 按 id 合并配置包中的自定义站点（内置站点跳过）；未通过校验的站点记入报告，不修改配置
+fn merge_bundle_sites(config: &mut AppConfig, sites: Vec<AiSite>) -> MergedSiteImport {
+    let builtin_ids: HashSet<String> = get_builtin_sites().into_iter().map(|s| s.id).collect();
+    let mut report = SiteImportReport {
+        added: 0,
+        updated: 0,
+        skipped: 0,
+        errors: Vec::new(),
+    };
+    let mut rebuild_site_ids: Vec<String> = Vec::new();
+    let mut private_site_ids: Vec<String> = Vec::new();
+    for (index, mut site) in sites.into_iter().enumerate() {
+        if builtin_ids.contains(&site.id) {
+            continue;
+        }
+        if let Err(error) = validate_imported_site(&site) {
+            report.errors.push(SiteImportError {
+                index,
+                name: site.name,
+                error,
+            });
+            continue;
+        }
+        site.builtin = false;
+        site.url = site.url.trim().to_string();
+        match config.sites.iter_mut().find(|s| s.id == site.id) {
+            Some(existing) => {
+                if site_needs_rebuild(existing, &site) {
+                    rebuild_site_ids.push(site.id.clone());
+                }
+                if site.private && !existing.private {
+                    private_site_ids.push(site.id.clone());
+                }
+                *existing = site;
+                report.updated += 1;
+            }
+            None => {
+                config.sites.push(site);
+                report.added += 1;
+            }
+        }
+    }
+    config
+        .recent_site_ids
+        .retain(|id| !private_site_ids.contains(id));
+    MergedSiteImport {
+        report,
+        rebuild_site_ids,
+        private_site_ids,
+    }
+}
+
+/// 导出自定义站点（不含内置站点）为 JSON 数组，供团队共享站点列表
+#[tauri::command]
+fn export_sites(webview: tauri::Webview, path: String) -> Result<usize, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("导出路径不能为空".into());
+    }
+    let sites = exported_sites(&APP_CONFIG.lock().unwrap());
+    let content =
+        serde_json::to_string_pretty(&sites).map_err(|e| format!("序列化站点失败: {}", e))?;
+    write_file_atomic(std::path::Path::new(path), content.as_bytes())
+        .map_err(|e| format!("写入站点文件失败: {}", e))?;
+    Ok(sites.len())
+}
+
+/// 按展示顺序列出要导出的自定义站点
+fn exported_sites(config: &AppConfig) -> Vec<AiSite> {
+    site_ids_in_order(config, true)
+        .iter()
+        .filter_map(|id| config.sites.iter().find(|s| &s.id == id && !s.builtin))
+        .cloned()
+        .collect()
+}
+
+/// 站点导入中出错的条目（`index` 为在 JSON 数组中的下标）
+#[derive(Debug, Clone, Serialize)]
+struct SiteImportError {
+    index: usize,
+    name: String,
+    error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SiteImportReport {
+    added: usize,
+    updated: usize,
+    /// 与已有站点地址相同且未覆盖
+    skipped: usize,
+    errors: Vec<SiteImportError>,
+}
+
+/// 导入 export_sites 导出的站点：按 URL 匹配已有站点，新站点重新生成 id 并追加到排序末尾；
+/// `overwrite_existing` 为 true 时用文件中的配置覆盖匹配到的自定义站点（内置站点不覆盖）。
+/// 单个条目出错只记录在报告中，不影响其余条目
+#[tauri::command]
+fn import_sites(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    path: String,
+    overwrite_existing: bool,
+) -> Result<SiteImportReport, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("import_sites")?;
+    let content =
+        fs::read_to_string(path.trim()).map_err(|e| format!("读取站点文件失败: {}", e))?;
+    let entries = serde_json::from_str::<Vec<serde_json::Value>>(&content)
+        .map_err(|e| format!("站点文件不是有效的 JSON 数组: {}", e))?;
+
+    let mut config = APP_CONFIG.lock().unwrap();
+    let merged = merge_imported_sites(&mut config, entries, overwrite_existing);
+    if merged.report.added > 0 || merged.report.updated > 0 {
+        save_config(&config)?;
+    }
+    drop(config);
+
+    apply_merged_site_import(&app, &merged);
+    Ok(merged.report)
+}
+
+/// 导入保存后的收尾：新转为隐私的站点匿名化历史记录，配置变化的站点关闭已打开的 Webview 以便重建
+fn apply_merged_site_import(app: &tauri::AppHandle, merged: &MergedSiteImport) {
+    for site_id in &merged.private_site_ids {
+        PAGE_TIMINGS.lock().unwrap().remove(site_id);
+        let tab_ids = tab_ids_for_site(site_id);
+        ai_client::pseudonymize_site(site_id, &tab_ids);
+        usage::pseudonymize_site(site_id);
+        site_stats::forget_site(site_id);
+    }
+    for site_id in &merged.rebuild_site_ids {
+        close_site_webviews(app, site_id);
+        OBSERVED_SITE_URLS.lock().unwrap().remove(site_id);
+    }
+}
+
+/// 校验从文件导入的站点（站点 JSON 与配置包共用），返回解析后的地址
+fn validate_imported_site(site: &AiSite) -> Result<tauri::Url, String> {
+    let parsed = site_url::parse_site_url(&site.url)?;
+    if !ANTI_DETECTION_MODES.contains(&site.anti_detection_mode.as_str()) {
+        return Err(format!("不支持的反检测模式: {}", site.anti_detection_mode));
+    }
+    if !POPUP_POLICIES.contains(&site.popup_policy.as_str()) {
+        return Err(format!("不支持的弹窗策略: {}", site.popup_policy));
+    }
+    if !(MIN_TAB_ZOOM..=MAX_TAB_ZOOM).contains(&site.zoom) {
+        return Err(format!("缩放比例超出范围: {}", site.zoom));
+    }
+    if site.name.trim().is_empty() {
+        return Err("站点名称不能为空".to_string());
+    }
+    validate_site_init_script(&site.init_script)?;
+    Ok(parsed)
+}
+
+/// 站点配置变化后已打开的 Webview 是否需要重建（地址、反检测模式或初始化脚本变化）
+fn site_needs_rebuild(old: &AiSite, new: &AiSite) -> bool {
+    old.url != new.url.trim()
+        || effective_anti_detection_mode(old) != effective_anti_detection_mode(new)
+        || old.init_script != new.init_script
+}
+
+/// 合并导入结果：报告，以及需要后续处理的站点
+struct MergedSiteImport {
+    report: SiteImportReport,
+    /// 覆盖后需要重建 Webview 的站点（地址、反检测模式或初始化脚本变化）
+    rebuild_site_ids: Vec<String>,
+    /// 新标记为隐私的站点
+    private_site_ids: Vec<String>,
+}
+
+/// 把导入的条目合并进配置（不保存）；逐条校验，出错的条目只记入报告
+fn merge_imported_sites(
+    config: &mut AppConfig,
+    entries: Vec<serde_json::Value>,
+    overwrite_existing: bool,
+) -> MergedSiteImport {
+    let mut report = SiteImportReport {
+        added: 0,
+        updated: 0,
+        skipped: 0,
+        errors: Vec::new(),
+    };
+    let mut rebuild_site_ids: Vec<String> = Vec::new();
+    let mut private_site_ids: Vec<String> = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let name = entry
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let result = serde_json::from_value::<AiSite>(entry)
+            .map_err(|e| format!("条目格式无效: {}", e))
+            .and_then(|site| validate_imported_site(&site).map(|parsed| (site, parsed)));
+        let (incoming, parsed) = match result {
+            Ok(ok) => ok,
+            Err(error) => {
+                report.errors.push(SiteImportError { index, name, error });
+                continue;
             }
-            if let Some(right) = layout.right_tab_id {
-                targets.push(right);
+        };
+
+        let existing_id = find_site_with_same_url(config, &parsed, None).map(|s| s.id.clone());
+        let Some(existing_id) = existing_id else {
+            let site = AiSite {
+                id: format!(
+                    "custom_{}",
+                    Uuid::new_v4().to_string().split('-').next().unwrap()
+                ),
+                url: incoming.url.trim().to_string(),
+                builtin: false,
+                name_key: String::new(),
+                enabled: true,
+                ..incoming
+            };
+            config.site_order.push(site.id.clone());
+            config.sites.push(site);
+            report.added += 1;
+            continue;
+        };
+
+        let Some(site) = config
+            .sites
+            .iter_mut()
+            .find(|s| s.id == existing_id && !s.builtin)
+            .filter(|_| overwrite_existing)
+        else {
+            report.skipped += 1;
+            continue;
+        };
+        let needs_rebuild = site_needs_rebuild(site, &incoming);
+        if incoming.private && !site.private {
+            private_site_ids.push(existing_id.clone());
+        }
+        *site = AiSite {
+            id: existing_id.clone(),
+            url: incoming.url.trim().to_string(),
+            builtin: false,
+            name_key: String::new(),
+            enabled: site.enabled,
+            ..incoming
+        };
+        if needs_rebuild {
+            rebuild_site_ids.push(existing_id);
+        }
+        report.updated += 1;
+    }
+    config
+        .recent_site_ids
+        .retain(|id| !private_site_ids.contains(id));
+    MergedSiteImport {
+        report,
+        rebuild_site_ids,
+        private_site_ids,
+    }
+}
+
+/// 获取本地自动化 API 状态（是否运行、端口、token 文件位置）
+#[tauri::command]
+fn get_automation_api_status(
+    webview: tauri::Webview,
+) -> Result<automation_api::AutomationApiStatus, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    let enabled = APP_CONFIG.lock().unwrap().automation_api.enabled;
+    Ok(automation_api::status(enabled))
+}
+
+/// 开启/关闭本地自动化 API（立即生效）
+#[tauri::command]
+fn set_automation_api_settings(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<automation_api::AutomationApiStatus, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_automation_api_settings")?;
+    let settings = {
+        let mut config = APP_CONFIG.lock().unwrap();
+        config.automation_api.enabled = enabled;
+        if let Some(port) = port {
+            if port < 1024 {
+                return Err("端口需在 1024-65535 之间".into());
             }
+            config.automation_api.port = port;
+        }
+        save_config(&config)?;
+        config.automation_api.clone()
+    };
+    if settings.enabled {
+        automation_api::start(&app, settings.port)?;
+    } else {
+        automation_api::stop();
+    }
+    Ok(automation_api::status(settings.enabled))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DeepLinkErrorPayload {
+    url: String,
+    message: String,
+}
+
+/// 处理 aihub:// 链接；格式错误或站点不存在时发送 `deep-link-error`，不会创建任何 Webview
+fn handle_deep_link(app: &tauri::AppHandle, raw: &str) {
+    println!("[deep-link] {}", raw);
+    let app = app.clone();
+    let raw = raw.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_deep_link(&app, &raw).await {
+            println!("[deep-link] 处理失败: {}", e);
+            let payload = DeepLinkErrorPayload {
+                url: raw,
+                message: e.message(),
+            };
+            let _ = app.emit("deep-link-error", payload);
+        }
+    });
+}
+
+async fn run_deep_link(app: &tauri::AppHandle, raw: &str) -> Result<(), AppError> {
+    match deep_link::parse(raw)? {
+        deep_link::DeepLink::Open { site_id } => {
+            // 先对照配置校验，站点不存在时不进入切换流程
+            get_site_by_id(&site_id)?;
+            switch_view_inner(app.clone(), site_id.clone()).await?;
+            let _ = app.emit("view-switched", site_id);
         }
+        deep_link::DeepLink::Summarize => {
+            ensure_summarize_allowed()?;
+            trigger_summarize_active_tab(app, None);
+        }
+    }
+    Ok(())
+}
+
+/// 退出应用（已运行的实例无响应时，用户选择不继续使用只读窗口）
+#[tauri::command]
+fn quit_app(webview: tauri::Webview, app: tauri::AppHandle) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    app.exit(0);
+    Ok(())
+}
+
+/// 获取存储状态（是否因其他进程占用而处于只读模式）
+#[tauri::command]
+fn get_storage_status(webview: tauri::Webview) -> Result<StorageStatus, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(storage_status())
+}
+
+/// 启动时发现的问题（目前为被移走的损坏文件），前端挂载后调用，避免错过启动期间的事件
+#[tauri::command]
+fn get_startup_warnings(webview: tauri::Webview) -> Result<Vec<CorruptFilePayload>, AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    Ok(corrupt_file_warnings())
+}
+
+/// 设置主题
+#[tauri::command]
+fn set_theme(webview: tauri::Webview, theme: String) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_theme")?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.theme = theme;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 设置隐藏 Tab 的休眠时长（分钟，0 = 不休眠）
+#[tauri::command]
+fn set_tab_hibernate_minutes(webview: tauri::Webview, minutes: u32) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
+    }
+    ensure_unlocked("set_tab_hibernate_minutes")?;
+    let mut config = APP_CONFIG.lock().unwrap();
+    config.tab_hibernate_minutes = minutes;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// 显示/隐藏当前活跃的子 Webview（用于在主 UI 上方显示弹窗）
+#[tauri::command]
+fn set_active_view_visible(webview: tauri::Webview, app: tauri::AppHandle, visible: bool) -> Result<(), AppError> {
+    if !is_main_invoker_webview(&webview) {
+        return Err(AppError::NotAllowed);
     }
+    ACTIVE_VIEW_SUPPRESSED.store(!visible, Ordering::SeqCst);
 
-    for tab_id in targets {
+    for tab_id in layout_visible_tab_ids() {
         let webview_label = format!("ai_{}", tab_id);
         if let Some(webview) = app.get_webview(&webview_label) {
             if visible {
@@ -2381,9 +12620,22 @@ fn set_active_view_visible(webview: tauri::Webview, app: tauri::AppHandle, visib
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 必须在首次访问 APP_CONFIG 之前完成，避免只读实例在 load_config 中写回配置
+    init_storage_lock();
+
+    // 已有实例在运行：把命令行参数转交给它后退出；它没有响应时继续以只读模式启动
+    if STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if single_instance::signal_existing(&args).is_ok() {
+            std::process::exit(0);
+        }
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(shortcuts::plugin())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // 监听主窗口事件
             let app_handle = app.handle().clone();
@@ -2399,28 +12651,85 @@ pub fn run() {
                 window.on_window_event(move |event| {
                     match event {
                         tauri::WindowEvent::Resized(_) => {
-                            if WEBVIEW_CREATE_IN_PROGRESS.load(Ordering::SeqCst) > 0 {
-                                return;
-                            }
-                            // 窗口大小改变，更新所有 Webview
-                            let _ = resize_webviews_bounds_only(app_handle_for_window.clone());
+                            // 窗口大小改变，停止调整后更新所有 Webview
+                            schedule_resize_webviews(app_handle_for_window.clone());
+                        }
+                        tauri::WindowEvent::Focused(focused) => {
+                            // 失焦（含最小化、隐藏到托盘）时暂停站点前台计时
+                            site_stats::set_focused(*focused);
                         }
                         tauri::WindowEvent::CloseRequested { .. } => {
                             // 关闭窗口时清理所有 Webview
-                            let views = CREATED_VIEWS.lock().unwrap().clone();
-                            for (site_id, _) in views {
-                                let label = format!("ai_{}", site_id);
+                            let views = APP_STATE.lock().unwrap().views.clone();
+                            for tab_id in views {
+                                let label = format!("ai_{}", tab_id);
                                 if let Some(wv) = app_handle_for_window.get_webview(&label) {
                                     let _ = wv.close();
                                 }
                             }
+                            // 独立窗口随主窗口一起关闭（不再移回）
+                            let detached: Vec<String> = TAB_RUNTIME
+                                .lock()
+                                .unwrap()
+                                .values_mut()
+                                .filter_map(|runtime| runtime.detached_window.take())
+                                .collect();
+                            for label in detached {
+                                if let Some(window) = app_handle_for_window.get_window(&label) {
+                                    let _ = window.destroy();
+                                }
+                            }
                         }
                         _ => {}
                     }
                 });
             }
 
+            // 确保配置已加载（损坏文件在加载时被移走，前端就绪后经 get_startup_warnings 获取）
+            Lazy::force(&APP_CONFIG);
+            if !STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+                project_store::migrate_legacy_file();
+                migrate_project_timestamps();
+            }
+            let status = storage_status();
+            if status.read_only {
+                let _ = app_handle.emit("storage-read-only", status);
+            } else {
+                if let Err(e) = single_instance::start_listener(&app_handle) {
+                    println!("[single-instance] 启动监听失败: {}", e);
+                }
+                std::thread::spawn(deep_link::register_scheme);
+            }
+
+            let http_proxy = APP_CONFIG.lock().unwrap().http_proxy.clone();
+            if let Err(e) = ai_client::set_proxy(http_proxy.as_deref()) {
+                println!("[ai_client] 代理配置无效，已忽略: {}", e);
+            }
+
             restore_last_active_state(&app_handle);
+            start_hibernate_timer(&app_handle);
+            start_self_check_timer(&app_handle);
+            start_webview_watchdog(&app_handle);
+            start_site_stats_timer();
+
+            let bindings = APP_CONFIG.lock().unwrap().shortcuts.clone();
+            if let Err(e) = shortcuts::apply(&app_handle, &bindings) {
+                println!("[shortcuts] 注册失败: {}", e);
+            }
+
+            // 通过 aihub:// 链接启动（Windows/Linux 以命令行参数传入）
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            if let Some(link) = deep_link::find_in_args(&args) {
+                handle_deep_link(&app_handle, link);
+            }
+
+            // 只读实例不启动自动化 API（端口与 token 文件归主实例所有）
+            let automation = APP_CONFIG.lock().unwrap().automation_api.clone();
+            if automation.enabled && !STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+                if let Err(e) = automation_api::start(&app_handle, automation.port) {
+                    println!("[automation] 启动失败: {}", e);
+                }
+            }
 
             Ok(())
         })
@@ -2430,39 +12739,2325 @@ pub fn run() {
             set_language,
             set_summary_prompt_template,
             get_ai_sites,
+            get_ordered_site_ids,
             get_current_view,
             get_tabs_state,
             switch_view,
             create_tab,
             switch_tab,
+            hibernate_tab,
+            detach_tab,
             set_layout,
+            set_split_ratio,
+            swap_split_panes,
+            list_closed_tabs,
+            reopen_closed_tab,
+            preview_clear_view_cache,
+            preview_remove_site,
+            clear_site_cookies,
+            clear_site_data,
+            get_storage_usage,
+            cleanup_orphaned_profiles,
             close_tab,
             refresh_view,
+            refresh_tab,
             clear_view_cache,
             open_devtools,
             set_sidebar_width,
             resize_webviews,
             add_site,
+            import_sites_from_bookmarks,
             update_site,
+            get_sites_runtime_state,
+            accept_observed_url,
             remove_site,
             update_sites_order,
             toggle_pin_site,
+            set_site_enabled,
             update_pinned_sites_order,
             clear_recent_sites,
             reset_navigation,
             set_active_project,
             list_projects,
+            list_all_tags,
+            set_project_tags,
             get_project,
             create_project,
             update_project,
+            rename_project,
+            update_project_notes,
+            update_project_summary,
             delete_project,
+            list_trashed_projects,
+            restore_project,
+            purge_project,
             summarize_text,
             aihub_submit_page_text,
             set_active_tab_id,
+            tab_has_unsent_input,
+            inject_context_to_tab,
+            broadcast_prompt,
+            get_tab_navigation_state,
+            copy_tab_url,
+            open_tab_in_browser,
+            set_tab_zoom,
+            set_tab_muted,
+            mute_all_tabs,
+            get_tab_zoom,
+            capture_tab_screenshot,
+            print_tab_to_pdf,
+            tab_go_back,
+            tab_go_forward,
+            find_in_page,
+            stop_find_in_page,
+            cancel_summary,
+            preflight_summary_check,
             summarize_active_tab,
+            resummarize_last_extraction,
+            extract_tab_text,
             set_theme,
+            set_demo_mode,
+            set_tab_hibernate_minutes,
+            list_project_entries,
+            delete_project_entry,
+            set_project_entry_limit,
+            set_summary_max_chars,
+            set_summary_options,
+            set_http_proxy,
+            set_download_dir,
+            list_recent_downloads,
+            open_download,
+            set_anti_detection_config,
+            set_notification_settings,
+            export_project_markdown,
             set_active_view_visible,
+            get_storage_status,
+            get_startup_warnings,
+            get_automation_api_status,
+            set_automation_api_settings,
+            get_ai_api_key_status,
+            list_ai_profiles,
+            upsert_ai_profile,
+            delete_ai_profile,
+            set_active_ai_profile,
+            start_summarize_active_tab,
+            start_summarize_text,
+            summarize_all_open_tabs,
+            summarize_split_comparison,
+            get_job,
+            list_jobs,
+            cancel_job,
+            export_config_bundle,
+            import_config_bundle,
+            export_sites,
+            import_sites,
+            tab_go_home,
+            aihub_report_page_timing,
+            aihub_report_bridge,
+            aihub_zoom_step,
+            aihub_report_alive,
+            restart_tab,
+            retry_tab_load,
+            aihub_report_title,
+            get_site_performance,
+            get_diagnostics,
+            test_ai_connection,
+            list_ai_models,
+            set_ai_request_options,
+            get_prompt_variables,
+            list_prompt_templates,
+            upsert_prompt_template,
+            delete_prompt_template,
+            run_self_check,
+            get_api_audit,
+            get_usage_stats,
+            get_site_stats,
+            reset_site_stats,
+            get_shortcuts,
+            set_shortcut,
+            quit_app,
+        ])
+        .build(tauri::generate_context!())
+        .expect("运行 Tauri 应用失败")
+        .run(|_app, event| {
+            // macOS 通过系统事件传入 aihub:// 链接
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &event {
+                for url in urls {
+                    handle_deep_link(_app, url.as_str());
+                }
+            }
+            if let tauri::RunEvent::Exit = event {
+                site_stats::flush();
+                automation_api::stop();
+                shortcuts::unregister_all(_app);
+                if !STORAGE_READ_ONLY.load(Ordering::SeqCst) {
+                    single_instance::stop();
+                }
+                release_storage_lock();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ========================================================================
+    // 反检测脚本
+    // ========================================================================
+
+    fn site_with_mode(mode: &str) -> AiSite {
+        AiSite {
+            id: "custom_test".to_string(),
+            url: "https://example.com".to_string(),
+            anti_detection_mode: mode.to_string(),
+            ..AiSite::default()
+        }
+    }
+
+    #[test]
+    fn anti_detection_toggle_combinations() {
+        for bits in 0..64u8 {
+            let settings = AntiDetectionConfig {
+                spoof_plugins: bits & 1 != 0,
+                spoof_languages: if bits & 2 != 0 {
+                    default_spoof_languages()
+                } else {
+                    Vec::new()
+                },
+                spoof_platform: bits & 4 != 0,
+                spoof_vendor: bits & 8 != 0,
+                spoof_timezone: bits & 16 != 0,
+                spoof_webgl: bits & 32 != 0,
+                ..AntiDetectionConfig::default()
+            };
+            let script = build_anti_detection_script("full", &settings, "zh-CN");
+            assert!(
+                script.starts_with(ANTI_DETECTION_WEBDRIVER_SCRIPT),
+                "bits={}",
+                bits
+            );
+            assert!(
+                script.ends_with(ANTI_DETECTION_CHROME_SCRIPT),
+                "bits={}",
+                bits
+            );
+            assert_eq!(
+                script.contains(ANTI_DETECTION_PLUGINS_SCRIPT),
+                settings.spoof_plugins
+            );
+            assert_eq!(
+                script.contains("伪造 languages"),
+                !settings.spoof_languages.is_empty()
+            );
+            assert_eq!(
+                script.contains(ANTI_DETECTION_PLATFORM_SCRIPT),
+                settings.spoof_platform
+            );
+            assert_eq!(
+                script.contains(ANTI_DETECTION_VENDOR_SCRIPT),
+                settings.spoof_vendor
+            );
+            assert_eq!(script.contains("伪造时区"), settings.spoof_timezone);
+            assert_eq!(script.contains("伪造 WebGL"), settings.spoof_webgl);
+            for placeholder in ["{languages}", "{timezone}", "{vendor}", "{renderer}"] {
+                assert!(!script.contains(placeholder), "bits={}", bits);
+            }
+        }
+    }
+
+    #[test]
+    fn anti_detection_default_matches_previous_behavior() {
+        let script = build_anti_detection_script("full", &AntiDetectionConfig::default(), "en");
+        assert!(script.contains(r#"get: () => ["zh-CN","zh","en-US","en"]"#));
+        assert!(script.contains(ANTI_DETECTION_PLUGINS_SCRIPT));
+        assert!(!script.contains("伪造时区"));
+        assert!(!script.contains("伪造 WebGL"));
+    }
+
+    #[test]
+    fn timezone_spoof_uses_configured_or_language_default_zone() {
+        let settings = AntiDetectionConfig {
+            spoof_timezone: true,
+            ..AntiDetectionConfig::default()
+        };
+        let tz_of = |settings: &AntiDetectionConfig, language: &str| {
+            let script = build_anti_detection_script("full", settings, language);
+            let start = script.find("const tz = ").unwrap() + "const tz = ".len();
+            let end = start + script[start..].find(';').unwrap();
+            script[start..end].to_string()
+        };
+        assert_eq!(tz_of(&settings, "zh-CN"), r#""Asia/Shanghai""#);
+        assert_eq!(tz_of(&settings, "zh-TW"), r#""Asia/Taipei""#);
+        assert_eq!(tz_of(&settings, "en"), r#""America/New_York""#);
+        assert_eq!(tz_of(&settings, "en-GB"), r#""Europe/London""#);
+        assert_eq!(tz_of(&settings, "ja-JP"), r#""Asia/Tokyo""#);
+        assert_eq!(tz_of(&settings, "xx"), r#""UTC""#);
+
+        let configured = AntiDetectionConfig {
+            timezone: "Europe/Berlin".to_string(),
+            ..settings.clone()
+        };
+        assert_eq!(tz_of(&configured, "zh-CN"), r#""Europe/Berlin""#);
+
+        // 伪造时区在 chrome 对象脚本之前，webdriver_only 模式不注入
+        let script = build_anti_detection_script("full", &configured, "en");
+        assert!(
+            script.find("伪造时区").unwrap() < script.find(ANTI_DETECTION_CHROME_SCRIPT).unwrap()
+        );
+        assert!(
+            !build_anti_detection_script("webdriver_only", &configured, "en").contains("伪造时区")
+        );
+    }
+
+    #[test]
+    fn webgl_spoof_writes_strings_as_json() {
+        let settings = AntiDetectionConfig {
+            spoof_webgl: true,
+            webgl_vendor: "Vendor \"quoted\" {renderer}".to_string(),
+            webgl_renderer: "Renderer</script>".to_string(),
+            ..AntiDetectionConfig::default()
+        };
+        let script = build_anti_detection_script("full", &settings, "en");
+        // 厂商字符串中的占位符原样保留，不会被再次替换
+        assert!(script.contains(r#"const vendor = "Vendor \"quoted\" {renderer}";"#));
+        assert!(script.contains(r#"const renderer = "Renderer</script>";"#));
+
+        let defaults = AntiDetectionConfig {
+            spoof_webgl: true,
+            ..AntiDetectionConfig::default()
+        };
+        let script = build_anti_detection_script("full", &defaults, "en");
+        assert!(script.contains(&serde_json::to_string(&default_webgl_vendor()).unwrap()));
+        assert!(script.contains(&serde_json::to_string(&default_webgl_renderer()).unwrap()));
+    }
+
+    #[test]
+    fn timezone_and_webgl_settings_are_validated() {
+        let normalize = |timezone: &str, vendor: &str| {
+            normalize_anti_detection_config(AntiDetectionConfig {
+                timezone: timezone.to_string(),
+                webgl_vendor: vendor.to_string(),
+                ..AntiDetectionConfig::default()
+            })
+        };
+        let ok = normalize(" America/Argentina/Buenos_Aires ", "  ").unwrap();
+        assert_eq!(ok.timezone, "America/Argentina/Buenos_Aires");
+        assert_eq!(ok.webgl_vendor, default_webgl_vendor());
+        assert_eq!(normalize("Etc/GMT+8", "X").unwrap().timezone, "Etc/GMT+8");
+        assert!(normalize("Asia/Shanghai\"; alert(1); \"", "X").is_err());
+        assert!(normalize(&"A".repeat(65), "X").is_err());
+        assert!(normalize("", &"v".repeat(MAX_WEBGL_STRING_CHARS)).is_ok());
+        assert!(normalize("", &"v".repeat(MAX_WEBGL_STRING_CHARS + 1)).is_err());
+
+        // 旧配置没有这些字段时两项伪造均关闭
+        let parsed: AntiDetectionConfig = serde_json::from_str(r#"{"enabled":true}"#).unwrap();
+        assert!(!parsed.spoof_timezone && !parsed.spoof_webgl);
+        assert!(parsed.timezone.is_empty());
+        assert_eq!(parsed.webgl_renderer, default_webgl_renderer());
+    }
+
+    #[test]
+    fn anti_detection_modes() {
+        let settings = AntiDetectionConfig::default();
+        assert_eq!(build_anti_detection_script("off", &settings, "en"), "");
+        assert_eq!(
+            build_anti_detection_script("webdriver_only", &settings, "en"),
+            ANTI_DETECTION_WEBDRIVER_SCRIPT
+        );
+        let disabled = AntiDetectionConfig {
+            enabled: false,
+            ..AntiDetectionConfig::default()
+        };
+        for mode in ANTI_DETECTION_MODES {
+            assert_eq!(build_anti_detection_script(mode, &disabled, "en"), "");
+        }
+    }
+
+    #[test]
+    fn site_opt_out_skips_anti_detection() {
+        let settings = AntiDetectionConfig::default();
+        let mut site = site_with_mode("full");
+        assert!(!site_anti_detection_script(&site, &settings, "en").is_empty());
+        site.disable_anti_detection = true;
+        assert_eq!(effective_anti_detection_mode(&site), "off");
+        assert_eq!(site_anti_detection_script(&site, &settings, "en"), "");
+        // 旧配置没有该字段时默认不关闭
+        let parsed: AiSite = serde_json::from_str(
+            r#"{"id":"a","name":"A","url":"https://a.example","icon":"custom"}"#,
+        )
+        .unwrap();
+        assert!(!parsed.disable_anti_detection);
+    }
+
+    #[test]
+    fn spoof_languages_reject_script_injection() {
+        assert!(normalize_spoof_languages(vec!["{languages}".to_string()]).is_err());
+        assert!(normalize_spoof_languages(vec!["en\"]; alert(1); [\"".to_string()]).is_err());
+        assert_eq!(
+            normalize_spoof_languages(vec![
+                " en-US ".to_string(),
+                "EN-us".to_string(),
+                String::new(),
+                "fr".to_string(),
+            ])
+            .unwrap(),
+            vec!["en-US".to_string(), "fr".to_string()]
+        );
+        // 即使绕过校验，语言也按 JSON 字符串写入脚本，占位符不会被二次替换
+        let settings = AntiDetectionConfig {
+            spoof_languages: vec!["{languages}".to_string()],
+            ..AntiDetectionConfig::default()
+        };
+        let script = build_anti_detection_script("full", &settings, "en");
+        assert!(script.contains(r#"get: () => ["{languages}"]"#));
+    }
+
+    // ========================================================================
+    // 配置文件原子写入与损坏恢复
+    // ========================================================================
+
+    #[test]
+    fn truncated_config_is_backed_up_and_replaced_by_defaults() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        let saved = AppConfig {
+            theme: "light".to_string(),
+            ..AppConfig::default()
+        };
+        let content = serde_json::to_string_pretty(&saved).unwrap();
+        let truncated = &content[..content.len() / 2];
+        fs::write(&path, truncated).unwrap();
+
+        let config = load_config_from(&path);
+        assert_eq!(config.theme, AppConfig::default().theme);
+        assert_eq!(config.sites.len(), AppConfig::default().sites.len());
+
+        // 原文件被默认配置替换，损坏内容保留在备份中并出现在启动警告里
+        let rewritten: AppConfig =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten.theme, config.theme);
+        let backup = fs::read_dir(tmp.path())
+            .unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| {
+                p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with("config.json.corrupt-"))
+            })
+            .expect("损坏的配置应被备份");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), truncated);
+        let backup = backup.to_string_lossy().to_string();
+        assert!(corrupt_file_warnings()
+            .iter()
+            .any(|w| w.backup == backup && w.original == path.to_string_lossy()));
+    }
+
+    #[test]
+    fn atomic_write_replaces_content_without_leaving_temp_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        write_file_atomic(&path, b"old").unwrap();
+        write_file_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let names: Vec<String> = fs::read_dir(tmp.path())
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["config.json".to_string()]);
+    }
+
+    // ========================================================================
+    // 站点展示顺序
+    // ========================================================================
+
+    fn config_with_sites(ids: &[&str]) -> AppConfig {
+        AppConfig {
+            sites: ids
+                .iter()
+                .map(|id| AiSite {
+                    id: id.to_string(),
+                    ..AiSite::default()
+                })
+                .collect(),
+            site_order: Vec::new(),
+            pinned_site_ids: Vec::new(),
+            ..AppConfig::default()
+        }
+    }
+
+    fn strings(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn ordered_site_ids_puts_pins_first_then_site_order() {
+        let mut config = config_with_sites(&["a", "b", "c", "d"]);
+        config.site_order = strings(&["d", "c", "b", "a"]);
+        config.pinned_site_ids = strings(&["b", "a"]);
+        assert_eq!(ordered_site_ids(&config), strings(&["b", "a", "d", "c"]));
+    }
+
+    #[test]
+    fn ordered_site_ids_skips_deleted_pins_and_duplicates() {
+        let mut config = config_with_sites(&["a", "b", "c"]);
+        config.site_order = strings(&["c", "gone", "c", "a", "b", "a"]);
+        config.pinned_site_ids = strings(&["gone", "b", "b"]);
+        assert_eq!(ordered_site_ids(&config), strings(&["b", "c", "a"]));
+    }
+
+    #[test]
+    fn ordered_site_ids_appends_sites_missing_from_site_order() {
+        let mut config = config_with_sites(&["a", "b", "c"]);
+        config.site_order = strings(&["c"]);
+        assert_eq!(ordered_site_ids(&config), strings(&["c", "a", "b"]));
+    }
+
+    #[test]
+    fn ordered_site_ids_hides_disabled_sites_unless_requested() {
+        let mut config = config_with_sites(&["a", "b", "c"]);
+        config.sites[1].enabled = false;
+        config.pinned_site_ids = strings(&["b"]);
+        assert_eq!(ordered_site_ids(&config), strings(&["a", "c"]));
+        assert_eq!(site_ids_in_order(&config, true), strings(&["b", "a", "c"]));
+    }
+
+    // ========================================================================
+    // 布局事务
+    // ========================================================================
+
+    type Shared<T> = Arc<Mutex<T>>;
+
+    /// 模拟 Webview 副作用层：`live` 为实际存在的 Webview，Tab 的站点 id 即 Tab id；
+    /// 创建第 `stall_at` 个 Webview 时挂起，模拟前端刷新导致 future 被丢弃
+    struct MockLayoutEffects {
+        state: Shared<AppState>,
+        live: Shared<HashSet<String>>,
+        stall_at: usize,
+        created: AtomicUsize,
+    }
+
+    impl LayoutEffects for MockLayoutEffects {
+        fn state(&self) -> std::sync::MutexGuard<'_, AppState> {
+            self.state.lock().unwrap()
+        }
+
+        fn tab_site_id(&self, tab_id: &str) -> Result<String, AppError> {
+            Ok(tab_id.to_string())
+        }
+
+        fn begin_transaction(&self) -> LayoutTransaction {
+            let state = self.state.clone();
+            let live = self.live.clone();
+            LayoutTransaction::with_abort(move || {
+                let live = live.lock().unwrap().clone();
+                if let Some(active) = state
+                    .lock()
+                    .unwrap()
+                    .reconcile_views(|id| live.contains(id))
+                {
+                    state.lock().unwrap().views.insert(active);
+                }
+            })
+        }
+
+        async fn ensure_webview(&self, tab_id: &str, _site_id: &str) -> Result<(), AppError> {
+            if self.created.fetch_add(1, Ordering::SeqCst) == self.stall_at {
+                std::future::pending::<()>().await;
+            }
+            self.live.lock().unwrap().insert(tab_id.to_string());
+            self.state.lock().unwrap().views.insert(tab_id.to_string());
+            Ok(())
+        }
+
+        fn resize(&self) -> Result<(), AppError> {
+            Ok(())
+        }
+
+        fn committed(&self, _pane_tab_ids: &[String]) {}
+    }
+
+    /// 用模拟副作用层执行真实的分屏切换流程
+    async fn mock_set_layout(
+        state: Shared<AppState>,
+        live: Shared<HashSet<String>>,
+        panes: Vec<String>,
+        stall_at: usize,
+    ) {
+        let effects = MockLayoutEffects {
+            state,
+            live,
+            stall_at,
+            created: AtomicUsize::new(0),
+        };
+        apply_split_layout(&effects, panes, &[], None)
+            .await
+            .unwrap();
+    }
+
+    /// 轮询一次后丢弃 future，返回是否已完成
+    fn poll_once_then_drop(fut: impl std::future::Future<Output = ()>) -> bool {
+        let mut fut = std::pin::pin!(fut);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        fut.as_mut().poll(&mut cx).is_ready()
+    }
+
+    fn split_state(panes: &[&str], active: &str) -> (Shared<AppState>, Shared<HashSet<String>>) {
+        let mut state = AppState::default();
+        state.layout.set_split(strings(panes), &[]);
+        state.views = panes.iter().map(|p| p.to_string()).collect();
+        state.set_active(active, active);
+        let live = state.views.clone();
+        (Arc::new(Mutex::new(state)), Arc::new(Mutex::new(live)))
+    }
+
+    #[test]
+    fn dropped_layout_change_keeps_previous_layout_and_converges() {
+        let (state, live) = split_state(&["a", "b"], "a");
+        // b 的 Webview 在切换期间被销毁（如崩溃后回收）
+        live.lock().unwrap().remove("b");
+
+        let done = poll_once_then_drop(mock_set_layout(
+            state.clone(),
+            live.clone(),
+            strings(&["c", "d"]),
+            1,
+        ));
+        assert!(!done);
+
+        let state = state.lock().unwrap();
+        // 布局没有被写成只创建了一侧的 [c, d]，失效的 b 已从分屏中移除
+        assert!(state.layout.is_split());
+        assert_eq!(state.layout.pane_tab_ids, strings(&["a"]));
+        let mut views: Vec<&String> = state.views.iter().collect();
+        views.sort();
+        assert_eq!(views, vec!["a", "c"]);
+        assert_eq!(state.active_tab, "a");
+    }
+
+    #[test]
+    fn completed_layout_change_skips_reconcile() {
+        let (state, live) = split_state(&["a", "b"], "a");
+        live.lock().unwrap().remove("b");
+
+        let done = poll_once_then_drop(mock_set_layout(
+            state.clone(),
+            live.clone(),
+            strings(&["c", "d"]),
+            usize::MAX,
+        ));
+        assert!(done);
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.layout.pane_tab_ids, strings(&["c", "d"]));
+        // 已提交：不做对账，清单中的 b 留给下次自检处理
+        assert!(state.views.contains("b"));
+    }
+
+    #[test]
+    fn reconcile_falls_back_to_single_and_reports_missing_active_tab() {
+        let (state, _) = split_state(&["a", "b"], "b");
+        let mut state = state.lock().unwrap();
+        let rebuild = state.reconcile_views(|_| false);
+        assert_eq!(rebuild.as_deref(), Some("b"));
+        assert!(!state.layout.is_split());
+        assert!(state.views.is_empty());
+    }
+
+    // ========================================================================
+    // 页面文本提取脚本
+    // ========================================================================
+
+    #[test]
+    fn extraction_selector_prefers_site_config_then_builtin_default() {
+        let builtin = AiSite {
+            id: "openai".to_string(),
+            ..AiSite::default()
+        };
+        assert_eq!(
+            extract_selector_for_site(&builtin),
+            "main [data-message-author-role]"
+        );
+        let custom = AiSite {
+            extract_selector: "  .chat .message  ".to_string(),
+            ..builtin
+        };
+        assert_eq!(extract_selector_for_site(&custom), ".chat .message");
+        let unknown = AiSite {
+            id: "custom_x".to_string(),
+            ..AiSite::default()
+        };
+        assert_eq!(extract_selector_for_site(&unknown), "");
+    }
+
+    #[test]
+    fn extraction_script_queries_selector_with_body_fallback() {
+        let script = page_text_extraction_script("main [data-message-author-role]");
+        assert!(script.contains(r#"const sel = "main [data-message-author-role]";"#));
+        assert!(script.contains("document.querySelectorAll(sel)"));
+        assert!(script.contains("parts.length > 0 ? parts.join('\\n\\n') : fallback()"));
+        assert!(script.contains("document?.body?.innerText"));
+
+        let empty = page_text_extraction_script("");
+        assert!(empty.contains(r#"const sel = "";"#));
+        assert!(empty.contains("if (!sel) return fallback();"));
+    }
+
+    #[test]
+    fn extraction_script_escapes_selector_as_js_string() {
+        let script = page_text_extraction_script(r#"a[title="x"]'); alert(1); ('"#);
+        assert!(script.contains(r#"const sel = "a[title=\"x\"]'); alert(1); ('";"#));
+        let script = page_text_extraction_script("</script>\n");
+        assert!(script.contains(r#"const sel = "</script>\n";"#));
+    }
+
+    // ========================================================================
+    // 加载配置时只在清理有改动时写回
+    // ========================================================================
+
+    /// 写入配置并把修改时间拨回过去，之后任何写回都会改变修改时间
+    fn write_config_file(path: &std::path::Path, config: &AppConfig) {
+        fs::write(path, serde_json::to_string_pretty(config).unwrap()).unwrap();
+        let past = std::time::SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+    }
+
+    fn file_snapshot(path: &std::path::Path) -> (std::time::SystemTime, String) {
+        (
+            fs::metadata(path).unwrap().modified().unwrap(),
+            fs::read_to_string(path).unwrap(),
+        )
+    }
+
+    /// 加载配置，返回是否写回了文件
+    fn load_rewrites(path: &std::path::Path) -> bool {
+        let before = file_snapshot(path);
+        load_config_from(path);
+        file_snapshot(path) != before
+    }
+
+    #[test]
+    fn loading_clean_config_does_not_write() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        write_config_file(&path, &AppConfig::default());
+        assert!(!load_rewrites(&path));
+        assert!(!load_rewrites(&path));
+    }
+
+    type ConfigCleanup = (&'static str, fn(&mut AppConfig));
+
+    #[test]
+    fn each_cleanup_rewrites_config_once() {
+        let cleanups: Vec<ConfigCleanup> = vec![
+            ("重复站点", |c| {
+                let dup = c.sites[0].clone();
+                c.sites.push(dup);
+            }),
+            ("缺少内置站点", |c| {
+                c.sites.retain(|s| s.id != "openai");
+                c.site_order.retain(|id| id != "openai");
+            }),
+            ("内置目录版本过旧", |c| {
+                c.builtin_catalog_version = 0
+            }),
+            ("站点顺序含不存在的站点", |c| {
+                c.site_order.push("gone".into())
+            }),
+            ("站点顺序缺少站点", |c| {
+                c.site_order.pop();
+            }),
+            ("侧边栏宽度迁移", |c| {
+                c.sidebar_width = 200.0;
+                c.sidebar_expanded_width = 64.0;
+            }),
+            ("API 地址为空", |c| c.ai_api_base_url.clear()),
+            ("置顶不存在的站点", |c| {
+                c.pinned_site_ids.push("gone".into())
+            }),
+            ("最近使用不存在的站点", |c| {
+                c.recent_site_ids.push("gone".into())
+            }),
+            ("上次活跃站点不存在", |c| {
+                c.last_active_site_id = "gone".into();
+                c.last_active_tab_id = "gone".into();
+            }),
+            ("活跃 API 配置不存在", |c| {
+                c.active_ai_profile_id = "gone".into()
+            }),
+        ];
+        for (name, cleanup) in cleanups {
+            let tmp = tempfile::tempdir().unwrap();
+            let path = tmp.path().join("config.json");
+            let mut config = AppConfig::default();
+            cleanup(&mut config);
+            write_config_file(&path, &config);
+            assert!(load_rewrites(&path), "{}: 应写回", name);
+            // 写回后的配置已是干净的，再次加载不再写入
+            assert!(!load_rewrites(&path), "{}: 不应重复写回", name);
+        }
+
+        // 密钥环不可用时明文 Key 无法迁移，留在配置文件中且不触发写回
+        assert!(!secret_store::backend().is_available());
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        let config = AppConfig {
+            ai_api_key: "sk-plain".into(),
+            ai_api_profiles: vec![AiApiProfile {
+                id: "profile_plain".into(),
+                key: "sk-profile".into(),
+                ..AiApiProfile::default()
+            }],
+            active_ai_profile_id: "profile_plain".into(),
+            ..AppConfig::default()
+        };
+        write_config_file(&path, &config);
+        assert!(!load_rewrites(&path), "密钥环不可用: 不应写回");
+        assert!(!load_rewrites(&path), "密钥环不可用: 不应重复写回");
+        let loaded = load_config_from(&path);
+        assert_eq!(loaded.ai_api_key, "sk-plain");
+        assert_eq!(loaded.ai_api_profiles[0].key, "sk-profile");
+        assert!(!loaded.ai_api_key_in_keyring && !loaded.ai_api_profiles[0].key_in_keyring);
+    }
+
+    // ========================================================================
+    // 站点删除与 Tab 映射一致性
+    // ========================================================================
+
+    /// 模拟 create_tab / remove_site 的锁顺序；状态修改与 reconcile_site_tabs 共用
+    /// `AppState::remove_stale_site_tabs`，不关闭真实的 Webview
+    struct MockTabs {
+        config: Mutex<AppConfig>,
+        state: Mutex<AppState>,
+    }
+
+    impl MockTabs {
+        fn new(site_ids: &[&str]) -> Self {
+            Self {
+                config: Mutex::new(config_with_sites(site_ids)),
+                state: Mutex::new(AppState::default()),
+            }
+        }
+
+        fn create_tab(&self, site_id: &str, tab_id: &str) -> Result<(), String> {
+            let config = self.config.lock().unwrap();
+            self.state
+                .lock()
+                .unwrap()
+                .register_tab(&config, site_id, tab_id)
+        }
+
+        fn remove_site(&self, site_id: &str) -> Vec<String> {
+            let site_ids: HashSet<String> = {
+                let mut config = self.config.lock().unwrap();
+                config.sites.retain(|s| s.id != site_id);
+                config.sites.iter().map(|s| s.id.clone()).collect()
+            };
+            self.state.lock().unwrap().remove_stale_site_tabs(&site_ids)
+        }
+
+        fn stale_tabs(&self) -> Vec<String> {
+            let site_ids: HashSet<String> = self
+                .config
+                .lock()
+                .unwrap()
+                .sites
+                .iter()
+                .map(|s| s.id.clone())
+                .collect();
+            self.state.lock().unwrap().stale_site_tabs(&site_ids)
+        }
+    }
+
+    #[test]
+    fn create_tab_after_site_removed_is_rejected() {
+        let mock = MockTabs::new(&["a", "x"]);
+        mock.remove_site("x");
+        let err = mock.create_tab("x", "x_1").unwrap_err();
+        assert!(err.contains("站点不存在"), "{}", err);
+        assert!(mock.state.lock().unwrap().tabs.is_empty());
+    }
+
+    #[test]
+    fn removing_site_closes_its_tabs_and_fixes_layout() {
+        let mock = MockTabs::new(&["a", "x"]);
+        mock.create_tab("x", "x_1").unwrap();
+        mock.create_tab("a", "a_1").unwrap();
+        {
+            let mut state = mock.state.lock().unwrap();
+            state.views.extend(strings(&["x", "x_1", "a_1"]));
+            state.layout.set_split(strings(&["x_1", "a_1"]), &[]);
+            state.set_active("x_1", "x");
+        }
+
+        let mut removed = mock.remove_site("x");
+        removed.sort();
+        assert_eq!(removed, strings(&["x", "x_1"]));
+        let state = mock.state.lock().unwrap();
+        assert_eq!(state.tabs.keys().collect::<Vec<_>>(), vec!["a_1"]);
+        assert_eq!(state.layout.pane_tab_ids, strings(&["a_1"]));
+        assert!(state.active_tab.is_empty());
+    }
+
+    #[test]
+    fn concurrent_create_and_remove_leave_no_stale_tabs() {
+        for _ in 0..20 {
+            let mock = Arc::new(MockTabs::new(&["a", "x"]));
+            let creators: Vec<_> = (0..4)
+                .map(|t| {
+                    let mock = mock.clone();
+                    std::thread::spawn(move || {
+                        for i in 0..50 {
+                            let _ = mock.create_tab("x", &format!("x_{}_{}", t, i));
+                        }
+                    })
+                })
+                .collect();
+            let remover = {
+                let mock = mock.clone();
+                std::thread::spawn(move || {
+                    std::thread::yield_now();
+                    mock.remove_site("x");
+                })
+            };
+            for t in creators {
+                t.join().unwrap();
+            }
+            remover.join().unwrap();
+            assert!(mock.stale_tabs().is_empty());
+            assert!(mock
+                .state
+                .lock()
+                .unwrap()
+                .tabs
+                .values()
+                .all(|site_id| site_id != "x"));
+        }
+    }
+
+    // ========================================================================
+    // 演示模式
+    // ========================================================================
+
+    #[test]
+    fn demo_mode_summarizes_without_network() {
+        use_temp_config_dir();
+        let previous = {
+            let mut config = APP_CONFIG.lock().unwrap();
+            std::mem::replace(&mut config.demo_mode, true)
+        };
+        let audit = ai_client::ApiCallContext::new(ai_client::ApiFeature::ManualSummarize);
+        // 网络层在测试中会 panic，能返回结果即说明整个流程没有联网
+        let result = tauri::async_runtime::block_on(summarize_text_inner(
+            "Hello demo\n\nSecond paragraph".to_string(),
+            None,
+            None,
+            None,
+            audit,
+        ));
+        APP_CONFIG.lock().unwrap().demo_mode = previous;
+
+        let summary = result.unwrap();
+        assert!(summary.contains("Hello demo Second paragraph"));
+        assert!(summary.starts_with("【演示模式") || summary.starts_with("[Demo mode"));
+    }
+
+    // ========================================================================
+    // AI 服务：无 Key 的本地模型
+    // ========================================================================
+
+    fn ai_config(provider: AiProvider, auth: AiAuthMode, base_url: &str, key: &str) -> AppConfig {
+        AppConfig {
+            ai_api_provider: provider,
+            ai_api_auth: auth,
+            ai_api_base_url: base_url.to_string(),
+            ai_api_model: "llama3".to_string(),
+            ai_api_key: key.to_string(),
+            ai_api_key_in_keyring: false,
+            active_ai_profile_id: String::new(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn keyless_local_endpoint_needs_no_key() {
+        let config = ai_config(
+            AiProvider::OpenAi,
+            AiAuthMode::None,
+            "http://localhost:11434/v1/",
+            "",
+        );
+        let settings = resolve_ai_settings(&config).unwrap();
+        assert_eq!(settings.base_url, "http://localhost:11434/v1");
+        assert!(!settings.missing_key());
+        assert!(matches!(settings.auth(), ai_client::ApiAuth::None));
+        assert!(settings.is_local());
+        assert_eq!(settings.summary_timeout(), LOCAL_MODEL_SUMMARY_TIMEOUT);
+        assert_eq!(summary_total_timeout(&config), LOCAL_MODEL_SUMMARY_TIMEOUT);
+
+        let (url, body) = settings.chat_request(None, "hi", None, false);
+        assert_eq!(url, "http://localhost:11434/v1/chat/completions");
+        assert_eq!(body["model"], "llama3");
+    }
+
+    #[test]
+    fn key_is_required_unless_openai_compatible_without_auth() {
+        let cases = [
+            (AiProvider::OpenAi, AiAuthMode::Bearer, true),
+            (AiProvider::OpenAi, AiAuthMode::None, false),
+            // Anthropic / Azure 忽略 auth 设置，始终需要 Key
+            (AiProvider::Anthropic, AiAuthMode::None, true),
+            (AiProvider::Azure, AiAuthMode::None, true),
+        ];
+        for (provider, auth, required) in cases {
+            let config = ai_config(provider, auth, "https://example.com", "");
+            let settings = resolve_ai_settings(&config).unwrap();
+            assert_eq!(
+                settings.missing_key(),
+                required,
+                "{:?} {:?}",
+                provider,
+                auth
+            );
+
+            let config = ai_config(provider, auth, "https://example.com", " sk-1 ");
+            let settings = resolve_ai_settings(&config).unwrap();
+            assert!(!settings.missing_key(), "{:?} {:?}", provider, auth);
+        }
+    }
+
+    #[test]
+    fn only_loopback_base_urls_count_as_local() {
+        for (base_url, local) in [
+            ("http://localhost:11434/v1", true),
+            ("http://LOCALHOST:8080", true),
+            ("http://127.0.0.1:1234/v1", true),
+            ("http://[::1]:11434/v1", true),
+            ("http://192.168.1.10:11434/v1", false),
+            ("https://api.openai.com/v1", false),
+            ("http://localhost.example.com/v1", false),
+            ("not a url", false),
+        ] {
+            let config = ai_config(AiProvider::OpenAi, AiAuthMode::None, base_url, "");
+            let settings = resolve_ai_settings(&config).unwrap();
+            assert_eq!(settings.is_local(), local, "{}", base_url);
+            let expected = if local {
+                LOCAL_MODEL_SUMMARY_TIMEOUT
+            } else {
+                SUMMARY_TIMEOUT
+            };
+            assert_eq!(settings.summary_timeout(), expected, "{}", base_url);
+        }
+
+        // 用户配置的超时优先于本地模型的默认值
+        let config = AppConfig {
+            summary_total_timeout_secs: Some(30),
+            ..ai_config(
+                AiProvider::OpenAi,
+                AiAuthMode::None,
+                "http://localhost:11434/v1",
+                "",
+            )
+        };
+        assert_eq!(summary_total_timeout(&config), Duration::from_secs(30));
+    }
+
+    // ========================================================================
+    // AI 服务：Azure OpenAI / Anthropic 请求地址
+    // ========================================================================
+
+    #[test]
+    fn azure_chat_url_uses_deployment_and_api_version() {
+        let config = AppConfig {
+            ai_api_deployment: " gpt4o-prod ".to_string(),
+            ai_api_version: "2024-10-21".to_string(),
+            ..ai_config(
+                AiProvider::Azure,
+                AiAuthMode::Bearer,
+                "https://contoso.openai.azure.com/",
+                "azure-key",
+            )
+        };
+        let settings = resolve_ai_settings(&config).unwrap();
+        assert!(matches!(
+            settings.auth(),
+            ai_client::ApiAuth::Azure("azure-key")
+        ));
+
+        let (url, body) = settings.chat_request(Some("sys"), "hi", Some(256), true);
+        assert_eq!(
+            url,
+            "https://contoso.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "hi");
+        assert_eq!(body["max_tokens"], 256);
+        // 旧版 api-version 不识别 stream_options
+        assert!(body.get("stream_options").is_none());
+        assert_eq!(
+            settings.models_url(),
+            "https://contoso.openai.azure.com/openai/models?api-version=2024-10-21"
+        );
+    }
+
+    #[test]
+    fn azure_falls_back_to_model_and_default_api_version() {
+        let config = ai_config(
+            AiProvider::Azure,
+            AiAuthMode::Bearer,
+            "https://contoso.openai.azure.com",
+            "azure-key",
+        );
+        let settings = resolve_ai_settings(&config).unwrap();
+        assert!(!settings.missing_model());
+        let (url, _) = settings.chat_request(None, "hi", None, false);
+        assert_eq!(
+            url,
+            format!(
+                "https://contoso.openai.azure.com/openai/deployments/llama3/chat/completions?api-version={}",
+                AZURE_DEFAULT_API_VERSION
+            )
+        );
+
+        let config = AppConfig {
+            ai_api_model: String::new(),
+            ..config
+        };
+        assert!(resolve_ai_settings(&config).unwrap().missing_model());
+    }
+
+    #[test]
+    fn openai_and_anthropic_chat_urls() {
+        let config = ai_config(AiProvider::OpenAi, AiAuthMode::Bearer, "", "sk-1");
+        let settings = resolve_ai_settings(&config).unwrap();
+        let (url, body) = settings.chat_request(None, "hi", None, true);
+        assert_eq!(url, "https://api.openai.com/v1/chat/completions");
+        assert_eq!(body["stream_options"]["include_usage"], true);
+        assert!(body.get("max_tokens").is_none());
+
+        let config = ai_config(AiProvider::Anthropic, AiAuthMode::Bearer, "", "sk-ant");
+        let settings = resolve_ai_settings(&config).unwrap();
+        assert!(matches!(
+            settings.auth(),
+            ai_client::ApiAuth::Anthropic("sk-ant")
+        ));
+        let (url, body) = settings.chat_request(Some("sys"), "hi", None, false);
+        assert_eq!(url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(body["system"], "sys");
+        assert_eq!(body["max_tokens"], ANTHROPIC_MAX_TOKENS);
+        assert_eq!(body["messages"][0]["role"], "user");
+    }
+
+    // ========================================================================
+    // 总结提示词模板
+    // ========================================================================
+
+    fn template(id: &str, body: &str) -> PromptTemplate {
+        PromptTemplate {
+            id: id.to_string(),
+            name: id.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    fn template_config() -> AppConfig {
+        let mut config = config_with_sites(&["plain", "override", "named", "dangling"]);
+        config.summary_prompt_template = "GLOBAL".to_string();
+        config.prompt_templates = vec![
+            template("meeting", "MEETING"),
+            template("review", "REVIEW"),
+            template("blank", "  \n"),
+        ];
+        config.sites[1].summary_prompt_override = "SITE".to_string();
+        config.sites[2].summary_prompt_override = "SITE".to_string();
+        config.sites[2].prompt_template_id = "review".to_string();
+        // 引用的模板已被删除
+        config.sites[3].prompt_template_id = "deleted".to_string();
+        config.sites[3].summary_prompt_override = "SITE".to_string();
+        config
+    }
+
+    #[test]
+    fn summary_template_resolution_order() {
+        let config = template_config();
+        let resolve = |site: Option<&str>, template_id: Option<&str>| {
+            resolve_summary_template(&config, site, template_id)
+        };
+
+        // 指定的命名模板优先于一切
+        assert_eq!(resolve(Some("named"), Some("meeting")), "MEETING");
+        assert_eq!(resolve(Some("override"), Some("meeting")), "MEETING");
+        assert_eq!(resolve(None, Some("meeting")), "MEETING");
+        // 站点引用的命名模板优先于站点自定义模板
+        assert_eq!(resolve(Some("named"), None), "REVIEW");
+        assert_eq!(resolve(Some("override"), None), "SITE");
+        assert_eq!(resolve(Some("plain"), None), "GLOBAL");
+        assert_eq!(resolve(None, None), "GLOBAL");
+        assert_eq!(resolve(Some("unknown_site"), None), "GLOBAL");
+    }
+
+    #[test]
+    fn missing_or_blank_templates_fall_back() {
+        let config = template_config();
+        let resolve = |site: Option<&str>, template_id: Option<&str>| {
+            resolve_summary_template(&config, site, template_id)
+        };
+
+        // 指定的模板不存在、为空或 id 为空时跳过该级
+        assert_eq!(resolve(Some("named"), Some("deleted")), "REVIEW");
+        assert_eq!(resolve(Some("override"), Some("blank")), "SITE");
+        assert_eq!(resolve(Some("plain"), Some("")), "GLOBAL");
+        // 站点引用的模板已被删除时回退到站点自定义模板
+        assert_eq!(resolve(Some("dangling"), None), "SITE");
+
+        let config = AppConfig {
+            summary_prompt_template: "   ".to_string(),
+            ..template_config()
+        };
+        assert_eq!(
+            resolve_summary_template(&config, Some("plain"), None),
+            default_summary_prompt_template()
+        );
+    }
+
+    #[test]
+    fn resolved_template_is_rendered_by_build_summary_prompt() {
+        let mut config = template_config();
+        config.prompt_templates[0].body = "Notes in {language}:\n{text}".to_string();
+        let template = resolve_summary_template(&config, Some("override"), Some("meeting"));
+        let prompt = build_summary_prompt(&template, "English", "body", &PromptContext::default());
+        assert_eq!(prompt, "Notes in English:\nbody");
+    }
+
+    fn sample_context() -> PromptContext {
+        PromptContext {
+            site_name: "ChatGPT".to_string(),
+            site_url: "https://chatgpt.com".to_string(),
+            project_title: "Q3 计划".to_string(),
+        }
+    }
+
+    #[test]
+    fn prompt_variables_are_substituted() {
+        let prompt = build_summary_prompt(
+            "[{site_name}]({site_url}) / {project_title} / {language}\n{text}",
+            "中文",
+            "正文",
+            &sample_context(),
+        );
+        assert_eq!(
+            prompt,
+            "[ChatGPT](https://chatgpt.com) / Q3 计划 / 中文\n正文"
+        );
+
+        // 每个公开的变量都能被替换（不会原样留在结果中）
+        for (name, _) in PROMPT_VARIABLES {
+            let placeholder = format!("{{{}}}", name);
+            let prompt = build_summary_prompt(&placeholder, "中文", "正文", &sample_context());
+            assert!(!prompt.contains(&placeholder), "{}", name);
+        }
+    }
+
+    #[test]
+    fn date_and_time_use_local_iso_format() {
+        let prompt = build_summary_prompt(
+            "{date}|{time}|{language}{text}",
+            "",
+            "",
+            &PromptContext::default(),
+        );
+        let (date, time) = prompt.split_once('|').unwrap();
+        let time = time.trim_end_matches('|');
+        let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        let parts: Vec<&str> = date.split('-').collect();
+        assert_eq!(parts.len(), 3, "{}", date);
+        assert!(parts[0].len() == 4 && parts[1].len() == 2 && parts[2].len() == 2);
+        assert!(parts.iter().all(|p| digits(p)), "{}", date);
+        let (hour, minute) = time.split_once(':').unwrap();
+        assert!(hour.len() == 2 && minute.len() == 2, "{}", time);
+        assert!(digits(hour) && digits(minute), "{}", time);
+    }
+
+    #[test]
+    fn missing_context_values_render_empty() {
+        let prompt = build_summary_prompt(
+            "来源：{site_name}（{site_url}）项目：{project_title}。{language}{text}",
+            "English",
+            "body",
+            &PromptContext::default(),
+        );
+        assert_eq!(prompt, "来源：（）项目：。Englishbody");
+    }
+
+    #[test]
+    fn unknown_placeholders_and_literal_braces_are_kept() {
+        let lookup = |name: &str| (name == "text").then(|| "T".to_string());
+        let cases = [
+            ("{unknown} {text}", "{unknown} T"),
+            ("{} {text}", "{} T"),
+            ("{ text } {text}", "{ text } T"),
+            ("json: {\"a\": {text}}", "json: {\"a\": T}"),
+            ("{{text}}", "{T}"),
+            ("open { {text}", "open { T"),
+            ("close } {text}", "close } T"),
+            ("dangling {text", "dangling {text"),
+            ("{site-name}", "{site-name}"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(
+                render_prompt_template(template, lookup),
+                expected,
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn substituted_values_are_not_expanded_again() {
+        let prompt = build_summary_prompt(
+            "{language}: {text}",
+            "English",
+            "page says {date} and {language}",
+            &sample_context(),
+        );
+        assert_eq!(prompt, "English: page says {date} and {language}");
+    }
+
+    #[test]
+    fn language_and_text_are_appended_when_absent() {
+        let prompt = build_summary_prompt("Summarize.", "English", "body", &sample_context());
+        assert_eq!(prompt, "Summarize.\n\nLanguage: English\n\nbody");
+    }
+
+    // ========================================================================
+    // 总结规模估算
+    // ========================================================================
+
+    #[test]
+    fn token_estimate_heuristic() {
+        assert_eq!(estimate_tokens(""), 0);
+        // 非 CJK 按约 4 字符 1 token 向上取整
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens(&"word ".repeat(100)), 125);
+        // CJK 约 1 字 1 token
+        assert_eq!(estimate_tokens("你好世界"), 4);
+        assert_eq!(estimate_tokens("こんにちは"), 5);
+        // 混合文本分别计算，全角空白不计入宽字符
+        assert_eq!(estimate_tokens("你好 world"), 2 + 2);
+        assert_eq!(estimate_tokens("你\u{3000}好"), 2 + 1);
+
+        let estimate = SummaryEstimate::of("你好 world");
+        assert_eq!(estimate.chars, 8);
+        assert_eq!(estimate.approx_tokens, 4);
+    }
+
+    #[test]
+    fn summary_size_limit() {
+        let estimate = SummaryEstimate::of(&"a".repeat(1000));
+        assert!(check_summary_size(estimate, None).is_ok());
+        assert!(check_summary_size(estimate, Some(1000)).is_ok());
+        let err = check_summary_size(estimate, Some(999)).unwrap_err();
+        assert!(err.contains("1000") && err.contains("999") && err.contains("250"));
+    }
+
+    #[test]
+    fn context_entries_store_the_estimate() {
+        let mut project = ProjectContext::new("p1".to_string(), "P".to_string());
+        project.append_entry("chatgpt", "你好 world", "summary", 10);
+        let estimate = project.entries[0].estimate.unwrap();
+        assert_eq!((estimate.chars, estimate.approx_tokens), (8, 4));
+
+        // 旧记录没有估算字段
+        let mut value = serde_json::to_value(&project.entries[0]).unwrap();
+        value.as_object_mut().unwrap().remove("estimate");
+        let entry: ContextEntry = serde_json::from_value(value).unwrap();
+        assert!(entry.estimate.is_none());
+    }
+
+    // ========================================================================
+    // 并发总结与页面文本回传
+    // ========================================================================
+
+    #[test]
+    fn summary_guard_is_per_tab() {
+        let left = SummaryInProgressGuard::acquire(&strings(&["guard_left"])).unwrap();
+        // 不同 Tab 可并行，同一 Tab 重复总结被拒绝
+        let right = SummaryInProgressGuard::acquire(&strings(&["guard_right"])).unwrap();
+        assert!(SummaryInProgressGuard::acquire(&strings(&["guard_left"])).is_err());
+
+        // 多 Tab 总结要么全部占用，要么一个都不占用
+        assert!(
+            SummaryInProgressGuard::acquire(&strings(&["guard_other", "guard_right"])).is_err()
+        );
+        assert!(!SUMMARIES_IN_PROGRESS
+            .lock()
+            .unwrap()
+            .contains("guard_other"));
+
+        drop(left);
+        let again = SummaryInProgressGuard::acquire(&strings(&["guard_left"])).unwrap();
+        drop(right);
+        drop(again);
+        let in_progress = SUMMARIES_IN_PROGRESS.lock().unwrap();
+        assert!(!in_progress.contains("guard_left") && !in_progress.contains("guard_right"));
+    }
+
+    /// 模拟一次“占用 Tab → 登记提取 → 页面延迟回传 → 等待结果”的总结流程
+    async fn simulated_extract(tab_id: &str, page_text: &str, delay_ms: u64) -> (String, String) {
+        let _guard = SummaryInProgressGuard::acquire(&strings(&[tab_id])).unwrap();
+        let request = PendingExtractRequest::register(MAX_SUBMITTED_PAGE_TEXT_BYTES);
+        let request_id = request.request_id.clone();
+        let (rid, token, text) = (
+            request.request_id.clone(),
+            request.token.clone(),
+            page_text.to_string(),
+        );
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            aihub_submit_page_text(rid, token, text).await.unwrap();
+        });
+        let text = request.wait(Duration::from_secs(5)).await.unwrap().unwrap();
+        (request_id, text)
+    }
+
+    #[test]
+    fn concurrent_summaries_on_different_tabs() {
+        let ((left_id, left), (right_id, right)) = tauri::async_runtime::block_on(async {
+            // 先开始的一方较晚完成，两个流程的执行区间重叠
+            let left =
+                tauri::async_runtime::spawn(simulated_extract("concurrent_left", "left page", 80));
+            let right = tauri::async_runtime::spawn(simulated_extract(
+                "concurrent_right",
+                "right page",
+                10,
+            ));
+            (left.await.unwrap(), right.await.unwrap())
+        });
+        assert_eq!(left, "left page");
+        assert_eq!(right, "right page");
+        assert_ne!(left_id, right_id);
+
+        let pending = PENDING_EXTRACTS.lock().unwrap();
+        assert!(!pending.contains_key(&left_id) && !pending.contains_key(&right_id));
+        let in_progress = SUMMARIES_IN_PROGRESS.lock().unwrap();
+        assert!(!in_progress.contains("concurrent_left"));
+        assert!(!in_progress.contains("concurrent_right"));
+    }
+
+    #[test]
+    fn submitted_text_requires_the_matching_token() {
+        let request = PendingExtractRequest::register(MAX_SUBMITTED_PAGE_TEXT_BYTES);
+        let rid = request.request_id.clone();
+        let token = request.token.clone();
+        let result = tauri::async_runtime::block_on(async {
+            // 错误的 token 被忽略，登记随之作废，等待方不会收到伪造的文本
+            aihub_submit_page_text(rid.clone(), "forged".to_string(), "evil".to_string())
+                .await
+                .unwrap();
+            aihub_submit_page_text(rid.clone(), token, "real".to_string())
+                .await
+                .unwrap();
+            request.wait(Duration::from_secs(1)).await
+        });
+        assert!(result.is_err());
+        assert!(!PENDING_EXTRACTS.lock().unwrap().contains_key(&rid));
+    }
+
+    fn pending_extract(
+        age: Duration,
+    ) -> (PendingExtract, oneshot::Receiver<Result<String, String>>) {
+        let (tx, rx) = oneshot::channel();
+        let pending = PendingExtract {
+            token: "token".to_string(),
+            tx,
+            created_at: std::time::Instant::now() - age,
+            max_bytes: MAX_SUBMITTED_PAGE_TEXT_BYTES,
+        };
+        (pending, rx)
+    }
+
+    #[test]
+    fn sweep_removes_stale_and_abandoned_extracts() {
+        let mut pending = HashMap::new();
+        let (fresh, _fresh_rx) = pending_extract(Duration::from_secs(1));
+        let (stale, _stale_rx) = pending_extract(PENDING_EXTRACT_MAX_AGE + Duration::from_secs(1));
+        let (abandoned, abandoned_rx) = pending_extract(Duration::from_secs(1));
+        drop(abandoned_rx);
+        pending.insert("fresh".to_string(), fresh);
+        pending.insert("stale".to_string(), stale);
+        pending.insert("abandoned".to_string(), abandoned);
+
+        assert_eq!(sweep_pending_extracts(&mut pending), 2);
+        assert_eq!(pending.keys().collect::<Vec<_>>(), vec!["fresh"]);
+        assert_eq!(sweep_pending_extracts(&mut pending), 0);
+    }
+
+    #[test]
+    fn timed_out_extract_is_removed() {
+        let request = PendingExtractRequest::register(MAX_SUBMITTED_PAGE_TEXT_BYTES);
+        let rid = request.request_id.clone();
+        assert!(PENDING_EXTRACTS.lock().unwrap().contains_key(&rid));
+
+        // 页面始终不回传
+        let result =
+            tauri::async_runtime::block_on(request.wait(Duration::from_millis(20))).unwrap();
+        assert_eq!(result, None);
+        assert!(!PENDING_EXTRACTS.lock().unwrap().contains_key(&rid));
+
+        // 迟到的回传找不到登记，直接忽略
+        tauri::async_runtime::block_on(aihub_submit_page_text(
+            rid,
+            "token".to_string(),
+            "late".to_string(),
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn abandoned_extract_is_removed_on_drop() {
+        let request = PendingExtractRequest::register(MAX_SUBMITTED_PAGE_TEXT_BYTES);
+        let rid = request.request_id.clone();
+        // 例如 child.eval 失败后提前返回
+        drop(request);
+        assert!(!PENDING_EXTRACTS.lock().unwrap().contains_key(&rid));
+    }
+
+    #[test]
+    fn oversized_page_text_is_rejected() {
+        let request = PendingExtractRequest::register(16);
+        let rid = request.request_id.clone();
+        let token = request.token.clone();
+        let (submitted, waited) = tauri::async_runtime::block_on(async {
+            let submitted = aihub_submit_page_text(rid.clone(), token, "x".repeat(17)).await;
+            (submitted, request.wait(Duration::from_secs(1)).await)
+        });
+        assert!(submitted.is_err());
+        assert!(waited.unwrap_err().contains("过大"));
+        assert!(!PENDING_EXTRACTS.lock().unwrap().contains_key(&rid));
+    }
+
+    // ========================================================================
+    // Tab 运行时状态
+    // ========================================================================
+
+    fn fill_tab_runtime(tab_id: &str, zoom: Option<f64>, muted: bool) {
+        update_tab_runtime(tab_id, |runtime| {
+            runtime.last_used_at = Some(1);
+            runtime.hibernated = true;
+            runtime.last_requested_url = Some("https://example.com/a".to_string());
+            runtime.bridge.ok = Some(true);
+            runtime.bridge.load_seq = 3;
+            runtime.page.title = "title".to_string();
+            runtime.page.loading = true;
+            runtime.detached_window = Some("detached_1".to_string());
+            runtime.zoom = zoom;
+            runtime.muted = muted;
+        });
+        cache_extraction(tab_id, "site", "https://example.com/a".to_string(), "text");
+        assert!(register_retry_attempt(tab_id, "https://example.com/a"));
+    }
+
+    #[test]
+    fn closing_the_webview_keeps_only_zoom_and_mute() {
+        fill_tab_runtime("runtime_kept", Some(1.5), true);
+
+        let removed = reset_tab_runtime("runtime_kept").unwrap();
+        assert_eq!(removed.detached_window.as_deref(), Some("detached_1"));
+        assert!(removed.extraction.is_some() && removed.retry.is_some());
+
+        let kept = read_tab_runtime("runtime_kept", |runtime| runtime.clone()).unwrap();
+        assert_eq!(kept.zoom, Some(1.5));
+        assert!(kept.muted);
+        assert!(kept.last_used_at.is_none() && !kept.hibernated);
+        assert!(kept.last_requested_url.is_none() && kept.retry.is_none());
+        assert!(kept.bridge.ok.is_none() && kept.bridge.load_seq == 0);
+        assert!(kept.page.title.is_empty() && !kept.page.loading);
+        assert!(kept.extraction.is_none() && kept.detached_window.is_none());
+        assert!(is_tab_muted("runtime_kept"));
+        assert_eq!(tab_zoom("runtime_kept", "missing_site"), 1.5);
+
+        // 关闭 Tab：整体移除
+        TAB_RUNTIME.lock().unwrap().remove("runtime_kept");
+        assert!(read_tab_runtime("runtime_kept", |_| ()).is_none());
+        assert!(!is_tab_muted("runtime_kept"));
+    }
+
+    #[test]
+    fn closing_the_webview_without_settings_removes_the_entry() {
+        fill_tab_runtime("runtime_plain", None, false);
+        assert!(hibernated_tab_ids().contains(&"runtime_plain".to_string()));
+        assert!(detached_tab_ids().contains(&"runtime_plain".to_string()));
+
+        reset_tab_runtime("runtime_plain");
+        assert!(read_tab_runtime("runtime_plain", |_| ()).is_none());
+        assert!(!hibernated_tab_ids().contains(&"runtime_plain".to_string()));
+        assert!(!is_tab_detached("runtime_plain"));
+        assert!(cached_extraction("runtime_plain").is_none());
+        assert!(reset_tab_runtime("runtime_plain").is_none());
+    }
+
+    #[test]
+    fn each_url_is_retried_once() {
+        let tab_id = "runtime_retry";
+        assert!(register_retry_attempt(tab_id, "https://example.com/a"));
+        assert!(!register_retry_attempt(tab_id, "https://example.com/a"));
+        // 放弃后重新计数；换了 URL 也重新计数
+        assert!(register_retry_attempt(tab_id, "https://example.com/a"));
+        assert!(register_retry_attempt(tab_id, "https://example.com/b"));
+        reset_retry_state(tab_id);
+        assert!(register_retry_attempt(tab_id, "https://example.com/b"));
+        TAB_RUNTIME.lock().unwrap().remove(tab_id);
+    }
+
+    #[test]
+    fn bridge_state_resets_on_each_load() {
+        let tab_id = "runtime_bridge";
+        assert_eq!(tab_bridge_ok(tab_id), None);
+        assert_eq!(reset_bridge_state(tab_id), 1);
+        update_tab_runtime(tab_id, |runtime| {
+            runtime.bridge.ok = Some(true);
+            runtime.bridge.unresponsive = true;
+        });
+        assert_eq!(tab_bridge_ok(tab_id), Some(true));
+        assert_eq!(reset_bridge_state(tab_id), 2);
+        assert_eq!(tab_bridge_ok(tab_id), None);
+        assert!(!read_tab_runtime(tab_id, |runtime| runtime.bridge.unresponsive).unwrap());
+        TAB_RUNTIME.lock().unwrap().remove(tab_id);
+    }
+
+    // ========================================================================
+    // 重启 Tab
+    // ========================================================================
+
+    #[test]
+    fn restart_bookkeeping_keeps_the_tab_and_its_settings() {
+        use_temp_config_dir();
+        let tab_id = "restart_site_tab1";
+        {
+            let mut state = APP_STATE.lock().unwrap();
+            state
+                .tabs
+                .insert(tab_id.to_string(), "restart_site".to_string());
+            state.views.insert(tab_id.to_string());
+        }
+        update_tab_runtime(tab_id, |runtime| {
+            runtime.zoom = Some(1.25);
+            runtime.muted = true;
+            runtime.bridge.ok = Some(true);
+            runtime.bridge.unresponsive = true;
+            runtime.page.loading = true;
+        });
+        assert_eq!(restartable_tab_site_id(tab_id).unwrap(), "restart_site");
+
+        assert_eq!(forget_tab_webview(tab_id), None);
+
+        // Webview 记录清除，ensure_tab_webview 会重新创建；Tab 映射保留
+        {
+            let state = APP_STATE.lock().unwrap();
+            assert!(!state.views.contains(tab_id));
+            assert_eq!(
+                state.tabs.get(tab_id).map(String::as_str),
+                Some("restart_site")
+            );
+        }
+        assert_eq!(restartable_tab_site_id(tab_id).unwrap(), "restart_site");
+        // 存活检测与加载状态重新开始，缩放与静音保留
+        let runtime = read_tab_runtime(tab_id, |runtime| runtime.clone()).unwrap();
+        assert!(!runtime.bridge.unresponsive && runtime.bridge.ok.is_none());
+        assert!(!runtime.page.loading && !runtime.hibernated);
+        assert_eq!(runtime.zoom, Some(1.25));
+        assert!(runtime.muted);
+
+        APP_STATE.lock().unwrap().tabs.remove(tab_id);
+        TAB_RUNTIME.lock().unwrap().remove(tab_id);
+    }
+
+    #[test]
+    fn restart_rejects_unknown_and_detached_tabs() {
+        use_temp_config_dir();
+        assert!(matches!(
+            restartable_tab_site_id("restart_missing"),
+            Err(AppError::TabNotFound(_))
+        ));
+
+        let tab_id = "restart_detached_tab";
+        APP_STATE
+            .lock()
+            .unwrap()
+            .tabs
+            .insert(tab_id.to_string(), "restart_site".to_string());
+        update_tab_runtime(tab_id, |runtime| {
+            runtime.detached_window = Some("detached_restart".to_string())
+        });
+        assert!(restartable_tab_site_id(tab_id).is_err());
+
+        // 关闭 Webview 时一并返回独立窗口，由调用方销毁
+        assert_eq!(
+            forget_tab_webview(tab_id).as_deref(),
+            Some("detached_restart")
+        );
+        assert!(restartable_tab_site_id(tab_id).is_ok());
+        APP_STATE.lock().unwrap().tabs.remove(tab_id);
+    }
+
+    #[test]
+    fn about_blank_fails_only_when_another_url_was_requested() {
+        let url = |raw: &str| tauri::Url::parse(raw).unwrap();
+        // 初次创建：尚未开始加载任何地址
+        assert!(!is_failed_load(&url("about:blank"), ""));
+        // 弹窗中间页：本次加载请求的就是 about:blank
+        assert!(!is_failed_load(&url("about:blank"), "about:blank"));
+        // 请求站点地址却停在 about:blank
+        assert!(is_failed_load(
+            &url("about:blank"),
+            "https://chat.example.com/"
+        ));
+        // 浏览器错误页始终算失败
+        assert!(is_failed_load(&url("chrome-error://chromewebdata/"), ""));
+        assert!(is_failed_load(
+            &url("edge-error://network"),
+            "https://chat.example.com/"
+        ));
+        assert!(!is_failed_load(
+            &url("https://chat.example.com/c/1"),
+            "https://chat.example.com/"
+        ));
+    }
+
+    #[test]
+    fn blank_and_error_pages_are_not_remembered_as_retry_targets() {
+        let tab = "test-tab-1300";
+        let url = |raw: &str| tauri::Url::parse(raw).unwrap();
+        mark_last_requested_url(tab, &url("https://chat.example.com/"));
+        mark_last_requested_url(tab, &url("about:blank"));
+        mark_last_requested_url(tab, &url("chrome-error://chromewebdata/"));
+        assert_eq!(
+            read_tab_runtime(tab, |runtime| runtime.last_requested_url.clone()).flatten(),
+            Some("https://chat.example.com/".to_string())
+        );
+        TAB_RUNTIME.lock().unwrap().remove(tab);
+    }
+
+    // ========================================================================
+    // 项目导出为 Markdown
+    // ========================================================================
+
+    /// 时间按运行测试的机器的本地时区渲染，快照中替换为占位符
+    fn redact_markdown_times(md: &str) -> String {
+        md.lines()
+            .map(|line| {
+                for prefix in ["- 创建时间: ", "- 更新时间: ", "- Created: ", "- Updated: "]
+                {
+                    if line.starts_with(prefix) {
+                        return format!("{}<time>", prefix);
+                    }
+                }
+                match line.strip_prefix("### ") {
+                    Some(rest) => match rest.split_once(" · ") {
+                        Some((_, sites)) => format!("### <time> · {}", sites),
+                        None => "### <time>".to_string(),
+                    },
+                    None => line.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn markdown_project() -> ProjectContext {
+        let mut project = ProjectContext::new("p_markdown".to_string(), "  Q3 计划  ".to_string());
+        project.notes = "- 待办一\n- 待办二".to_string();
+        project.sources = vec![
+            ProjectSource {
+                tab_id: "t1".to_string(),
+                site_id: "chatgpt".to_string(),
+                site_name: "ChatGPT".to_string(),
+                title: " 对话 ".to_string(),
+                url: "https://chatgpt.com/c/1".to_string(),
+            },
+            ProjectSource {
+                tab_id: "t2".to_string(),
+                site_id: "kimi".to_string(),
+                site_name: "Kimi".to_string(),
+                title: String::new(),
+                url: "https://kimi.com/chat/2".to_string(),
+            },
+        ];
+        project.append_entry("chatgpt", "第一行\n第二行", "第一次总结", 10);
+        project.append_entry("", "", "合并总结", 10);
+        project.append_entry("unknown", "", "对比总结", 10);
+        project.entries[2].site_ids = strings(&["chatgpt", "custom_site"]);
+        // append_entry 会把 summary 设为最近一次总结
+        project.summary = "  最新总结  \n".to_string();
+        project
+    }
+
+    #[test]
+    fn markdown_export_snapshot_zh() {
+        let site_names: HashMap<String, String> =
+            [("chatgpt".to_string(), "ChatGPT".to_string())].into();
+        let md = render_project_markdown(&markdown_project(), &site_names, "zh-CN");
+        assert_eq!(
+            redact_markdown_times(&md),
+            "\
+# Q3 计划
+
+- 创建时间: <time>
+- 更新时间: <time>
+
+## 总结
+
+最新总结
+
+## 笔记
+
+- 待办一
+- 待办二
+
+## 来源
+
+- [对话](https://chatgpt.com/c/1) — ChatGPT
+- [https://kimi.com/chat/2](https://kimi.com/chat/2) — Kimi
+
+## 历次总结
+
+### <time> · ChatGPT
+
+第一次总结
+
+**原文摘录**
+
+> 第一行
+> 第二行
+
+### <time>
+
+合并总结
+
+### <time> · ChatGPT / custom_site
+
+对比总结"
+        );
+        assert!(md.ends_with("对比总结\n"));
+    }
+
+    #[test]
+    fn markdown_export_snapshot_en_skips_empty_sections() {
+        let mut project = ProjectContext::new("p_empty".to_string(), "Empty".to_string());
+        project.notes = "  \n".to_string();
+        let md = render_project_markdown(&project, &HashMap::new(), "en");
+        assert_eq!(
+            redact_markdown_times(&md),
+            "# Empty\n\n- Created: <time>\n- Updated: <time>"
+        );
+
+        // 时间为本地 ISO 8601（带时区偏移）
+        let created = md.lines().nth(2).unwrap().trim_start_matches("- Created: ");
+        assert_eq!(
+            created.len(),
+            "2024-01-01T00:00:00+00:00".len(),
+            "{}",
+            created
+        );
+    }
+
+    #[test]
+    fn markdown_export_file_names_are_safe() {
+        let name = |title: &str| {
+            markdown_export_file_name(&ProjectContext::new(
+                "0123456789abcdef".to_string(),
+                title.to_string(),
+            ))
+        };
+        assert_eq!(name("Q3 Plan v1.2"), "Q3-Plan-v1-2.md");
+        assert_eq!(name("  ../../etc/passwd  "), "etcpasswd.md");
+        assert_eq!(name("a   b..c"), "a-b-c.md");
+        assert_eq!(name("Q3 计划：复盘"), "Q3.md");
+        // 全部为非 ASCII 字符时回退到 id
+        assert_eq!(name("第三季度计划"), "project-01234567.md");
+        assert_eq!(name("🚀"), "project-01234567.md");
+        assert_eq!(name(&"x".repeat(100)), format!("{}.md", "x".repeat(60)));
+    }
+
+    // ========================================================================
+    // 项目字段级更新
+    // ========================================================================
+
+    #[test]
+    fn notes_edits_and_summary_writes_do_not_clobber_each_other() {
+        use_temp_config_dir();
+        let id = "p_interleave";
+        project_store::save_project(&ProjectContext::new(id.to_string(), "P".to_string())).unwrap();
+
+        // 编辑器持有旧快照期间，总结写入与笔记保存交错进行
+        let stale = project_store::load_project(id).unwrap();
+        project_store::update_project(id, |p| {
+            record_project_summary(p, "chatgpt", "原文一", "总结一", 10, false);
+            Ok(())
+        })
+        .unwrap();
+        update_project_notes_inner(id, format!("{}我的笔记", stale.notes)).unwrap();
+        project_store::update_project(id, |p| {
+            record_project_summary(p, "kimi", "原文二", "总结二", 10, true);
+            Ok(())
+        })
+        .unwrap();
+        rename_project_inner(id, " 新标题 ").unwrap();
+
+        let project = project_store::load_project(id).unwrap();
+        assert_eq!(project.title, "新标题");
+        assert_eq!(project.summary, "总结二");
+        assert_eq!(project.entries.len(), 2);
+        assert_eq!(project.notes, "我的笔记\n\n原文二");
+    }
+
+    #[test]
+    fn concurrent_notes_and_summary_writes_are_all_kept() {
+        use_temp_config_dir();
+        let id = "p_concurrent_fields";
+        project_store::save_project(&ProjectContext::new(id.to_string(), "P".to_string())).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    if i % 2 == 0 {
+                        update_project_notes_inner(id, format!("notes {}", i)).unwrap();
+                    } else {
+                        project_store::update_project(id, |p| {
+                            record_project_summary(
+                                p,
+                                "chatgpt",
+                                "src",
+                                &format!("summary {}", i),
+                                10,
+                                false,
+                            );
+                            Ok(())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let project = project_store::load_project(id).unwrap();
+        assert_eq!(project.entries.len(), 4);
+        assert!(project.notes.starts_with("notes "), "{}", project.notes);
+        assert_eq!(project.summary, project.entries.last().unwrap().summary);
+    }
+
+    #[test]
+    fn field_updates_reject_blank_titles_and_missing_projects() {
+        use_temp_config_dir();
+        let id = "p_field_errors";
+        project_store::save_project(&ProjectContext::new(id.to_string(), "P".to_string())).unwrap();
+        assert!(rename_project_inner(id, "   ").is_err());
+        assert_eq!(project_store::load_project(id).unwrap().title, "P");
+        assert!(update_project_notes_inner("p_missing", "n".to_string()).is_err());
+        assert!(update_project_summary_inner("p_missing", "s".to_string()).is_err());
+    }
+
+    // ========================================================================
+    // 书签导入
+    // ========================================================================
+
+    #[test]
+    fn bookmark_import_dedupes_and_appends_to_site_order() {
+        let mut config = config_with_sites(&["claude"]);
+        config.sites[0].name = "Claude".to_string();
+        config.sites[0].url = "https://claude.ai/new".to_string();
+        config.site_order = strings(&["claude"]);
+
+        let html = include_str!("../tests/fixtures/bookmarks_messy.html");
+        let report = add_bookmark_sites(&mut config, bookmarks::parse(html, None).unwrap());
+
+        let imported: Vec<(&str, &str)> = report
+            .imported
+            .iter()
+            .map(|s| (s.name.as_str(), s.url.as_str()))
+            .collect();
+        assert_eq!(
+            imported,
+            vec![
+                ("Gemini", "https://gemini.google.com/app"),
+                ("通义 Qwen", "https://chat.qwen.ai/"),
+                // 没有标题时用域名
+                ("no-title.example.com", "https://no-title.example.com/"),
+                ("Outside &unknown; entity", "https://outside.example.com/"),
+            ]
+        );
+        assert!(report
+            .imported
+            .iter()
+            .all(|s| s.id.starts_with("custom_") && !s.builtin && s.icon == "custom"));
+
+        // 已有站点与文件内的重复书签都被跳过
+        let skipped: Vec<&str> = report.skipped.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(skipped, vec!["Claude", "Claude duplicate"]);
+        assert!(report.skipped[0].reason.contains("Claude"));
+        let invalid: Vec<&str> = report.invalid.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(invalid, vec!["FTP"]);
+
+        let mut expected_order = strings(&["claude"]);
+        expected_order.extend(report.imported.iter().map(|s| s.id.clone()));
+        assert_eq!(config.site_order, expected_order);
+        assert_eq!(config.sites.len(), 5);
+
+        // 再次导入同一文件不会产生新站点
+        let again = add_bookmark_sites(&mut config, bookmarks::parse(html, None).unwrap());
+        assert!(again.imported.is_empty());
+        assert_eq!(again.skipped.len(), 6);
+    }
+
+    // ========================================================================
+    // 站点 JSON 导出 / 导入
+    // ========================================================================
+
+    fn custom_site(id: &str, name: &str, url: &str) -> AiSite {
+        AiSite {
+            id: id.to_string(),
+            name: name.to_string(),
+            url: url.to_string(),
+            icon: "custom".to_string(),
+            ..AiSite::default()
+        }
+    }
+
+    fn import_json(config: &mut AppConfig, json: &str, overwrite: bool) -> MergedSiteImport {
+        let entries = serde_json::from_str::<Vec<serde_json::Value>>(json).unwrap();
+        merge_imported_sites(config, entries, overwrite)
+    }
+
+    #[test]
+    fn exported_sites_round_trip_into_a_clean_profile() {
+        let mut source = AppConfig::default();
+        let mut internal = custom_site("custom_a", "Internal GPT", "https://gpt.corp.example.com/");
+        internal.extract_selector = "main .answer".to_string();
+        internal.private = true;
+        internal.zoom = 1.25;
+        source.sites.push(internal);
+        source.sites.push(custom_site(
+            "custom_b",
+            "Wiki Bot",
+            "https://wiki.corp.example.com/bot",
+        ));
+        source.site_order.push("custom_b".to_string());
+        source.site_order.push("custom_a".to_string());
+
+        let exported = exported_sites(&source);
+        let names: Vec<&str> = exported.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Wiki Bot", "Internal GPT"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sites.json");
+        write_file_atomic(
+            &path,
+            serde_json::to_string_pretty(&exported).unwrap().as_bytes(),
+        )
+        .unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+
+        let mut target = AppConfig::default();
+        let builtin_order = target.site_order.clone();
+        let merged = import_json(&mut target, &json, false);
+        assert_eq!(merged.report.added, 2);
+        assert_eq!((merged.report.updated, merged.report.skipped), (0, 0));
+        assert!(merged.report.errors.is_empty());
+
+        // 新 id 追加在排序末尾，get_ai_sites 使用的顺序中立即可见
+        let ordered = site_ids_in_order(&target, false);
+        assert_eq!(&ordered[..builtin_order.len()], &builtin_order[..]);
+        let imported: Vec<&AiSite> = ordered[builtin_order.len()..]
+            .iter()
+            .map(|id| target.sites.iter().find(|s| &s.id == id).unwrap())
+            .collect();
+        assert_eq!(imported.len(), 2);
+        for (site, original) in imported.iter().zip(&exported) {
+            assert!(site.id.starts_with("custom_") && site.id != original.id);
+            assert_eq!(site.name, original.name);
+            assert_eq!(site.url, original.url);
+            assert_eq!(site.extract_selector, original.extract_selector);
+            assert_eq!(site.private, original.private);
+            assert_eq!(site.zoom, original.zoom);
+            assert!(!site.builtin && site.enabled);
+        }
+
+        // 再次导入：按 URL 匹配，不产生重复
+        let again = import_json(&mut target, &json, false);
+        assert_eq!((again.report.added, again.report.skipped), (0, 2));
+        let overwrite = import_json(&mut target, &json, true);
+        assert_eq!((overwrite.report.added, overwrite.report.updated), (0, 2));
+        assert_eq!(target.site_order.len(), builtin_order.len() + 2);
+    }
+
+    #[test]
+    fn site_import_reports_invalid_entries_and_keeps_going() {
+        let mut config = AppConfig::default();
+        let builtin_url = config.sites[0].url.clone();
+        // 条目与 export_sites 的输出格式相同
+        let entry = |name: &str, url: &str| {
+            serde_json::to_value(custom_site("custom_x", name, url)).unwrap()
+        };
+        let mut bad_mode = entry("Bad mode", "https://mode.example.com/");
+        bad_mode["anti_detection_mode"] = "nope".into();
+        let json = serde_json::json!([
+            entry("FTP", "ftp://files.example.com/"),
+            entry(" ", "https://no-name.example.com/"),
+            "not an object",
+            entry("Good", "https://good.example.com/"),
+            bad_mode,
+            entry("Builtin copy", &builtin_url),
         ])
-        .run(tauri::generate_context!())
-        .expect("运行 Tauri 应用失败");
+        .to_string();
+        let merged = import_json(&mut config, &json, true);
+
+        assert_eq!(merged.report.added, 1);
+        // 内置站点即使 overwrite 也不覆盖
+        assert_eq!((merged.report.updated, merged.report.skipped), (0, 1));
+        let indexes: Vec<usize> = merged.report.errors.iter().map(|e| e.index).collect();
+        assert_eq!(indexes, vec![0, 1, 2, 4]);
+        assert_eq!(merged.report.errors[0].name, "FTP");
+        assert!(config.sites.iter().any(|s| s.name == "Good"));
+        assert!(!config.sites.iter().any(|s| s.name == "FTP"));
+    }
+
+    // ========================================================================
+    // 导出 PDF
+    // ========================================================================
+
+    #[test]
+    fn pdf_path_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let pdf = dir.path().join("chat.PDF");
+        assert_eq!(
+            validate_pdf_path(&format!("  {}  ", pdf.display())).unwrap(),
+            pdf
+        );
+        assert_eq!(
+            validate_pdf_path("chat.pdf").unwrap(),
+            PathBuf::from("chat.pdf")
+        );
+
+        assert!(validate_pdf_path("").is_err());
+        assert!(validate_pdf_path("   ").is_err());
+        assert!(validate_pdf_path(&dir.path().join("chat.png").display().to_string()).is_err());
+        assert!(validate_pdf_path(&dir.path().join("chat").display().to_string()).is_err());
+        let missing = dir.path().join("missing").join("chat.pdf");
+        let err = validate_pdf_path(&missing.display().to_string()).unwrap_err();
+        assert!(err.contains("missing"), "{}", err);
+    }
+
+    #[test]
+    fn print_target_checks_the_path_before_the_tab() {
+        let dir = tempfile::tempdir().unwrap();
+        let pdf = dir.path().join("chat.pdf").display().to_string();
+        let tab_id = "print_site_tab1";
+        APP_STATE
+            .lock()
+            .unwrap()
+            .tabs
+            .insert(tab_id.to_string(), "print_site".to_string());
+
+        assert_eq!(resolve_print_target(tab_id, &pdf).unwrap(), "print_site");
+        // 路径无效时即使 Tab 不存在也先报告路径错误
+        assert!(matches!(
+            resolve_print_target("print_missing_tab", "chat.txt"),
+            Err(AppError::Other(_))
+        ));
+        assert!(matches!(
+            resolve_print_target("print_missing_tab", &pdf),
+            Err(AppError::TabNotFound(id)) if id == "print_missing_tab"
+        ));
+        // 参数检查不会为 Tab 留下运行时记录
+        assert!(read_tab_runtime("print_missing_tab", |_| ()).is_none());
+
+        APP_STATE.lock().unwrap().tabs.remove(tab_id);
+    }
+
+    #[test]
+    fn print_waits_for_the_pending_load() {
+        let tab_id = "print_loading_tab";
+        // 未知 Tab 与已加载完成的 Tab 立即返回
+        let started = std::time::Instant::now();
+        tauri::async_runtime::block_on(wait_tab_page_loaded(tab_id, Duration::from_secs(5)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        update_tab_page_state(tab_id, |state| state.loading = true);
+        let finisher = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            update_tab_page_state(tab_id, |state| state.loading = false);
+        });
+        let started = std::time::Instant::now();
+        tauri::async_runtime::block_on(wait_tab_page_loaded(tab_id, Duration::from_secs(5)));
+        let waited = started.elapsed();
+        finisher.join().unwrap();
+        assert!(waited >= Duration::from_millis(200), "{:?}", waited);
+        assert!(waited < Duration::from_secs(5), "{:?}", waited);
+
+        // 一直在加载时到达上限后继续
+        update_tab_page_state(tab_id, |state| state.loading = true);
+        let started = std::time::Instant::now();
+        tauri::async_runtime::block_on(wait_tab_page_loaded(tab_id, Duration::from_millis(300)));
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        TAB_RUNTIME.lock().unwrap().remove(tab_id);
+    }
+
+    // ========================================================================
+    // 总结请求重试
+    // ========================================================================
+
+    fn response(status: u16, retry_after: Option<u64>) -> SummaryAttemptOutcome {
+        SummaryAttemptOutcome::Response {
+            status,
+            retry_after: retry_after.map(Duration::from_secs),
+        }
+    }
+
+    #[test]
+    fn summary_retries_only_rate_limits_server_errors_and_connection_failures() {
+        for outcome in [
+            response(429, None),
+            response(500, None),
+            response(503, None),
+            SummaryAttemptOutcome::Transient,
+        ] {
+            assert!(summary_retry_delay(outcome, 1).is_some(), "{:?}", outcome);
+            assert!(summary_retry_delay(outcome, 2).is_some(), "{:?}", outcome);
+            // 达到最大尝试次数后不再重试
+            assert_eq!(
+                summary_retry_delay(outcome, SUMMARY_MAX_ATTEMPTS),
+                None,
+                "{:?}",
+                outcome
+            );
+        }
+        for outcome in [
+            response(200, None),
+            response(400, Some(1)),
+            response(401, None),
+            response(404, None),
+            SummaryAttemptOutcome::Fatal,
+        ] {
+            assert_eq!(summary_retry_delay(outcome, 1), None, "{:?}", outcome);
+        }
+    }
+
+    #[test]
+    fn summary_retry_after_is_honored_up_to_the_cap() {
+        assert_eq!(
+            summary_retry_delay(response(429, Some(3)), 1),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            summary_retry_delay(response(503, Some(0)), 1),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            summary_retry_delay(response(429, Some(SUMMARY_RETRY_MAX_DELAY.as_secs())), 1),
+            Some(SUMMARY_RETRY_MAX_DELAY)
+        );
+        // 服务要求等待更久时直接返回错误，不让没有截止时间的命令长时间挂起
+        assert_eq!(summary_retry_delay(response(429, Some(3600)), 1), None);
+        assert_eq!(
+            summary_retry_delay(
+                response(503, Some(SUMMARY_RETRY_MAX_DELAY.as_secs() + 1)),
+                1
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn summary_backoff_grows_with_jitter_and_is_capped() {
+        for _ in 0..50 {
+            let first = summary_retry_backoff(1);
+            assert!(first >= Duration::from_secs(1) && first <= Duration::from_millis(1500));
+            let second = summary_retry_backoff(2);
+            assert!(second >= Duration::from_secs(2) && second <= Duration::from_secs(3));
+            let delay = summary_retry_delay(SummaryAttemptOutcome::Transient, 2).unwrap();
+            assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(3));
+            for attempt in [6, 7, 30] {
+                assert_eq!(summary_retry_backoff(attempt), SUMMARY_RETRY_MAX_DELAY);
+            }
+        }
+    }
+
+    #[test]
+    fn bundle_sites_are_validated_and_changed_sites_rebuilt() {
+        let mut config = AppConfig::default();
+        config.sites.push(custom_site(
+            "custom_a",
+            "Internal",
+            "https://a.example.com/",
+        ));
+        config.recent_site_ids = strings(&["custom_a"]);
+
+        let mut moved = custom_site("custom_a", "Internal", " https://b.example.com/ ");
+        moved.private = true;
+        let bad_url = custom_site("custom_b", "Bad URL", "javascript:alert(1)");
+        let mut bad_mode = custom_site("custom_c", "Bad mode", "https://c.example.com/");
+        bad_mode.anti_detection_mode = "stealth".to_string();
+        let mut bad_popup = custom_site("custom_d", "Bad popup", "https://d.example.com/");
+        bad_popup.popup_policy = "anything".to_string();
+        let added = custom_site("custom_e", "New", "https://e.example.com/");
+
+        let merged = merge_bundle_sites(
+            &mut config,
+            vec![moved, bad_url, bad_mode, bad_popup, added],
+        );
+
+        assert_eq!(merged.report.added, 1);
+        assert_eq!(merged.report.updated, 1);
+        let indexes: Vec<usize> = merged.report.errors.iter().map(|e| e.index).collect();
+        assert_eq!(indexes, vec![1, 2, 3]);
+        assert_eq!(merged.rebuild_site_ids, strings(&["custom_a"]));
+        assert_eq!(merged.private_site_ids, strings(&["custom_a"]));
+        assert!(config.recent_site_ids.is_empty());
+        let ids: Vec<&str> = config.sites.iter().map(|s| s.id.as_str()).collect();
+        assert!(!ids.contains(&"custom_b") && ids.contains(&"custom_e"));
+        let moved = config.sites.iter().find(|s| s.id == "custom_a").unwrap();
+        assert_eq!(moved.url, "https://b.example.com/");
+    }
+
+    #[test]
+    fn ai_profile_is_committed_only_after_the_config_is_saved() {
+        use_temp_config_dir();
+        let profile = AiApiProfile {
+            id: "aip_commit_test".to_string(),
+            name: "Test".to_string(),
+            ..Default::default()
+        };
+
+        let missing = commit_ai_profile(&profile, false);
+        assert!(missing.is_err());
+        assert!(!APP_CONFIG
+            .lock()
+            .unwrap()
+            .ai_api_profiles
+            .iter()
+            .any(|p| p.id == profile.id));
+
+        let info = commit_ai_profile(&profile, true).unwrap();
+        assert_eq!(info.id, profile.id);
+        let saved: AppConfig =
+            serde_json::from_str(&fs::read_to_string(get_config_path()).unwrap()).unwrap();
+        assert!(saved.ai_api_profiles.iter().any(|p| p.id == profile.id));
+        APP_CONFIG
+            .lock()
+            .unwrap()
+            .ai_api_profiles
+            .retain(|p| p.id != profile.id);
+    }
 }
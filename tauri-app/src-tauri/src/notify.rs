@@ -0,0 +1,113 @@
+// ============================================================================
+// 桌面通知
+// ============================================================================
+//
+// - 通过 notification 插件发出（Windows toast / macOS 通知中心 / Linux 桌面通知服务）
+// - 插件在桌面平台不回报点击；Linux 上先尝试支持 `--action` 的 `notify-send`
+//   （libnotify 0.7.9+），用户点击时回调 `on_click`，不可用时再交给插件
+// 通知在后台线程发出，失败只记录日志
+
+use tauri_plugin_notification::NotificationExt;
+
+/// 通知正文的字符上限
+pub(crate) const BODY_MAX_CHARS: usize = 100;
+
+/// 发出通知；`on_click` 仅在平台支持点击回调时调用
+pub(crate) fn show(
+    app: &tauri::AppHandle,
+    title: &str,
+    body: &str,
+    on_click: impl FnOnce() + Send + 'static,
+) {
+    let app = app.clone();
+    let title = title.to_string();
+    let body = truncate(body, BODY_MAX_CHARS);
+    std::thread::spawn(move || {
+        match platform::show_clickable(&title, &body) {
+            Ok(true) => return on_click(),
+            Ok(false) => return,
+            Err(e) => println!("[notify] 无法发出可点击的通知，改用通知插件: {}", e),
+        }
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title(&title)
+            .body(&body)
+            .show()
+        {
+            println!("[notify] 发送通知失败: {}", e);
+        }
+    });
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut truncated: String = text.chars().take(max_chars).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::process::Command;
+
+    /// `notify-send` 的参数；标题与正文放在 `--` 之后，以 `-` 开头（如 Markdown 列表）时不会被当作选项
+    pub(super) fn notify_send_args<'a>(title: &'a str, body: &'a str) -> [&'a str; 8] {
+        [
+            "--app-name",
+            "AI Hub",
+            "--action",
+            "default=打开",
+            "--wait",
+            "--",
+            title,
+            body,
+        ]
+    }
+
+    /// 发出可点击的通知并等待，返回用户是否点击；`notify-send` 不存在或不支持 `--action` 时返回错误
+    pub(super) fn show_clickable(title: &str, body: &str) -> Result<bool, String> {
+        let output = Command::new("notify-send")
+            .args(notify_send_args(title, body))
+            .output()
+            .map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).trim() == "default")
+        } else {
+            Err(format!("notify-send 退出码 {:?}", output.status.code()))
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+mod platform {
+    pub(super) fn show_clickable(_title: &str, _body: &str) -> Result<bool, String> {
+        Err("当前平台的通知不支持点击回调".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_and_collapses_whitespace() {
+        assert_eq!(truncate("  a\n\n b\tc ", 10), "a b c");
+        assert_eq!(
+            truncate(&"字".repeat(101), 100),
+            format!("{}…", "字".repeat(100))
+        );
+        assert_eq!(truncate(&"x".repeat(100), 100), "x".repeat(100));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn notify_send_title_and_body_follow_the_option_terminator() {
+        let args = platform::notify_send_args("- 项目", "- 要点一");
+        let end = args.iter().position(|a| *a == "--").unwrap();
+        assert_eq!(&args[end + 1..end + 3], &["- 项目", "- 要点一"]);
+    }
+}
@@ -0,0 +1,137 @@
+// ============================================================================
+// 破坏性操作确认
+// ============================================================================
+//
+// - 破坏性命令统一登记在 `DESTRUCTIVE_COMMANDS`，新增命令只需登记并在入口调用 `authorize`
+// - 主窗口 Webview 发起的调用不受影响（界面自身已有确认流程）
+// - 其他入口（本地自动化 API 等）首次调用时返回一次性确认 token 与操作说明，
+//   需在 CONFIRMATION_TTL_SECS 内携带该 token 再次调用才会执行
+// - 经确认执行的破坏性操作记录到 `destructive-actions.log`
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::now_ts;
+
+/// 需要确认的错误（`AppError::ConfirmationRequired`）转为字符串时的前缀，后接 JSON：
+/// `{"confirm_token": "...", "command": "...", "target": "...", "description": "...", "expires_in_secs": n}`
+pub(crate) const CONFIRMATION_REQUIRED_PREFIX: &str = "[confirm_required]";
+
+/// 确认 token 有效期
+const CONFIRMATION_TTL_SECS: u64 = 60;
+
+/// 破坏性命令及其说明
+const DESTRUCTIVE_COMMANDS: &[(&str, &str)] = &[
+    ("delete_project", "把项目移到回收站"),
+    ("purge_project", "永久删除回收站中的项目及其笔记与总结"),
+    ("clear_view_cache", "清除站点的缓存与登录数据"),
+    ("remove_site", "删除自定义站点"),
+];
+
+/// 命令的调用入口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InvokeSurface {
+    MainWebview,
+    AutomationApi,
+}
+
+impl InvokeSurface {
+    fn as_str(self) -> &'static str {
+        match self {
+            InvokeSurface::MainWebview => "main_webview",
+            InvokeSurface::AutomationApi => "automation_api",
+        }
+    }
+}
+
+struct PendingConfirmation {
+    surface: InvokeSurface,
+    command: String,
+    target: String,
+    expires_at: u64,
+}
+
+static PENDING_CONFIRMATIONS: Lazy<Mutex<HashMap<String, PendingConfirmation>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn description_for(command: &str) -> Option<&'static str> {
+    DESTRUCTIVE_COMMANDS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, description)| *description)
+}
+
+/// 破坏性命令的入口校验：`target` 为操作对象（项目 id、站点 id 等），
+/// token 只能用于同一入口、同一命令与同一对象，且只能使用一次
+pub(crate) fn authorize(
+    surface: InvokeSurface,
+    command: &str,
+    target: &str,
+    confirm_token: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(description) = description_for(command) else {
+        return Ok(());
+    };
+    if surface == InvokeSurface::MainWebview {
+        return Ok(());
+    }
+
+    let now = now_ts();
+    let mut pending = PENDING_CONFIRMATIONS.lock().unwrap();
+    pending.retain(|_, p| p.expires_at > now);
+
+    if let Some(token) = confirm_token.map(str::trim).filter(|t| !t.is_empty()) {
+        return match pending.remove(token) {
+            Some(p) if p.surface == surface && p.command == command && p.target == target => {
+                drop(pending);
+                append_audit_log(surface, command, target);
+                Ok(())
+            }
+            _ => Err("确认 token 无效或已过期".into()),
+        };
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    pending.insert(
+        token.clone(),
+        PendingConfirmation {
+            surface,
+            command: command.to_string(),
+            target: target.to_string(),
+            expires_at: now + CONFIRMATION_TTL_SECS,
+        },
+    );
+    let detail = serde_json::json!({
+        "confirm_token": token,
+        "command": command,
+        "target": target,
+        "description": description,
+        "expires_in_secs": CONFIRMATION_TTL_SECS,
+    });
+    Err(AppError::ConfirmationRequired(detail))
+}
+
+fn append_audit_log(surface: InvokeSurface, command: &str, target: &str) {
+    println!(
+        "[permissions] 已确认破坏性操作 surface={} command={} target={}",
+        surface.as_str(),
+        command,
+        target
+    );
+    let path = crate::get_destructive_audit_log_path();
+    if let Ok(mut file) = std::fs::OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(
+            file,
+            "[{}] surface={} command={} target={}",
+            now_ts(),
+            surface.as_str(),
+            command,
+            target
+        );
+    }
+}
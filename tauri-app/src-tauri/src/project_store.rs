@@ -0,0 +1,646 @@
+// ============================================================================
+// 项目存储：每个项目一个文件
+// ============================================================================
+//
+// - 项目保存在配置目录下的 `contexts/<project_id>.json`，只改写被修改的项目；
+//   id 含文件名不安全字符（或与索引文件重名）时改用 `id_<hex>.json`
+// - 项目文件是唯一的数据来源，保存与删除只改动该项目的文件，不经过任何全局锁
+// - `contexts/index.json` 只是列表缓存：记录每个文件的修改时间与大小以及 id、标题、时间、标签与字符数；
+//   `list_index` 扫描目录，只重新解析大小或修改时间变化的文件，有变化时写回缓存（缺失或损坏时整体重建）
+// - 同一项目的“读取-修改-写回”由该项目自己的锁串行化，不同项目互不等待
+// - 旧版本的 `contexts.json` 在首次启动时拆分迁移，原文件改名为 `contexts.json.migrated-<timestamp>` 保留
+// - 回收站中超过 TRASH_RETENTION_SECS 的项目在读取索引时清除
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::time::Timestamp;
+use crate::{
+    ensure_storage_writable, file_lock, get_contexts_dir, get_contexts_path,
+    quarantine_corrupt_file, write_file_atomic, ProjectContext, TRASH_RETENTION_SECS,
+};
+
+const INDEX_FILE: &str = "index.json";
+
+/// 索引中的一条记录（列表展示所需的全部字段）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ProjectIndexEntry {
+    pub id: String,
+    pub title: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<Timestamp>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes_chars: usize,
+    #[serde(default)]
+    pub summary_chars: usize,
+}
+
+impl ProjectIndexEntry {
+    fn of(project: &ProjectContext) -> Self {
+        Self {
+            id: project.id.clone(),
+            title: project.title.clone(),
+            created_at: project.created_at,
+            updated_at: project.updated_at,
+            deleted_at: project.deleted_at,
+            tags: project.tags.clone(),
+            notes_chars: project.notes.chars().count(),
+            summary_chars: project.summary.chars().count(),
+        }
+    }
+
+    pub(crate) fn is_trashed(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+}
+
+/// 索引缓存中的一条记录：项目文件名、文件状态与列表字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexRecord {
+    file: String,
+    modified_ns: u64,
+    len: u64,
+    #[serde(flatten)]
+    entry: ProjectIndexEntry,
+}
+
+/// 每个项目文件的写锁（按路径区分）
+static PROJECT_LOCKS: Lazy<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 串行化索引缓存的写回（只在 `list_index` 中持有，保存项目不需要）
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+fn project_lock(path: &Path) -> Arc<Mutex<()>> {
+    PROJECT_LOCKS
+        .lock()
+        .unwrap()
+        .entry(path.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+fn project_file_name(project_id: &str) -> String {
+    let safe = !project_id.is_empty()
+        && project_id.len() <= 64
+        && project_id != "index"
+        && project_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if safe {
+        format!("{}.json", project_id)
+    } else {
+        let hex: String = project_id.bytes().map(|b| format!("{:02x}", b)).collect();
+        format!("id_{}.json", hex)
+    }
+}
+
+fn is_expired(deleted_at: Option<Timestamp>, now: Timestamp) -> bool {
+    deleted_at.is_some_and(|t| now.as_secs().saturating_sub(t.as_secs()) > TRASH_RETENTION_SECS)
+}
+
+fn read_project_file(path: &Path) -> Option<ProjectContext> {
+    let content = fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<ProjectContext>(&content) {
+        Ok(mut project) => {
+            project.migrate_legacy_summary();
+            Some(project)
+        }
+        Err(e) => {
+            println!("[projects] {} 解析失败: {}", path.display(), e);
+            quarantine_corrupt_file(path);
+            None
+        }
+    }
+}
+
+/// 文件的（修改时间纳秒, 大小），用于判断索引缓存是否过期
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos() as u64;
+    Some((modified, meta.len()))
+}
+
+/// 某个项目目录上的存储操作（`contexts/` 与其上一级的旧版 `contexts.json`）
+struct Store {
+    dir: PathBuf,
+    legacy_path: PathBuf,
+}
+
+fn store() -> Store {
+    Store {
+        dir: get_contexts_dir(),
+        legacy_path: get_contexts_path(),
+    }
+}
+
+impl Store {
+    fn project_path(&self, project_id: &str) -> PathBuf {
+        self.dir.join(project_file_name(project_id))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(INDEX_FILE)
+    }
+
+    /// 尚未迁移（只读实例启动时主实例还没迁移）时直接读取旧的 contexts.json
+    fn legacy_projects(&self) -> Option<Vec<ProjectContext>> {
+        if self.index_path().exists() {
+            return None;
+        }
+        let content = fs::read_to_string(&self.legacy_path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn load_project(&self, project_id: &str) -> Option<ProjectContext> {
+        let project = match self.legacy_projects() {
+            Some(projects) => projects.into_iter().find(|p| p.id == project_id),
+            None => read_project_file(&self.project_path(project_id)),
+        }?;
+        if project.id != project_id || is_expired(project.deleted_at, Timestamp::now()) {
+            return None;
+        }
+        Some(project)
+    }
+
+    /// 写入项目文件（调用方持有该项目的锁）
+    fn write_project(&self, project: &ProjectContext) -> Result<(), String> {
+        ensure_storage_writable()?;
+        fs::create_dir_all(&self.dir).map_err(|e| format!("创建项目目录失败: {}", e))?;
+        let path = self.project_path(&project.id);
+        let content =
+            serde_json::to_string_pretty(project).map_err(|e| format!("序列化项目失败: {}", e))?;
+        let _lock = file_lock::acquire_file_lock(&path)?;
+        write_file_atomic(&path, content.as_bytes()).map_err(|e| format!("写入项目失败: {}", e))
+    }
+
+    fn save_project(&self, project: &ProjectContext) -> Result<(), String> {
+        let lock = project_lock(&self.project_path(&project.id));
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_project(project)
+    }
+
+    fn upsert_project<R>(
+        &self,
+        project_id: &str,
+        create: impl FnOnce() -> Option<ProjectContext>,
+        f: impl FnOnce(&mut ProjectContext) -> Result<R, String>,
+    ) -> Result<R, String> {
+        let lock = project_lock(&self.project_path(project_id));
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut project = self
+            .load_project(project_id)
+            .or_else(create)
+            .ok_or_else(|| "项目不存在".to_string())?;
+        let result = f(&mut project)?;
+        self.write_project(&project)?;
+        Ok(result)
+    }
+
+    fn remove_project(&self, project_id: &str) -> Result<bool, String> {
+        ensure_storage_writable()?;
+        let path = self.project_path(project_id);
+        let lock = project_lock(&path);
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !path.exists() {
+            return Ok(false);
+        }
+        let _lock = file_lock::acquire_file_lock(&path)?;
+        fs::remove_file(&path).map_err(|e| format!("删除项目失败: {}", e))?;
+        Ok(true)
+    }
+
+    fn list_index(&self) -> Vec<ProjectIndexEntry> {
+        if let Some(projects) = self.legacy_projects() {
+            let now = Timestamp::now();
+            return projects
+                .iter()
+                .filter(|p| !is_expired(p.deleted_at, now))
+                .map(ProjectIndexEntry::of)
+                .collect();
+        }
+        self.refresh_index()
+    }
+
+    fn read_index(&self) -> Option<Vec<IndexRecord>> {
+        let content = fs::read_to_string(self.index_path()).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(records) => Some(records),
+            Err(e) => {
+                println!("[projects] 索引解析失败，将重建: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 对照目录中的项目文件刷新索引缓存，清除已过回收站保留期的项目
+    fn refresh_index(&self) -> Vec<ProjectIndexEntry> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let cached = self.read_index();
+        let mut changed = cached.is_none();
+        let mut cached: HashMap<String, IndexRecord> = cached
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| (r.file.clone(), r))
+            .collect();
+
+        let mut records = Vec::new();
+        let files = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension().is_some_and(|ext| ext == "json")
+                    && path.file_name().is_some_and(|name| name != INDEX_FILE)
+            });
+        for path in files {
+            let Some(file) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+                continue;
+            };
+            // 先取文件状态再读内容：读取期间文件被改写时，记下的是旧状态，下次会重新解析
+            let Some((modified_ns, len)) = file_stamp(&path) else {
+                continue;
+            };
+            match cached.remove(&file) {
+                Some(record) if record.modified_ns == modified_ns && record.len == len => {
+                    records.push(record)
+                }
+                _ => {
+                    changed = true;
+                    if let Some(project) = read_project_file(&path) {
+                        records.push(IndexRecord {
+                            file,
+                            modified_ns,
+                            len,
+                            entry: ProjectIndexEntry::of(&project),
+                        });
+                    }
+                }
+            }
+        }
+        // 缓存中剩下的是已被删除的文件
+        changed |= !cached.is_empty();
+
+        let now = Timestamp::now();
+        let writable = ensure_storage_writable().is_ok();
+        if writable {
+            // 只读实例不清理，交由持有实例锁的进程处理
+            for record in records
+                .iter()
+                .filter(|r| is_expired(r.entry.deleted_at, now))
+            {
+                let _ = fs::remove_file(self.dir.join(&record.file));
+                changed = true;
+            }
+            records.retain(|r| !is_expired(r.entry.deleted_at, now));
+        }
+        records.sort_by(|a, b| {
+            a.entry
+                .created_at
+                .cmp(&b.entry.created_at)
+                .then_with(|| a.entry.id.cmp(&b.entry.id))
+        });
+        if changed && writable {
+            if let Err(e) = self.write_index(&records) {
+                println!("[projects] 写入项目索引失败: {}", e);
+            }
+        }
+        records
+            .into_iter()
+            .map(|r| r.entry)
+            .filter(|e| !is_expired(e.deleted_at, now))
+            .collect()
+    }
+
+    fn write_index(&self, records: &[IndexRecord]) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| format!("创建项目目录失败: {}", e))?;
+        let path = self.index_path();
+        let content =
+            serde_json::to_string(records).map_err(|e| format!("序列化项目索引失败: {}", e))?;
+        let _lock = file_lock::acquire_file_lock(&path)?;
+        write_file_atomic(&path, content.as_bytes()).map_err(|e| format!("写入项目索引失败: {}", e))
+    }
+}
+
+/// 读取单个项目；不存在、损坏或已过回收站保留期时返回 None
+pub(crate) fn load_project(project_id: &str) -> Option<ProjectContext> {
+    store().load_project(project_id)
+}
+
+/// 写入单个项目（索引在下次 `list_index` 时刷新）
+pub(crate) fn save_project(project: &ProjectContext) -> Result<(), String> {
+    store().save_project(project)
+}
+
+/// 在该项目的写锁内读取、修改并保存；项目不存在时返回“项目不存在”，`f` 返回错误时不写回
+pub(crate) fn update_project<R>(
+    project_id: &str,
+    f: impl FnOnce(&mut ProjectContext) -> Result<R, String>,
+) -> Result<R, String> {
+    store().upsert_project(project_id, || None, f)
+}
+
+/// 读取项目（不存在时用 `create` 新建）后修改并保存
+pub(crate) fn upsert_project<R>(
+    project_id: &str,
+    create: impl FnOnce() -> ProjectContext,
+    f: impl FnOnce(&mut ProjectContext) -> Result<R, String>,
+) -> Result<R, String> {
+    store().upsert_project(project_id, || Some(create()), f)
+}
+
+/// 永久删除项目文件；项目不存在时返回 false
+pub(crate) fn remove_project(project_id: &str) -> Result<bool, String> {
+    store().remove_project(project_id)
+}
+
+/// 全部项目的索引（含回收站，不含已过保留期的项目）
+pub(crate) fn list_index() -> Vec<ProjectIndexEntry> {
+    store().list_index()
+}
+
+/// 读取全部项目（导出配置包等需要完整内容的场景）
+pub(crate) fn load_all() -> Vec<ProjectContext> {
+    let store = store();
+    store
+        .list_index()
+        .into_iter()
+        .filter_map(|e| store.load_project(&e.id))
+        .collect()
+}
+
+/// 项目文件的修改时间（修正时间戳时回填用）
+pub(crate) fn project_modified_at(project_id: &str) -> Option<std::time::SystemTime> {
+    fs::metadata(store().project_path(project_id))
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+/// 启动时把旧版本的 contexts.json 拆分为每个项目一个文件；已有同 id 的文件不覆盖
+pub(crate) fn migrate_legacy_file() {
+    store().migrate_legacy_file()
+}
+
+impl Store {
+    fn migrate_legacy_file(&self) {
+        let legacy = &self.legacy_path;
+        if !legacy.exists() || ensure_storage_writable().is_err() {
+            return;
+        }
+        let projects = match fs::read_to_string(legacy)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<Vec<ProjectContext>>(&content).map_err(|e| e.to_string())
+            }) {
+            Ok(projects) => projects,
+            Err(e) => {
+                println!("[projects] contexts.json 解析失败: {}", e);
+                quarantine_corrupt_file(legacy);
+                return;
+            }
+        };
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            println!("[projects] 创建项目目录失败: {}", e);
+            return;
+        }
+        for mut project in projects {
+            let path = self.project_path(&project.id);
+            if path.exists() {
+                continue;
+            }
+            project.migrate_legacy_summary();
+            let written = serde_json::to_string_pretty(&project)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    write_file_atomic(&path, content.as_bytes()).map_err(|e| e.to_string())
+                });
+            if let Err(e) = written {
+                // 保留 contexts.json，下次启动重试
+                println!("[projects] 迁移项目 {} 失败: {}", project.id, e);
+                return;
+            }
+        }
+        // 索引文件存在即表示迁移完成（见 `legacy_projects`），写入失败时保留 contexts.json
+        self.refresh_index();
+        if !self.index_path().exists() {
+            return;
+        }
+        let backup = legacy.with_file_name(format!(
+            "contexts.json.migrated-{}",
+            Timestamp::now().as_secs()
+        ));
+        match fs::rename(legacy, &backup) {
+            Ok(()) => println!(
+                "[projects] 已迁移 contexts.json，原文件保留为 {}",
+                backup.display()
+            ),
+            Err(e) => println!("[projects] 备份 contexts.json 失败: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn temp_store() -> (tempfile::TempDir, Store) {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = Store {
+            dir: tmp.path().join("contexts"),
+            legacy_path: tmp.path().join("contexts.json"),
+        };
+        (tmp, store)
+    }
+
+    fn titles(store: &Store) -> Vec<(String, String)> {
+        store
+            .list_index()
+            .into_iter()
+            .map(|e| (e.id, e.title))
+            .collect()
+    }
+
+    #[test]
+    fn index_follows_saves_and_removals() {
+        let (_tmp, store) = temp_store();
+        store
+            .save_project(&ProjectContext::new("p1".into(), "aaa".into()))
+            .unwrap();
+        assert_eq!(titles(&store), vec![("p1".into(), "aaa".into())]);
+
+        // 长度不变的改写也能被察觉
+        store
+            .upsert_project(
+                "p1",
+                || None,
+                |p| {
+                    p.title = "bbb".into();
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(titles(&store), vec![("p1".into(), "bbb".into())]);
+
+        assert!(store.remove_project("p1").unwrap());
+        assert!(!store.remove_project("p1").unwrap());
+        assert!(titles(&store).is_empty());
+    }
+
+    #[test]
+    fn missing_or_corrupt_index_is_rebuilt_from_files() {
+        let (_tmp, store) = temp_store();
+        for id in ["p1", "p2"] {
+            store
+                .save_project(&ProjectContext::new(id.into(), id.into()))
+                .unwrap();
+        }
+        assert_eq!(store.list_index().len(), 2);
+
+        fs::write(store.index_path(), "[{").unwrap();
+        assert_eq!(store.list_index().len(), 2);
+
+        fs::remove_file(store.index_path()).unwrap();
+        // 绕过存储层直接写入的文件同样会出现在列表中
+        let external = ProjectContext::new("p3".into(), "p3".into());
+        fs::write(
+            store.project_path("p3"),
+            serde_json::to_string(&external).unwrap(),
+        )
+        .unwrap();
+        let ids: Vec<String> = store.list_index().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids.len(), 3);
+        assert!(store.index_path().exists());
+    }
+
+    #[test]
+    fn update_missing_project_fails_without_creating_it() {
+        let (_tmp, store) = temp_store();
+        let err = store
+            .upsert_project("nope", || None, |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(err, "项目不存在");
+        assert!(!store.project_path("nope").exists());
+    }
+
+    #[test]
+    fn concurrent_updates_to_one_project_are_not_lost() {
+        let (_tmp, store) = temp_store();
+        let store = Arc::new(store);
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let store = store.clone();
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        store
+                            .upsert_project(
+                                "shared",
+                                || Some(ProjectContext::new("shared".into(), "t".into())),
+                                |p| {
+                                    p.notes.push('x');
+                                    Ok(())
+                                },
+                            )
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        let project = store.load_project("shared").unwrap();
+        assert_eq!(project.notes.len(), 80);
+        let entry = store.list_index().pop().unwrap();
+        assert_eq!(entry.notes_chars, 80);
+    }
+
+    #[test]
+    fn saves_interleaved_with_listing_end_consistent() {
+        let (_tmp, store) = temp_store();
+        let store = Arc::new(store);
+        let writers: Vec<_> = (0..4)
+            .map(|i| {
+                let store = store.clone();
+                thread::spawn(move || {
+                    let id = format!("p{}", i);
+                    for round in 0..10 {
+                        store
+                            .upsert_project(
+                                &id,
+                                || Some(ProjectContext::new(id.clone(), String::new())),
+                                |p| {
+                                    p.title = format!("{}-{}", id, round);
+                                    Ok(())
+                                },
+                            )
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        let reader = {
+            let store = store.clone();
+            thread::spawn(move || {
+                for _ in 0..20 {
+                    store.list_index();
+                }
+            })
+        };
+        for t in writers {
+            t.join().unwrap();
+        }
+        reader.join().unwrap();
+
+        let mut listed = titles(&store);
+        listed.sort();
+        let expected: Vec<(String, String)> = (0..4)
+            .map(|i| (format!("p{}", i), format!("p{}-9", i)))
+            .collect();
+        assert_eq!(listed, expected);
+    }
+
+    #[test]
+    fn legacy_contexts_file_is_split_and_kept_as_backup() {
+        let (tmp, store) = temp_store();
+        let legacy = vec![
+            ProjectContext::new("a".into(), "A".into()),
+            ProjectContext::new("b/c".into(), "B".into()),
+        ];
+        fs::write(&store.legacy_path, serde_json::to_string(&legacy).unwrap()).unwrap();
+        // 迁移前直接读取旧文件
+        assert_eq!(store.list_index().len(), 2);
+
+        store.migrate_legacy_file();
+        assert!(!store.legacy_path.exists());
+        assert!(store.project_path("b/c").ends_with("id_622f63.json"));
+        assert_eq!(store.load_project("b/c").unwrap().title, "B");
+        assert_eq!(store.list_index().len(), 2);
+        let backups = fs::read_dir(tmp.path())
+            .unwrap()
+            .flatten()
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with("contexts.json.migrated-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+}
@@ -0,0 +1,163 @@
+// ============================================================================
+// 页面截图
+// ============================================================================
+//
+// Tauri 没有跨平台的 Webview 截图接口，这里在页面内完成渲染：
+// 克隆 DOM 并内联计算样式，放入 SVG <foreignObject> 绘制到 canvas，得到可见区域的 PNG data URL，
+// 经 `aihub_submit_page_text` 回传后在此解码、拼接并编码为 PNG。
+// 限制：跨域图片、视频与 iframe 不会出现在截图中；<canvas> 会转为图片保留。
+
+use base64::Engine;
+
+/// 截图脚本（表达式，结果为 `data:image/png;base64,...`）
+pub(crate) const CAPTURE_SCRIPT: &str = r#"(async () => {
+  const root = document.documentElement;
+  const width = window.innerWidth;
+  const height = window.innerHeight;
+  const scale = window.devicePixelRatio || 1;
+  const clone = root.cloneNode(true);
+  const sources = [root, ...root.querySelectorAll('*')];
+  const targets = [clone, ...clone.querySelectorAll('*')];
+  const scrolled = [];
+  for (let i = 0; i < sources.length && i < targets.length; i++) {
+    const src = sources[i];
+    const dst = targets[i];
+    const style = getComputedStyle(src);
+    let text = '';
+    for (const prop of style) text += prop + ':' + style.getPropertyValue(prop) + ';';
+    dst.setAttribute('style', text);
+    if (src instanceof HTMLCanvasElement) {
+      try {
+        const img = document.createElement('img');
+        img.src = src.toDataURL();
+        img.setAttribute('style', text);
+        dst.replaceWith(img);
+      } catch (_) {}
+    } else if (src instanceof HTMLInputElement || src instanceof HTMLTextAreaElement) {
+      dst.setAttribute('value', src.value);
+      if (src instanceof HTMLTextAreaElement) dst.textContent = src.value;
+    }
+    // 文档滚动记在根元素上（scrollingElement 可能是 <html> 或 <body>）
+    const scroller = document.scrollingElement || root;
+    const el = src === root ? scroller : src === scroller ? null : src;
+    if (el && (el.scrollTop || el.scrollLeft)) scrolled.push([dst, el.scrollLeft, el.scrollTop]);
+  }
+  // SVG 图片中无法滚动：把滚动容器的内容整体平移到当前滚动位置
+  for (const [dst, left, top] of scrolled) {
+    const target = dst === clone ? clone.querySelector('body') : dst;
+    if (!target) continue;
+    const wrapper = document.createElement('div');
+    wrapper.setAttribute('style', `transform: translate(${-left}px, ${-top}px)`);
+    while (target.firstChild) wrapper.appendChild(target.firstChild);
+    target.appendChild(wrapper);
+    target.style.overflow = 'hidden';
+  }
+  clone.querySelectorAll('script, noscript, iframe, video').forEach((n) => n.remove());
+  clone.style.width = width + 'px';
+  clone.style.height = height + 'px';
+  const xml = new XMLSerializer().serializeToString(clone);
+  const svg = `<svg xmlns="http://www.w3.org/2000/svg" width="${width}" height="${height}"><foreignObject x="0" y="0" width="100%" height="100%">${xml}</foreignObject></svg>`;
+  const image = new Image();
+  image.src = 'data:image/svg+xml;charset=utf-8,' + encodeURIComponent(svg);
+  await image.decode();
+  const canvas = document.createElement('canvas');
+  canvas.width = Math.round(width * scale);
+  canvas.height = Math.round(height * scale);
+  const ctx = canvas.getContext('2d');
+  ctx.scale(scale, scale);
+  const background = getComputedStyle(document.body || root).backgroundColor;
+  ctx.fillStyle = !background || background === 'rgba(0, 0, 0, 0)' ? '#ffffff' : background;
+  ctx.fillRect(0, 0, width, height);
+  ctx.drawImage(image, 0, 0, width, height);
+  return canvas.toDataURL('image/png');
+})()"#;
+
+/// 解析页面回传的 `data:image/png;base64,...`，返回 PNG 字节
+pub(crate) fn decode_png_data_url(data_url: &str) -> Result<Vec<u8>, String> {
+    let encoded = data_url
+        .trim()
+        .strip_prefix("data:image/png;base64,")
+        .ok_or("页面未返回 PNG 图片")?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("截图数据无效: {}", e))?;
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Err("截图数据不是 PNG".to_string());
+    }
+    Ok(bytes)
+}
+
+struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+fn decode_rgba(png_bytes: &[u8]) -> Result<RgbaImage, String> {
+    let mut decoder = png::Decoder::new(png_bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("解码截图失败: {}", e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| format!("解码截图失败: {}", e))?;
+    let data = &buf[..info.buffer_size()];
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => data.to_vec(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return Err("不支持的截图颜色格式".to_string()),
+    };
+    Ok(RgbaImage {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+/// 把多张 PNG 从左到右拼成一张；高度取最高的一张，空白处填白色
+pub(crate) fn stitch_horizontally(images: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    let images = images
+        .iter()
+        .map(|bytes| decode_rgba(bytes))
+        .collect::<Result<Vec<_>, _>>()?;
+    let width: u32 = images.iter().map(|img| img.width).sum();
+    let height = images.iter().map(|img| img.height).max().unwrap_or(0);
+    if width == 0 || height == 0 {
+        return Err("截图为空".to_string());
+    }
+    let row_bytes = width as usize * 4;
+    let mut canvas = vec![255u8; row_bytes * height as usize];
+    let mut x_offset = 0usize;
+    for img in &images {
+        let img_row = img.width as usize * 4;
+        for y in 0..img.height as usize {
+            let dst = y * row_bytes + x_offset;
+            canvas[dst..dst + img_row].copy_from_slice(&img.pixels[y * img_row..(y + 1) * img_row]);
+        }
+        x_offset += img_row;
+    }
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("编码截图失败: {}", e))?;
+        writer
+            .write_image_data(&canvas)
+            .map_err(|e| format!("编码截图失败: {}", e))?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,136 @@
+// ============================================================================
+// 系统密钥环（用于保存 API Key）
+// ============================================================================
+//
+// 通过 `keyring` crate 访问各平台的密钥环，密钥只经由系统 API 传递，不出现在进程参数中：
+// - Windows: Credential Manager
+// - macOS: Keychain（Security framework）
+// - Linux: Secret Service（D-Bus）
+// 均不可用时返回 `Unavailable`，由调用方回退到 config.json 明文存储
+
+use keyring::{Entry, Error as KeyringError};
+use once_cell::sync::Lazy;
+
+/// 密钥环中的服务名
+const KEYRING_SERVICE: &str = "com.aihub.app";
+
+/// 探测可用性时查询的账户（不会写入）
+const PROBE_ACCOUNT: &str = "__probe__";
+
+// 每个平台只会构造其中一部分变体
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyringBackend {
+    WindowsCredentialManager,
+    MacosKeychain,
+    SecretService,
+    Unavailable,
+}
+
+impl KeyringBackend {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            KeyringBackend::WindowsCredentialManager => "windows_credential_manager",
+            KeyringBackend::MacosKeychain => "macos_keychain",
+            KeyringBackend::SecretService => "secret_service",
+            KeyringBackend::Unavailable => "unavailable",
+        }
+    }
+
+    pub(crate) fn is_available(self) -> bool {
+        self != KeyringBackend::Unavailable
+    }
+}
+
+static BACKEND: Lazy<KeyringBackend> = Lazy::new(detect_backend);
+
+/// 当前平台可用的密钥环后端（首次调用时探测并缓存）
+pub(crate) fn backend() -> KeyringBackend {
+    *BACKEND
+}
+
+#[cfg(windows)]
+const PLATFORM_BACKEND: KeyringBackend = KeyringBackend::WindowsCredentialManager;
+
+#[cfg(target_os = "macos")]
+const PLATFORM_BACKEND: KeyringBackend = KeyringBackend::MacosKeychain;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const PLATFORM_BACKEND: KeyringBackend = KeyringBackend::SecretService;
+
+/// 查询一个不存在的条目：能得到“未找到”说明密钥环可以访问（Secret Service 未运行时会报平台错误）。
+/// 测试默认视为不可用，避免写入开发机的真实密钥环；设置 `AIHUB_TEST_KEYRING=1` 时才探测
+fn detect_backend() -> KeyringBackend {
+    if cfg!(test) && std::env::var_os("AIHUB_TEST_KEYRING").is_none() {
+        return KeyringBackend::Unavailable;
+    }
+    match Entry::new(KEYRING_SERVICE, PROBE_ACCOUNT).and_then(|e| e.get_password()) {
+        Ok(_) | Err(KeyringError::NoEntry) => PLATFORM_BACKEND,
+        Err(e) => {
+            println!("[secret] 系统密钥环不可用: {}", e);
+            KeyringBackend::Unavailable
+        }
+    }
+}
+
+fn entry(account: &str) -> Result<Entry, String> {
+    Entry::new(KEYRING_SERVICE, account).map_err(describe)
+}
+
+fn describe(e: KeyringError) -> String {
+    format!("系统密钥环错误: {}", e)
+}
+
+/// 写入（覆盖）密钥
+pub(crate) fn set_secret(account: &str, secret: &str) -> Result<(), String> {
+    if !backend().is_available() {
+        return Err("系统密钥环不可用".to_string());
+    }
+    entry(account)?.set_password(secret).map_err(describe)
+}
+
+/// 读取密钥，不存在时返回 `Ok(None)`
+pub(crate) fn get_secret(account: &str) -> Result<Option<String>, String> {
+    if !backend().is_available() {
+        return Err("系统密钥环不可用".to_string());
+    }
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(KeyringError::NoEntry) => Ok(None),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+/// 删除密钥（不存在时视为成功）
+pub(crate) fn delete_secret(account: &str) -> Result<(), String> {
+    if !backend().is_available() {
+        return Ok(());
+    }
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(KeyringError::NoEntry) => Ok(()),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 需要可用的系统密钥环（桌面会话），CI 中默认跳过：
+    /// `AIHUB_TEST_KEYRING=1 cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn round_trip_through_system_keyring() {
+        assert!(backend().is_available(), "系统密钥环不可用");
+        let account = format!("test:{}", uuid::Uuid::new_v4());
+        assert_eq!(get_secret(&account).unwrap(), None);
+        set_secret(&account, "sk-test-1").unwrap();
+        assert_eq!(get_secret(&account).unwrap().as_deref(), Some("sk-test-1"));
+        set_secret(&account, "sk-test-2").unwrap();
+        assert_eq!(get_secret(&account).unwrap().as_deref(), Some("sk-test-2"));
+        delete_secret(&account).unwrap();
+        assert_eq!(get_secret(&account).unwrap(), None);
+        // 重复删除视为成功
+        delete_secret(&account).unwrap();
+    }
+}
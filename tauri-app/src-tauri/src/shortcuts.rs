@@ -0,0 +1,287 @@
+// ============================================================================
+// 全局快捷键
+// ============================================================================
+//
+// - AppConfig.shortcuts 保存 动作 → 快捷键，如 `toggle_window` → `Ctrl+Shift+Space`
+// - 动作：`toggle_window` 显示/隐藏主窗口；`site_1`..`site_9` 跳到站点顺序中的第 N 个站点
+// - 快捷键保存前统一规范化（修饰键顺序 Ctrl+Alt+Shift+Super），同一快捷键不能分配给两个动作
+// - 通过 global-shortcut 插件在系统中注册；配置变化时先注销旧快捷键，退出时全部注销
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use tauri::plugin::TauriPlugin;
+use tauri::Wry;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// 显示/隐藏主窗口
+pub(crate) const TOGGLE_WINDOW_ACTION: &str = "toggle_window";
+
+/// 跳到第 N 个站点的动作前缀（`site_1`..`site_9`）
+const SITE_ACTION_PREFIX: &str = "site_";
+
+const MAX_SITE_SHORTCUTS: usize = 9;
+
+const MOD_CTRL: u8 = 1;
+const MOD_ALT: u8 = 1 << 1;
+const MOD_SHIFT: u8 = 1 << 2;
+const MOD_SUPER: u8 = 1 << 3;
+
+/// 已在系统中注册成功的快捷键 → 动作
+static REGISTERED: Lazy<Mutex<HashMap<Shortcut, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn known_actions() -> Vec<String> {
+    std::iter::once(TOGGLE_WINDOW_ACTION.to_string())
+        .chain((1..=MAX_SITE_SHORTCUTS).map(|n| format!("{}{}", SITE_ACTION_PREFIX, n)))
+        .collect()
+}
+
+pub(crate) fn is_known_action(action: &str) -> bool {
+    action == TOGGLE_WINDOW_ACTION || site_index(action).is_some()
+}
+
+/// `site_3` → Some(2)
+pub(crate) fn site_index(action: &str) -> Option<usize> {
+    let n: usize = action.strip_prefix(SITE_ACTION_PREFIX)?.parse().ok()?;
+    (1..=MAX_SITE_SHORTCUTS).contains(&n).then(|| n - 1)
+}
+
+/// 当前平台能否注册系统级快捷键
+pub(crate) fn is_supported() -> bool {
+    cfg!(desktop)
+}
+
+pub(crate) fn is_registered(action: &str) -> bool {
+    REGISTERED.lock().unwrap().values().any(|a| a == action)
+}
+
+/// 全局快捷键插件：按下已注册的快捷键时执行对应动作
+pub(crate) fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            let action = REGISTERED.lock().unwrap().get(shortcut).cloned();
+            if let Some(action) = action {
+                crate::handle_shortcut_action(app, &action);
+            }
+        })
+        .build()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Accelerator {
+    modifiers: u8,
+    /// 规范化的按键名（如 `A`、`F5`、`Space`）
+    key: String,
+}
+
+impl Accelerator {
+    /// 解析 `Ctrl+Shift+Space` 形式的快捷键（大小写不敏感）
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = raw.split('+').map(str::trim).collect();
+        if parts.iter().any(|p| p.is_empty()) {
+            return Err(format!("快捷键格式无效: {}", raw));
+        }
+        let (key, modifier_names) = parts.split_last().ok_or("快捷键为空")?;
+        let mut modifiers = 0;
+        for name in modifier_names {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmdorctrl" | "commandorcontrol" => MOD_CTRL,
+                "alt" | "option" => MOD_ALT,
+                "shift" => MOD_SHIFT,
+                "super" | "meta" | "cmd" | "command" | "win" => MOD_SUPER,
+                _ => return Err(format!("未知的修饰键: {}", name)),
+            };
+        }
+        let (key, function_key) = parse_key(key).ok_or_else(|| format!("不支持的按键: {}", key))?;
+        if modifiers & (MOD_CTRL | MOD_ALT | MOD_SUPER) == 0 && !function_key {
+            return Err("快捷键需包含 Ctrl、Alt 或 Super（功能键除外）".to_string());
+        }
+        Ok(Self { modifiers, key })
+    }
+
+    /// 转换为插件的快捷键（规范化名称与插件的按键名一致）
+    fn to_shortcut(&self) -> Result<Shortcut, String> {
+        self.to_string()
+            .parse()
+            .map_err(|e| format!("快捷键无效 {}: {}", self, e))
+    }
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (MOD_CTRL, "Ctrl"),
+            (MOD_ALT, "Alt"),
+            (MOD_SHIFT, "Shift"),
+            (MOD_SUPER, "Super"),
+        ] {
+            if self.modifiers & flag != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+/// 按键名 → (规范化名称, 是否为功能键)
+fn parse_key(key: &str) -> Option<(String, bool)> {
+    let lower = key.to_ascii_lowercase();
+    if let [c] = lower.as_bytes() {
+        if c.is_ascii_alphanumeric() {
+            return Some(((c.to_ascii_uppercase() as char).to_string(), false));
+        }
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u16>().ok()) {
+        if (1..=24).contains(&n) {
+            return Some((format!("F{}", n), true));
+        }
+    }
+    let name = match lower.as_str() {
+        "space" => "Space",
+        "enter" | "return" => "Enter",
+        "tab" => "Tab",
+        "esc" | "escape" => "Escape",
+        "up" => "Up",
+        "down" => "Down",
+        "left" => "Left",
+        "right" => "Right",
+        "home" => "Home",
+        "end" => "End",
+        "pageup" => "PageUp",
+        "pagedown" => "PageDown",
+        "insert" => "Insert",
+        "delete" => "Delete",
+        "`" | "backquote" => "`",
+        _ => return None,
+    };
+    Some((name.to_string(), false))
+}
+
+/// 把另一个动作已占用的同一快捷键找出来
+pub(crate) fn find_conflict<'a>(
+    bindings: &'a BTreeMap<String, String>,
+    action: &str,
+    accelerator: &Accelerator,
+) -> Option<&'a str> {
+    bindings
+        .iter()
+        .filter(|(other, _)| other.as_str() != action)
+        .find(|(_, raw)| Accelerator::parse(raw).is_ok_and(|a| a == *accelerator))
+        .map(|(other, _)| other.as_str())
+}
+
+/// 按配置重新注册全部快捷键（先注销旧的）；任一快捷键注册失败时整体失败且不保留任何注册
+pub(crate) fn apply(
+    app: &tauri::AppHandle,
+    bindings: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let mut parsed: Vec<(Shortcut, String)> = Vec::new();
+    for (action, raw) in bindings {
+        if !is_known_action(action) {
+            continue;
+        }
+        parsed.push((Accelerator::parse(raw)?.to_shortcut()?, action.clone()));
+    }
+    unregister_all(app);
+    let manager = app.global_shortcut();
+    let mut registered: HashMap<Shortcut, String> = HashMap::new();
+    for (shortcut, action) in parsed {
+        if let Err(e) = manager.register(shortcut) {
+            for shortcut in registered.keys() {
+                let _ = manager.unregister(*shortcut);
+            }
+            return Err(format!(
+                "快捷键 {} 注册失败（可能已被其他程序占用）: {}",
+                shortcut.into_string(),
+                e
+            ));
+        }
+        registered.insert(shortcut, action);
+    }
+    *REGISTERED.lock().unwrap() = registered;
+    Ok(())
+}
+
+/// 注销本应用注册的全部快捷键（修改配置前与退出时调用）
+pub(crate) fn unregister_all(app: &tauri::AppHandle) {
+    let registered = std::mem::take(&mut *REGISTERED.lock().unwrap());
+    if registered.is_empty() {
+        return;
+    }
+    let shortcuts: Vec<Shortcut> = registered.into_keys().collect();
+    if let Err(e) = app.global_shortcut().unregister_multiple(shortcuts) {
+        println!("[shortcuts] 注销快捷键失败: {}", e);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_normalizes_modifier_order_and_key_names() {
+        let accelerator = Accelerator::parse("shift + CTRL+space").unwrap();
+        assert_eq!(accelerator.to_string(), "Ctrl+Shift+Space");
+        assert_eq!(
+            Accelerator::parse("cmd+Alt+esc").unwrap().to_string(),
+            "Alt+Super+Escape"
+        );
+        assert_eq!(Accelerator::parse("F5").unwrap().to_string(), "F5");
+    }
+
+    #[test]
+    fn parse_rejects_invalid_accelerators() {
+        for raw in [
+            "",
+            "Ctrl+",
+            "Shift+A",
+            "A",
+            "Ctrl+Hyper+A",
+            "Ctrl+F25",
+            "Ctrl+NoSuchKey",
+        ] {
+            assert!(Accelerator::parse(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn normalized_keys_are_accepted_by_the_plugin() {
+        let keys = [
+            "A", "Z", "0", "9", "F1", "F24", "Space", "Enter", "Tab", "Escape", "Up", "Down",
+            "Left", "Right", "Home", "End", "PageUp", "PageDown", "Insert", "Delete", "`",
+        ];
+        for key in keys {
+            let accelerator = Accelerator::parse(&format!("Ctrl+Alt+Shift+Super+{}", key)).unwrap();
+            assert!(accelerator.to_shortcut().is_ok(), "{}", accelerator);
+        }
+    }
+
+    #[test]
+    fn conflicts_ignore_the_action_being_changed() {
+        let mut bindings = BTreeMap::new();
+        bindings.insert(
+            TOGGLE_WINDOW_ACTION.to_string(),
+            "Ctrl+Shift+Space".to_string(),
+        );
+        let same = Accelerator::parse("shift+ctrl+SPACE").unwrap();
+        assert_eq!(
+            find_conflict(&bindings, "site_1", &same),
+            Some(TOGGLE_WINDOW_ACTION)
+        );
+        assert_eq!(find_conflict(&bindings, TOGGLE_WINDOW_ACTION, &same), None);
+    }
+
+    #[test]
+    fn site_actions_map_to_zero_based_indices() {
+        assert_eq!(site_index("site_1"), Some(0));
+        assert_eq!(site_index("site_9"), Some(8));
+        assert_eq!(site_index("site_0"), None);
+        assert_eq!(site_index("site_10"), None);
+        assert_eq!(known_actions().len(), 10);
+    }
+}
@@ -0,0 +1,180 @@
+// ============================================================================
+// 单实例：再次启动时唤起已运行的实例
+// ============================================================================
+//
+// - 持有实例锁的主实例在 127.0.0.1 的随机端口监听，端口与随机 token 写入配置目录下的
+//   `instance_ipc`（unix 下权限 0600）
+// - 再次启动时（实例锁被其他存活进程持有）连接该端口发送命令行参数；主实例在主线程上前置主窗口后
+//   回复 `ok`，第二个进程随即退出。`--site <id>` 按侧边栏的站点切换流程打开该站点
+// - 主实例无响应（连接失败、超时或主线程卡住）时第二个进程照常以只读模式启动，
+//   由前端询问用户是继续使用还是退出
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 连接主实例的超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 等待主实例回复的超时（需大于主实例等待主线程的时长）
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 主实例等待主线程前置窗口的时长，超时视为主线程卡住
+pub(crate) const MAIN_THREAD_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 单条请求的大小上限
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// 本进程启动时已有实例但其没有响应
+static EXISTING_UNRESPONSIVE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Handshake {
+    port: u16,
+    token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ActivateRequest {
+    token: String,
+    args: Vec<String>,
+}
+
+/// 第二个进程：把命令行参数转交给主实例；主实例确认后返回 Ok，调用方应随即退出
+pub(crate) fn signal_existing(args: &[String]) -> Result<(), String> {
+    let result = try_signal(args);
+    if let Err(e) = &result {
+        println!("[single-instance] 已运行的实例无响应: {}", e);
+        EXISTING_UNRESPONSIVE.store(true, Ordering::SeqCst);
+    }
+    result
+}
+
+fn try_signal(args: &[String]) -> Result<(), String> {
+    let content = std::fs::read_to_string(crate::get_instance_ipc_path())
+        .map_err(|e| format!("读取实例端口失败: {}", e))?;
+    let handshake: Handshake =
+        serde_json::from_str(&content).map_err(|e| format!("实例端口文件无效: {}", e))?;
+    let addr = SocketAddr::from(([127, 0, 0, 1], handshake.port));
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| format!("连接失败: {}", e))?;
+    let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(REPLY_TIMEOUT));
+
+    let request = ActivateRequest {
+        token: handshake.token,
+        args: args.to_vec(),
+    };
+    let mut line = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("发送失败: {}", e))?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("等待回复超时: {}", e))?;
+    match reply.trim() {
+        "ok" => Ok(()),
+        "" => Err("连接被关闭".to_string()),
+        other => Err(format!("实例忙: {}", other)),
+    }
+}
+
+pub(crate) fn existing_unresponsive() -> bool {
+    EXISTING_UNRESPONSIVE.load(Ordering::SeqCst)
+}
+
+/// 主实例：监听后续启动的进程
+pub(crate) fn start_listener(app: &tauri::AppHandle) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).map_err(|e| format!("监听失败: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("获取端口失败: {}", e))?
+        .port();
+    let token = Uuid::new_v4().simple().to_string();
+    write_handshake(
+        &crate::get_instance_ipc_path(),
+        &Handshake {
+            port,
+            token: token.clone(),
+        },
+    )?;
+    println!("[single-instance] 监听 127.0.0.1:{}", port);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => handle_connection(&app, stream, &token),
+                Err(e) => println!("[single-instance] 接受连接失败: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(app: &tauri::AppHandle, mut stream: TcpStream, token: &str) {
+    let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+    let mut line = String::new();
+    let read = BufReader::new((&stream).take(MAX_REQUEST_BYTES)).read_line(&mut line);
+    if read.is_err() {
+        return;
+    }
+    let Ok(request) = serde_json::from_str::<ActivateRequest>(&line) else {
+        return;
+    };
+    if request.token != token {
+        println!("[single-instance] token 不匹配，忽略请求");
+        return;
+    }
+    let reply = if crate::activate_from_second_instance(app, request.args) {
+        "ok\n"
+    } else {
+        "busy\n"
+    };
+    let _ = stream.write_all(reply.as_bytes());
+}
+
+/// 退出时删除端口文件
+pub(crate) fn stop() {
+    let _ = std::fs::remove_file(crate::get_instance_ipc_path());
+}
+
+/// 命令行中的 `--site <id>` / `--site=<id>`
+pub(crate) fn site_arg(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--site" {
+            return iter.next().cloned();
+        }
+        if let Some(site_id) = arg.strip_prefix("--site=") {
+            return Some(site_id.to_string());
+        }
+    }
+    None
+}
+
+fn write_handshake(path: &Path, handshake: &Handshake) -> Result<(), String> {
+    let content = serde_json::to_vec(handshake).map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("写入实例端口失败: {}", e))?;
+    file.write_all(&content)
+        .map_err(|e| format!("写入实例端口失败: {}", e))
+}
@@ -0,0 +1,185 @@
+// ============================================================================
+// 站点使用统计
+// ============================================================================
+//
+// - 每个站点记录：激活次数（切换到该站点/其 Tab）、累计前台时长、最后使用时间，存于 stats.json
+// - 前台时长只在主窗口获得焦点时累计；窗口失焦、最小化或隐藏到托盘时暂停
+// - 变更先记在内存中，由定时器每分钟落盘一次，退出时再写一次
+// - 隐私站点不记录（由调用方过滤）
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::time::Timestamp;
+
+/// 落盘间隔
+pub(crate) const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+static STATS: Lazy<Mutex<StatsState>> = Lazy::new(|| {
+    Mutex::new(StatsState {
+        table: load_table(),
+        dirty: false,
+        foreground: None,
+        since: None,
+        focused: true,
+    })
+});
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct SiteStats {
+    #[serde(default)]
+    pub activations: u64,
+    #[serde(default)]
+    pub foreground_secs: u64,
+    #[serde(default)]
+    pub last_used_at: Option<Timestamp>,
+}
+
+struct StatsState {
+    table: BTreeMap<String, SiteStats>,
+    /// 有尚未落盘的变更
+    dirty: bool,
+    /// 当前前台站点
+    foreground: Option<String>,
+    /// 前台计时的起点；窗口失焦或没有前台站点时为 None
+    since: Option<Instant>,
+    focused: bool,
+}
+
+impl StatsState {
+    /// 把计时起点到现在的整秒数计入前台站点，不足一秒的部分留到下次
+    fn accrue(&mut self) {
+        let (Some(site_id), Some(since)) = (self.foreground.as_ref(), self.since) else {
+            return;
+        };
+        let secs = since.elapsed().as_secs();
+        if secs == 0 {
+            return;
+        }
+        let stats = self.table.entry(site_id.clone()).or_default();
+        stats.foreground_secs += secs;
+        stats.last_used_at = Some(Timestamp::now());
+        self.since = Some(since + Duration::from_secs(secs));
+        self.dirty = true;
+    }
+
+    fn restart_timer(&mut self) {
+        self.since = (self.focused && self.foreground.is_some()).then(Instant::now);
+    }
+}
+
+/// 切换到站点（或其 Tab）：激活次数 +1，并作为新的前台站点开始计时
+pub(crate) fn record_activation(site_id: &str) {
+    let mut state = STATS.lock().unwrap();
+    state.accrue();
+    let stats = state.table.entry(site_id.to_string()).or_default();
+    stats.activations += 1;
+    stats.last_used_at = Some(Timestamp::now());
+    state.foreground = Some(site_id.to_string());
+    state.restart_timer();
+    state.dirty = true;
+}
+
+/// 更新前台站点（不计激活次数）；None 表示当前没有显示站点
+pub(crate) fn set_foreground(site_id: Option<&str>) {
+    let mut state = STATS.lock().unwrap();
+    if state.foreground.as_deref() == site_id {
+        return;
+    }
+    state.accrue();
+    state.foreground = site_id.map(str::to_string);
+    state.restart_timer();
+}
+
+/// 主窗口焦点变化：失焦时暂停计时
+pub(crate) fn set_focused(focused: bool) {
+    let mut state = STATS.lock().unwrap();
+    if state.focused == focused {
+        return;
+    }
+    state.accrue();
+    state.focused = focused;
+    state.restart_timer();
+}
+
+/// 当前统计（含正在计时的前台时长）
+pub(crate) fn snapshot() -> BTreeMap<String, SiteStats> {
+    let mut state = STATS.lock().unwrap();
+    state.accrue();
+    state.table.clone()
+}
+
+/// 清空统计；当前前台站点从现在重新计时
+pub(crate) fn reset() {
+    let mut state = STATS.lock().unwrap();
+    state.table.clear();
+    state.restart_timer();
+    state.dirty = false;
+    persist(&state.table);
+}
+
+/// 删除站点或把站点标记为隐私时丢弃其统计
+pub(crate) fn forget_site(site_id: &str) {
+    let mut state = STATS.lock().unwrap();
+    if state.foreground.as_deref() == Some(site_id) {
+        state.foreground = None;
+        state.since = None;
+    }
+    if state.table.remove(site_id).is_some() {
+        state.dirty = true;
+    }
+}
+
+/// 把内存中的变更写入 stats.json（无变更时不写）
+pub(crate) fn flush() {
+    let mut state = STATS.lock().unwrap();
+    state.accrue();
+    if !state.dirty {
+        return;
+    }
+    persist(&state.table);
+    state.dirty = false;
+}
+
+// ============================================================================
+// 统计存储
+// ============================================================================
+
+fn load_table() -> BTreeMap<String, SiteStats> {
+    let path = crate::get_site_stats_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return BTreeMap::new();
+    };
+    match serde_json::from_str(&content) {
+        Ok(table) => table,
+        Err(e) => {
+            println!("[site-stats] 解析失败，重新开始统计: {}", e);
+            crate::quarantine_corrupt_file(&path);
+            BTreeMap::new()
+        }
+    }
+}
+
+fn persist(table: &BTreeMap<String, SiteStats>) {
+    // 只读实例只保留内存中的统计
+    if crate::ensure_storage_writable().is_err() {
+        return;
+    }
+    let path = crate::get_site_stats_path();
+    let content = match serde_json::to_vec(table) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("[site-stats] 序列化失败: {}", e);
+            return;
+        }
+    };
+    let result = crate::file_lock::acquire_file_lock(&path)
+        .and_then(|_lock| crate::write_file_atomic(&path, &content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("[site-stats] 写入失败: {}", e);
+    }
+}
@@ -0,0 +1,345 @@
+// ============================================================================
+// 时间戳
+// ============================================================================
+//
+// 持久化的时间统一为 Unix 秒（UTC），新写入的时间都经由 `Timestamp` 取得：
+// - `Timestamp::now()`（`now_ts()` 也基于它）
+// - `normalized()` 识别误存为毫秒、为 0 或明显错误的历史值
+// - `to_local_iso8601()` 按本地时区渲染（导出/诊断用），`relative()` 渲染“3 天前”
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// 早于 2020-01-01T00:00:00Z 的值视为无效（本应用不可能产生更早的记录）
+const MIN_VALID_SECS: u64 = 1_577_836_800;
+
+/// 不小于此值的时间戳视为误存的毫秒（秒级要到公元 5138 年才会达到）
+const MILLIS_THRESHOLD: u64 = 100_000_000_000;
+
+/// 允许的时钟偏差：晚于当前时间超过该值视为无效
+const MAX_FUTURE_SKEW_SECS: u64 = 24 * 60 * 60;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub(crate) struct Timestamp(u64);
+
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Self(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        )
+    }
+
+    pub(crate) fn from_secs(secs: u64) -> Self {
+        Self(secs)
+    }
+
+    pub(crate) fn as_secs(self) -> u64 {
+        self.0
+    }
+
+    /// 修正历史数据：毫秒换算为秒；0、过早或远在未来的值返回 None
+    pub(crate) fn normalized(self) -> Option<Self> {
+        let secs = if self.0 >= MILLIS_THRESHOLD {
+            self.0 / 1000
+        } else {
+            self.0
+        };
+        let latest = Self::now().0 + MAX_FUTURE_SKEW_SECS;
+        (MIN_VALID_SECS..=latest)
+            .contains(&secs)
+            .then_some(Self(secs))
+    }
+
+    /// UTC 日期（YYYY-MM-DD）
+    pub(crate) fn utc_date(self) -> String {
+        let (year, month, day) = civil_from_days((self.0 / 86_400) as i64);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    /// 本地时区的日期（YYYY-MM-DD）
+    pub(crate) fn local_date(self) -> String {
+        self.date_with_offset(local_offset_secs(self.0))
+    }
+
+    /// 本地时区的时刻（HH:MM）
+    pub(crate) fn local_time(self) -> String {
+        self.time_with_offset(local_offset_secs(self.0))
+    }
+
+    /// 按给定的 UTC 偏移（秒）渲染日期
+    fn date_with_offset(self, offset: i64) -> String {
+        let local = self.0 as i64 + offset;
+        let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    /// 按给定的 UTC 偏移（秒）渲染时刻
+    fn time_with_offset(self, offset: i64) -> String {
+        let secs_of_day = (self.0 as i64 + offset).rem_euclid(86_400);
+        format!("{:02}:{:02}", secs_of_day / 3600, secs_of_day % 3600 / 60)
+    }
+
+    /// 本地时区的 ISO-8601，如 `2024-03-31T02:30:00+02:00`
+    pub(crate) fn to_local_iso8601(self) -> String {
+        self.to_iso8601_with_offset(local_offset_secs(self.0))
+    }
+
+    /// 按给定的 UTC 偏移（秒）渲染 ISO-8601
+    fn to_iso8601_with_offset(self, offset: i64) -> String {
+        let local = self.0 as i64 + offset;
+        let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+        let secs_of_day = local.rem_euclid(86_400);
+        let sign = if offset < 0 { '-' } else { '+' };
+        let offset_minutes = offset.abs() / 60;
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            secs_of_day % 3600 / 60,
+            secs_of_day % 60,
+            sign,
+            offset_minutes / 60,
+            offset_minutes % 60
+        )
+    }
+
+    /// 相对时间（“3 天前”/“3 days ago”）；超过 30 天显示本地日期
+    pub(crate) fn relative(self, now: Timestamp, language: &str) -> String {
+        let zh = language.starts_with("zh");
+        let diff = now.0.saturating_sub(self.0);
+        let (value, zh_unit, en_unit) = match diff {
+            0..=59 => return if zh { "刚刚" } else { "just now" }.to_string(),
+            60..=3_599 => (diff / 60, "分钟", "minute"),
+            3_600..=86_399 => (diff / 3_600, "小时", "hour"),
+            86_400..=2_591_999 => (diff / 86_400, "天", "day"),
+            _ => return self.local_date(),
+        };
+        if zh {
+            format!("{} {}前", value, zh_unit)
+        } else if value == 1 {
+            format!("1 {} ago", en_unit)
+        } else {
+            format!("{} {}s ago", value, en_unit)
+        }
+    }
+}
+
+/// 天数（自 1970-01-01）→ 公历年月日（Howard Hinnant 的 civil_from_days）
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// 本地时区在该时刻相对 UTC 的偏移（秒），按当时的规则计算（含夏令时）
+#[cfg(unix)]
+fn local_offset_secs(secs: u64) -> i64 {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r 只写入调用方提供的 tm
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::localtime_r(&time, &mut tm) };
+    if result.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+#[cfg(windows)]
+fn local_offset_secs(secs: u64) -> i64 {
+    use windows_sys::Win32::Foundation::{FILETIME, SYSTEMTIME};
+    use windows_sys::Win32::System::Time::{
+        FileTimeToSystemTime, SystemTimeToFileTime, SystemTimeToTzSpecificLocalTime,
+    };
+
+    // FILETIME：自 1601-01-01 起的 100 纳秒数
+    let utc_ticks = (secs + 11_644_473_600) * 10_000_000;
+    let utc_ft = FILETIME {
+        dwLowDateTime: utc_ticks as u32,
+        dwHighDateTime: (utc_ticks >> 32) as u32,
+    };
+    // SAFETY: 所有指针都指向本函数内的局部变量
+    unsafe {
+        let mut utc: SYSTEMTIME = std::mem::zeroed();
+        let mut local: SYSTEMTIME = std::mem::zeroed();
+        let mut local_ft: FILETIME = std::mem::zeroed();
+        if FileTimeToSystemTime(&utc_ft, &mut utc) == 0
+            || SystemTimeToTzSpecificLocalTime(std::ptr::null(), &utc, &mut local) == 0
+            || SystemTimeToFileTime(&local, &mut local_ft) == 0
+        {
+            return 0;
+        }
+        let local_ticks = ((local_ft.dwHighDateTime as u64) << 32) | local_ft.dwLowDateTime as u64;
+        (local_ticks as i64 - utc_ticks as i64) / 10_000_000
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn local_offset_secs(_secs: u64) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-31T01:00:00Z：欧洲中部夏令时开始
+    const EU_SPRING_FORWARD: u64 = 1_711_846_800;
+    /// 2024-10-27T01:00:00Z：欧洲中部夏令时结束
+    const EU_FALL_BACK: u64 = 1_729_990_800;
+    /// 2024-03-10T07:00:00Z：美国东部夏令时开始
+    const US_SPRING_FORWARD: u64 = 1_710_054_000;
+
+    #[test]
+    fn iso8601_renders_offsets() {
+        let ts = Timestamp::from_secs(EU_SPRING_FORWARD);
+        assert_eq!(ts.to_iso8601_with_offset(0), "2024-03-31T01:00:00+00:00");
+        assert_eq!(
+            ts.to_iso8601_with_offset(2 * 3600),
+            "2024-03-31T03:00:00+02:00"
+        );
+        assert_eq!(
+            ts.to_iso8601_with_offset(-5 * 3600),
+            "2024-03-30T20:00:00-05:00"
+        );
+        assert_eq!(
+            ts.to_iso8601_with_offset(5 * 3600 + 45 * 60),
+            "2024-03-31T06:45:00+05:45"
+        );
+        assert_eq!(ts.utc_date(), "2024-03-31");
+    }
+
+    /// 2024 年 Europe/Berlin 的偏移规则：(生效时刻, UTC 偏移秒数)
+    const BERLIN_2024: &[(u64, i64)] = &[
+        (0, 3600),
+        (EU_SPRING_FORWARD, 2 * 3600),
+        (EU_FALL_BACK, 3600),
+    ];
+    /// 2024 年 America/New_York 的偏移规则（截至秋季切换前）
+    const NEW_YORK_2024: &[(u64, i64)] = &[(0, -5 * 3600), (US_SPRING_FORWARD, -4 * 3600)];
+
+    fn offset_at(table: &[(u64, i64)], secs: u64) -> i64 {
+        table
+            .iter()
+            .rev()
+            .find(|(start, _)| secs >= *start)
+            .map(|(_, offset)| *offset)
+            .unwrap()
+    }
+
+    /// 偏移取自固定的切换表，不修改进程的 TZ，可与其他测试并行
+    #[test]
+    fn local_rendering_follows_dst_boundaries() {
+        let iso = |table: &[(u64, i64)], secs: u64| {
+            Timestamp::from_secs(secs).to_iso8601_with_offset(offset_at(table, secs))
+        };
+
+        assert_eq!(
+            iso(BERLIN_2024, EU_SPRING_FORWARD - 1),
+            "2024-03-31T01:59:59+01:00"
+        );
+        assert_eq!(
+            iso(BERLIN_2024, EU_SPRING_FORWARD),
+            "2024-03-31T03:00:00+02:00"
+        );
+        assert_eq!(
+            iso(BERLIN_2024, EU_FALL_BACK - 1),
+            "2024-10-27T02:59:59+02:00"
+        );
+        assert_eq!(iso(BERLIN_2024, EU_FALL_BACK), "2024-10-27T02:00:00+01:00");
+        // 本地日期早于 UTC 日期切换
+        let late_secs = EU_SPRING_FORWARD + 22 * 3600;
+        let late = Timestamp::from_secs(late_secs);
+        let late_offset = offset_at(BERLIN_2024, late_secs);
+        assert_eq!(late.utc_date(), "2024-03-31");
+        assert_eq!(late.date_with_offset(late_offset), "2024-04-01");
+        assert_eq!(late.time_with_offset(late_offset), "01:00");
+
+        assert_eq!(
+            iso(NEW_YORK_2024, US_SPRING_FORWARD - 1),
+            "2024-03-10T01:59:59-05:00"
+        );
+        assert_eq!(
+            iso(NEW_YORK_2024, US_SPRING_FORWARD),
+            "2024-03-10T03:00:00-04:00"
+        );
+        assert_eq!(
+            Timestamp::from_secs(US_SPRING_FORWARD)
+                .date_with_offset(offset_at(NEW_YORK_2024, US_SPRING_FORWARD)),
+            "2024-03-10"
+        );
+        // 本地渲染就是按当时的系统偏移渲染
+        let now = Timestamp::now();
+        let offset = local_offset_secs(now.as_secs());
+        assert_eq!(now.to_local_iso8601(), now.to_iso8601_with_offset(offset));
+        assert_eq!(now.local_date(), now.date_with_offset(offset));
+    }
+
+    #[test]
+    fn relative_formats_in_zh_and_en() {
+        let now = Timestamp::from_secs(EU_FALL_BACK);
+        let ago = |secs: u64| Timestamp::from_secs(EU_FALL_BACK - secs);
+        let cases = [
+            (0, "刚刚", "just now"),
+            (59, "刚刚", "just now"),
+            (60, "1 分钟前", "1 minute ago"),
+            (3 * 60 + 30, "3 分钟前", "3 minutes ago"),
+            (3_600, "1 小时前", "1 hour ago"),
+            (86_399, "23 小时前", "23 hours ago"),
+            (86_400, "1 天前", "1 day ago"),
+            (3 * 86_400, "3 天前", "3 days ago"),
+            (29 * 86_400, "29 天前", "29 days ago"),
+        ];
+        for (secs, zh, en) in cases {
+            assert_eq!(ago(secs).relative(now, "zh-CN"), zh);
+            assert_eq!(ago(secs).relative(now, "en-US"), en);
+        }
+        // 超过 30 天显示日期；时间在未来（时钟偏差）视为刚刚
+        let old = ago(40 * 86_400);
+        // 按本地时区为 9 月 16 或 17 日
+        assert!(old.relative(now, "en-US").starts_with("2024-09-1"));
+        assert_eq!(
+            Timestamp::from_secs(EU_FALL_BACK + 10).relative(now, "zh-CN"),
+            "刚刚"
+        );
+    }
+
+    #[test]
+    fn normalized_repairs_millis_and_rejects_garbage() {
+        assert_eq!(
+            Timestamp::from_secs(EU_FALL_BACK * 1000).normalized(),
+            Some(Timestamp::from_secs(EU_FALL_BACK))
+        );
+        assert_eq!(
+            Timestamp::from_secs(EU_FALL_BACK).normalized(),
+            Some(Timestamp::from_secs(EU_FALL_BACK))
+        );
+        assert_eq!(Timestamp::from_secs(0).normalized(), None);
+        assert_eq!(Timestamp::from_secs(MIN_VALID_SECS - 1).normalized(), None);
+        let far_future = Timestamp::now().as_secs() + 2 * MAX_FUTURE_SKEW_SECS;
+        assert_eq!(Timestamp::from_secs(far_future).normalized(), None);
+    }
+
+    #[test]
+    fn civil_from_days_handles_leap_years() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+}
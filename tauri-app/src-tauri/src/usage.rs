@@ -0,0 +1,165 @@
+// ============================================================================
+// 总结 token 用量统计
+// ============================================================================
+//
+// - 每次成功的总结请求记录一条用量（站点、模型、prompt/completion token）到 usage.json
+// - 服务端未返回 usage 时按 0 记录（仍计入调用次数）
+// - 与 API 审计记录共用保留策略：超过 HISTORY_MAX_RECORDS 条时丢弃最旧的记录
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::time::Timestamp;
+
+/// 隐私站点在用量记录中的占位符（与审计记录一致）
+const PRIVATE_PLACEHOLDER: &str = "private";
+
+static USAGE_LOG: Lazy<Mutex<Vec<UsageRecord>>> = Lazy::new(|| Mutex::new(load_records()));
+
+/// 一次请求的 token 用量（服务端未返回时为 0）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct UsageRecord {
+    pub timestamp: Timestamp,
+    /// 来源站点；总结文本未指定站点时为空
+    #[serde(default)]
+    pub site_id: String,
+    pub model: String,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+/// 按模型或站点汇总的用量
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.total_tokens += record.prompt_tokens + record.completion_tokens;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct UsageStats {
+    pub days: u32,
+    pub total: UsageTotals,
+    pub by_model: BTreeMap<String, UsageTotals>,
+    pub by_site: BTreeMap<String, UsageTotals>,
+}
+
+pub(crate) fn record(site_id: Option<&str>, model: &str, usage: TokenUsage) {
+    let record = UsageRecord {
+        timestamp: Timestamp::now(),
+        site_id: site_id.unwrap_or_default().to_string(),
+        model: model.to_string(),
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+    };
+    println!(
+        "[usage] model={} site={} prompt={} completion={}",
+        record.model, record.site_id, record.prompt_tokens, record.completion_tokens
+    );
+    let mut log = USAGE_LOG.lock().unwrap();
+    log.push(record);
+    crate::keep_newest_records(&mut log);
+    persist(&log);
+}
+
+/// 最近 `days` 天的用量汇总（按模型、按站点）
+pub(crate) fn stats(days: u32) -> UsageStats {
+    let since = crate::now_ts().saturating_sub(days as u64 * 86_400);
+    let mut stats = UsageStats {
+        days,
+        total: UsageTotals::default(),
+        by_model: BTreeMap::new(),
+        by_site: BTreeMap::new(),
+    };
+    let log = USAGE_LOG.lock().unwrap();
+    for record in log.iter().filter(|r| r.timestamp.as_secs() >= since) {
+        stats.total.add(record);
+        stats
+            .by_model
+            .entry(record.model.clone())
+            .or_default()
+            .add(record);
+        stats
+            .by_site
+            .entry(record.site_id.clone())
+            .or_default()
+            .add(record);
+    }
+    stats
+}
+
+/// 站点被标记为隐私后，把已有记录中的站点替换为占位符
+pub(crate) fn pseudonymize_site(site_id: &str) {
+    let mut log = USAGE_LOG.lock().unwrap();
+    let mut changed = false;
+    for record in log.iter_mut().filter(|r| r.site_id == site_id) {
+        record.site_id = PRIVATE_PLACEHOLDER.to_string();
+        changed = true;
+    }
+    if changed {
+        persist(&log);
+    }
+}
+
+// ============================================================================
+// 用量记录存储
+// ============================================================================
+
+fn load_records() -> Vec<UsageRecord> {
+    let path = crate::get_usage_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<UsageRecord>>(&content) {
+        Ok(mut records) => {
+            crate::keep_newest_records(&mut records);
+            records
+        }
+        Err(e) => {
+            println!("[usage] 解析失败，重新开始记录: {}", e);
+            crate::quarantine_corrupt_file(&path);
+            Vec::new()
+        }
+    }
+}
+
+fn persist(log: &[UsageRecord]) {
+    // 只读实例只保留内存中的记录
+    if crate::ensure_storage_writable().is_err() {
+        return;
+    }
+    let path = crate::get_usage_path();
+    let content = match serde_json::to_vec(log) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("[usage] 序列化失败: {}", e);
+            return;
+        }
+    };
+    let result = crate::file_lock::acquire_file_lock(&path)
+        .and_then(|_lock| crate::write_file_atomic(&path, &content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("[usage] 写入失败: {}", e);
+    }
+}
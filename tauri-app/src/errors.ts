@@ -0,0 +1,10 @@
+import type { AppError } from "./types";
+
+export function isAppError(e: unknown): e is AppError {
+  return typeof e === "object" && e !== null && "code" in e && "message" in e;
+}
+
+// 命令返回的 AppError 取 message，其他异常直接转为字符串
+export function errorMessage(e: unknown): string {
+  return isAppError(e) ? e.message : String(e);
+}
@@ -63,6 +63,12 @@ const zhCN: Dict = {
   "settings.saveApiSettings": "保存 API 设置",
   "settings.savePromptTemplate": "保存模板",
   "settings.clearKey": "清空 Key",
+  "settings.aiProvider": "接口协议",
+  "settings.aiAuth": "鉴权方式",
+  "settings.aiMaxTokensHint": "留空使用服务默认值",
+  "settings.aiSystemPrompt": "系统提示词",
+  "settings.aiSystemPromptHint": "留空使用默认系统提示词",
+  "settings.aiAuthNone": "无需 Key（本地模型）",
   "settings.aiApiSaved": "AI API 设置已保存",
   "settings.apiKeyCleared": "已清空 API Key",
   "settings.clearApiKeyConfirm": "确认清空 API Key？清空后自动总结将不可用。",
@@ -78,6 +84,16 @@ const zhCN: Dict = {
   "siteSettings.icon": "图标",
   "siteSettings.summaryPromptOverride": "总结提示词模板（覆盖）",
   "siteSettings.useGlobalPrompt": "使用全局模板",
+  "siteSettings.initScript": "初始化脚本",
+  "siteSettings.initScriptHint": "页面加载时注入的 JavaScript（如关闭 Cookie 提示）；修改后该站点的页面会重新加载",
+  "siteSettings.popupPolicy": "弹窗",
+  "siteSettings.popupPolicyHint": "打开新窗口的链接（如“查看来源”）的处理方式；与当前页面同源的弹窗（如登录窗口）总是直接打开",
+  "siteSettings.disableAntiDetection": "不注入反检测脚本",
+  "siteSettings.disableAntiDetectionHint": "站点自身的机器人检测被伪造的浏览器特征（如 navigator.plugins）干扰时开启；修改后重新加载该站点",
+  "siteSettings.popupPolicy.system": "在系统浏览器中打开",
+  "siteSettings.popupPolicy.newTab": "在新 Tab 中打开",
+  "siteSettings.popupPolicy.block": "拦截",
+  "common.popupBlocked": "已拦截页面弹窗",
   "sidebar.searchPlaceholder": "搜索站点（名称/URL）",
   "sidebar.sectionPinned": "置顶",
   "sidebar.sectionRecent": "最近",
@@ -85,6 +101,10 @@ const zhCN: Dict = {
   "sidebar.menu.pin": "置顶",
   "sidebar.menu.unpin": "取消置顶",
   "sidebar.menu.refresh": "刷新页面",
+  "sidebar.menu.hardRefresh": "强制刷新（忽略缓存）",
+  "sidebar.menu.openInBrowser": "在浏览器中打开",
+  "sidebar.menu.copyLink": "复制链接",
+  "sidebar.menu.screenshot": "截图",
   "sidebar.menu.clearCache": "清除缓存",
   "sidebar.menu.devtools": "开发者工具",
   "sidebar.menu.removeSite": "删除站点",
@@ -100,6 +120,10 @@ const zhCN: Dict = {
   "addSite.add": "添加",
   "top.multiSession": "多会话",
   "top.closeTab": "关闭标签",
+  "top.muteTab": "静音标签",
+  "top.unmuteTab": "取消静音",
+  "top.muteAll": "全部静音",
+  "top.unmuteAll": "全部取消静音",
   "top.splitNeedTwoTabs": "请先创建第二个标签页（新建标签），再开启分屏。",
   "summary.title": "总结",
   "summary.placeholder": "总结结果会出现在这里",
@@ -113,6 +137,12 @@ const zhCN: Dict = {
   "common.siteNotFound": "站点不存在",
   "common.fillNameUrl": "请填写站点名称和 URL",
   "common.refreshFailed": "刷新失败",
+  "common.linkCopied": "链接已复制",
+  "common.screenshotSaved": "截图已保存",
+  "common.screenshotFailed": "截图失败",
+  "common.downloadFinished": "下载完成",
+  "common.downloadFailed": "下载失败",
+  "common.openInBrowserFailed": "无法在浏览器中打开",
   "common.clearCacheFailed": "清除缓存失败",
 };
 
@@ -158,6 +188,12 @@ const en: Dict = {
   "settings.saveApiSettings": "Save API settings",
   "settings.savePromptTemplate": "Save template",
   "settings.clearKey": "Clear key",
+  "settings.aiProvider": "API protocol",
+  "settings.aiAuth": "Authentication",
+  "settings.aiMaxTokensHint": "Empty = provider default",
+  "settings.aiSystemPrompt": "System prompt",
+  "settings.aiSystemPromptHint": "Leave empty to use the default system prompt",
+  "settings.aiAuthNone": "No key (local model)",
   "settings.aiApiSaved": "AI API settings saved",
   "settings.apiKeyCleared": "API key cleared",
   "settings.clearApiKeyConfirm": "Clear API key? Auto-summarize will be unavailable.",
@@ -173,6 +209,16 @@ const en: Dict = {
   "siteSettings.icon": "Icon",
   "siteSettings.summaryPromptOverride": "Summary prompt template (override)",
   "siteSettings.useGlobalPrompt": "Use global template",
+  "siteSettings.initScript": "Init script",
+  "siteSettings.initScriptHint": "JavaScript injected on page load (e.g. dismiss cookie banners); saving reloads this site's pages",
+  "siteSettings.popupPolicy": "Popups",
+  "siteSettings.popupPolicyHint": "How links that open a new window (e.g. \"view source\") are handled; same-origin popups such as login windows always open directly",
+  "siteSettings.disableAntiDetection": "Disable anti-detection",
+  "siteSettings.disableAntiDetectionHint": "Turn on if the site's own bot check is confused by the spoofed browser fingerprint (e.g. navigator.plugins); the site reloads after saving",
+  "siteSettings.popupPolicy.system": "Open in system browser",
+  "siteSettings.popupPolicy.newTab": "Open in a new tab",
+  "siteSettings.popupPolicy.block": "Block",
+  "common.popupBlocked": "Blocked a popup from the page",
   "sidebar.searchPlaceholder": "Search sites (name/URL)",
   "sidebar.sectionPinned": "Pinned",
   "sidebar.sectionRecent": "Recent",
@@ -180,6 +226,10 @@ const en: Dict = {
   "sidebar.menu.pin": "Pin",
   "sidebar.menu.unpin": "Unpin",
   "sidebar.menu.refresh": "Reload",
+  "sidebar.menu.hardRefresh": "Hard reload (bypass cache)",
+  "sidebar.menu.openInBrowser": "Open in browser",
+  "sidebar.menu.copyLink": "Copy link",
+  "sidebar.menu.screenshot": "Screenshot",
   "sidebar.menu.clearCache": "Clear cache",
   "sidebar.menu.devtools": "DevTools",
   "sidebar.menu.removeSite": "Remove site",
@@ -195,6 +245,10 @@ const en: Dict = {
   "addSite.add": "Add",
   "top.multiSession": "Multi-session",
   "top.closeTab": "Close tab",
+  "top.muteTab": "Mute tab",
+  "top.unmuteTab": "Unmute tab",
+  "top.muteAll": "Mute all tabs",
+  "top.unmuteAll": "Unmute all tabs",
   "top.splitNeedTwoTabs": "Create another tab first (New Tab), then enable split view.",
   "summary.title": "Summary",
   "summary.placeholder": "The summary will appear here",
@@ -208,6 +262,12 @@ const en: Dict = {
   "common.siteNotFound": "Site not found",
   "common.fillNameUrl": "Please fill in name and URL",
   "common.refreshFailed": "Refresh failed",
+  "common.linkCopied": "Link copied",
+  "common.screenshotSaved": "Screenshot saved",
+  "common.screenshotFailed": "Screenshot failed",
+  "common.downloadFinished": "Download complete",
+  "common.downloadFailed": "Download failed",
+  "common.openInBrowserFailed": "Could not open in browser",
   "common.clearCacheFailed": "Failed to clear cache",
 };
 
@@ -253,6 +313,12 @@ const ja: Dict = {
   "settings.saveApiSettings": "AI API 設定を保存",
   "settings.savePromptTemplate": "テンプレートを保存",
   "settings.clearKey": "キーを消去",
+  "settings.aiProvider": "API プロトコル",
+  "settings.aiAuth": "認証方式",
+  "settings.aiMaxTokensHint": "空欄でサービスの既定値",
+  "settings.aiSystemPrompt": "システムプロンプト",
+  "settings.aiSystemPromptHint": "空欄で既定のシステムプロンプトを使用",
+  "settings.aiAuthNone": "キー不要（ローカルモデル）",
   "settings.aiApiSaved": "AI API 設定を保存しました",
   "settings.apiKeyCleared": "API キーを消去しました",
   "settings.clearApiKeyConfirm": "API キーを消去しますか？自動要約が使えなくなります。",
@@ -268,6 +334,16 @@ const ja: Dict = {
   "siteSettings.icon": "アイコン",
   "siteSettings.summaryPromptOverride": "要約プロンプト（上書き）",
   "siteSettings.useGlobalPrompt": "全体テンプレートを使用",
+  "siteSettings.initScript": "初期化スクリプト",
+  "siteSettings.initScriptHint": "ページ読み込み時に注入する JavaScript（Cookie バナーを閉じるなど）。変更するとこのサイトのページが再読み込みされます",
+  "siteSettings.popupPolicy": "ポップアップ",
+  "siteSettings.popupPolicyHint": "新しいウィンドウを開くリンク（「ソースを表示」など）の扱い。ログイン画面など同一オリジンのポップアップは常にそのまま開きます",
+  "siteSettings.disableAntiDetection": "検出回避スクリプトを無効化",
+  "siteSettings.disableAntiDetectionHint": "偽装したブラウザ特性（navigator.plugins など）がサイト独自のボット判定を妨げる場合にオンにします。保存後にサイトを再読み込みします",
+  "siteSettings.popupPolicy.system": "システムのブラウザで開く",
+  "siteSettings.popupPolicy.newTab": "新しいタブで開く",
+  "siteSettings.popupPolicy.block": "ブロック",
+  "common.popupBlocked": "ページのポップアップをブロックしました",
   "sidebar.searchPlaceholder": "サイト検索（名前/URL）",
   "sidebar.sectionPinned": "ピン留め",
   "sidebar.sectionRecent": "最近",
@@ -275,6 +351,10 @@ const ja: Dict = {
   "sidebar.menu.pin": "ピン留め",
   "sidebar.menu.unpin": "ピン解除",
   "sidebar.menu.refresh": "再読み込み",
+  "sidebar.menu.hardRefresh": "キャッシュを無視して再読み込み",
+  "sidebar.menu.openInBrowser": "ブラウザで開く",
+  "sidebar.menu.copyLink": "リンクをコピー",
+  "sidebar.menu.screenshot": "スクリーンショット",
   "sidebar.menu.clearCache": "キャッシュを消去",
   "sidebar.menu.devtools": "開発者ツール",
   "sidebar.menu.removeSite": "サイトを削除",
@@ -290,6 +370,10 @@ const ja: Dict = {
   "addSite.add": "追加",
   "top.multiSession": "複数セッション",
   "top.closeTab": "タブを閉じる",
+  "top.muteTab": "タブをミュート",
+  "top.unmuteTab": "ミュートを解除",
+  "top.muteAll": "すべてのタブをミュート",
+  "top.unmuteAll": "すべてのミュートを解除",
   "top.splitNeedTwoTabs": "分割を使うには別のタブ（新しいタブ）を作成してください。",
   "summary.title": "要約",
   "summary.placeholder": "要約結果はここに表示されます",
@@ -303,6 +387,12 @@ const ja: Dict = {
   "common.siteNotFound": "サイトが見つかりません",
   "common.fillNameUrl": "名前とURLを入力してください",
   "common.refreshFailed": "更新に失敗しました",
+  "common.linkCopied": "リンクをコピーしました",
+  "common.screenshotSaved": "スクリーンショットを保存しました",
+  "common.screenshotFailed": "スクリーンショットに失敗しました",
+  "common.downloadFinished": "ダウンロード完了",
+  "common.downloadFailed": "ダウンロードに失敗しました",
+  "common.openInBrowserFailed": "ブラウザで開けませんでした",
   "common.clearCacheFailed": "キャッシュ削除に失敗しました",
 };
 
@@ -348,6 +438,12 @@ const ko: Dict = {
   "settings.saveApiSettings": "AI API 설정 저장",
   "settings.savePromptTemplate": "템플릿 저장",
   "settings.clearKey": "키 지우기",
+  "settings.aiProvider": "API 프로토콜",
+  "settings.aiAuth": "인증 방식",
+  "settings.aiMaxTokensHint": "비우면 서비스 기본값",
+  "settings.aiSystemPrompt": "시스템 프롬프트",
+  "settings.aiSystemPromptHint": "비우면 기본 시스템 프롬프트 사용",
+  "settings.aiAuthNone": "키 없음(로컬 모델)",
   "settings.aiApiSaved": "AI API 설정이 저장되었습니다",
   "settings.apiKeyCleared": "API 키가 지워졌습니다",
   "settings.clearApiKeyConfirm": "API 키를 지울까요? 자동 요약을 사용할 수 없게 됩니다.",
@@ -363,6 +459,16 @@ const ko: Dict = {
   "siteSettings.icon": "아이콘",
   "siteSettings.summaryPromptOverride": "요약 프롬프트(재정의)",
   "siteSettings.useGlobalPrompt": "전역 템플릿 사용",
+  "siteSettings.initScript": "초기화 스크립트",
+  "siteSettings.initScriptHint": "페이지 로드 시 주입되는 JavaScript(예: 쿠키 배너 닫기). 변경하면 이 사이트의 페이지가 다시 로드됩니다",
+  "siteSettings.popupPolicy": "팝업",
+  "siteSettings.popupPolicyHint": "새 창을 여는 링크(예: \"출처 보기\") 처리 방식. 로그인 창 등 같은 출처의 팝업은 항상 바로 열립니다",
+  "siteSettings.disableAntiDetection": "탐지 회피 스크립트 비활성화",
+  "siteSettings.disableAntiDetectionHint": "위조된 브라우저 특성(navigator.plugins 등)이 사이트 자체 봇 검사를 방해할 때 켜세요. 저장 후 사이트를 다시 불러옵니다",
+  "siteSettings.popupPolicy.system": "시스템 브라우저에서 열기",
+  "siteSettings.popupPolicy.newTab": "새 탭에서 열기",
+  "siteSettings.popupPolicy.block": "차단",
+  "common.popupBlocked": "페이지 팝업을 차단했습니다",
   "sidebar.searchPlaceholder": "사이트 검색(이름/URL)",
   "sidebar.sectionPinned": "고정",
   "sidebar.sectionRecent": "최근",
@@ -370,6 +476,10 @@ const ko: Dict = {
   "sidebar.menu.pin": "고정",
   "sidebar.menu.unpin": "고정 해제",
   "sidebar.menu.refresh": "새로고침",
+  "sidebar.menu.hardRefresh": "강력 새로고침(캐시 무시)",
+  "sidebar.menu.openInBrowser": "브라우저에서 열기",
+  "sidebar.menu.copyLink": "링크 복사",
+  "sidebar.menu.screenshot": "스크린샷",
   "sidebar.menu.clearCache": "캐시 삭제",
   "sidebar.menu.devtools": "개발자 도구",
   "sidebar.menu.removeSite": "사이트 삭제",
@@ -385,6 +495,10 @@ const ko: Dict = {
   "addSite.add": "추가",
   "top.multiSession": "다중 세션",
   "top.closeTab": "탭 닫기",
+  "top.muteTab": "탭 음소거",
+  "top.unmuteTab": "음소거 해제",
+  "top.muteAll": "모든 탭 음소거",
+  "top.unmuteAll": "모든 탭 음소거 해제",
   "top.splitNeedTwoTabs": "분할을 켜려면 먼저 다른 탭(새 탭)을 만드세요.",
   "summary.title": "요약",
   "summary.placeholder": "요약 결과가 여기에 표시됩니다",
@@ -398,6 +512,12 @@ const ko: Dict = {
   "common.siteNotFound": "사이트를 찾을 수 없습니다",
   "common.fillNameUrl": "이름과 URL을 입력하세요",
   "common.refreshFailed": "새로고침 실패",
+  "common.linkCopied": "링크를 복사했습니다",
+  "common.screenshotSaved": "스크린샷을 저장했습니다",
+  "common.screenshotFailed": "스크린샷 실패",
+  "common.downloadFinished": "다운로드 완료",
+  "common.downloadFailed": "다운로드 실패",
+  "common.openInBrowserFailed": "브라우저에서 열 수 없습니다",
   "common.clearCacheFailed": "캐시 삭제 실패",
 };
 
@@ -443,6 +563,12 @@ const es: Dict = {
   "settings.saveApiSettings": "Guardar configuración de AI API",
   "settings.savePromptTemplate": "Guardar plantilla",
   "settings.clearKey": "Borrar clave",
+  "settings.aiProvider": "Protocolo de API",
+  "settings.aiAuth": "Autenticación",
+  "settings.aiMaxTokensHint": "Vacío = valor del proveedor",
+  "settings.aiSystemPrompt": "Prompt del sistema",
+  "settings.aiSystemPromptHint": "Déjalo vacío para usar el prompt del sistema predeterminado",
+  "settings.aiAuthNone": "Sin clave (modelo local)",
   "settings.aiApiSaved": "Configuración de AI API guardada",
   "settings.apiKeyCleared": "Clave API borrada",
   "settings.clearApiKeyConfirm": "¿Borrar la clave API? El resumen automático no estará disponible.",
@@ -458,6 +584,16 @@ const es: Dict = {
   "siteSettings.icon": "Icono",
   "siteSettings.summaryPromptOverride": "Plantilla de prompt (anulación)",
   "siteSettings.useGlobalPrompt": "Usar plantilla global",
+  "siteSettings.initScript": "Script de inicio",
+  "siteSettings.initScriptHint": "JavaScript inyectado al cargar la página (p. ej. cerrar avisos de cookies); al guardar se recargan las páginas del sitio",
+  "siteSettings.popupPolicy": "Ventanas emergentes",
+  "siteSettings.popupPolicyHint": "Cómo se abren los enlaces que abren una ventana nueva (p. ej. \"ver fuente\"); las ventanas del mismo origen, como el inicio de sesión, siempre se abren directamente",
+  "siteSettings.disableAntiDetection": "Desactivar antidetección",
+  "siteSettings.disableAntiDetectionHint": "Actívalo si la huella de navegador simulada (p. ej. navigator.plugins) confunde la comprobación de bots del sitio; el sitio se recarga al guardar",
+  "siteSettings.popupPolicy.system": "Abrir en el navegador del sistema",
+  "siteSettings.popupPolicy.newTab": "Abrir en una pestaña nueva",
+  "siteSettings.popupPolicy.block": "Bloquear",
+  "common.popupBlocked": "Se bloqueó una ventana emergente de la página",
   "sidebar.searchPlaceholder": "Buscar sitios (nombre/URL)",
   "sidebar.sectionPinned": "Fijados",
   "sidebar.sectionRecent": "Recientes",
@@ -465,6 +601,10 @@ const es: Dict = {
   "sidebar.menu.pin": "Fijar",
   "sidebar.menu.unpin": "Desfijar",
   "sidebar.menu.refresh": "Recargar",
+  "sidebar.menu.hardRefresh": "Recarga forzada (sin caché)",
+  "sidebar.menu.openInBrowser": "Abrir en el navegador",
+  "sidebar.menu.copyLink": "Copiar enlace",
+  "sidebar.menu.screenshot": "Captura de pantalla",
   "sidebar.menu.clearCache": "Borrar caché",
   "sidebar.menu.devtools": "DevTools",
   "sidebar.menu.removeSite": "Eliminar sitio",
@@ -480,6 +620,10 @@ const es: Dict = {
   "addSite.add": "Añadir",
   "top.multiSession": "Multi-sesión",
   "top.closeTab": "Cerrar pestaña",
+  "top.muteTab": "Silenciar pestaña",
+  "top.unmuteTab": "Reactivar sonido",
+  "top.muteAll": "Silenciar todas las pestañas",
+  "top.unmuteAll": "Reactivar sonido de todas",
   "top.splitNeedTwoTabs": "Crea otra pestaña primero (Nueva pestaña) y luego activa la vista dividida.",
   "summary.title": "Resumen",
   "summary.placeholder": "El resumen aparecerá aquí",
@@ -493,6 +637,12 @@ const es: Dict = {
   "common.siteNotFound": "Sitio no encontrado",
   "common.fillNameUrl": "Rellena el nombre y la URL",
   "common.refreshFailed": "Error al recargar",
+  "common.linkCopied": "Enlace copiado",
+  "common.screenshotSaved": "Captura guardada",
+  "common.screenshotFailed": "Error al capturar la pantalla",
+  "common.downloadFinished": "Descarga completada",
+  "common.downloadFailed": "Error en la descarga",
+  "common.openInBrowserFailed": "No se pudo abrir en el navegador",
   "common.clearCacheFailed": "Error al borrar caché",
 };
 
@@ -538,6 +688,12 @@ const fr: Dict = {
   "settings.saveApiSettings": "Enregistrer AI API",
   "settings.savePromptTemplate": "Enregistrer le modèle",
   "settings.clearKey": "Effacer la clé",
+  "settings.aiProvider": "Protocole d’API",
+  "settings.aiAuth": "Authentification",
+  "settings.aiMaxTokensHint": "Vide = valeur du fournisseur",
+  "settings.aiSystemPrompt": "Prompt système",
+  "settings.aiSystemPromptHint": "Laisser vide pour utiliser le prompt système par défaut",
+  "settings.aiAuthNone": "Sans clé (modèle local)",
   "settings.aiApiSaved": "Paramètres AI API enregistrés",
   "settings.apiKeyCleared": "Clé API effacée",
   "settings.clearApiKeyConfirm": "Effacer la clé API ? Le résumé automatique sera indisponible.",
@@ -553,6 +709,16 @@ const fr: Dict = {
   "siteSettings.icon": "Icône",
   "siteSettings.summaryPromptOverride": "Modèle de prompt (remplacement)",
   "siteSettings.useGlobalPrompt": "Utiliser le modèle global",
+  "siteSettings.initScript": "Script d'initialisation",
+  "siteSettings.initScriptHint": "JavaScript injecté au chargement de la page (ex. fermer les bannières de cookies) ; l'enregistrement recharge les pages du site",
+  "siteSettings.popupPolicy": "Fenêtres pop-up",
+  "siteSettings.popupPolicyHint": "Traitement des liens qui ouvrent une nouvelle fenêtre (ex. « voir la source ») ; les pop-ups de même origine, comme la connexion, s'ouvrent toujours directement",
+  "siteSettings.disableAntiDetection": "Désactiver l'anti-détection",
+  "siteSettings.disableAntiDetectionHint": "À activer si l'empreinte de navigateur simulée (ex. navigator.plugins) perturbe la détection de robots du site ; le site est rechargé après l'enregistrement",
+  "siteSettings.popupPolicy.system": "Ouvrir dans le navigateur du système",
+  "siteSettings.popupPolicy.newTab": "Ouvrir dans un nouvel onglet",
+  "siteSettings.popupPolicy.block": "Bloquer",
+  "common.popupBlocked": "Une fenêtre pop-up de la page a été bloquée",
   "sidebar.searchPlaceholder": "Rechercher des sites (nom/URL)",
   "sidebar.sectionPinned": "Épinglés",
   "sidebar.sectionRecent": "Récents",
@@ -560,6 +726,10 @@ const fr: Dict = {
   "sidebar.menu.pin": "Épingler",
   "sidebar.menu.unpin": "Désépingler",
   "sidebar.menu.refresh": "Recharger",
+  "sidebar.menu.hardRefresh": "Rechargement forcé (sans cache)",
+  "sidebar.menu.openInBrowser": "Ouvrir dans le navigateur",
+  "sidebar.menu.copyLink": "Copier le lien",
+  "sidebar.menu.screenshot": "Capture d'écran",
   "sidebar.menu.clearCache": "Vider le cache",
   "sidebar.menu.devtools": "DevTools",
   "sidebar.menu.removeSite": "Supprimer le site",
@@ -575,6 +745,10 @@ const fr: Dict = {
   "addSite.add": "Ajouter",
   "top.multiSession": "Multi-session",
   "top.closeTab": "Fermer l’onglet",
+  "top.muteTab": "Couper le son de l’onglet",
+  "top.unmuteTab": "Réactiver le son",
+  "top.muteAll": "Couper le son de tous les onglets",
+  "top.unmuteAll": "Réactiver le son partout",
   "top.splitNeedTwoTabs": "Créez d’abord un autre onglet (Nouvel onglet), puis activez la vue scindée.",
   "summary.title": "Résumé",
   "summary.placeholder": "Le résumé apparaîtra ici",
@@ -588,6 +762,12 @@ const fr: Dict = {
   "common.siteNotFound": "Site introuvable",
   "common.fillNameUrl": "Veuillez renseigner le nom et l’URL",
   "common.refreshFailed": "Échec du rechargement",
+  "common.linkCopied": "Lien copié",
+  "common.screenshotSaved": "Capture d'écran enregistrée",
+  "common.screenshotFailed": "Échec de la capture d'écran",
+  "common.downloadFinished": "Téléchargement terminé",
+  "common.downloadFailed": "Échec du téléchargement",
+  "common.openInBrowserFailed": "Impossible d’ouvrir dans le navigateur",
   "common.clearCacheFailed": "Échec du vidage du cache",
 };
 
@@ -5,6 +5,90 @@ export interface AiSite {
   icon: string;
   builtin: boolean;
   summary_prompt_override?: string;
+  anti_detection_mode?: "full" | "webdriver_only" | "off";
+  disable_anti_detection?: boolean;
+  input_selector?: string;
+  extract_selector?: string;
+  name_key?: string;
+  private?: boolean;
+  prompt_template_id?: string;
+  submit_selector?: string;
+  init_script?: string;
+  enabled?: boolean;
+  popup_policy?: "system" | "new_tab" | "block";
+  zoom?: number;
+}
+
+export interface BookmarkImportEntry {
+  title: string;
+  url: string;
+  reason: string;
+}
+
+export interface BookmarkImportReport {
+  imported: AiSite[];
+  skipped: BookmarkImportEntry[];
+  invalid: BookmarkImportEntry[];
+}
+
+export interface SiteImportError {
+  index: number;
+  name: string;
+  error: string;
+}
+
+export interface SiteImportReport {
+  added: number;
+  updated: number;
+  skipped: number;
+  errors: SiteImportError[];
+}
+
+export interface BroadcastTabResult {
+  ok: boolean;
+  error?: string | null;
+}
+
+export interface PromptVariable {
+  name: string;
+  description: string;
+}
+
+export interface PromptTemplate {
+  id: string;
+  name: string;
+  body: string;
+}
+
+export type AiProvider = "openai" | "anthropic" | "azure";
+
+export type AiAuthMode = "bearer" | "none";
+
+export interface AiApiProfile {
+  id: string;
+  name: string;
+  provider?: AiProvider;
+  auth?: AiAuthMode;
+  base_url: string;
+  model: string;
+  deployment?: string;
+  api_version?: string;
+  key: string;
+  key_in_keyring?: boolean;
+}
+
+export interface AiApiProfileInfo {
+  id: string;
+  name: string;
+  provider: AiProvider;
+  auth: AiAuthMode;
+  base_url: string;
+  model: string;
+  deployment: string;
+  api_version: string;
+  has_key: boolean;
+  key_in_keyring: boolean;
+  active: boolean;
 }
 
 export interface AppConfig {
@@ -17,18 +101,93 @@ export interface AppConfig {
   sidebar_expanded_width: number;
   language: string;
   summary_prompt_template: string;
+  prompt_templates?: PromptTemplate[];
+  ai_api_temperature?: number;
+  ai_api_max_tokens?: number | null;
+  ai_system_prompt?: string;
+  ai_api_provider?: AiProvider;
+  ai_api_auth?: AiAuthMode;
   ai_api_base_url: string;
   ai_api_model: string;
+  ai_api_deployment?: string;
+  ai_api_version?: string;
   ai_api_key: string;
+  ai_api_key_in_keyring?: boolean;
+  ai_api_profiles?: AiApiProfile[];
+  active_ai_profile_id?: string;
   active_project_id: string;
   last_active_tab_id?: string;
   last_active_site_id?: string;
+  locked?: boolean;
+  automation_api?: { enabled: boolean; port: number };
+  anti_detection?: AntiDetectionConfig;
+  demo_mode?: boolean;
+  tab_hibernate_minutes?: number;
+  project_entry_limit?: number;
+  summary_max_chars?: number | null;
+  summary_extract_timeout_secs?: number | null;
+  summary_total_timeout_secs?: number | null;
+  summary_append_notes?: boolean;
+  http_proxy?: string | null;
+  download_dir?: string;
+  shortcuts?: Record<string, string>;
+  notify_on_summary?: boolean;
+  notify_on_summary_failure?: boolean;
+  session?: SessionState;
+}
+
+export interface AntiDetectionConfig {
+  enabled: boolean;
+  spoof_plugins: boolean;
+  spoof_languages: string[];
+  spoof_platform: boolean;
+  spoof_vendor: boolean;
+  spoof_timezone?: boolean;
+  timezone?: string;
+  spoof_webgl?: boolean;
+  webgl_vendor?: string;
+  webgl_renderer?: string;
+}
+
+export interface SessionState {
+  tabs: Array<{ tab_id: string; site_id: string }>;
+  active_tab_id: string;
+  layout_mode: "single" | "split";
+  ratio: number;
+  left_tab_id?: string | null;
+  right_tab_id?: string | null;
+  pane_tab_ids?: string[];
+  pane_ratios?: number[];
+  orientation?: "horizontal" | "vertical";
+  last_split?: LastSplit | null;
+  closed_tabs?: ClosedTab[];
+}
+
+export interface ClosedTab {
+  tab_id: string;
+  site_id: string;
+  url: string;
+  closed_at: number;
+}
+
+export interface LastSplit {
+  left_tab_id?: string | null;
+  right_tab_id?: string | null;
+  ratio: number;
+  pane_tab_ids: string[];
+  pane_ratios: number[];
+  orientation: "horizontal" | "vertical";
 }
 
 export interface ProjectSummary {
   id: string;
   title: string;
   updated_at: number;
+  updated_label?: string;
+  tags?: string[];
+  created_at?: number;
+  notes_chars?: number;
+  summary_chars?: number;
 }
 
 export interface ProjectContext {
@@ -36,6 +195,96 @@ export interface ProjectContext {
   title: string;
   notes: string;
   summary: string;
+  entries?: ContextEntry[];
+  tags?: string[];
   created_at: number;
   updated_at: number;
 }
+
+export interface ActiveTabSummary {
+  summary: string;
+  project_id: string;
+  project_created: boolean;
+}
+
+export interface ResummarizeResult {
+  summary: string;
+  url: string;
+  extracted_at: number;
+  age_secs: number;
+}
+
+export interface ExtractedTabText {
+  tab_id: string;
+  site_id: string;
+  text: string;
+  length: number;
+  url: string;
+  truncated: boolean;
+}
+
+export interface TrashedProject {
+  id: string;
+  title: string;
+  deleted_at: number;
+  purge_at: number;
+}
+
+export interface ContextEntry {
+  id: string;
+  site_id: string;
+  site_ids?: string[];
+  created_at: number;
+  source_excerpt: string;
+  summary: string;
+  estimate?: SummaryEstimate | null;
+}
+
+export interface SummaryEstimate {
+  chars: number;
+  approx_tokens: number;
+}
+
+export interface UsageTotals {
+  requests: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  total_tokens: number;
+}
+
+export interface UsageStats {
+  days: number;
+  total: UsageTotals;
+  by_model: Record<string, UsageTotals>;
+  by_site: Record<string, UsageTotals>;
+}
+
+export interface SiteStats {
+  activations: number;
+  foreground_secs: number;
+  last_used_at?: number | null;
+}
+
+export interface PrintToPdfResult {
+  path?: string | null;
+  print_dialog_opened: boolean;
+}
+
+export interface DownloadRecord {
+  id: string;
+  tab_id: string;
+  site_id: string;
+  url: string;
+  file_name: string;
+  path: string;
+  bytes: number;
+  state: "in_progress" | "completed" | "failed";
+  started_at: number;
+  finished_at?: number | null;
+}
+
+export interface AppError {
+  code: string;
+  message: string;
+  details?: Record<string, unknown> | null;
+}